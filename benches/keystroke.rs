@@ -0,0 +1,113 @@
+//! Per-keystroke work of the editor for large inputs
+//!
+//! Every key press edits the buffer, highlights it, asks the hinter for a hint and renders the
+//! styled buffer, traversing the history replaces the first two steps by a history lookup.
+//! Run with `cargo bench` and compare the results before and after a change, the times
+//! should stay far below the ~16ms of a frame.
+
+use {
+    criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion},
+    reedline::{
+        ByteOffset, DefaultHighlighter, FileBackedHistory, Highlighter, Hinter, History,
+        HistoryHinter, HistoryNavigationQuery, LineBuffer, MatchingBracketHighlighter,
+    },
+};
+
+const LARGE_HISTORY: usize = 100_000;
+
+/// A buffer of `lines` lines like a long pasted script
+fn large_buffer(lines: usize) -> String {
+    (0..lines)
+        .map(|idx| format!("echo \"line {}\" | grep (line {}) | wc -l", idx, idx))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn large_history() -> FileBackedHistory {
+    let mut history = FileBackedHistory::new(LARGE_HISTORY);
+    for idx in 0..LARGE_HISTORY {
+        history.append(&format!("cargo test --package crate{} -- --nocapture", idx));
+    }
+    history
+}
+
+fn editing(c: &mut Criterion) {
+    let buffer = large_buffer(1_000);
+
+    c.bench_function("insert char in the middle of 1000 lines", |b| {
+        b.iter_batched_ref(
+            || {
+                let mut line_buffer = LineBuffer::new();
+                line_buffer.set_buffer(buffer.clone());
+                line_buffer.set_insertion_point(buffer.len() / 2);
+                line_buffer
+            },
+            |line_buffer| line_buffer.insert_char(black_box('x')),
+            BatchSize::SmallInput,
+        )
+    });
+
+    // Large enough to be kept in a rope with the `rope_buffer` feature, compare the times
+    // with and without it
+    let buffer = large_buffer(50_000);
+    c.bench_function("type and move in the middle of 50000 lines", |b| {
+        let mut line_buffer = LineBuffer::new();
+        line_buffer.set_buffer(buffer.clone());
+        line_buffer.set_insertion_point(buffer.len() / 2);
+        b.iter(|| {
+            line_buffer.insert_char(black_box('x'));
+            line_buffer.delete_left_grapheme();
+            line_buffer.move_line_down();
+            line_buffer.move_line_up();
+        })
+    });
+}
+
+fn highlighting(c: &mut Criterion) {
+    let buffer = large_buffer(1_000);
+    let cursor = ByteOffset(buffer.len() / 2);
+    let commands = (0..1_000).map(|idx| format!("command{}", idx)).collect();
+    let default_highlighter = DefaultHighlighter::new(commands);
+    let bracket_highlighter = MatchingBracketHighlighter::default();
+
+    c.bench_function("highlight 1000 lines", |b| {
+        b.iter(|| default_highlighter.highlight(black_box(&buffer), cursor))
+    });
+    c.bench_function("highlight matching brackets in 1000 lines", |b| {
+        b.iter(|| bracket_highlighter.highlight(black_box(&buffer), cursor))
+    });
+    c.bench_function("render highlighted 1000 lines", |b| {
+        let styled_text = default_highlighter.highlight(&buffer, cursor);
+        b.iter(|| styled_text.render_around_insertion_point(black_box(cursor.0), "::: ", true))
+    });
+}
+
+fn history(c: &mut Criterion) {
+    let history = large_history();
+
+    c.bench_function("hint from 100000 history entries", |b| {
+        let mut hinter = HistoryHinter::default();
+        b.iter(|| {
+            hinter.handle(
+                black_box("cargo test --package crate5"),
+                ByteOffset(27),
+                &history,
+                true,
+            )
+        })
+    });
+
+    c.bench_function("search 100000 history entries", |b| {
+        let mut history = large_history();
+        b.iter(|| {
+            // Only matches one of the oldest entries, the search passes all others
+            history.set_navigation(HistoryNavigationQuery::SubstringSearch(black_box(
+                "crate1 ".to_string(),
+            )));
+            history.back();
+        })
+    });
+}
+
+criterion_group!(benches, editing, highlighting, history);
+criterion_main!(benches);
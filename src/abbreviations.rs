@@ -0,0 +1,145 @@
+/// Words expanded in place when they are typed as the first word of the line
+///
+/// Like the abbreviations of fish: typing a trigger word followed by `Space` or submitting the
+/// line with `Enter` replaces the trigger by its expansion, which can then be edited. Only the
+/// first word of the line is expanded, and only if the cursor is right behind it, so
+/// arguments and pasted text are left alone.
+///
+/// An expansion containing the cursor marker set with [`Abbreviations::with_cursor_marker()`]
+/// puts the cursor at the marker instead of behind the expansion, the marker itself is removed.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{Abbreviations, Reedline};
+///
+/// let abbreviations = Abbreviations::default()
+///     .with_abbreviation("gco", "git checkout")
+///     .with_abbreviation("gcm", "git commit -m \"%\"")
+///     .with_cursor_marker("%");
+/// let line_editor = Reedline::create()?.with_abbreviations(abbreviations);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Abbreviations {
+    entries: Vec<(String, String)>,
+    cursor_marker: Option<String>,
+}
+
+impl Abbreviations {
+    /// A builder that expands `trigger` to `expansion`, replacing a previous expansion
+    pub fn with_abbreviation(
+        mut self,
+        trigger: impl Into<String>,
+        expansion: impl Into<String>,
+    ) -> Self {
+        let trigger = trigger.into();
+        let expansion = expansion.into();
+        match self
+            .entries
+            .iter_mut()
+            .find(|(existing, _)| *existing == trigger)
+        {
+            Some(entry) => entry.1 = expansion,
+            None => self.entries.push((trigger, expansion)),
+        }
+        self
+    }
+
+    /// A builder that sets the marker in expansions where the cursor is put
+    pub fn with_cursor_marker(mut self, cursor_marker: impl Into<String>) -> Self {
+        self.cursor_marker = Some(cursor_marker.into()).filter(|marker| !marker.is_empty());
+        self
+    }
+
+    /// The expansion of `trigger`, if it is an abbreviation
+    pub fn get(&self, trigger: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(existing, _)| existing == trigger)
+            .map(|(_, expansion)| expansion.as_str())
+    }
+
+    /// Expand the first word of `buffer` if the cursor at byte offset `cursor` is right behind it
+    ///
+    /// Returns the expanded buffer, the new cursor offset and whether the cursor was placed
+    /// at a marker.
+    pub(crate) fn expand(&self, buffer: &str, cursor: usize) -> Option<(String, usize, bool)> {
+        let before = buffer.get(..cursor)?;
+        let after = &buffer[cursor..];
+        let word_start = before.len() - before.trim_start().len();
+        let word = &before[word_start..];
+        if word.is_empty()
+            || word.contains(char::is_whitespace)
+            || !matches!(after.chars().next(), None | Some(' ' | '\t' | '\n'))
+        {
+            return None;
+        }
+
+        let expansion = self.get(word)?;
+        let marker = self
+            .cursor_marker
+            .as_deref()
+            .and_then(|marker| Some((expansion.find(marker)?, marker.len())));
+        let (expansion, cursor_in_expansion) = match marker {
+            Some((position, len)) => (
+                format!("{}{}", &expansion[..position], &expansion[position + len..]),
+                position,
+            ),
+            None => (expansion.to_string(), expansion.len()),
+        };
+
+        Some((
+            format!("{}{}{}", &before[..word_start], expansion, after),
+            word_start + cursor_in_expansion,
+            marker.is_some(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn abbreviations() -> Abbreviations {
+        Abbreviations::default()
+            .with_abbreviation("gco", "git checkout")
+            .with_abbreviation("gcm", "git commit -m \"%\"")
+            .with_cursor_marker("%")
+    }
+
+    #[rstest]
+    #[case("gco", 3, Some(("git checkout", 12, false)))]
+    #[case("  gco", 5, Some(("  git checkout", 14, false)))]
+    #[case("gco main", 3, Some(("git checkout main", 12, false)))]
+    #[case("gcm", 3, Some(("git commit -m \"\"", 15, true)))]
+    #[case("gc", 2, None)]
+    #[case("gco", 2, None)]
+    #[case("gcox", 3, None)]
+    #[case("git gco", 7, None)]
+    #[case("", 0, None)]
+    fn expands_first_word_before_cursor(
+        #[case] buffer: &str,
+        #[case] cursor: usize,
+        #[case] expected: Option<(&str, usize, bool)>,
+    ) {
+        assert_eq!(
+            abbreviations().expand(buffer, cursor),
+            expected.map(|(buffer, cursor, at_marker)| (buffer.to_string(), cursor, at_marker))
+        );
+    }
+
+    #[test]
+    fn later_abbreviation_replaces_earlier_one() {
+        let abbreviations = Abbreviations::default()
+            .with_abbreviation("l", "ls")
+            .with_abbreviation("l", "ls -la");
+
+        assert_eq!(abbreviations.get("l"), Some("ls -la"));
+        assert_eq!(
+            abbreviations.expand("l", 1),
+            Some(("ls -la".to_string(), 6, false))
+        );
+    }
+}
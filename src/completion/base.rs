@@ -1,13 +1,17 @@
-use crate::core_editor::LineBuffer;
+use {
+    crate::{core_editor::LineBuffer, ByteOffset},
+    nu_ansi_term::Style,
+    std::{ops::Range, sync::mpsc::Receiver},
+};
 
 /// A span of source code, with positions in bytes
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub struct Span {
     /// The starting position of the span, in bytes
-    pub start: usize,
+    pub start: ByteOffset,
 
     /// The ending position of the span, in bytes
-    pub end: usize,
+    pub end: ByteOffset,
 }
 
 impl Span {
@@ -24,7 +28,117 @@ impl Span {
             end
         );
 
-        Span { start, end }
+        Span {
+            start: ByteOffset(start),
+            end: ByteOffset(end),
+        }
+    }
+
+    /// Number of bytes covered by the span
+    pub fn len(&self) -> usize {
+        self.end.0 - self.start.0
+    }
+
+    /// Check if the span covers no bytes
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The byte range covered by the span, suitable for slicing the line
+    pub fn range(&self) -> Range<usize> {
+        self.start.0..self.end.0
+    }
+}
+
+/// A completion candidate offered by a [`Completer`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Suggestion {
+    /// The text replacing the span
+    pub value: String,
+
+    /// Optional explanation shown next to the value, e.g. the signature of a command
+    pub description: Option<String>,
+
+    /// Style of the value when it is listed, e.g. to set directories apart from files
+    pub style: Option<Style>,
+
+    /// The part of the line that is replaced by the value
+    pub span: Span,
+
+    /// Add a space after the value when it is inserted, e.g. after a complete word
+    pub append_whitespace: bool,
+
+    /// Details shown in the status row while the suggestion is selected, e.g. the resolved
+    /// absolute path or the full expansion of an alias
+    pub preview: Option<String>,
+
+    /// Where to place the cursor after inserting, counted from the start of the value,
+    /// e.g. between inserted quotes. Behind the value (and the added space) if not set.
+    pub cursor_offset: Option<ByteOffset>,
+}
+
+impl Suggestion {
+    /// A suggestion replacing `span` with `value` without description or style
+    pub fn new(value: impl Into<String>, span: Span) -> Suggestion {
+        Suggestion {
+            value: value.into(),
+            span,
+            ..Suggestion::default()
+        }
+    }
+
+    /// A builder that sets the description shown next to the value
+    pub fn with_description(mut self, description: impl Into<String>) -> Suggestion {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// A builder that sets the style of the listed value
+    pub fn with_style(mut self, style: Style) -> Suggestion {
+        self.style = Some(style);
+        self
+    }
+
+    /// A builder to configure if a space is added after the inserted value
+    pub fn with_append_whitespace(mut self, append_whitespace: bool) -> Suggestion {
+        self.append_whitespace = append_whitespace;
+        self
+    }
+
+    /// A builder that sets the details previewed while the suggestion is selected
+    pub fn with_preview(mut self, preview: impl Into<String>) -> Suggestion {
+        self.preview = Some(preview.into());
+        self
+    }
+
+    /// A builder that places the cursor `offset` bytes into the value once it is inserted,
+    /// e.g. `1` for `""`
+    pub fn with_cursor_offset(mut self, offset: impl Into<ByteOffset>) -> Suggestion {
+        self.cursor_offset = Some(offset.into());
+        self
+    }
+
+    /// Replace the span in `line_buffer` with the value and move the cursor behind it, or to
+    /// the [`Suggestion::cursor_offset`]
+    ///
+    /// The space requested by [`Suggestion::append_whitespace`] is skipped if the text after the
+    /// span already starts with whitespace.
+    pub(crate) fn apply(&self, line_buffer: &mut LineBuffer) {
+        let mut value = self.value.clone();
+        let followed_by_whitespace =
+            line_buffer.get_buffer()[self.span.end.0..].starts_with(char::is_whitespace);
+        if self.append_whitespace && !followed_by_whitespace {
+            value.push(' ');
+        }
+
+        let offset = match self.cursor_offset {
+            Some(cursor_offset) => self.span.start.0 + cursor_offset.0.min(self.value.len()),
+            None => self.span.start.0 + value.len(),
+        };
+
+        // TODO improve the support for multiline replace
+        line_buffer.replace(self.span.range(), &value);
+        line_buffer.set_insertion_point(offset);
     }
 }
 
@@ -34,11 +148,139 @@ impl Span {
 pub trait CompletionActionHandler {
     /// Handle the completion action from the given line buffer
     fn handle(&mut self, line: &mut LineBuffer);
+
+    /// The [`Suggestion::preview`] of the suggestion inserted by the last action, shown in the
+    /// status row until the next key is pressed
+    fn preview(&self) -> Option<String> {
+        None
+    }
+
+    /// Complete `line` with the suggestions that arrived since the last action, returns whether
+    /// the line or the loading state changed
+    ///
+    /// Called regularly while [`CompletionActionHandler::is_loading()`], by handlers asking
+    /// with [`Completer::complete_async()`]. Suggestions of a line edited in the meantime are
+    /// dropped.
+    fn poll(&mut self, _line: &mut LineBuffer) -> bool {
+        false
+    }
+
+    /// Whether the suggestions asked for by the last action have not arrived yet
+    fn is_loading(&self) -> bool {
+        false
+    }
 }
 
 /// A trait that defines how to convert a line and position to a list of potential completions in that position.
 pub trait Completer {
     /// the action that will take the line and position and convert it to a vector of completions, which include the
     /// span to replace and the contents of that replacement
-    fn complete(&self, line: &str, pos: usize) -> Vec<(Span, String)>;
+    fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion>;
+
+    /// Start completing the line without waiting for the suggestions
+    ///
+    /// Menus and the handlers of `Tab` ask for completions this way, they show a loading row
+    /// until the suggestions arrive and keep reading keys in the meantime. Completers backed
+    /// by a language server or the network can override it to compute the suggestions on
+    /// another thread and send them through a channel. The default returns the suggestions of
+    /// [`Completer::complete()`].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use reedline::{AsyncSuggestions, Completer, Span, Suggestion};
+    /// use std::{sync::mpsc, thread};
+    ///
+    /// struct RemoteCompleter;
+    ///
+    /// impl Completer for RemoteCompleter {
+    ///     fn complete(&self, _line: &str, pos: usize) -> Vec<Suggestion> {
+    ///         // Ask the server and wait for the answer
+    ///         vec![Suggestion::new("checkout", Span::new(4, pos))]
+    ///     }
+    ///
+    ///     fn complete_async(&self, line: &str, pos: usize) -> AsyncSuggestions {
+    ///         let (sender, receiver) = mpsc::channel();
+    ///         let line = line.to_string();
+    ///         thread::spawn(move || sender.send(RemoteCompleter.complete(&line, pos)));
+    ///         AsyncSuggestions::Pending(receiver)
+    ///     }
+    /// }
+    /// ```
+    fn complete_async(&self, line: &str, pos: usize) -> AsyncSuggestions {
+        AsyncSuggestions::Ready(self.complete(line, pos))
+    }
+}
+
+/// A hook reordering suggestions before they are shown, e.g. by how often they are used
+pub type SuggestionSorter = Box<dyn Fn(&mut Vec<Suggestion>)>;
+
+/// The suggestions of [`Completer::complete_async()`], known right away or later
+#[derive(Debug)]
+pub enum AsyncSuggestions {
+    /// The suggestions are complete
+    Ready(Vec<Suggestion>),
+
+    /// The suggestions are sent to the channel once they are known
+    ///
+    /// Dropping the sender without sending is taken as no suggestions. Suggestions of a line
+    /// that was edited in the meantime are not shown, the receiver of the outdated line is
+    /// dropped when the completions of the edited one are asked for.
+    Pending(Receiver<Vec<Suggestion>>),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn buffer_with(content: &str, offset: usize) -> LineBuffer {
+        let mut line_buffer = LineBuffer::new();
+        line_buffer.insert_str(content);
+        line_buffer.set_insertion_point(offset);
+
+        line_buffer
+    }
+
+    #[test]
+    fn apply_replaces_span_and_moves_cursor() {
+        let mut line_buffer = buffer_with("git ch", 6);
+
+        Suggestion::new("checkout", Span::new(4, 6)).apply(&mut line_buffer);
+
+        assert_eq!(line_buffer, buffer_with("git checkout", 12));
+    }
+
+    #[test]
+    fn apply_appends_whitespace() {
+        let mut line_buffer = buffer_with("git ch", 6);
+
+        Suggestion::new("checkout", Span::new(4, 6))
+            .with_append_whitespace(true)
+            .apply(&mut line_buffer);
+
+        assert_eq!(line_buffer, buffer_with("git checkout ", 13));
+    }
+
+    #[test]
+    fn apply_places_cursor_inside_value() {
+        let mut line_buffer = buffer_with("echo $", 6);
+
+        Suggestion::new("\"${}\"", Span::new(5, 6))
+            .with_cursor_offset(3)
+            .with_append_whitespace(true)
+            .apply(&mut line_buffer);
+
+        assert_eq!(line_buffer, buffer_with("echo \"${}\" ", 8));
+    }
+
+    #[test]
+    fn apply_does_not_duplicate_following_whitespace() {
+        let mut line_buffer = buffer_with("git ch main", 6);
+
+        Suggestion::new("checkout", Span::new(4, 6))
+            .with_append_whitespace(true)
+            .apply(&mut line_buffer);
+
+        assert_eq!(line_buffer, buffer_with("git checkout main", 12));
+    }
 }
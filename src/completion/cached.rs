@@ -0,0 +1,368 @@
+use {
+    crate::{Completer, Span, Suggestion},
+    std::{
+        cell::RefCell,
+        collections::VecDeque,
+        time::{Duration, Instant},
+    },
+};
+
+/// Number of words whose completions are kept by default
+const DEFAULT_CAPACITY: usize = 16;
+
+/// A completer remembering the suggestions of a slow completer
+///
+/// Hinters and menus complete the line again on every repaint and keystroke, which is too
+/// slow for completers asking the file system or a language server. This wrapper remembers
+/// the suggestions by the text in front of the cursor, i.e. the line up to the word being
+/// completed and the word, the text between the whitespace in front of the cursor and the
+/// cursor. Editing the line behind the cursor reuses them. Typing on at the end of the word
+/// filters the suggestions of the shorter word, keeping those that still start with what was
+/// typed, so the wrapped completer is only asked again for a word it has not seen the start
+/// of. Empty words and words without suggestions are not filtered, many completers suggest
+/// nothing for them. Turn the filtering off with [`CachedCompleter::with_filtering()`] for
+/// completers whose suggestions do not start with the word, e.g. fuzzy ones.
+///
+/// Suggestions older than the time to live set with [`CachedCompleter::with_ttl()`] are asked
+/// for again, e.g. to notice new files. Without it they are kept until they are among the
+/// least recently used beyond the capacity or [`CachedCompleter::invalidate()`] is called.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{CachedCompleter, Completer, DefaultCompleter};
+/// use std::time::Duration;
+///
+/// let completer = DefaultCompleter::new(vec!["cargo".into(), "clear".into()]);
+/// let completer = CachedCompleter::new(Box::new(completer)).with_ttl(Duration::from_secs(5));
+///
+/// let suggestions = completer.complete("ca", 2);
+/// // Reused after editing the line behind the cursor
+/// assert_eq!(completer.complete("ca --help", 2), suggestions);
+/// ```
+pub struct CachedCompleter {
+    completer: Box<dyn Completer>,
+    ttl: Option<Duration>,
+    capacity: usize,
+    filtering: bool,
+    // Most recently used last
+    cache: RefCell<VecDeque<CacheEntry>>,
+}
+
+struct CacheEntry {
+    // The line in front of the word
+    context: String,
+    word: String,
+    // Start of the word and the cursor in the line the suggestions were made for
+    word_start: usize,
+    pos: usize,
+    created: Instant,
+    suggestions: Vec<Suggestion>,
+}
+
+impl CachedCompleter {
+    /// Remember the suggestions of `completer` until they are pushed out by newer ones
+    pub fn new(completer: Box<dyn Completer>) -> Self {
+        CachedCompleter {
+            completer,
+            ttl: None,
+            capacity: DEFAULT_CAPACITY,
+            filtering: true,
+            cache: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// A builder that sets how long suggestions are reused before they are asked for again
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// A builder that sets for how many words the suggestions are kept, 16 by default
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// A builder that sets whether typing on at the end of a word filters its remembered
+    /// suggestions instead of asking the wrapped completer, on by default
+    pub fn with_filtering(mut self, filtering: bool) -> Self {
+        self.filtering = filtering;
+        self
+    }
+
+    /// Forget all remembered suggestions, e.g. after a command changed the files to complete
+    pub fn invalidate(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        match self.ttl {
+            Some(ttl) => entry.created.elapsed() < ttl,
+            None => true,
+        }
+    }
+
+    /// The remembered suggestions of `entry` for `word` starting at `word_start` in `line`
+    ///
+    /// `None` if a suggestion cannot be moved to the word.
+    fn reuse(
+        entry: &CacheEntry,
+        line: &str,
+        word: &str,
+        word_start: usize,
+        pos: usize,
+    ) -> Option<Vec<Suggestion>> {
+        let mut suggestions = Vec::with_capacity(entry.suggestions.len());
+        for suggestion in &entry.suggestions {
+            let offset = suggestion.span.start.0.checked_sub(entry.word_start)?;
+            // The part of the word the value replaces has to be typed so far
+            if word != entry.word && !suggestion.value.starts_with(word.get(offset..)?) {
+                continue;
+            }
+            let start = word_start + offset;
+            let end = match suggestion.span.end.0.checked_sub(entry.pos) {
+                Some(behind_cursor) => (pos + behind_cursor).min(line.len()),
+                None => word_start + suggestion.span.end.0.checked_sub(entry.word_start)?,
+            };
+            if start > end || !line.is_char_boundary(start) || !line.is_char_boundary(end) {
+                return None;
+            }
+            suggestions.push(Suggestion {
+                span: Span::new(start, end),
+                ..suggestion.clone()
+            });
+        }
+
+        Some(suggestions)
+    }
+}
+
+/// Start of the word ending at the end of `prefix`, words are separated by whitespace
+fn word_start(prefix: &str) -> usize {
+    prefix
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(index, c)| index + c.len_utf8())
+}
+
+impl Completer for CachedCompleter {
+    fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let prefix = match line.get(..pos) {
+            Some(prefix) => prefix,
+            None => return self.completer.complete(line, pos),
+        };
+        let word_start = word_start(prefix);
+        let (context, word) = prefix.split_at(word_start);
+
+        let mut cache = self.cache.borrow_mut();
+        cache.retain(|entry| self.is_fresh(entry));
+        // The same word, otherwise the longest one the word continues
+        let found = cache
+            .iter()
+            .position(|entry| entry.context == context && entry.word == word)
+            .or_else(|| {
+                cache
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| {
+                        self.filtering
+                            && entry.context == context
+                            && !entry.word.is_empty()
+                            && !entry.suggestions.is_empty()
+                            && word.starts_with(&entry.word)
+                    })
+                    .max_by_key(|(_, entry)| entry.word.len())
+                    .map(|(index, _)| index)
+            });
+        if let Some(entry) = found.and_then(|index| cache.remove(index)) {
+            let suggestions = Self::reuse(&entry, line, word, word_start, pos);
+            cache.push_back(entry);
+            if let Some(suggestions) = suggestions {
+                return suggestions;
+            }
+        }
+
+        let suggestions = self.completer.complete(line, pos);
+        if let Some(index) = cache
+            .iter()
+            .position(|entry| entry.context == context && entry.word == word)
+        {
+            cache.remove(index);
+        }
+        if cache.len() >= self.capacity {
+            cache.pop_front();
+        }
+        cache.push_back(CacheEntry {
+            context: context.to_string(),
+            word: word.to_string(),
+            word_start,
+            pos,
+            created: Instant::now(),
+            suggestions: suggestions.clone(),
+        });
+
+        suggestions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::{cell::Cell, rc::Rc};
+
+    /// Suggests the subcommands starting with the word in front of the cursor, nothing for an
+    /// empty word, counting how often it is asked
+    struct CountingCompleter(Rc<Cell<usize>>);
+
+    impl Completer for CountingCompleter {
+        fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+            self.0.set(self.0.get() + 1);
+            let start = word_start(&line[..pos]);
+            if start == pos {
+                return vec![];
+            }
+            ["checkout", "cherry-pick", "clone"]
+                .iter()
+                .filter(|value| value.starts_with(&line[start..pos]))
+                .map(|value| Suggestion::new(*value, Span::new(start, pos)))
+                .collect()
+        }
+    }
+
+    fn cached() -> (CachedCompleter, Rc<Cell<usize>>) {
+        let calls = Rc::new(Cell::new(0));
+        let completer = CachedCompleter::new(Box::new(CountingCompleter(calls.clone())));
+        (completer, calls)
+    }
+
+    fn values(suggestions: &[Suggestion]) -> Vec<(&str, Span)> {
+        suggestions
+            .iter()
+            .map(|suggestion| (suggestion.value.as_str(), suggestion.span))
+            .collect()
+    }
+
+    #[test]
+    fn same_word_is_completed_once() {
+        let (completer, calls) = cached();
+
+        let first = completer.complete("git ch", 6);
+        assert_eq!(completer.complete("git ch", 6), first);
+        assert_eq!(calls.get(), 1);
+
+        completer.complete("git cl", 6);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(completer.complete("git ch", 6), first);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn edits_behind_the_cursor_keep_the_suggestions() {
+        let (completer, calls) = cached();
+        completer.complete("git ch", 6);
+
+        assert_eq!(
+            values(&completer.complete("git ch --quiet", 6)),
+            vec![
+                ("checkout", Span::new(4, 6)),
+                ("cherry-pick", Span::new(4, 6)),
+            ]
+        );
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn words_behind_different_text_are_completed_separately() {
+        let (completer, calls) = cached();
+        completer.complete("git ch", 6);
+
+        assert_eq!(
+            values(&completer.complete("cargo ch", 8)),
+            vec![
+                ("checkout", Span::new(6, 8)),
+                ("cherry-pick", Span::new(6, 8)),
+            ]
+        );
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn empty_words_and_suggestions_are_not_filtered() {
+        let (completer, calls) = cached();
+        completer.complete("", 0);
+
+        assert_eq!(
+            values(&completer.complete("c", 1)),
+            vec![
+                ("checkout", Span::new(0, 1)),
+                ("cherry-pick", Span::new(0, 1)),
+                ("clone", Span::new(0, 1))
+            ]
+        );
+        assert_eq!(calls.get(), 2);
+
+        completer.complete("git x", 5);
+        assert!(completer.complete("git xy", 6).is_empty());
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[test]
+    fn typing_on_filters_the_suggestions() {
+        let (completer, calls) = cached();
+        completer.complete("git c", 5);
+
+        assert_eq!(
+            values(&completer.complete("git che", 7)),
+            vec![
+                ("checkout", Span::new(4, 7)),
+                ("cherry-pick", Span::new(4, 7))
+            ]
+        );
+        assert_eq!(
+            values(&completer.complete("git cher", 8)),
+            vec![("cherry-pick", Span::new(4, 8))]
+        );
+        assert_eq!(calls.get(), 1);
+
+        let (completer, calls) = cached();
+        let completer = completer.with_filtering(false);
+        completer.complete("git c", 5);
+        completer.complete("git che", 7);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn least_recently_used_words_are_dropped() {
+        let (completer, calls) = cached();
+        let completer = completer.with_capacity(2).with_filtering(false);
+
+        completer.complete("a", 1);
+        completer.complete("b", 1);
+        completer.complete("a", 1);
+        completer.complete("c", 1);
+        assert_eq!(calls.get(), 3);
+
+        completer.complete("a", 1);
+        assert_eq!(calls.get(), 3);
+        completer.complete("b", 1);
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[test]
+    fn expired_and_invalidated_suggestions_are_asked_again() {
+        let (completer, calls) = cached();
+        let completer = completer.with_ttl(Duration::from_secs(0));
+
+        completer.complete("ls", 2);
+        completer.complete("ls", 2);
+        assert_eq!(calls.get(), 2);
+
+        let (completer, calls) = cached();
+        completer.complete("ls", 2);
+        completer.invalidate();
+        completer.complete("ls", 2);
+        assert_eq!(calls.get(), 2);
+    }
+}
@@ -1,12 +1,25 @@
-use crate::{core_editor::LineBuffer, Completer, CompletionActionHandler, DefaultCompleter};
+use {
+    crate::{
+        core_editor::LineBuffer, AsyncSuggestions, Completer, CompletionActionHandler,
+        DefaultCompleter, Suggestion, SuggestionSorter,
+    },
+    std::sync::mpsc::{Receiver, TryRecvError},
+};
 
 /// A simple handler that will do a cycle-based rotation through the options given by the Completer
 pub struct CircularCompletionHandler {
     completer: Box<dyn Completer>,
+    sorter: Option<SuggestionSorter>,
     initial_line: LineBuffer,
     index: usize,
+    // Completions of the initial line cycled through
+    completions: Vec<Suggestion>,
+    // Completions not known yet and the line they were asked for
+    pending: Option<(Receiver<Vec<Suggestion>>, LineBuffer)>,
 
     last_buffer: Option<LineBuffer>,
+    // Preview of the inserted suggestion
+    preview: Option<String>,
 }
 
 impl CircularCompletionHandler {
@@ -18,13 +31,13 @@ impl CircularCompletionHandler {
     ///
     /// # Example
     /// ```
-    /// use reedline::{CircularCompletionHandler, DefaultCompleter, Completer, Span};
+    /// use reedline::{CircularCompletionHandler, DefaultCompleter, Completer, Span, Suggestion};
     ///
     /// let mut completer = DefaultCompleter::default();
     /// completer.insert(vec!["test-hyphen","test_underscore"].iter().map(|s| s.to_string()).collect());
     /// assert_eq!(
     ///     completer.complete("te",2),
-    ///     vec![(Span { start: 0, end: 2 }, "test".into())]);
+    ///     vec![Suggestion::new("test", Span::new(0, 2))]);
     ///
     /// let mut completions = CircularCompletionHandler::default().with_completer(Box::new(completer));
     /// ```
@@ -32,14 +45,39 @@ impl CircularCompletionHandler {
         self.completer = completer;
         self
     }
+
+    /// A builder that sets a hook reordering the suggestions before they are cycled through
+    ///
+    /// Lets hosts rank the suggestions of any completer, e.g. by how often they are used in the
+    /// history. The suggestions are sorted once per line, before the first of them is inserted.
+    ///
+    /// # Example
+    /// ```
+    /// use reedline::{CircularCompletionHandler, DefaultCompleter};
+    ///
+    /// let completer = DefaultCompleter::new(vec!["checkout".into(), "cherry".into()]);
+    /// let completions = CircularCompletionHandler::default()
+    ///     .with_completer(Box::new(completer))
+    ///     .with_sorter(Box::new(|suggestions| {
+    ///         suggestions.sort_by_key(|suggestion| suggestion.value.len())
+    ///     }));
+    /// ```
+    pub fn with_sorter(mut self, sorter: SuggestionSorter) -> CircularCompletionHandler {
+        self.sorter = Some(sorter);
+        self
+    }
 }
 impl Default for CircularCompletionHandler {
     fn default() -> Self {
         CircularCompletionHandler {
             completer: Box::new(DefaultCompleter::default()),
+            sorter: None,
             initial_line: LineBuffer::new(),
             index: 0,
+            completions: vec![],
+            pending: None,
             last_buffer: None,
+            preview: None,
         }
     }
 }
@@ -48,6 +86,30 @@ impl CircularCompletionHandler {
     fn reset_index(&mut self) {
         self.index = 0;
     }
+
+    fn set_completions(&mut self, mut completions: Vec<Suggestion>) {
+        if let Some(sorter) = &self.sorter {
+            sorter(&mut completions);
+        }
+        self.completions = completions;
+    }
+
+    /// Insert the next completion, after the last one return to the initial line
+    fn cycle(&mut self, present_buffer: &mut LineBuffer) {
+        if !self.completions.is_empty() {
+            match self.index {
+                index if index < self.completions.len() => {
+                    self.index += 1;
+                    self.completions[index].apply(present_buffer);
+                    self.preview = self.completions[index].preview.clone();
+                }
+                _ => {
+                    self.reset_index();
+                }
+            }
+        }
+        self.last_buffer = Some(present_buffer.clone());
+    }
 }
 
 impl CompletionActionHandler for CircularCompletionHandler {
@@ -57,44 +119,71 @@ impl CompletionActionHandler for CircularCompletionHandler {
     //  in the line_buffer only the specified range of characters.
     // If internal index is 0 it means that is the first tab event pressed.
     // If internal index is greater than completions vector, we bring it back to 0.
+    // Completions arriving later are inserted by `poll`, unless the line was edited meanwhile.
     fn handle(&mut self, present_buffer: &mut LineBuffer) {
+        self.preview = None;
         if let Some(last_buffer) = &self.last_buffer {
             if last_buffer != present_buffer {
                 self.reset_index();
             }
         }
+        match &self.pending {
+            // Still waiting for the completions of this line
+            Some((_, requested)) if requested == present_buffer => return,
+            Some(_) => self.pending = None,
+            None => {}
+        }
 
         // NOTE: This is required to cycle through the tabs for what is presently present in the
         // buffer. Without this `repetitive_calls_to_handle_works` will not work
         if self.index == 0 {
             self.initial_line = present_buffer.clone();
+            match self
+                .completer
+                .complete_async(present_buffer.get_buffer(), present_buffer.offset())
+            {
+                AsyncSuggestions::Ready(completions) => self.set_completions(completions),
+                AsyncSuggestions::Pending(receiver) => {
+                    self.pending = Some((receiver, present_buffer.clone()));
+                    return;
+                }
+            }
         } else {
             *present_buffer = self.initial_line.clone();
         }
 
-        let completions = self
-            .completer
-            .complete(present_buffer.get_buffer(), present_buffer.offset());
-
-        if !completions.is_empty() {
-            match self.index {
-                index if index < completions.len() => {
-                    self.index += 1;
-                    let span = completions[index].0;
+        self.cycle(present_buffer);
+    }
 
-                    let mut offset = present_buffer.offset();
-                    offset += completions[index].1.len() - (span.end - span.start);
+    fn preview(&self) -> Option<String> {
+        self.preview.clone()
+    }
 
-                    // TODO improve the support for multiline replace
-                    present_buffer.replace(span.start..span.end, &completions[index].1);
-                    present_buffer.set_insertion_point(offset);
-                }
-                _ => {
-                    self.reset_index();
+    fn poll(&mut self, present_buffer: &mut LineBuffer) -> bool {
+        let received = match &self.pending {
+            Some((receiver, _)) => receiver.try_recv(),
+            None => return false,
+        };
+        match received {
+            Ok(completions) => {
+                if let Some((_, requested)) = self.pending.take() {
+                    if requested == *present_buffer {
+                        self.set_completions(completions);
+                        self.cycle(present_buffer);
+                    }
                 }
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => {
+                self.pending = None;
+                true
             }
         }
-        self.last_buffer = Some(present_buffer.clone());
+    }
+
+    fn is_loading(&self) -> bool {
+        self.pending.is_some()
     }
 }
 
@@ -141,6 +230,19 @@ mod test {
         assert_eq!(buf, buffer_with("te"));
     }
 
+    #[test]
+    fn appends_whitespace_after_whole_words() {
+        let mut completer = DefaultCompleter::default().with_append_whitespace(true);
+        completer.insert(vec!["login".to_string(), "logout".to_string()]);
+        let mut tab = CircularCompletionHandler::default().with_completer(Box::new(completer));
+        let mut buf = buffer_with("lo");
+        tab.handle(&mut buf);
+
+        assert_eq!(buf, buffer_with("login "));
+        tab.handle(&mut buf);
+        assert_eq!(buf, buffer_with("logout "));
+    }
+
     #[test]
     fn auto_resets_on_new_query() {
         let mut tab = get_tab_handler_with(vec!["login", "logout", "exit"]);
@@ -170,4 +272,95 @@ mod test {
         tab.handle(&mut buf);
         assert_eq!(buf, buffer_with("that is my test that"));
     }
+
+    #[test]
+    fn sorter_decides_the_order() {
+        let mut tab = get_tab_handler_with(vec!["login", "logout", "log"]).with_sorter(Box::new(
+            |suggestions: &mut Vec<Suggestion>| suggestions.sort_by_key(|s| s.value.len()),
+        ));
+        let mut buf = buffer_with("lo");
+
+        tab.handle(&mut buf);
+        assert_eq!(buf, buffer_with("log"));
+        tab.handle(&mut buf);
+        assert_eq!(buf, buffer_with("login"));
+    }
+
+    #[test]
+    fn completing_inside_word_replaces_its_rest() {
+        let mut tab = get_tab_handler_with(vec!["checkout", "cherry"]);
+        let mut buf = buffer_with("git chkout main");
+        buf.set_insertion_point(6);
+
+        tab.handle(&mut buf);
+
+        let mut expected_buffer = buffer_with("git checkout main");
+        expected_buffer.set_insertion_point(12);
+        assert_eq!(buf, expected_buffer);
+    }
+
+    #[test]
+    fn preview_of_inserted_suggestion_is_kept() {
+        struct PreviewCompleter;
+
+        impl Completer for PreviewCompleter {
+            fn complete(&self, _line: &str, pos: usize) -> Vec<crate::Suggestion> {
+                vec![
+                    crate::Suggestion::new("ll", crate::Span::new(0, pos)).with_preview("ls -l"),
+                    crate::Suggestion::new("la", crate::Span::new(0, pos)),
+                ]
+            }
+        }
+
+        let mut tab =
+            CircularCompletionHandler::default().with_completer(Box::new(PreviewCompleter));
+        let mut buf = buffer_with("l");
+
+        tab.handle(&mut buf);
+        assert_eq!(tab.preview(), Some("ls -l".to_string()));
+        tab.handle(&mut buf);
+        assert_eq!(tab.preview(), None);
+    }
+
+    /// Answers through a channel, as if the suggestions were computed on another thread
+    struct LaterCompleter;
+
+    impl Completer for LaterCompleter {
+        fn complete(&self, _line: &str, pos: usize) -> Vec<Suggestion> {
+            vec![Suggestion::new("login", crate::Span::new(0, pos))]
+        }
+
+        fn complete_async(&self, line: &str, pos: usize) -> AsyncSuggestions {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            sender.send(self.complete(line, pos)).unwrap();
+            AsyncSuggestions::Pending(receiver)
+        }
+    }
+
+    #[test]
+    fn completions_arriving_later_are_inserted_by_poll() {
+        let mut tab = CircularCompletionHandler::default().with_completer(Box::new(LaterCompleter));
+        let mut buf = buffer_with("lo");
+
+        tab.handle(&mut buf);
+        assert_eq!(buf, buffer_with("lo"));
+        assert!(tab.is_loading());
+
+        assert!(tab.poll(&mut buf));
+        assert_eq!(buf, buffer_with("login"));
+        assert!(!tab.is_loading());
+        assert!(!tab.poll(&mut buf));
+    }
+
+    #[test]
+    fn completions_of_an_edited_line_are_dropped() {
+        let mut tab = CircularCompletionHandler::default().with_completer(Box::new(LaterCompleter));
+        let mut buf = buffer_with("lo");
+        tab.handle(&mut buf);
+
+        let mut edited = buffer_with("lx");
+        assert!(tab.poll(&mut edited));
+        assert_eq!(edited, buffer_with("lx"));
+        assert!(!tab.is_loading());
+    }
 }
@@ -0,0 +1,110 @@
+use crate::{Completer, Suggestion};
+
+/// A completer asking several others and merging their suggestions
+///
+/// Each completer is added with a priority. The suggestions of completers with a higher
+/// priority come first, those of completers with the same priority in the order the completers
+/// were added, and each completer keeps the order of its own suggestions. A suggestion
+/// replacing the same span with the same value as an earlier one is dropped.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{CombinedCompleter, Completer, DefaultCompleter, HistoryCompleter};
+///
+/// let commands = DefaultCompleter::new(vec!["git".into(), "grep".into()]);
+/// let history = HistoryCompleter::new(vec!["git status".into()]);
+/// let completer = CombinedCompleter::default()
+///     .with_completer(Box::new(commands), 1)
+///     .with_completer(Box::new(history), 2);
+///
+/// let values: Vec<String> = completer
+///     .complete("g", 1)
+///     .into_iter()
+///     .map(|suggestion| suggestion.value)
+///     .collect();
+/// assert_eq!(values, vec!["it status", "git", "grep"]);
+/// ```
+#[derive(Default)]
+pub struct CombinedCompleter {
+    completers: Vec<(u32, Box<dyn Completer>)>,
+}
+
+impl CombinedCompleter {
+    /// A builder that adds `completer` with a `priority`, higher priorities are listed first
+    pub fn with_completer(mut self, completer: Box<dyn Completer>, priority: u32) -> Self {
+        self.completers.push((priority, completer));
+        self
+    }
+}
+
+impl Completer for CombinedCompleter {
+    fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let mut completers: Vec<&(u32, Box<dyn Completer>)> = self.completers.iter().collect();
+        // The sort is stable, completers of the same priority keep their order
+        completers.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+
+        let mut suggestions: Vec<Suggestion> = vec![];
+        for (_, completer) in completers {
+            for suggestion in completer.complete(line, pos) {
+                let duplicate = suggestions.iter().any(|existing| {
+                    existing.span == suggestion.span && existing.value == suggestion.value
+                });
+                if !duplicate {
+                    suggestions.push(suggestion);
+                }
+            }
+        }
+
+        suggestions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Span;
+    use pretty_assertions::assert_eq;
+
+    struct FixedCompleter(Vec<&'static str>);
+
+    impl Completer for FixedCompleter {
+        fn complete(&self, _line: &str, pos: usize) -> Vec<Suggestion> {
+            self.0
+                .iter()
+                .map(|value| Suggestion::new(*value, Span::new(0, pos)))
+                .collect()
+        }
+    }
+
+    fn values(completer: &CombinedCompleter) -> Vec<String> {
+        completer
+            .complete("c", 1)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect()
+    }
+
+    #[test]
+    fn suggestions_are_ordered_by_priority() {
+        let completer = CombinedCompleter::default()
+            .with_completer(Box::new(FixedCompleter(vec!["cd", "cp"])), 0)
+            .with_completer(Box::new(FixedCompleter(vec!["cargo"])), 5)
+            .with_completer(Box::new(FixedCompleter(vec!["cat"])), 0);
+
+        assert_eq!(values(&completer), vec!["cargo", "cd", "cp", "cat"]);
+    }
+
+    #[test]
+    fn duplicates_keep_the_higher_priority() {
+        let completer = CombinedCompleter::default()
+            .with_completer(Box::new(FixedCompleter(vec!["cd", "clear"])), 1)
+            .with_completer(Box::new(FixedCompleter(vec!["clear", "cargo"])), 2);
+
+        assert_eq!(values(&completer), vec!["clear", "cargo", "cd"]);
+    }
+
+    #[test]
+    fn empty_combination_completes_nothing() {
+        assert_eq!(values(&CombinedCompleter::default()), Vec::<String>::new());
+    }
+}
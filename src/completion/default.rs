@@ -1,47 +1,65 @@
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     rc::Rc,
     str::Chars,
 };
 
-use crate::{Completer, Span};
+use crate::{Completer, Span, Suggestion};
 
 /// A history-specific completer with a focus on completing whole lines of history
+///
+/// Suggests the entries starting with the text in front of the cursor, the most recent first
+/// and each distinct completion once, so hinters and menus can pick or rank them. Entries
+/// identical to the line are passed over, as completing them would not add anything.
 pub struct HistoryCompleter {
     history: Vec<String>,
+    skip_identical: bool,
 }
 
 impl HistoryCompleter {
+    /// Complete lines from the given history entries, most recent last
     pub fn new(history: Vec<String>) -> Self {
-        Self { history }
+        Self {
+            history,
+            skip_identical: true,
+        }
+    }
+
+    /// A builder that sets whether entries identical to the line are passed over, on by default
+    ///
+    /// Without it a match equal to the line is suggested as well, completing nothing.
+    pub fn with_skip_identical(mut self, skip_identical: bool) -> Self {
+        self.skip_identical = skip_identical;
+        self
     }
 }
 
 impl Completer for HistoryCompleter {
-    fn complete(&self, line: &str, pos: usize) -> Vec<(Span, String)> {
-        let mut completions = vec![];
-
+    fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
         if line.is_empty() {
             return vec![];
         }
 
-        for hist in &self.history {
-            if hist.starts_with(&line[0..pos]) {
-                completions.push((
-                    Span {
-                        start: pos,
-                        end: line.len(),
-                    },
-                    hist[pos..].to_string(),
-                ));
+        // A position inside a character or past the line has no text in front of it to match
+        let prefix = match line.get(..pos) {
+            Some(prefix) => prefix,
+            None => return vec![],
+        };
+
+        let mut suggestions: Vec<Suggestion> = vec![];
+        let mut seen = HashSet::new();
+        for hist in self.history.iter().rev() {
+            if (self.skip_identical && hist == line) || !hist.starts_with(prefix) {
+                continue;
+            }
+            // Starting with the prefix, the entry has a character boundary at `pos` as well
+            let value = &hist[pos..];
+            if seen.insert(value) {
+                suggestions.push(Suggestion::new(value, Span::new(pos, line.len())));
             }
         }
 
-        if let Some(last) = completions.last() {
-            vec![last.clone()]
-        } else {
-            completions
-        }
+        suggestions
     }
 }
 
@@ -69,6 +87,7 @@ impl Completer for HistoryCompleter {
 pub struct DefaultCompleter {
     root: CompletionNode,
     min_word_len: usize,
+    append_whitespace: bool,
 }
 
 impl Default for DefaultCompleter {
@@ -77,6 +96,7 @@ impl Default for DefaultCompleter {
         Self {
             root: CompletionNode::new(inclusions),
             min_word_len: 2,
+            append_whitespace: false,
         }
     }
 }
@@ -91,29 +111,41 @@ impl Completer for DefaultCompleter {
     ///
     /// # Example
     /// ```
-    /// use reedline::{DefaultCompleter,Completer,Span};
+    /// use reedline::{DefaultCompleter,Completer,Span,Suggestion};
     ///
     /// let mut completions = DefaultCompleter::default();
     /// completions.insert(vec!["batman","robin","batmobile","batcave","robber"].iter().map(|s| s.to_string()).collect());
     /// assert_eq!(
     ///     completions.complete("bat",3),
     ///     vec![
-    ///         (Span { start: 0, end: 3 }, "batcave".into()),
-    ///         (Span { start: 0, end: 3 }, "batman".into()),
-    ///         (Span { start: 0, end: 3 }, "batmobile".into()),
+    ///         Suggestion::new("batcave", Span::new(0, 3)),
+    ///         Suggestion::new("batman", Span::new(0, 3)),
+    ///         Suggestion::new("batmobile", Span::new(0, 3)),
     ///     ]);
     ///
     /// assert_eq!(
     ///     completions.complete("to the bat",10),
     ///     vec![
-    ///         (Span { start: 7, end: 10 }, "batcave".into()),
-    ///         (Span { start: 7, end: 10 }, "batman".into()),
-    ///         (Span { start: 7, end: 10 }, "batmobile".into()),
+    ///         Suggestion::new("batcave", Span::new(7, 10)),
+    ///         Suggestion::new("batman", Span::new(7, 10)),
+    ///         Suggestion::new("batmobile", Span::new(7, 10)),
+    ///     ]);
+    ///
+    /// // The span covers the whole word under the cursor
+    /// assert_eq!(
+    ///     completions.complete("robxx now",3),
+    ///     vec![
+    ///         Suggestion::new("robber", Span::new(0, 5)),
+    ///         Suggestion::new("robin", Span::new(0, 5)),
     ///     ]);
     /// ```
-    fn complete(&self, line: &str, pos: usize) -> Vec<(Span, String)> {
+    fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
         let mut span_line_whitespaces = 0;
         let mut completions = vec![];
+        // The rest of the word under the cursor is replaced as well
+        let word_end = line[pos..]
+            .find(' ')
+            .map_or(line.len(), |space| pos + space);
         if !line.is_empty() {
             let mut splitted = line[0..pos].split(' ').rev();
             let mut span_line: String = String::new();
@@ -134,16 +166,16 @@ impl Completer for DefaultCompleter {
                             extensions
                                 .iter()
                                 .map(|ext| {
-                                    (
+                                    Suggestion::new(
+                                        format!("{}{}", span_line, ext),
                                         Span::new(
                                             pos - span_line.len() - span_line_whitespaces,
-                                            pos,
+                                            word_end,
                                         ),
-                                        format!("{}{}", span_line, ext),
                                     )
+                                    .with_append_whitespace(self.append_whitespace)
                                 })
-                                .filter(|t| t.1.len() > (t.0.end - t.0.start))
-                                .collect::<Vec<(Span, String)>>(),
+                                .collect::<Vec<Suggestion>>(),
                         );
                     }
                 }
@@ -205,21 +237,21 @@ impl DefaultCompleter {
     ///
     /// # Example
     /// ```
-    /// use reedline::{DefaultCompleter,Completer,Span};
+    /// use reedline::{DefaultCompleter,Completer,Span,Suggestion};
     ///
     /// let mut completions = DefaultCompleter::default();
     /// completions.insert(vec!["test-hyphen","test_underscore"].iter().map(|s| s.to_string()).collect());
     /// assert_eq!(
     ///     completions.complete("te",2),
-    ///     vec![(Span { start: 0, end: 2 }, "test".into())]);
+    ///     vec![Suggestion::new("test", Span::new(0, 2))]);
     ///
     /// let mut completions = DefaultCompleter::with_inclusions(&['-', '_']);
     /// completions.insert(vec!["test-hyphen","test_underscore"].iter().map(|s| s.to_string()).collect());
     /// assert_eq!(
     ///     completions.complete("te",2),
     ///     vec![
-    ///         (Span { start: 0, end: 2 }, "test-hyphen".into()),
-    ///         (Span { start: 0, end: 2 }, "test_underscore".into()),
+    ///         Suggestion::new("test-hyphen", Span::new(0, 2)),
+    ///         Suggestion::new("test_underscore", Span::new(0, 2)),
     ///     ]);
     /// ```
     pub fn with_inclusions(incl: &[char]) -> Self {
@@ -304,6 +336,22 @@ impl DefaultCompleter {
         self.min_word_len = len;
         self
     }
+
+    /// A builder to configure if a space is added after an inserted completion
+    ///
+    /// Completions are always whole words, so typing can continue with the next word.
+    /// # Example
+    /// ```
+    /// use reedline::{DefaultCompleter,Completer};
+    ///
+    /// let mut completions = DefaultCompleter::default().with_append_whitespace(true);
+    /// completions.insert(vec!["batman"].iter().map(|s| s.to_string()).collect());
+    /// assert!(completions.complete("bat",3)[0].append_whitespace);
+    /// ```
+    pub fn with_append_whitespace(mut self, append_whitespace: bool) -> DefaultCompleter {
+        self.append_whitespace = append_whitespace;
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -387,3 +435,90 @@ impl CompletionNode {
         completions
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn history() -> Vec<String> {
+        vec![
+            "привет мир".to_string(),
+            "echo 🦀 crab".to_string(),
+            "echo é".to_string(),
+            "echo 🦀".to_string(),
+            "привет".to_string(),
+        ]
+    }
+
+    #[test]
+    fn history_completer_returns_every_match_most_recent_first() {
+        let completer = HistoryCompleter::new(vec![
+            "git status".to_string(),
+            "git commit".to_string(),
+            "ls".to_string(),
+            "git status".to_string(),
+        ]);
+
+        let values: Vec<String> = completer
+            .complete("git ", 4)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(values, vec!["status", "commit"]);
+    }
+
+    #[test]
+    fn history_completer_lists_identical_entries_unless_skipped() {
+        let history = vec!["ls -la".to_string(), "ls".to_string()];
+
+        assert_eq!(
+            HistoryCompleter::new(history.clone()).complete("ls", 2),
+            vec![Suggestion::new(" -la", Span::new(2, 2))]
+        );
+        assert_eq!(
+            HistoryCompleter::new(history)
+                .with_skip_identical(false)
+                .complete("ls", 2),
+            vec![
+                Suggestion::new("", Span::new(2, 2)),
+                Suggestion::new(" -la", Span::new(2, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn history_completer_completes_multibyte_prefixes() {
+        let completer = HistoryCompleter::new(history());
+
+        assert_eq!(
+            completer.complete("при", "при".len()),
+            vec![
+                Suggestion::new("вет", Span::new(6, 6)),
+                Suggestion::new("вет мир", Span::new(6, 6)),
+            ]
+        );
+        assert_eq!(
+            completer.complete("echo 🦀", "echo 🦀".len()),
+            vec![Suggestion::new(" crab", Span::new(9, 9))]
+        );
+    }
+
+    #[test]
+    fn history_completer_accepts_any_position() {
+        let completer = HistoryCompleter::new(history());
+        let lines = ["привет", "echo 🦀 cr", "é", "🦀", "echo"];
+
+        for line in lines.iter() {
+            for pos in 0..=line.len() + 1 {
+                for suggestion in completer.complete(line, pos) {
+                    // Every suggestion continues a history entry from the text before `pos`
+                    assert!(line.is_char_boundary(pos));
+                    let completed = format!("{}{}", &line[..pos], suggestion.value);
+                    assert!(history().contains(&completed), "{:?} at {}", line, pos);
+                    assert_eq!(suggestion.span, Span::new(pos, line.len()));
+                }
+            }
+        }
+    }
+}
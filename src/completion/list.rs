@@ -1,9 +1,18 @@
-use crate::{core_editor::LineBuffer, Completer, CompletionActionHandler, DefaultCompleter, Span};
+use {
+    crate::{
+        core_editor::LineBuffer, AsyncSuggestions, Completer, CompletionActionHandler,
+        DefaultCompleter, Suggestion, SuggestionSorter,
+    },
+    std::sync::mpsc::{Receiver, TryRecvError},
+};
 
 /// A simple handler that will do a cycle-based rotation through the options given by the Completer
 pub struct ListCompletionHandler {
     completer: Box<dyn Completer>,
+    sorter: Option<SuggestionSorter>,
     complete: bool,
+    // Completions not known yet and the line they were asked for
+    pending: Option<(Receiver<Vec<Suggestion>>, LineBuffer)>,
 }
 
 impl ListCompletionHandler {
@@ -15,13 +24,13 @@ impl ListCompletionHandler {
     ///
     /// # Example
     /// ```
-    /// use reedline::{ListCompletionHandler, DefaultCompleter, Completer, Span};
+    /// use reedline::{ListCompletionHandler, DefaultCompleter, Completer, Span, Suggestion};
     ///
     /// let mut completer = DefaultCompleter::default();
     /// completer.insert(vec!["test-hyphen","test_underscore"].iter().map(|s| s.to_string()).collect());
     /// assert_eq!(
     ///     completer.complete("te",2),
-    ///     vec![(Span { start: 0, end: 2 }, "test".into())]);
+    ///     vec![Suggestion::new("test", Span::new(0, 2))]);
     ///
     /// let mut completions = ListCompletionHandler::default().with_completer(Box::new(completer));
     /// ```
@@ -29,12 +38,20 @@ impl ListCompletionHandler {
         self.completer = completer;
         self
     }
+
+    /// A builder that sets a hook reordering the suggestions before they are listed
+    pub fn with_sorter(mut self, sorter: SuggestionSorter) -> ListCompletionHandler {
+        self.sorter = Some(sorter);
+        self
+    }
 }
 impl Default for ListCompletionHandler {
     fn default() -> Self {
         ListCompletionHandler {
             completer: Box::new(DefaultCompleter::default()),
+            sorter: None,
             complete: true,
+            pending: None,
         }
     }
 }
@@ -61,47 +78,90 @@ impl CompletionActionHandler for ListCompletionHandler {
         //     *present_buffer = self.initial_line.clone();
         // }
 
-        let completions = self
+        match &self.pending {
+            // Still waiting for the completions of this line
+            Some((_, requested)) if requested == present_buffer => return,
+            Some(_) => self.pending = None,
+            None => {}
+        }
+
+        match self
             .completer
-            .complete(present_buffer.get_buffer(), present_buffer.offset());
+            .complete_async(present_buffer.get_buffer(), present_buffer.offset())
+        {
+            AsyncSuggestions::Ready(completions) => self.complete_with(completions, present_buffer),
+            AsyncSuggestions::Pending(receiver) => {
+                self.pending = Some((receiver, present_buffer.clone()))
+            }
+        }
+    }
+
+    fn poll(&mut self, present_buffer: &mut LineBuffer) -> bool {
+        let received = match &self.pending {
+            Some((receiver, _)) => receiver.try_recv(),
+            None => return false,
+        };
+        match received {
+            Ok(completions) => {
+                if let Some((_, requested)) = self.pending.take() {
+                    if requested == *present_buffer {
+                        self.complete_with(completions, present_buffer);
+                    }
+                }
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => {
+                self.pending = None;
+                true
+            }
+        }
+    }
+
+    fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+impl ListCompletionHandler {
+    /// Insert the only completion or the common prefix of several and list them
+    fn complete_with(&mut self, mut completions: Vec<Suggestion>, present_buffer: &mut LineBuffer) {
+        if let Some(sorter) = &self.sorter {
+            sorter(&mut completions);
+        }
 
         if completions.is_empty() {
             // do nothing
         } else if completions.len() == 1 {
-            let span = completions[0].0;
-
-            let mut offset = present_buffer.offset();
-            offset += completions[0].1.len() - (span.end - span.start);
-
-            // TODO improve the support for multiline replace
-            present_buffer.replace(span.start..span.end, &completions[0].1);
-            present_buffer.set_insertion_point(offset);
+            completions[0].apply(present_buffer);
             self.complete = true;
         } else {
-            let prefix = calculate_prefix(&completions);
-
-            let span = completions[0].0;
-            let mut offset = present_buffer.offset();
-            offset += prefix.len() - (span.end - span.start);
-
-            present_buffer.replace(span.start..span.end, &prefix);
-            present_buffer.set_insertion_point(offset);
+            // Only the common prefix is inserted, it is not a complete value yet
+            Suggestion::new(calculate_prefix(&completions), completions[0].span)
+                .apply(present_buffer);
 
             print!("\r\n");
             for completion in completions {
                 // TODO: make this list pretty
-                print!("{}\r\n", completion.1);
+                let value = match completion.style {
+                    Some(style) => style.paint(&completion.value).to_string(),
+                    None => completion.value,
+                };
+                match completion.description {
+                    Some(description) => print!("{}  {}\r\n", value, description),
+                    None => print!("{}\r\n", value),
+                }
             }
             print!("\r\n");
         }
     }
 }
 
-fn calculate_prefix(inputs: &[(Span, String)]) -> String {
+fn calculate_prefix(inputs: &[Suggestion]) -> String {
     let mut iter = inputs.iter();
 
     if let Some(first) = iter.next() {
-        let prefix = first.1.clone();
+        let prefix = first.value.clone();
         let prefix_bytes = prefix.as_bytes();
 
         let mut longest_match = prefix.len();
@@ -109,7 +169,8 @@ fn calculate_prefix(inputs: &[(Span, String)]) -> String {
         for i in iter {
             longest_match = std::cmp::min(
                 longest_match,
-                i.1.as_bytes()
+                i.value
+                    .as_bytes()
                     .iter()
                     .zip(prefix_bytes)
                     .take_while(|(x, y)| x == y)
@@ -191,4 +252,33 @@ mod test {
         tab.handle(&mut buf);
         assert_eq!(buf, buffer_with("that is my test that"));
     }
+
+    /// Answers through a channel, as if the suggestions were computed on another thread
+    struct LaterCompleter;
+
+    impl Completer for LaterCompleter {
+        fn complete(&self, _line: &str, pos: usize) -> Vec<Suggestion> {
+            vec![Suggestion::new("login", crate::Span::new(0, pos))]
+        }
+
+        fn complete_async(&self, line: &str, pos: usize) -> AsyncSuggestions {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            sender.send(self.complete(line, pos)).unwrap();
+            AsyncSuggestions::Pending(receiver)
+        }
+    }
+
+    #[test]
+    fn completions_arriving_later_are_inserted_by_poll() {
+        let mut tab = ListCompletionHandler::default().with_completer(Box::new(LaterCompleter));
+        let mut buf = buffer_with("lo");
+
+        tab.handle(&mut buf);
+        assert_eq!(buf, buffer_with("lo"));
+        assert!(tab.is_loading());
+
+        assert!(tab.poll(&mut buf));
+        assert_eq!(buf, buffer_with("login"));
+        assert!(!tab.is_loading());
+    }
 }
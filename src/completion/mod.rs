@@ -1,9 +1,15 @@
 mod base;
+mod cached;
 mod circular;
+mod combined;
 mod default;
 mod list;
 
-pub use base::{Completer, CompletionActionHandler, Span};
+pub use base::{
+    AsyncSuggestions, Completer, CompletionActionHandler, Span, Suggestion, SuggestionSorter,
+};
+pub use cached::CachedCompleter;
 pub use circular::CircularCompletionHandler;
+pub use combined::CombinedCompleter;
 pub use default::{DefaultCompleter, HistoryCompleter};
 pub use list::ListCompletionHandler;
@@ -0,0 +1,79 @@
+use {crate::KeyCombination, std::fmt};
+
+/// How serious a [`ConfigurationIssue`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueSeverity {
+    /// Part of the configuration probably does not work as intended
+    Warning,
+    /// The configuration is inconsistent, [`Reedline::read_line()`](crate::Reedline::read_line)
+    /// refuses to start
+    Error,
+}
+
+/// An inconsistency between the parts configured with the builders of a
+/// [`Reedline`](crate::Reedline) engine
+///
+/// Found by [`Reedline::validate_configuration()`](crate::Reedline::validate_configuration).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigurationIssue {
+    /// A key opens a menu that is not registered with
+    /// [`Reedline::with_menu()`](crate::Reedline::with_menu)
+    UnknownMenu {
+        /// The bound key combination
+        key: KeyCombination,
+        /// Name of the missing menu
+        menu: String,
+    },
+    /// Several menus are registered under the same name, only the first one can be opened
+    DuplicateMenu(String),
+    /// A key inserts a [`QuickInserts`](crate::QuickInserts) entry that is not set (yet)
+    UnknownQuickInsert {
+        /// The bound key combination
+        key: KeyCombination,
+        /// Key of the missing entry
+        entry: String,
+    },
+    /// A key is bound to an event without any commands, pressing it does nothing
+    EmptyBinding(KeyCombination),
+}
+
+impl ConfigurationIssue {
+    /// How serious the issue is
+    pub fn severity(&self) -> IssueSeverity {
+        match self {
+            ConfigurationIssue::UnknownMenu { .. } | ConfigurationIssue::DuplicateMenu(_) => {
+                IssueSeverity::Error
+            }
+            ConfigurationIssue::UnknownQuickInsert { .. } | ConfigurationIssue::EmptyBinding(_) => {
+                IssueSeverity::Warning
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConfigurationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigurationIssue::UnknownMenu { key, menu } => {
+                write!(
+                    f,
+                    "{} opens the menu '{}', which is not registered",
+                    key, menu
+                )
+            }
+            ConfigurationIssue::DuplicateMenu(menu) => {
+                write!(f, "more than one menu is registered as '{}'", menu)
+            }
+            ConfigurationIssue::UnknownQuickInsert { key, entry } => {
+                write!(
+                    f,
+                    "{} inserts the quick insert entry '{}', which is not set",
+                    key, entry
+                )
+            }
+            ConfigurationIssue::EmptyBinding(key) => {
+                write!(f, "{} is bound to an event without commands", key)
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::core_editor::get_default_clipboard;
+use std::ops::Range;
 
-use super::{Clipboard, ClipboardMode, LineBuffer};
+use super::{Clipboard, ClipboardMode, LineBuffer, WordBoundary};
 
 pub struct Editor {
     line_buffer: LineBuffer,
@@ -29,14 +30,22 @@ impl Editor {
     }
 
     pub fn set_line_buffer(&mut self, line_buffer: LineBuffer) {
+        let word_boundary = self.line_buffer.word_boundary();
         self.line_buffer = line_buffer;
+        self.line_buffer.set_word_boundary(word_boundary);
+    }
+
+    pub fn set_word_boundary(&mut self, word_boundary: WordBoundary) {
+        self.line_buffer.set_word_boundary(word_boundary);
     }
 
     pub fn move_to_start(&mut self) {
+        self.line_buffer.record_jump();
         self.line_buffer.move_to_start();
     }
 
     pub fn move_to_end(&mut self) {
+        self.line_buffer.record_jump();
         self.line_buffer.move_to_end();
     }
 
@@ -64,6 +73,14 @@ impl Editor {
         self.line_buffer.move_word_right();
     }
 
+    pub fn move_big_word_left(&mut self) {
+        self.line_buffer.move_big_word_left();
+    }
+
+    pub fn move_big_word_right(&mut self) {
+        self.line_buffer.move_big_word_right();
+    }
+
     pub fn move_line_up(&mut self) {
         self.line_buffer.move_line_up();
     }
@@ -76,6 +93,11 @@ impl Editor {
         self.line_buffer.insert_char(c);
     }
 
+    /// Insert `string` at the cursor in one splice instead of character by character
+    pub fn insert_str(&mut self, string: &str) {
+        self.line_buffer.insert_str(string);
+    }
+
     pub fn backspace(&mut self) {
         self.line_buffer.delete_left_grapheme();
     }
@@ -116,6 +138,51 @@ impl Editor {
         self.line_buffer.swap_graphemes();
     }
 
+    pub fn set_mark(&mut self, name: char) {
+        self.line_buffer.set_mark(name);
+    }
+
+    pub fn set_anchor(&mut self) {
+        self.line_buffer.set_anchor();
+    }
+
+    pub fn swap_cursor_and_anchor(&mut self) {
+        self.line_buffer.swap_cursor_and_anchor();
+    }
+
+    /// Cut the selection, the word left of the insertion point without an anchor
+    pub fn cut_selection(&mut self) {
+        match self.line_buffer.selection_range() {
+            Some(range) => self.cut_range(range),
+            None => self.cut_word_left(),
+        }
+    }
+
+    pub fn copy_selection(&mut self) {
+        if let Some(range) = self.line_buffer.selection_range() {
+            self.copy_range(range, ClipboardMode::Normal);
+        }
+    }
+
+    pub fn move_to_mark(&mut self, name: char) {
+        if self.line_buffer.mark(name).is_some() {
+            self.line_buffer.record_jump();
+            self.line_buffer.move_to_mark(name);
+        }
+    }
+
+    pub fn record_jump(&mut self) {
+        self.line_buffer.record_jump();
+    }
+
+    pub fn jump_back(&mut self) {
+        self.line_buffer.jump_back();
+    }
+
+    pub fn jump_forward(&mut self) {
+        self.line_buffer.jump_forward();
+    }
+
     pub fn set_insertion_point(&mut self, pos: usize) {
         self.line_buffer.set_insertion_point(pos);
     }
@@ -151,10 +218,6 @@ impl Editor {
         self.line_buffer.offset()
     }
 
-    pub fn line(&self) -> usize {
-        self.line_buffer.line()
-    }
-
     pub fn num_lines(&self) -> usize {
         self.line_buffer.num_lines()
     }
@@ -208,7 +271,12 @@ impl Editor {
     }
 
     fn undo_internal(&mut self) -> Option<()> {
+        // Marks, jumps and the word boundary are not part of the undo history
+        let tracked = self.line_buffer.take_tracked_positions();
+        let word_boundary = self.line_buffer.word_boundary();
         self.line_buffer = self.edits.get(self.get_index_undo())?.clone();
+        self.line_buffer.restore_tracked_positions(tracked);
+        self.line_buffer.set_word_boundary(word_boundary);
 
         if self.index_undo <= self.edits.len() {
             self.index_undo = self.index_undo.checked_add(1)?;
@@ -285,8 +353,17 @@ impl Editor {
     }
 
     pub fn cut_word_left(&mut self) {
-        let insertion_offset = self.line_buffer.offset();
         let left_index = self.line_buffer.word_left_index();
+        self.cut_left_to(left_index);
+    }
+
+    pub fn cut_big_word_left(&mut self) {
+        let left_index = self.line_buffer.big_word_left_index();
+        self.cut_left_to(left_index);
+    }
+
+    fn cut_left_to(&mut self, left_index: usize) {
+        let insertion_offset = self.line_buffer.offset();
         if left_index < insertion_offset {
             let cut_range = left_index..insertion_offset;
             self.cut_buffer.set(
@@ -299,8 +376,17 @@ impl Editor {
     }
 
     pub fn cut_word_right(&mut self) {
-        let insertion_offset = self.line_buffer.offset();
         let right_index = self.line_buffer.word_right_index();
+        self.cut_right_to(right_index);
+    }
+
+    pub fn cut_big_word_right(&mut self) {
+        let right_index = self.line_buffer.big_word_right_index();
+        self.cut_right_to(right_index);
+    }
+
+    fn cut_right_to(&mut self, right_index: usize) {
+        let insertion_offset = self.line_buffer.offset();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
             self.cut_buffer.set(
@@ -311,6 +397,85 @@ impl Editor {
         }
     }
 
+    pub fn cut_inner_word(&mut self) {
+        let range = self.line_buffer.inner_word_range();
+        self.cut_range(range);
+    }
+
+    pub fn cut_around_word(&mut self) {
+        let range = self.line_buffer.around_word_range();
+        self.cut_range(range);
+    }
+
+    pub fn cut_inside_pair(&mut self, left: char, right: char) {
+        if let Some(range) = self.line_buffer.inside_pair_range(left, right) {
+            self.cut_range(range);
+        }
+    }
+
+    pub fn cut_around_pair(&mut self, left: char, right: char) {
+        if let Some(range) = self.line_buffer.around_pair_range(left, right) {
+            self.cut_range(range);
+        }
+    }
+
+    pub fn copy_inner_word(&mut self) {
+        let range = self.line_buffer.inner_word_range();
+        self.copy_range(range, ClipboardMode::Normal);
+    }
+
+    pub fn copy_around_word(&mut self) {
+        let range = self.line_buffer.around_word_range();
+        self.copy_range(range, ClipboardMode::Normal);
+    }
+
+    pub fn copy_inside_pair(&mut self, left: char, right: char) {
+        if let Some(range) = self.line_buffer.inside_pair_range(left, right) {
+            self.copy_range(range, ClipboardMode::Normal);
+        }
+    }
+
+    pub fn copy_around_pair(&mut self, left: char, right: char) {
+        if let Some(range) = self.line_buffer.around_pair_range(left, right) {
+            self.copy_range(range, ClipboardMode::Normal);
+        }
+    }
+
+    pub fn copy_word_right(&mut self) {
+        let range = self.line_buffer.offset()..self.line_buffer.word_right_index();
+        self.copy_range(range, ClipboardMode::Normal);
+    }
+
+    pub fn copy_to_line_end(&mut self) {
+        let range = self.line_buffer.offset()..self.line_buffer.find_current_line_end();
+        self.copy_range(range, ClipboardMode::Normal);
+    }
+
+    pub fn copy_current_line(&mut self) {
+        let range = self.line_buffer.current_line_range();
+        self.copy_range(range, ClipboardMode::Lines);
+    }
+
+    /// Move `range` into the cut buffer and continue at its start
+    fn cut_range(&mut self, range: Range<usize>) {
+        if !range.is_empty() {
+            self.cut_buffer.set(
+                &self.line_buffer.get_buffer()[range.clone()],
+                ClipboardMode::Normal,
+            );
+            self.clear_range(range.clone());
+            self.line_buffer.set_insertion_point(range.start);
+        }
+    }
+
+    /// Put a copy of `range` into the cut buffer, leaving the buffer unchanged
+    fn copy_range(&mut self, range: Range<usize>, mode: ClipboardMode) {
+        if !range.is_empty() {
+            self.cut_buffer
+                .set(&self.line_buffer.get_buffer()[range], mode);
+        }
+    }
+
     pub fn insert_cut_buffer_before(&mut self) {
         match self.cut_buffer.get() {
             (content, ClipboardMode::Normal) => {
@@ -430,4 +595,63 @@ mod test {
             editor.edits
         );
     }
+
+    #[test]
+    fn undo_keeps_marks() {
+        let mut editor = Editor::default();
+        editor.line_buffer().insert_str("ls");
+        editor.remember_undo_state(true);
+        editor.line_buffer().insert_str(" -la");
+        editor.remember_undo_state(true);
+        editor.set_insertion_point(1);
+        editor.set_mark('a');
+        editor.set_insertion_point(5);
+        editor.set_mark('b');
+
+        editor.undo();
+
+        assert_eq!(editor.get_buffer(), "ls");
+        assert_eq!(editor.line_buffer().mark('a'), Some(1));
+        assert_eq!(editor.line_buffer().mark('b'), None);
+    }
+
+    #[test]
+    fn inserted_string_is_undone_at_once() {
+        let mut editor = Editor::default();
+        editor.insert_str("ls");
+        editor.remember_undo_state(true);
+        editor.insert_str(" -la\n/tmp");
+        editor.remember_undo_state(true);
+        assert_eq!(editor.offset(), 11);
+
+        editor.undo();
+        assert_eq!(editor.get_buffer(), "ls");
+    }
+
+    #[test]
+    fn cut_inside_pair_keeps_delimiters() {
+        let mut editor = Editor::default();
+        editor.line_buffer().insert_str("echo \"hello world\"");
+        editor.set_insertion_point(8);
+
+        editor.cut_inside_pair('"', '"');
+        assert_eq!(editor.get_buffer(), "echo \"\"");
+        assert_eq!(editor.offset(), 6);
+
+        editor.insert_cut_buffer_before();
+        assert_eq!(editor.get_buffer(), "echo \"hello world\"");
+    }
+
+    #[test]
+    fn copy_leaves_buffer_unchanged() {
+        let mut editor = Editor::default();
+        editor.line_buffer().insert_str("ls -la");
+        editor.set_insertion_point(1);
+
+        editor.copy_inner_word();
+        editor.move_to_end();
+        editor.insert_cut_buffer_before();
+
+        assert_eq!(editor.get_buffer(), "ls -lals");
+    }
 }
@@ -1,5 +1,10 @@
 use {
-    std::{convert::From, ops::Range},
+    super::text_storage::TextStorage,
+    std::{
+        collections::HashMap,
+        convert::From,
+        ops::{Bound, Range, RangeBounds},
+    },
     unicode_segmentation::UnicodeSegmentation,
 };
 
@@ -21,11 +26,59 @@ impl Default for InsertionPoint {
     }
 }
 
+/// Maximal number of positions remembered in the jump list
+const MAX_JUMPS: usize = 100;
+
+/// Positions in the buffer that move along with the text they were set on
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub(crate) struct TrackedPositions {
+    marks: HashMap<char, usize>,
+    // Positions jumped away from, oldest first
+    jumps: Vec<usize>,
+    // Position in `jumps` while moving through it, `jumps.len()` outside
+    jump_index: usize,
+    // The other end of the selection, the insertion point being the first one
+    anchor: Option<usize>,
+}
+
+impl TrackedPositions {
+    fn offsets_mut(&mut self) -> impl Iterator<Item = &mut usize> {
+        self.marks
+            .values_mut()
+            .chain(self.jumps.iter_mut())
+            .chain(self.anchor.iter_mut())
+    }
+}
+
+/// Which characters word-wise movements and edits treat as part of a word
+///
+/// Used by [`LineBuffer::word_left_index()`], [`LineBuffer::word_right_index()`] and
+/// [`LineBuffer::current_word_range()`], so by `MoveWordLeft`, `CutWordRight`,
+/// `UppercaseWord` and the other commands acting on words.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum WordBoundary {
+    /// Words as found by the Unicode word segmentation, `/usr/bin` has the words `usr` and `bin`
+    #[default]
+    Unicode,
+    /// Words are separated by whitespace only, `/usr/bin` is a single word
+    Whitespace,
+    /// Words are runs of alphanumeric characters and `_`, `snake_case` is a single word
+    AlphanumericUnderscore,
+    /// Words are the tokens of a shell command line
+    ///
+    /// Tokens are separated by whitespace outside of quotes, so `'my file.txt'` is a single
+    /// word. A backslash escapes the character following it.
+    ShellToken,
+}
+
 /// In memory representation of the entered line(s) to facilitate cursor based editing.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct LineBuffer {
-    lines: String,
+    lines: TextStorage,
     insertion_point: InsertionPoint,
+    // Marks and jump list
+    tracked: TrackedPositions,
+    word_boundary: WordBoundary,
 }
 
 impl Default for LineBuffer {
@@ -46,14 +99,26 @@ impl LineBuffer {
     /// Create a line buffer instance
     pub fn new() -> LineBuffer {
         LineBuffer {
-            lines: String::new(),
+            lines: TextStorage::default(),
             insertion_point: InsertionPoint::new(),
+            tracked: TrackedPositions::default(),
+            word_boundary: WordBoundary::default(),
         }
     }
 
+    /// Which characters word-wise movements and edits treat as part of a word
+    pub fn word_boundary(&self) -> WordBoundary {
+        self.word_boundary
+    }
+
+    /// Set which characters word-wise movements and edits treat as part of a word
+    pub fn set_word_boundary(&mut self, word_boundary: WordBoundary) {
+        self.word_boundary = word_boundary;
+    }
+
     /// Replaces the content between [`start`..`end`] with `text`
     pub fn replace(&mut self, range: Range<usize>, text: &str) {
-        self.lines.replace_range(range, text);
+        self.replace_range(range, text);
     }
 
     /// Check to see if the line buffer is empty
@@ -77,21 +142,34 @@ impl LineBuffer {
     }
 
     /// Output the current line in the multiline buffer
+    ///
+    /// With the `rope_buffer` feature a large buffer is flattened into one string on the first
+    /// call after an edit.
     pub fn get_buffer(&self) -> &str {
-        &self.lines
+        self.lines.as_str()
     }
 
     /// Set to a single line of `buffer` and reset the `InsertionPoint` cursor to the end
     pub fn set_buffer(&mut self, buffer: String) {
         let offset = buffer.len();
-        self.lines = buffer;
+        self.lines = TextStorage::new(buffer);
         self.insertion_point = InsertionPoint { offset };
+        // Marks refer to the replaced text, the jump list keeps the places where the cursor was
+        self.tracked.marks.clear();
+        self.tracked.anchor = None;
+        for jump in self.tracked.jumps.iter_mut() {
+            *jump = (*jump).min(offset);
+            while !self.lines.is_char_boundary(*jump) {
+                *jump -= 1;
+            }
+        }
+        self.tracked.jump_index = self.tracked.jumps.len();
     }
 
     /// Calculates the current the user is on
     pub fn line(&self) -> usize {
         let offset = self.insertion_point.offset;
-        let count = self.lines[0..offset].lines().count();
+        let count = self.lines.slice(0..offset).lines().count();
         if count == 0 {
             0
         } else {
@@ -101,18 +179,16 @@ impl LineBuffer {
 
     /// Counts the number of lines in the buffer
     pub fn num_lines(&self) -> usize {
-        let count = self.lines.split('\n').count();
-
-        if count == 0 {
-            1
-        } else {
-            count
-        }
+        self.lines
+            .chunks()
+            .map(|chunk| chunk.matches('\n').count())
+            .sum::<usize>()
+            + 1
     }
 
     /// Checks to see if the buffer ends with a given character
     pub fn ends_with(&self, c: char) -> bool {
-        self.lines.ends_with(c)
+        self.lines.char_before(self.lines.len()) == Some(c)
     }
 
     /// Reset the insertion point to the start of the buffer
@@ -122,8 +198,9 @@ impl LineBuffer {
 
     /// Move the cursor before the first character of the line
     pub fn move_to_line_start(&mut self) {
-        self.insertion_point.offset = self.lines[..self.insertion_point.offset]
-            .rfind('\n')
+        self.insertion_point.offset = self
+            .lines
+            .rfind_char(self.insertion_point.offset, '\n')
             .map_or(0, |offset| offset + 1);
         // str is guaranteed to be utf8, thus \n is safe to assume 1 byte long
     }
@@ -139,11 +216,10 @@ impl LineBuffer {
     /// - end of buffer (`len()`)
     /// - `\n` or `\r\n` (on the first byte)
     pub fn find_current_line_end(&self) -> usize {
-        self.lines[self.insertion_point.offset..]
-            .find('\n')
-            .map_or(self.lines.len(), |i| {
-                let absolute_index = i + self.insertion_point.offset;
-                if self.lines.as_bytes()[absolute_index] == b'\r' {
+        self.lines
+            .find_char(self.insertion_point.offset, '\n')
+            .map_or(self.lines.len(), |absolute_index| {
+                if self.lines.char_after(absolute_index) == Some('\r') {
                     absolute_index - 1
                 } else {
                     absolute_index
@@ -160,40 +236,92 @@ impl LineBuffer {
     }
 
     /// Cursor position *behind* the next unicode grapheme to the right
+    ///
+    /// Graphemes are extended grapheme clusters found with the whole buffer as context, so a
+    /// cursor inside of a cluster, e.g. between a letter and its combining mark, moves to its end.
     pub fn grapheme_right_index(&self) -> usize {
-        self.lines[self.insertion_point.offset..]
-            .grapheme_indices(true)
-            .nth(1)
-            .map(|(i, _)| self.insertion_point.offset + i)
-            .unwrap_or_else(|| self.lines.len())
+        self.lines
+            .next_grapheme_boundary(self.insertion_point.offset)
     }
 
     /// Cursor position *in front of* the next unicode grapheme to the left
+    ///
+    /// A cursor inside of an extended grapheme cluster moves to its start.
     pub fn grapheme_left_index(&self) -> usize {
-        self.lines[..self.insertion_point.offset]
-            .grapheme_indices(true)
-            .last()
-            .map(|(i, _)| i)
-            .unwrap_or(0)
+        self.lines
+            .prev_grapheme_boundary(self.insertion_point.offset)
     }
 
     /// Cursor position *behind* the next word to the right
     pub fn word_right_index(&self) -> usize {
-        self.lines[self.insertion_point.offset..]
-            .split_word_bound_indices()
-            .find(|(_, word)| !is_word_boundary(word))
-            .map(|(i, word)| self.insertion_point.offset + i + word.len())
-            .unwrap_or_else(|| self.lines.len())
+        if self.word_boundary != WordBoundary::Unicode {
+            return self.word_end_after(self.insertion_point.offset, self.word_boundary);
+        }
+        // Words never span a line break, so the lines can be searched one after another
+        let mut start = self.insertion_point.offset;
+        while start < self.lines.len() {
+            let end = self
+                .lines
+                .find_char(start, '\n')
+                .map_or(self.lines.len(), |offset| offset + 1);
+            let word_end = self
+                .lines
+                .slice(start..end)
+                .split_word_bound_indices()
+                .find(|(_, word)| !is_word_boundary(word))
+                .map(|(i, word)| start + i + word.len());
+            if let Some(word_end) = word_end {
+                return word_end;
+            }
+            start = end;
+        }
+        self.lines.len()
     }
 
     /// Cursor position *in front of* the next word to the left
     pub fn word_left_index(&self) -> usize {
-        self.lines[..self.insertion_point.offset]
-            .split_word_bound_indices()
-            .filter(|(_, word)| !is_word_boundary(word))
-            .last()
-            .map(|(i, _)| i)
-            .unwrap_or(0)
+        if self.word_boundary != WordBoundary::Unicode {
+            return self.word_start_before(self.insertion_point.offset, self.word_boundary);
+        }
+        self.word_start_in_lines_before(self.insertion_point.offset)
+    }
+
+    /// Start of the last Unicode word starting before `offset`, searching line by line
+    fn word_start_in_lines_before(&self, offset: usize) -> usize {
+        let mut end = offset;
+        while end > 0 {
+            // The line break in front of `end` belongs to the line searched
+            let line_end = match self.lines.char_before(end) {
+                Some('\n') => end - 1,
+                _ => end,
+            };
+            let start = self
+                .lines
+                .rfind_char(line_end, '\n')
+                .map_or(0, |offset| offset + 1);
+            let word_start = self
+                .lines
+                .slice(start..end)
+                .split_word_bound_indices()
+                .rev()
+                .find(|(_, word)| !is_word_boundary(word))
+                .map(|(i, _)| start + i);
+            if let Some(word_start) = word_start {
+                return word_start;
+            }
+            end = start;
+        }
+        0
+    }
+
+    /// Cursor position *behind* the next whitespace delimited word (vi WORD) to the right
+    pub fn big_word_right_index(&self) -> usize {
+        self.word_end_after(self.insertion_point.offset, WordBoundary::Whitespace)
+    }
+
+    /// Cursor position *in front of* the next whitespace delimited word (vi WORD) to the left
+    pub fn big_word_left_index(&self) -> usize {
+        self.word_start_before(self.insertion_point.offset, WordBoundary::Whitespace)
     }
 
     /// Move cursor position *behind* the next unicode grapheme to the right
@@ -218,11 +346,28 @@ impl LineBuffer {
         self.insertion_point.offset
     }
 
-    ///Insert a single character at the insertion point and move right
+    /// Move cursor position *in front of* the next whitespace delimited word to the left
+    pub fn move_big_word_left(&mut self) -> usize {
+        self.insertion_point.offset = self.big_word_left_index();
+        self.insertion_point.offset
+    }
+
+    /// Move cursor position *behind* the next whitespace delimited word to the right
+    pub fn move_big_word_right(&mut self) -> usize {
+        self.insertion_point.offset = self.big_word_right_index();
+        self.insertion_point.offset
+    }
+
+    /// Insert a single character at the insertion point and move behind it
+    ///
+    /// The cursor stays right behind the character even if it joins a grapheme with the text
+    /// following it, e.g. a letter typed in front of a combining mark.
     pub fn insert_char(&mut self, c: char) {
         let pos = self.insertion_point();
-        self.lines.insert(pos.offset, c);
-        self.move_right();
+        self.shift_positions(pos.offset..pos.offset, c.len_utf8());
+        self.lines
+            .replace_range(pos.offset..pos.offset, c.encode_utf8(&mut [0; 4]));
+        self.insertion_point.offset = pos.offset + c.len_utf8();
     }
 
     /// Insert `&str` at the `idx` position in the current line.
@@ -230,20 +375,24 @@ impl LineBuffer {
     /// TODO: Check unicode validation
     pub fn insert_str(&mut self, string: &str) {
         let pos = self.insertion_point();
-        self.lines.insert_str(pos.offset, string);
+        self.shift_positions(pos.offset..pos.offset, string.len());
+        self.lines.replace_range(pos.offset..pos.offset, string);
         self.insertion_point.offset = pos.offset + string.len();
     }
 
     /// Empty buffer and reset cursor
     pub fn clear(&mut self) {
-        self.lines = String::new();
+        self.lines = TextStorage::default();
         self.insertion_point = InsertionPoint::new();
+        self.tracked = TrackedPositions::default();
     }
 
     /// Clear everything beginning at the cursor to the right/end.
     /// Keeps the cursor at the end.
     pub fn clear_to_end(&mut self) {
-        self.lines.truncate(self.insertion_point.offset);
+        let range = self.insertion_point.offset..self.lines.len();
+        self.shift_positions(range.clone(), 0);
+        self.lines.replace_range(range, "");
     }
 
     /// Clear everything beginning at the cursor up to the end of the line.
@@ -274,31 +423,295 @@ impl LineBuffer {
     /// Safety: Does not change the insertion point/offset and is thus not unicode safe!
     pub(crate) fn replace_range<R>(&mut self, range: R, replace_with: &str)
     where
-        R: std::ops::RangeBounds<usize>,
+        R: RangeBounds<usize>,
     {
-        self.lines.replace_range(range, replace_with);
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.lines.len(),
+        };
+        self.shift_positions(start..end, replace_with.len());
+        self.lines.replace_range(start..end, replace_with);
+    }
+
+    /// Adjust the marks and jumps to `range` being replaced by `inserted_len` bytes
+    ///
+    /// Positions behind the range move with the text, those inside a removed range end up at its start.
+    /// Text inserted at a position is inserted in front of it.
+    fn shift_positions(&mut self, range: Range<usize>, inserted_len: usize) {
+        for offset in self.tracked.offsets_mut() {
+            if *offset >= range.end {
+                *offset = *offset - range.len() + inserted_len;
+            } else if *offset > range.start {
+                *offset = range.start;
+            }
+        }
+    }
+
+    /// Remember the insertion point as the mark `name`, replacing an existing one
+    pub fn set_mark(&mut self, name: char) {
+        self.tracked.marks.insert(name, self.insertion_point.offset);
+    }
+
+    /// Offset of the mark `name` if it is set
+    pub fn mark(&self, name: char) -> Option<usize> {
+        self.tracked.marks.get(&name).copied()
+    }
+
+    /// Move the insertion point to the mark `name`, does nothing if it is not set
+    pub fn move_to_mark(&mut self, name: char) {
+        if let Some(offset) = self.mark(name) {
+            self.insertion_point.offset = offset;
+        }
+    }
+
+    /// Anchor the selection at the insertion point (the Emacs mark)
+    ///
+    /// The selection reaches from the anchor to the insertion point, wherever it moves.
+    pub fn set_anchor(&mut self) {
+        self.tracked.anchor = Some(self.insertion_point.offset);
+    }
+
+    /// Offset of the selection anchor if it is set
+    pub fn anchor(&self) -> Option<usize> {
+        self.tracked.anchor
+    }
+
+    /// Remove the selection anchor
+    pub fn clear_anchor(&mut self) {
+        self.tracked.anchor = None;
+    }
+
+    /// Range between the selection anchor and the insertion point, `None` without an anchor
+    pub fn selection_range(&self) -> Option<Range<usize>> {
+        let offset = self.insertion_point.offset;
+        self.tracked
+            .anchor
+            .map(|anchor| anchor.min(offset)..anchor.max(offset))
+    }
+
+    /// Move the insertion point to the selection anchor and anchor the selection where it was
+    ///
+    /// Does nothing without an anchor.
+    pub fn swap_cursor_and_anchor(&mut self) {
+        if let Some(anchor) = self.tracked.anchor {
+            self.tracked.anchor = Some(self.insertion_point.offset);
+            self.insertion_point.offset = anchor;
+        }
+    }
+
+    /// Add the insertion point to the jump list before moving the cursor far away
+    ///
+    /// Like in Vim the position is moved to the end of the list if it is already present.
+    pub fn record_jump(&mut self) {
+        let offset = self.insertion_point.offset;
+        let jumps = &mut self.tracked.jumps;
+        jumps.retain(|jump| *jump != offset);
+        jumps.push(offset);
+        if jumps.len() > MAX_JUMPS {
+            jumps.remove(0);
+        }
+        self.tracked.jump_index = jumps.len();
+    }
+
+    /// Move the insertion point to the previous position in the jump list
+    pub fn jump_back(&mut self) {
+        if self.tracked.jump_index == self.tracked.jumps.len() {
+            // Remember where the cursor left the end of the list to be able to come back
+            self.record_jump();
+            self.tracked.jump_index -= 1;
+        }
+        if self.tracked.jump_index > 0 {
+            self.tracked.jump_index -= 1;
+            self.insertion_point.offset = self.tracked.jumps[self.tracked.jump_index];
+        }
+    }
+
+    /// Move the insertion point to the next position in the jump list
+    pub fn jump_forward(&mut self) {
+        if self.tracked.jump_index + 1 < self.tracked.jumps.len() {
+            self.tracked.jump_index += 1;
+            self.insertion_point.offset = self.tracked.jumps[self.tracked.jump_index];
+        }
+    }
+
+    /// Take the marks and jumps out of the buffer, e.g. to carry them over to a different state of it
+    pub(crate) fn take_tracked_positions(&mut self) -> TrackedPositions {
+        std::mem::take(&mut self.tracked)
+    }
+
+    /// Set the marks and jumps, dropping those that are not a valid position in the buffer
+    pub(crate) fn restore_tracked_positions(&mut self, mut tracked: TrackedPositions) {
+        let lines = &self.lines;
+        tracked
+            .marks
+            .retain(|_, offset| lines.is_char_boundary(*offset));
+        tracked
+            .jumps
+            .retain(|offset| lines.is_char_boundary(*offset));
+        tracked.jump_index = tracked.jump_index.min(tracked.jumps.len());
+        tracked.anchor = tracked
+            .anchor
+            .filter(|offset| lines.is_char_boundary(*offset));
+        self.tracked = tracked;
     }
 
     /// Checks to see if the current edit position is pointing to whitespace
     pub fn on_whitespace(&self) -> bool {
-        self.lines[self.insertion_point.offset..]
-            .chars()
-            .next()
+        self.lines
+            .char_after(self.insertion_point.offset)
             .map(char::is_whitespace)
             .unwrap_or(false)
     }
 
+    /// Start of the last word starting before `offset` under a non-Unicode [`WordBoundary`]
+    fn word_start_before(&self, offset: usize, word_boundary: WordBoundary) -> usize {
+        word_ranges(self.lines.as_str(), word_boundary)
+            .into_iter()
+            .rev()
+            .find(|word| word.start < offset)
+            .map_or(0, |word| word.start)
+    }
+
+    /// End of the first word ending after `offset` under a non-Unicode [`WordBoundary`]
+    fn word_end_after(&self, offset: usize, word_boundary: WordBoundary) -> usize {
+        word_ranges(self.lines.as_str(), word_boundary)
+            .into_iter()
+            .find(|word| word.end > offset)
+            .map_or(self.lines.len(), |word| word.end)
+    }
+
     /// Gets the range of the word the current edit position is pointing to
     pub fn current_word_range(&self) -> Range<usize> {
         let right_index = self.word_right_index();
-        let left_index = self.lines[..right_index]
-            .split_word_bound_indices()
-            .filter(|(_, word)| !is_word_boundary(word))
-            .last()
-            .map(|(i, _)| i)
-            .unwrap_or(0);
+        if self.word_boundary != WordBoundary::Unicode {
+            return self.word_start_before(right_index, self.word_boundary)..right_index;
+        }
+        self.word_start_in_lines_before(right_index)..right_index
+    }
 
-        left_index..right_index
+    /// Range of the word or the run of blanks at the insertion point (vi `iw`)
+    pub fn inner_word_range(&self) -> Range<usize> {
+        let offset = self.insertion_point.offset;
+        let segments = self.word_segments();
+        segments
+            .iter()
+            .find(|segment| segment.contains(&offset))
+            // At the end of the buffer the last word counts
+            .or_else(|| segments.iter().find(|segment| segment.end == offset))
+            .cloned()
+            .unwrap_or(offset..offset)
+    }
+
+    /// Range of the word at the insertion point with the blanks after it, or before it if
+    /// there are none after it (vi `aw`)
+    ///
+    /// On blanks the range covers them and the following word instead.
+    pub fn around_word_range(&self) -> Range<usize> {
+        let inner = self.inner_word_range();
+        let segments = self.word_segments();
+        let is_blank = |segment: &Range<usize>| {
+            self.lines
+                .slice(segment.clone())
+                .chars()
+                .all(|c| c.is_whitespace() && c != '\n' && c != '\r')
+        };
+        let next = segments.iter().find(|segment| segment.start == inner.end);
+        let previous = segments.iter().find(|segment| segment.end == inner.start);
+
+        match (is_blank(&inner), next, previous) {
+            _ if inner.is_empty() => inner,
+            (true, Some(next), _) if !is_blank(next) => inner.start..next.end,
+            (false, Some(next), _) if is_blank(next) => inner.start..next.end,
+            (false, _, Some(previous)) if is_blank(previous) => previous.start..inner.end,
+            _ => inner,
+        }
+    }
+
+    /// Range between the delimiters enclosing the insertion point, e.g. `(` and `)` (vi `i(`)
+    ///
+    /// Brackets may be nested and span lines. Quotes, where `left` and `right` are the same,
+    /// pair up from the start of the current line. Before the first pair of quotes the next
+    /// pair on the line counts.
+    pub fn inside_pair_range(&self, left: char, right: char) -> Option<Range<usize>> {
+        let offset = self.insertion_point.offset;
+
+        let (start, end) = if left == right {
+            let line = self.current_line_range();
+            let quotes: Vec<usize> = self
+                .lines
+                .slice(line.clone())
+                .match_indices(left)
+                .map(|(index, _)| line.start + index)
+                .collect();
+            let mut pairs = quotes.chunks_exact(2).map(|pair| (pair[0], pair[1]));
+            pairs
+                .clone()
+                .find(|(start, end)| (*start..=*end).contains(&offset))
+                .or_else(|| pairs.find(|(start, _)| *start > offset))?
+        } else {
+            let start = if self.lines.char_after(offset) == Some(left) {
+                offset
+            } else {
+                let mut depth = 0;
+                self.lines.as_str()[..offset]
+                    .char_indices()
+                    .rev()
+                    .find(|(_, c)| {
+                        if *c == right {
+                            depth += 1;
+                        } else if *c == left {
+                            if depth == 0 {
+                                return true;
+                            }
+                            depth -= 1;
+                        }
+                        false
+                    })?
+                    .0
+            };
+
+            let content_start = start + left.len_utf8();
+            let mut depth = 0;
+            let end = self.lines.as_str()[content_start..]
+                .char_indices()
+                .find(|(_, c)| {
+                    if *c == left {
+                        depth += 1;
+                    } else if *c == right {
+                        if depth == 0 {
+                            return true;
+                        }
+                        depth -= 1;
+                    }
+                    false
+                })?
+                .0
+                + content_start;
+            (start, end)
+        };
+
+        Some(start + left.len_utf8()..end)
+    }
+
+    /// Range of [`LineBuffer::inside_pair_range()`] including the delimiters (vi `a(`)
+    pub fn around_pair_range(&self, left: char, right: char) -> Option<Range<usize>> {
+        self.inside_pair_range(left, right)
+            .map(|inside| inside.start - left.len_utf8()..inside.end + right.len_utf8())
+    }
+
+    /// Ranges of the words, blanks and other characters in the buffer, in order
+    fn word_segments(&self) -> Vec<Range<usize>> {
+        self.lines
+            .as_str()
+            .split_word_bound_indices()
+            .map(|(start, segment)| start..start + segment.len())
+            .collect()
     }
 
     /// Range over the current line
@@ -307,12 +720,14 @@ impl LineBuffer {
     /// extending beyond the potential carriage return and line feed characters
     /// terminating the line
     pub fn current_line_range(&self) -> Range<usize> {
-        let left_index = self.lines[..self.insertion_point.offset]
-            .rfind('\n')
+        let left_index = self
+            .lines
+            .rfind_char(self.insertion_point.offset, '\n')
             .map_or(0, |offset| offset + 1);
-        let right_index = self.lines[self.insertion_point.offset..]
-            .find('\n')
-            .map_or(self.lines.len(), |i| i + self.insertion_point.offset + 1);
+        let right_index = self
+            .lines
+            .find_char(self.insertion_point.offset, '\n')
+            .map_or(self.lines.len(), |offset| offset + 1);
 
         left_index..right_index
     }
@@ -320,7 +735,7 @@ impl LineBuffer {
     /// Uppercases the current word
     pub fn uppercase_word(&mut self) {
         let change_range = self.current_word_range();
-        let uppercased = self.get_buffer()[change_range.clone()].to_uppercase();
+        let uppercased = self.lines.slice(change_range.clone()).to_uppercase();
         self.replace_range(change_range, &uppercased);
         self.move_word_right();
     }
@@ -328,14 +743,23 @@ impl LineBuffer {
     /// Lowercases the current word
     pub fn lowercase_word(&mut self) {
         let change_range = self.current_word_range();
-        let uppercased = self.get_buffer()[change_range.clone()].to_lowercase();
+        let uppercased = self.lines.slice(change_range.clone()).to_lowercase();
         self.replace_range(change_range, &uppercased);
         self.move_word_right();
     }
 
     /// Counts the number of words in the buffer
     pub fn word_count(&self) -> usize {
-        self.lines.trim().split_whitespace().count()
+        // Counted over the chunks, the undo history compares the count after every edit
+        let mut count = 0;
+        let mut in_word = false;
+        for c in self.lines.chunks().flat_map(str::chars) {
+            if !in_word && !c.is_whitespace() {
+                count += 1;
+            }
+            in_word = !c.is_whitespace();
+        }
+        count
     }
 
     /// Capitallize the character at insertion point and move the insertion point right one
@@ -350,7 +774,7 @@ impl LineBuffer {
 
         if right_index > insertion_offset {
             let change_range = insertion_offset..right_index;
-            let uppercased = self.get_buffer()[change_range.clone()].to_uppercase();
+            let uppercased = self.lines.slice(change_range.clone()).to_uppercase();
             self.replace_range(change_range, &uppercased);
             self.move_right();
         }
@@ -396,9 +820,8 @@ impl LineBuffer {
 
         if word_1_range != word_2_range {
             self.move_word_left();
-            let insertion_line = self.get_buffer();
-            let word_1 = insertion_line[word_1_range.clone()].to_string();
-            let word_2 = insertion_line[word_2_range.clone()].to_string();
+            let word_1 = self.lines.slice(word_1_range.clone()).into_owned();
+            let word_2 = self.lines.slice(word_2_range.clone()).into_owned();
             self.replace_range(word_2_range, &word_1);
             self.replace_range(word_1_range, &word_2);
         }
@@ -410,7 +833,7 @@ impl LineBuffer {
 
         if initial_offset == 0 {
             self.move_right();
-        } else if initial_offset == self.get_buffer().len() {
+        } else if initial_offset == self.lines.len() {
             self.move_left();
         }
 
@@ -419,8 +842,14 @@ impl LineBuffer {
         let grapheme_2_end = self.grapheme_right_index();
 
         if grapheme_1_start < updated_offset && grapheme_2_end > updated_offset {
-            let grapheme_1 = self.get_buffer()[grapheme_1_start..updated_offset].to_string();
-            let grapheme_2 = self.get_buffer()[updated_offset..grapheme_2_end].to_string();
+            let grapheme_1 = self
+                .lines
+                .slice(grapheme_1_start..updated_offset)
+                .into_owned();
+            let grapheme_2 = self
+                .lines
+                .slice(updated_offset..grapheme_2_end)
+                .into_owned();
             self.replace_range(updated_offset..grapheme_2_end, &grapheme_1);
             self.replace_range(grapheme_1_start..updated_offset, &grapheme_2);
             self.insertion_point.offset = grapheme_2_end;
@@ -433,86 +862,68 @@ impl LineBuffer {
     pub fn move_line_up(&mut self) {
         if !self.is_cursor_at_first_line() {
             // If we're not at the top, move up a line in the multiline buffer
-            let mut position = self.offset();
-            let mut num_of_move_lefts = 0;
-            let buffer = self.get_buffer().to_string();
-
-            // Move left until we're looking at the newline
-            // Observe what column we were on
-            while position > 0 && &buffer[(position - 1)..position] != "\n" {
-                self.move_left();
-                num_of_move_lefts += 1;
-                position = self.offset();
-            }
+            let (line_start, column) = self.line_start_and_column();
 
             // Find start of previous line
-            let mut matches = buffer[0..(position - 1)].rmatch_indices('\n');
-
-            if let Some((pos, _)) = matches.next() {
-                position = pos + 1;
-            } else {
-                position = 0;
-            }
-            self.set_insertion_point(position);
-
-            // Move right from this position to the column we were at
-            while &buffer[position..=position] != "\n" && num_of_move_lefts > 0 {
-                self.move_right();
-                position = self.offset();
-                num_of_move_lefts -= 1;
-            }
+            let previous_line_start = self
+                .lines
+                .rfind_char(line_start - 1, '\n')
+                .map_or(0, |pos| pos + 1);
+            self.insertion_point.offset = self.offset_in_line(previous_line_start, column);
         }
     }
 
     /// Moves one line down
     pub fn move_line_down(&mut self) {
         if !self.is_cursor_at_last_line() {
-            // If we're not at the top, move up a line in the multiline buffer
-            let mut position = self.offset();
-            let mut num_of_move_lefts = 0;
-            let buffer = self.get_buffer().to_string();
-
-            // Move left until we're looking at the newline
-            // Observe what column we were on
-            while position > 0 && &buffer[(position - 1)..position] != "\n" {
-                self.move_left();
-                num_of_move_lefts += 1;
-                position = self.offset();
-            }
+            let (_, column) = self.line_start_and_column();
 
             // Find start of next line
-            let mut matches = buffer[position..].match_indices('\n');
-
             // Assume this always succeeds
+            let next_line_start = self
+                .lines
+                .find_char(self.insertion_point.offset, '\n')
+                .expect("internal error: should have found newline")
+                + 1;
+            self.insertion_point.offset = self.offset_in_line(next_line_start, column);
+        }
+    }
 
-            let (pos, _) = matches
-                .next()
-                .expect("internal error: should have found newline");
+    /// Start of the current line and the number of graphemes in front of the insertion point on it
+    fn line_start_and_column(&self) -> (usize, usize) {
+        let offset = self.insertion_point.offset;
+        let line_start = self.lines.rfind_char(offset, '\n').map_or(0, |pos| pos + 1);
+        let column = self.lines.slice(line_start..offset).graphemes(true).count();
 
-            position += pos + 1;
+        (line_start, column)
+    }
 
-            self.set_insertion_point(position);
+    /// Position `column` graphemes into the line starting at `line_start`, at most its end
+    fn offset_in_line(&self, line_start: usize, column: usize) -> usize {
+        let line_end = self
+            .lines
+            .find_char(line_start, '\n')
+            .map_or(self.lines.len(), |pos| pos + 1);
+        let width: usize = self
+            .lines
+            .slice(line_start..line_end)
+            .graphemes(true)
+            .take(column)
+            .take_while(|grapheme| !grapheme.ends_with('\n'))
+            .map(str::len)
+            .sum();
 
-            // Move right from this position to the column we were at
-            while position < buffer.len()
-                && &buffer[position..=position] != "\n"
-                && num_of_move_lefts > 0
-            {
-                self.move_right();
-                position = self.offset();
-                num_of_move_lefts -= 1;
-            }
-        }
+        line_start + width
     }
 
     /// Checks to see if the cursor is on the first line of the buffer
     pub fn is_cursor_at_first_line(&self) -> bool {
-        !self.get_buffer()[0..self.offset()].contains('\n')
+        self.lines.rfind_char(self.offset(), '\n').is_none()
     }
 
     /// Checks to see if the cursor is on the last line of the buffer
     pub fn is_cursor_at_last_line(&self) -> bool {
-        !self.get_buffer()[self.offset()..].contains('\n')
+        self.lines.find_char(self.offset(), '\n').is_none()
     }
 
     /// Finds index for the first occurrence of a char to the right of offset
@@ -521,10 +932,7 @@ impl LineBuffer {
             return None;
         }
 
-        let search_str = &self.lines[self.grapheme_right_index()..];
-        search_str
-            .find(c)
-            .map(|index| index + self.grapheme_right_index())
+        self.lines.find_char(self.grapheme_right_index(), c)
     }
 
     /// Finds index for the first occurrence of a char to the left of offset
@@ -533,8 +941,7 @@ impl LineBuffer {
             return None;
         }
 
-        let search_str = &self.lines[..self.offset()];
-        search_str.rfind(c)
+        self.lines.rfind_char(self.offset(), c)
     }
 
     /// Moves the insertion point until the next char to the right
@@ -610,6 +1017,47 @@ fn is_word_boundary(s: &str) -> bool {
     !s.chars().any(char::is_alphanumeric)
 }
 
+/// Byte ranges of the words in `text` under a character based [`WordBoundary`]
+fn word_ranges(text: &str, word_boundary: WordBoundary) -> Vec<Range<usize>> {
+    let mut words = vec![];
+    let mut start = None;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (index, c) in text.char_indices() {
+        let in_word = match word_boundary {
+            WordBoundary::Unicode | WordBoundary::Whitespace => !c.is_whitespace(),
+            WordBoundary::AlphanumericUnderscore => c.is_alphanumeric() || c == '_',
+            WordBoundary::ShellToken => {
+                let in_word = escaped || quote.is_some() || !c.is_whitespace();
+                if escaped {
+                    escaped = false;
+                } else if quote == Some(c) {
+                    quote = None;
+                } else if quote.is_none() && (c == '\'' || c == '"') {
+                    quote = Some(c);
+                } else if quote != Some('\'') && c == '\\' {
+                    escaped = true;
+                }
+                in_word
+            }
+        };
+        match (in_word, start) {
+            (true, None) => start = Some(index),
+            (false, Some(word_start)) => {
+                words.push(word_start..index);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(word_start) = start {
+        words.push(word_start..text.len());
+    }
+
+    words
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -623,6 +1071,110 @@ mod test {
         line_buffer
     }
 
+    #[test]
+    fn marks_follow_edits_in_front_of_them() {
+        let mut line_buffer = buffer_with("let x = 1");
+        line_buffer.set_insertion_point(4);
+        line_buffer.set_mark('a');
+        line_buffer.set_insertion_point(8);
+        line_buffer.set_mark('b');
+
+        line_buffer.set_insertion_point(0);
+        line_buffer.insert_str("    ");
+        assert_eq!(line_buffer.mark('a'), Some(8));
+        assert_eq!(line_buffer.mark('b'), Some(12));
+
+        line_buffer.insert_char('ö');
+        assert_eq!(line_buffer.mark('a'), Some(10));
+
+        line_buffer.clear_range(..6);
+        assert_eq!(line_buffer.mark('a'), Some(4));
+
+        line_buffer.replace_range(4..5, "yy");
+        assert_eq!(line_buffer.get_buffer(), "let yy = 1");
+        assert_eq!(line_buffer.mark('a'), Some(4));
+        assert_eq!(line_buffer.mark('b'), Some(9));
+
+        line_buffer.move_to_mark('b');
+        assert_eq!(line_buffer.offset(), 9);
+    }
+
+    #[test]
+    fn marks_inside_removed_text_move_to_its_start() {
+        let mut line_buffer = buffer_with("echo hello world");
+        line_buffer.set_insertion_point(8);
+        line_buffer.set_mark('a');
+        line_buffer.set_insertion_point(5);
+
+        line_buffer.clear_to_end();
+
+        assert_eq!(line_buffer.mark('a'), Some(5));
+    }
+
+    #[test]
+    fn marks_are_dropped_with_the_buffer() {
+        let mut line_buffer = buffer_with("ls");
+        line_buffer.set_mark('a');
+
+        line_buffer.set_buffer("pwd".to_string());
+        assert_eq!(line_buffer.mark('a'), None);
+
+        line_buffer.set_mark('a');
+        line_buffer.clear();
+        assert_eq!(line_buffer.mark('a'), None);
+    }
+
+    #[test]
+    fn jump_list_moves_back_and_forth() {
+        let mut line_buffer = buffer_with("echo hello world");
+        line_buffer.set_insertion_point(5);
+        line_buffer.record_jump();
+        line_buffer.move_to_start();
+        line_buffer.record_jump();
+        line_buffer.move_to_end();
+
+        line_buffer.jump_back();
+        assert_eq!(line_buffer.offset(), 0);
+        line_buffer.jump_back();
+        assert_eq!(line_buffer.offset(), 5);
+        line_buffer.jump_back();
+        assert_eq!(line_buffer.offset(), 5);
+
+        line_buffer.jump_forward();
+        assert_eq!(line_buffer.offset(), 0);
+        line_buffer.jump_forward();
+        assert_eq!(line_buffer.offset(), 16);
+        line_buffer.jump_forward();
+        assert_eq!(line_buffer.offset(), 16);
+    }
+
+    #[test]
+    fn jump_list_follows_edits_and_survives_buffer_replacement() {
+        let mut line_buffer = buffer_with("echo hello world");
+        line_buffer.set_insertion_point(10);
+        line_buffer.record_jump();
+        line_buffer.set_insertion_point(0);
+        line_buffer.insert_str("sudo ");
+
+        line_buffer.jump_back();
+        assert_eq!(line_buffer.offset(), 15);
+
+        line_buffer.set_buffer("ls -la".to_string());
+        line_buffer.jump_back();
+        assert_eq!(line_buffer.offset(), 5);
+        line_buffer.jump_forward();
+        assert_eq!(line_buffer.offset(), 6);
+    }
+
+    #[test]
+    fn move_to_unset_mark_keeps_position() {
+        let mut line_buffer = buffer_with("ls");
+
+        line_buffer.move_to_mark('z');
+
+        assert_eq!(line_buffer.offset(), 2);
+    }
+
     #[test]
     fn test_new_buffer_is_empty() {
         let line_buffer = LineBuffer::new();
@@ -707,6 +1259,113 @@ mod test {
         assert_eq!(expected_line_buffer, line_buffer);
     }
 
+    // Letter with combining acute accent, skin tone modifier, ZWJ family and two flags
+    const E_ACUTE: &str = "e\u{301}";
+    const THUMBS_UP: &str = "\u{1f44d}\u{1f3fd}";
+    const FAMILY: &str = "\u{1f469}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+    const FLAGS: &str = "\u{1f1e9}\u{1f1ea}\u{1f1eb}\u{1f1f7}";
+
+    #[rstest]
+    #[case(E_ACUTE, 0, 3)]
+    #[case(THUMBS_UP, 0, 8)]
+    #[case(FAMILY, 0, 18)]
+    #[case(FLAGS, 0, 8)]
+    #[case(FLAGS, 4, 8)]
+    #[case("\r\nx", 0, 2)]
+    #[case("\u{1100}\u{1161}\u{11a8}!", 0, 9)]
+    #[case("e\u{301}x", 1, 3)]
+    #[case("ab", 2, 2)]
+    fn move_right_skips_grapheme_clusters(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.move_right();
+
+        assert_eq!(line_buffer.offset(), out_location);
+    }
+
+    #[rstest]
+    #[case(E_ACUTE, 3, 0)]
+    #[case(THUMBS_UP, 8, 0)]
+    #[case(FAMILY, 18, 0)]
+    #[case(FLAGS, 16, 8)]
+    #[case(FLAGS, 12, 8)]
+    #[case("x\r\n", 3, 1)]
+    #[case("e\u{301}x", 1, 0)]
+    #[case("ab", 0, 0)]
+    fn move_left_skips_grapheme_clusters(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.move_left();
+
+        assert_eq!(line_buffer.offset(), out_location);
+    }
+
+    #[rstest]
+    #[case(format!("a{}", E_ACUTE), "a")]
+    #[case(format!("a{}", FAMILY), "a")]
+    #[case(format!("a{}", FLAGS), format!("a{}", &FLAGS[..8]))]
+    #[case("a\r\n".to_string(), "a")]
+    fn backspace_deletes_grapheme_clusters(
+        #[case] input: String,
+        #[case] expected: impl AsRef<str>,
+    ) {
+        let mut line_buffer = buffer_with(&input);
+        line_buffer.delete_left_grapheme();
+
+        assert_eq!(line_buffer.get_buffer(), expected.as_ref());
+        assert_eq!(line_buffer.offset(), expected.as_ref().len());
+    }
+
+    #[rstest]
+    #[case(format!("{}a", E_ACUTE), "a")]
+    #[case(format!("{}a", THUMBS_UP), "a")]
+    #[case(format!("{}a", FAMILY), "a")]
+    #[case(format!("{}a", FLAGS), format!("{}a", &FLAGS[8..]))]
+    fn delete_removes_grapheme_clusters(#[case] input: String, #[case] expected: impl AsRef<str>) {
+        let mut line_buffer = buffer_with(&input);
+        line_buffer.set_insertion_point(0);
+        line_buffer.delete_right_grapheme();
+
+        assert_eq!(line_buffer.get_buffer(), expected.as_ref());
+        assert_eq!(line_buffer.offset(), 0);
+    }
+
+    #[test]
+    fn swap_graphemes_keeps_clusters_together() {
+        let mut line_buffer = buffer_with(&format!("{}{}", E_ACUTE, FAMILY));
+        line_buffer.set_insertion_point(E_ACUTE.len());
+        line_buffer.swap_graphemes();
+
+        assert_eq!(line_buffer.get_buffer(), format!("{}{}", FAMILY, E_ACUTE));
+        assert_eq!(line_buffer.offset(), FAMILY.len() + E_ACUTE.len());
+    }
+
+    #[test]
+    fn typed_characters_build_grapheme_clusters() {
+        let mut line_buffer = LineBuffer::new();
+        for c in FAMILY.chars() {
+            line_buffer.insert_char(c);
+        }
+        assert_eq!(line_buffer.offset(), FAMILY.len());
+
+        line_buffer.set_insertion_point(0);
+        line_buffer.insert_str("\u{301}");
+        line_buffer.set_insertion_point(0);
+        line_buffer.insert_char('e');
+        assert_eq!(line_buffer.offset(), 1);
+        line_buffer.move_left();
+        line_buffer.move_right();
+        assert_eq!(line_buffer.offset(), E_ACUTE.len());
+    }
+
     #[test]
     fn delete_word_left_works() {
         let mut line_buffer = buffer_with("This is a test");
@@ -728,6 +1387,107 @@ mod test {
         assert_eq!(expected_line_buffer, line_buffer);
     }
 
+    #[rstest]
+    #[case(WordBoundary::Unicode, "ls /usr/local/bin", "ls /usr/local/")]
+    #[case(WordBoundary::Whitespace, "ls /usr/local/bin", "ls ")]
+    #[case(WordBoundary::AlphanumericUnderscore, "let my_var", "let ")]
+    #[case(WordBoundary::Unicode, "cat 'my file.txt'", "cat 'my ")]
+    #[case(WordBoundary::ShellToken, "cat 'my file.txt'", "cat ")]
+    #[case(WordBoundary::ShellToken, "cat my\\ file.txt  ", "cat ")]
+    #[case(WordBoundary::ShellToken, "echo \"a b\"c d", "echo \"a b\"c ")]
+    fn delete_word_left_follows_word_boundary(
+        #[case] word_boundary: WordBoundary,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_word_boundary(word_boundary);
+        line_buffer.delete_word_left();
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case(WordBoundary::Whitespace, "a.b c", 0, 3)]
+    #[case(WordBoundary::AlphanumericUnderscore, "a.b c", 0, 1)]
+    #[case(WordBoundary::AlphanumericUnderscore, "a.b c", 1, 3)]
+    #[case(WordBoundary::ShellToken, "x 'a b' c", 2, 7)]
+    #[case(WordBoundary::ShellToken, "x 'a b' c", 9, 9)]
+    fn word_right_index_follows_word_boundary(
+        #[case] word_boundary: WordBoundary,
+        #[case] input: &str,
+        #[case] offset: usize,
+        #[case] expected: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_word_boundary(word_boundary);
+        line_buffer.set_insertion_point(offset);
+
+        assert_eq!(line_buffer.word_right_index(), expected);
+    }
+
+    #[rstest]
+    #[case("ls /usr/local/bin", 17, 3)]
+    #[case("ls /usr/local/bin", 3, 0)]
+    #[case("ls  foo.bar", 5, 4)]
+    #[case("ls", 0, 0)]
+    fn move_big_word_left_skips_punctuation(
+        #[case] input: &str,
+        #[case] offset: usize,
+        #[case] expected: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(offset);
+
+        assert_eq!(line_buffer.move_big_word_left(), expected);
+    }
+
+    #[rstest]
+    #[case("ls /usr/local/bin", 0, 2)]
+    #[case("ls /usr/local/bin", 2, 17)]
+    #[case("a.b  c-d ", 3, 8)]
+    #[case("a.b ", 3, 4)]
+    fn move_big_word_right_skips_punctuation(
+        #[case] input: &str,
+        #[case] offset: usize,
+        #[case] expected: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(offset);
+
+        assert_eq!(line_buffer.move_big_word_right(), expected);
+    }
+
+    #[test]
+    fn anchor_moves_with_edits_and_swaps_with_cursor() {
+        let mut line_buffer = buffer_with("echo hello world");
+        line_buffer.set_insertion_point(5);
+        line_buffer.set_anchor();
+        line_buffer.move_to_end();
+        assert_eq!(line_buffer.selection_range(), Some(5..16));
+
+        line_buffer.move_to_start();
+        line_buffer.insert_str("sudo ");
+        assert_eq!(line_buffer.anchor(), Some(10));
+        assert_eq!(line_buffer.selection_range(), Some(5..10));
+
+        line_buffer.swap_cursor_and_anchor();
+        assert_eq!(line_buffer.offset(), 10);
+        assert_eq!(line_buffer.anchor(), Some(5));
+
+        line_buffer.set_buffer("ls".to_string());
+        assert_eq!(line_buffer.selection_range(), None);
+    }
+
+    #[test]
+    fn current_word_range_follows_word_boundary() {
+        let mut line_buffer = buffer_with("git add src/main.rs");
+        line_buffer.set_word_boundary(WordBoundary::Whitespace);
+        line_buffer.set_insertion_point(12);
+
+        assert_eq!(line_buffer.current_word_range(), 8..19);
+    }
+
     #[rstest]
     #[case("This is a te", 4)]
     #[case("This is a test", 4)]
@@ -854,6 +1614,8 @@ mod test {
     #[rstest]
     #[case("line 1\nline 2", 7, "line 1\nline 2", 0)]
     #[case("line 1\nline 2", 0, "line 1\nline 2", 0)]
+    #[case("äb\nxyz", 6, "äb\nxyz", 3)]
+    #[case("a\r\nbc", 5, "a\r\nbc", 1)]
     fn moving_up_works(
         #[case] input: &str,
         #[case] in_location: usize,
@@ -874,6 +1636,8 @@ mod test {
     #[rstest]
     #[case("line 1\nline 2", 0, "line 1\nline 2", 7)]
     #[case("line 1\nline 2", 7, "line 1\nline 2", 7)]
+    #[case("xyz\näb", 2, "xyz\näb", 7)]
+    #[case("abc\r\nd", 2, "abc\r\nd", 6)]
     fn moving_down_works(
         #[case] input: &str,
         #[case] in_location: usize,
@@ -944,7 +1708,7 @@ mod test {
 
         line_buffer.delete_right_until_char(c);
 
-        assert_eq!(line_buffer.lines, expected);
+        assert_eq!(line_buffer.get_buffer(), expected);
     }
 
     #[rstest]
@@ -965,7 +1729,7 @@ mod test {
 
         line_buffer.delete_right_before_char(c);
 
-        assert_eq!(line_buffer.lines, expected);
+        assert_eq!(line_buffer.get_buffer(), expected);
     }
 
     #[rstest]
@@ -999,7 +1763,7 @@ mod test {
 
         line_buffer.delete_left_until_char(c);
 
-        assert_eq!(line_buffer.lines, expected);
+        assert_eq!(line_buffer.get_buffer(), expected);
     }
 
     #[rstest]
@@ -1017,6 +1781,127 @@ mod test {
 
         line_buffer.delete_left_before_char(c);
 
-        assert_eq!(line_buffer.lines, expected);
+        assert_eq!(line_buffer.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case("foo bar baz", 5, 4..7)]
+    #[case("foo bar baz", 3, 3..4)]
+    #[case("foo   bar", 4, 3..6)]
+    #[case("foo bar", 7, 4..7)]
+    #[case("foo,bar", 3, 3..4)]
+    #[case("", 0, 0..0)]
+    fn inner_word_range_works(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: Range<usize>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.inner_word_range(), expected);
+    }
+
+    #[rstest]
+    #[case("foo bar baz", 5, 4..8)]
+    #[case("foo bar", 5, 3..7)]
+    #[case("foo bar baz", 3, 3..7)]
+    #[case("foo\nbar", 1, 0..3)]
+    #[case("foo", 1, 0..3)]
+    fn around_word_range_works(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: Range<usize>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.around_word_range(), expected);
+    }
+
+    #[rstest]
+    #[case("f(a, (b), c)", 3, '(', ')', Some(2..11))]
+    #[case("f(a, (b), c)", 6, '(', ')', Some(6..7))]
+    #[case("f(a, (b), c)", 1, '(', ')', Some(2..11))]
+    #[case("f(a, (b), c)", 11, '(', ')', Some(2..11))]
+    #[case("f(a, (b), c)", 0, '(', ')', None)]
+    #[case("{\n  x\n}", 4, '{', '}', Some(1..6))]
+    #[case(r#"say "hi" and "bye""#, 6, '"', '"', Some(5..7))]
+    #[case(r#"say "hi" and "bye""#, 9, '"', '"', Some(14..17))]
+    #[case(r#"say "hi" and "bye""#, 0, '"', '"', Some(5..7))]
+    #[case("say \"hi\"\n\"x", 10, '"', '"', None)]
+    fn inside_pair_range_works(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] left: char,
+        #[case] right: char,
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.inside_pair_range(left, right), expected);
+    }
+
+    #[test]
+    fn around_pair_range_includes_delimiters() {
+        let mut line_buffer = buffer_with("f(a, [b], c)");
+        line_buffer.set_insertion_point(6);
+
+        assert_eq!(line_buffer.around_pair_range('[', ']'), Some(5..8));
+        assert_eq!(line_buffer.around_pair_range('{', '}'), None);
+    }
+
+    #[rstest]
+    #[case("ab\n\n  cd", 2, 0, 8)]
+    #[case("ab\n\n  cd", 6, 0, 8)]
+    #[case("ab \r\n cd ef", 5, 0, 8)]
+    #[case("ab \r\n cd ef", 9, 6, 11)]
+    #[case("ab\n", 3, 0, 3)]
+    fn word_movement_crosses_lines(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] left: usize,
+        #[case] right: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.word_left_index(), left);
+        assert_eq!(line_buffer.word_right_index(), right);
+    }
+
+    #[cfg(feature = "rope_buffer")]
+    #[test]
+    fn editing_and_moving_in_a_large_buffer_does_not_flatten_it() {
+        let mut lines = (0..5_000)
+            .map(|idx| format!("echo \"line {}\" | wc -l", idx))
+            .collect::<Vec<_>>();
+        let buffer = lines.join("\n");
+        let mut line_buffer = LineBuffer::new();
+        line_buffer.set_buffer(buffer.clone());
+        line_buffer.set_insertion_point(buffer.len() / 2);
+        line_buffer.move_to_line_start();
+
+        line_buffer.insert_char('ä');
+        line_buffer.move_word_right();
+        line_buffer.delete_right_grapheme();
+        line_buffer.move_line_down();
+        line_buffer.move_to_line_end();
+        line_buffer.insert_str("\n");
+        line_buffer.move_line_up();
+        line_buffer.delete_left_grapheme();
+        assert!(line_buffer.word_count() > 0);
+        assert!(matches!(
+            &line_buffer.lines,
+            TextStorage::Rope { flat, .. } if flat.get().is_none()
+        ));
+
+        // The line of the cursor lost the blank behind its first word and got the next line
+        // appended, which is followed by an empty line
+        let line = buffer[..buffer.len() / 2].matches('\n').count();
+        lines[line] = format!("äecho\"line {}\" | wc -l{}", line, lines[line + 1]);
+        lines[line + 1] = String::new();
+        assert_eq!(line_buffer.get_buffer(), lines.join("\n"));
     }
 }
@@ -1,7 +1,14 @@
 mod clip_buffer;
 mod editor;
 mod line_buffer;
+mod scratch_file;
+mod search_replace;
+mod snippet;
+mod text_storage;
 
 pub(crate) use clip_buffer::{get_default_clipboard, Clipboard, ClipboardMode};
 pub use editor::Editor;
-pub use line_buffer::LineBuffer;
+pub use line_buffer::{LineBuffer, WordBoundary};
+pub(crate) use scratch_file::ScratchFile;
+pub(crate) use search_replace::{SearchReplace, SearchReplaceStage};
+pub(crate) use snippet::Snippet;
@@ -0,0 +1,119 @@
+use std::{
+    ffi::OsString,
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+/// Number of edits after which the buffer is written to the scratch file again
+const SAVE_EVERY_EDITS: usize = 5;
+
+/// Copy of the buffer being edited kept on disk, to recover it after a crash
+///
+/// The file only exists while a line is being edited and is removed once the line is done.
+pub(crate) struct ScratchFile {
+    path: PathBuf,
+    unsaved_edits: usize,
+}
+
+impl ScratchFile {
+    /// Keep the buffer in the file at `path`, creating the directories leading to it
+    pub fn new(path: PathBuf) -> io::Result<Self> {
+        if let Some(base_dir) = path.parent() {
+            fs::create_dir_all(base_dir)?;
+        }
+
+        Ok(ScratchFile {
+            path,
+            unsaved_edits: 0,
+        })
+    }
+
+    /// The buffer left behind by a session that did not finish its line, if any
+    pub fn recover(&self) -> io::Result<Option<String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(buffer) if buffer.is_empty() => Ok(None),
+            Ok(buffer) => Ok(Some(buffer)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Count an edit of `buffer`, saving it every few edits
+    pub fn edited(&mut self, buffer: &str) {
+        self.unsaved_edits += 1;
+        if self.unsaved_edits >= SAVE_EVERY_EDITS {
+            self.save(buffer);
+        }
+    }
+
+    /// Write `buffer` to the file, an empty buffer removes it
+    ///
+    /// Recovery is best effort: editing continues if the file cannot be written.
+    pub fn save(&mut self, buffer: &str) {
+        self.unsaved_edits = 0;
+        if buffer.is_empty() {
+            self.discard();
+            return;
+        }
+
+        // Replace the file at once, a crash while writing leaves the previous copy intact
+        let mut temp_path = OsString::from(&self.path);
+        temp_path.push(".tmp");
+        if fs::write(&temp_path, buffer).is_ok() {
+            let _ = fs::rename(&temp_path, &self.path);
+        }
+    }
+
+    /// Remove the file as the line was finished
+    pub fn discard(&mut self) {
+        self.unsaved_edits = 0;
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn nothing_to_recover_without_file() {
+        let tmp = tempdir().unwrap();
+        let scratch_file = ScratchFile::new(tmp.path().join("nested").join("buffer")).unwrap();
+
+        assert_eq!(scratch_file.recover().unwrap(), None);
+    }
+
+    #[test]
+    fn saves_every_few_edits() {
+        let tmp = tempdir().unwrap();
+        let mut scratch_file = ScratchFile::new(tmp.path().join("buffer")).unwrap();
+
+        for _ in 1..SAVE_EVERY_EDITS {
+            scratch_file.edited("for i in 1..10 {\n");
+        }
+        assert_eq!(scratch_file.recover().unwrap(), None);
+
+        scratch_file.edited("for i in 1..10 {\n");
+        assert_eq!(
+            scratch_file.recover().unwrap(),
+            Some("for i in 1..10 {\n".to_string())
+        );
+    }
+
+    #[test]
+    fn discard_and_empty_buffer_remove_file() {
+        let tmp = tempdir().unwrap();
+        let mut scratch_file = ScratchFile::new(tmp.path().join("buffer")).unwrap();
+
+        scratch_file.save("ls");
+        scratch_file.discard();
+        assert_eq!(scratch_file.recover().unwrap(), None);
+
+        scratch_file.save("ls");
+        scratch_file.save("");
+        assert_eq!(scratch_file.recover().unwrap(), None);
+    }
+}
@@ -0,0 +1,272 @@
+use {
+    crate::{enums::EditCommand, styled_text::StyledText},
+    nu_ansi_term::Style,
+    std::ops::Range,
+    unicode_segmentation::UnicodeSegmentation,
+};
+
+/// The part of the search and replace query that is currently edited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SearchReplaceStage {
+    /// Typing the pattern to search for
+    Pattern,
+    /// Typing the replacement, matches can be replaced
+    Replacement,
+}
+
+/// State of an interactive search and replace within the line buffer
+///
+/// Only tracks the query, the buffer itself is owned by the [`Editor`](super::Editor).
+#[derive(Debug)]
+pub(crate) struct SearchReplace {
+    pattern: String,
+    replacement: String,
+    stage: SearchReplaceStage,
+    // Byte offset in the buffer from where the current match is searched
+    position: usize,
+}
+
+impl SearchReplace {
+    /// Start a new search and replace looking for matches from `position` onwards
+    pub fn new(position: usize) -> Self {
+        SearchReplace {
+            pattern: String::new(),
+            replacement: String::new(),
+            stage: SearchReplaceStage::Pattern,
+            position,
+        }
+    }
+
+    pub fn stage(&self) -> SearchReplaceStage {
+        self.stage
+    }
+
+    /// Apply an edit to the part of the query that is currently typed.
+    ///
+    /// Only simple insertions and deletions are supported, other commands are ignored.
+    pub fn edit(&mut self, command: &EditCommand) {
+        let query = match self.stage {
+            SearchReplaceStage::Pattern => &mut self.pattern,
+            SearchReplaceStage::Replacement => &mut self.replacement,
+        };
+        match command {
+            EditCommand::InsertChar(c) => query.push(*c),
+            EditCommand::InsertString(s) => query.push_str(s),
+            EditCommand::Backspace => {
+                if let Some((start, _)) = query.grapheme_indices(true).next_back() {
+                    query.truncate(start);
+                }
+            }
+            EditCommand::Clear | EditCommand::CutFromStart | EditCommand::CutCurrentLine => {
+                query.clear()
+            }
+            _ => {}
+        }
+    }
+
+    /// Finish typing the pattern and continue with the replacement
+    pub fn confirm_pattern(&mut self) {
+        self.stage = SearchReplaceStage::Replacement;
+    }
+
+    /// All non overlapping matches of the pattern in `buffer`
+    pub fn matches(&self, buffer: &str) -> Vec<Range<usize>> {
+        if self.pattern.is_empty() {
+            return vec![];
+        }
+        buffer
+            .match_indices(&self.pattern)
+            .map(|(start, matched)| start..start + matched.len())
+            .collect()
+    }
+
+    /// The match at or after the current position, wrapping around to the first one
+    pub fn current_match(&self, buffer: &str) -> Option<Range<usize>> {
+        let matches = self.matches(buffer);
+        matches
+            .iter()
+            .find(|m| m.start >= self.position)
+            .or_else(|| matches.first())
+            .cloned()
+    }
+
+    /// Skip the current match
+    pub fn next_match(&mut self, buffer: &str) {
+        if let Some(current) = self.current_match(buffer) {
+            self.position = current.end;
+        }
+    }
+
+    /// Go back to the match before the current one, wrapping around to the last one
+    pub fn previous_match(&mut self, buffer: &str) {
+        let matches = self.matches(buffer);
+        if let Some(current) = self.current_match(buffer) {
+            if let Some(previous) = matches
+                .iter()
+                .rev()
+                .find(|m| m.start < current.start)
+                .or_else(|| matches.last())
+            {
+                self.position = previous.start;
+            }
+        }
+    }
+
+    /// Replace the current match, returning the new buffer
+    ///
+    /// Searching continues after the inserted replacement.
+    pub fn replace_current(&mut self, buffer: &str) -> Option<String> {
+        let current = self.current_match(buffer)?;
+        let mut new_buffer = buffer.to_string();
+        new_buffer.replace_range(current.clone(), &self.replacement);
+        self.position = current.start + self.replacement.len();
+
+        Some(new_buffer)
+    }
+
+    /// Replace all matches, returning the new buffer
+    pub fn replace_all(&self, buffer: &str) -> Option<String> {
+        if self.matches(buffer).is_empty() {
+            None
+        } else {
+            Some(buffer.replace(&self.pattern, &self.replacement))
+        }
+    }
+
+    /// Text describing the query to be shown in the status row
+    pub fn status(&self, buffer: &str) -> String {
+        let matches = self.matches(buffer);
+        let progress = match self.current_match(buffer) {
+            Some(current) => {
+                let index = matches.iter().position(|m| *m == current).unwrap_or(0);
+                format!("[{}/{}]", index + 1, matches.len())
+            }
+            None if self.pattern.is_empty() => String::new(),
+            None => "[no match]".to_string(),
+        };
+
+        match self.stage {
+            SearchReplaceStage::Pattern => format!("(replace{}): {}", progress, self.pattern),
+            SearchReplaceStage::Replacement => format!(
+                "(replace{} {} with): {}",
+                progress, self.pattern, self.replacement
+            ),
+        }
+    }
+
+    /// Style `buffer` emphasizing the matches, the current match stronger than the others
+    pub fn highlight_matches(
+        &self,
+        buffer: &str,
+        neutral_style: Style,
+        match_style: Style,
+        current_style: Style,
+    ) -> StyledText {
+        let current = self.current_match(buffer);
+        let mut styled_text = StyledText::new();
+        let mut last_end = 0;
+        for m in self.matches(buffer) {
+            styled_text.push((neutral_style, buffer[last_end..m.start].to_string()));
+            let style = if Some(&m) == current.as_ref() {
+                current_style
+            } else {
+                match_style
+            };
+            styled_text.push((style, buffer[m.clone()].to_string()));
+            last_end = m.end;
+        }
+        styled_text.push((neutral_style, buffer[last_end..].to_string()));
+
+        styled_text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn search_replace(pattern: &str, replacement: &str) -> SearchReplace {
+        let mut search_replace = SearchReplace::new(0);
+        search_replace.edit(&EditCommand::InsertString(pattern.to_string()));
+        search_replace.confirm_pattern();
+        search_replace.edit(&EditCommand::InsertString(replacement.to_string()));
+        search_replace
+    }
+
+    #[test]
+    fn empty_pattern_has_no_matches() {
+        let search_replace = SearchReplace::new(0);
+
+        assert_eq!(search_replace.matches("abc"), Vec::<Range<usize>>::new());
+        assert_eq!(search_replace.current_match("abc"), None);
+    }
+
+    #[test]
+    fn edits_apply_to_current_stage() {
+        let mut search_replace = SearchReplace::new(0);
+        search_replace.edit(&EditCommand::InsertString("fo".to_string()));
+        search_replace.edit(&EditCommand::InsertChar('x'));
+        search_replace.edit(&EditCommand::Backspace);
+        search_replace.edit(&EditCommand::InsertChar('o'));
+        search_replace.confirm_pattern();
+        search_replace.edit(&EditCommand::InsertChar('b'));
+
+        assert_eq!(search_replace.stage(), SearchReplaceStage::Replacement);
+        assert_eq!(search_replace.status("foo"), "(replace[1/1] foo with): b");
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_grapheme() {
+        let mut search_replace = SearchReplace::new(0);
+        search_replace.edit(&EditCommand::InsertString("cafe\u{301}👨‍👩‍👧".to_string()));
+        search_replace.edit(&EditCommand::Backspace);
+        assert_eq!(search_replace.pattern, "cafe\u{301}");
+
+        search_replace.edit(&EditCommand::Backspace);
+        assert_eq!(search_replace.pattern, "caf");
+    }
+
+    #[test]
+    fn current_match_starts_at_position_and_wraps() {
+        let mut search_replace = search_replace("a", "b");
+        search_replace.position = 3;
+
+        assert_eq!(search_replace.current_match("a a a"), Some(4..5));
+        search_replace.next_match("a a a");
+        assert_eq!(search_replace.current_match("a a a"), Some(0..1));
+        search_replace.previous_match("a a a");
+        assert_eq!(search_replace.current_match("a a a"), Some(4..5));
+    }
+
+    #[test]
+    fn replace_current_continues_after_replacement() {
+        let mut search_replace = search_replace("a", "aa");
+
+        let buffer = search_replace.replace_current("a a").unwrap();
+        assert_eq!(buffer, "aa a");
+        assert_eq!(search_replace.current_match(&buffer), Some(3..4));
+
+        let buffer = search_replace.replace_current(&buffer).unwrap();
+        assert_eq!(buffer, "aa aa");
+    }
+
+    #[test]
+    fn replace_all_replaces_every_match() {
+        let search_replace = search_replace("ö", "o");
+
+        assert_eq!(
+            search_replace.replace_all("föö bar"),
+            Some("foo bar".to_string())
+        );
+        assert_eq!(search_replace.replace_all("bar"), None);
+    }
+
+    #[test]
+    fn status_reports_missing_match() {
+        let mut search_replace = SearchReplace::new(0);
+        search_replace.edit(&EditCommand::InsertChar('z'));
+
+        assert_eq!(search_replace.status("abc"), "(replace[no match]): z");
+    }
+}
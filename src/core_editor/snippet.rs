@@ -0,0 +1,263 @@
+use std::ops::Range;
+
+/// A snippet inserted into the line buffer whose placeholders are visited with `Tab`
+///
+/// Templates mark the placeholders as `${1}`, `${2:default text}` or `$3`, visited in the order
+/// of their numbers. `${0}` or `$0` is where the cursor ends up after the last placeholder,
+/// behind the snippet if it is missing. A `$` not starting a placeholder is kept, `\$` is a
+/// literal `$`.
+///
+/// Only the placeholders are tracked, the text itself lives in the [`Editor`](super::Editor).
+/// The ranges follow the edits of the buffer, see [`Snippet::adjust()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Snippet {
+    // Byte ranges of the placeholders in the buffer in the order they are visited
+    stops: Vec<Range<usize>>,
+    // Where the cursor is put after the last placeholder
+    end: usize,
+    current: usize,
+    // The current placeholder still holds its default text, replaced by the first insertion
+    pristine: bool,
+}
+
+impl Snippet {
+    /// Parse `template` to be inserted at byte offset `offset` of the buffer
+    ///
+    /// Returns the text to insert and the snippet, `None` if the template has no placeholders.
+    pub fn parse(template: &str, offset: usize) -> (String, Option<Snippet>) {
+        let mut text = String::new();
+        let mut stops: Vec<(usize, Range<usize>)> = vec![];
+        let mut end = None;
+
+        let mut rest = template;
+        while let Some(c) = rest.chars().next() {
+            if c == '\\' && rest[1..].starts_with('$') {
+                text.push('$');
+                rest = &rest[2..];
+                continue;
+            }
+            if c == '$' {
+                if let Some((number, default, len)) = parse_placeholder(&rest[1..]) {
+                    let start = offset + text.len();
+                    text.push_str(default);
+                    if number == 0 {
+                        end.get_or_insert(start);
+                    } else {
+                        stops.push((number, start..offset + text.len()));
+                    }
+                    rest = &rest[1 + len..];
+                    continue;
+                }
+            }
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+
+        if stops.is_empty() {
+            return (text, None);
+        }
+        // The sort is stable, placeholders sharing a number are visited from left to right
+        stops.sort_by_key(|(number, _)| *number);
+        let end = end.unwrap_or(offset + text.len());
+
+        let snippet = Snippet {
+            stops: stops.into_iter().map(|(_, range)| range).collect(),
+            end,
+            current: 0,
+            pristine: true,
+        };
+        (text, Some(snippet))
+    }
+
+    /// The range of the current placeholder in the buffer
+    pub fn current(&self) -> Range<usize> {
+        self.stops[self.current].clone()
+    }
+
+    /// Whether the next insertion at the cursor replaces the default text of the placeholder
+    pub fn replaces_default(&self, cursor: usize) -> bool {
+        self.pristine && self.current().start == cursor && !self.current().is_empty()
+    }
+
+    /// Move on to the next placeholder, `None` once the last one was left
+    ///
+    /// Returns the new cursor position: the start of the placeholder or the end of the snippet.
+    pub fn next(&mut self) -> Option<usize> {
+        if self.current + 1 < self.stops.len() {
+            self.current += 1;
+            self.pristine = true;
+            Some(self.current().start)
+        } else {
+            None
+        }
+    }
+
+    /// Move back to the previous placeholder, staying at the first one
+    pub fn previous(&mut self) -> usize {
+        if self.current > 0 {
+            self.current -= 1;
+            self.pristine = true;
+        }
+        self.current().start
+    }
+
+    /// Where the cursor is put after the last placeholder
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Whether the cursor at byte offset `cursor` is still inside one of the placeholders
+    pub fn contains(&self, cursor: usize) -> bool {
+        self.stops
+            .iter()
+            .any(|stop| stop.start <= cursor && cursor <= stop.end)
+    }
+
+    /// Follow an edit of the buffer from `old` to `new`
+    ///
+    /// The edit is taken as the replacement of the part between the common prefix and suffix
+    /// of both. Text inserted at the end of a placeholder extends it, as typing there should.
+    pub fn adjust(&mut self, old: &str, new: &str) {
+        if old == new {
+            return;
+        }
+        let prefix = common_prefix_len(old, new);
+        let suffix = common_suffix_len(&old[prefix..], &new[prefix..]);
+        let old_end = old.len() - suffix;
+        let new_end = new.len() - suffix;
+
+        let start_of = |position: usize| {
+            if position <= prefix {
+                position
+            } else if position >= old_end {
+                position - old_end + new_end
+            } else {
+                prefix
+            }
+        };
+        let end_of = |position: usize| {
+            if position < prefix {
+                position
+            } else if position >= old_end {
+                position - old_end + new_end
+            } else {
+                new_end
+            }
+        };
+        for stop in &mut self.stops {
+            *stop = start_of(stop.start)..end_of(stop.end).max(start_of(stop.start));
+        }
+        self.end = end_of(self.end);
+        self.pristine = false;
+    }
+}
+
+/// Parse the placeholder following a `$`: its number, default text and length in the template
+fn parse_placeholder(template: &str) -> Option<(usize, &str, usize)> {
+    if let Some(inner) = template.strip_prefix('{') {
+        let close = inner.find('}')?;
+        let (number, default) = match inner[..close].split_once(':') {
+            Some((number, default)) => (number, default),
+            None => (&inner[..close], ""),
+        };
+        if number.is_empty() || !number.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        Some((number.parse().ok()?, default, close + 2))
+    } else {
+        let len = template
+            .bytes()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        Some((template[..len].parse().ok()?, "", len))
+    }
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(a.len().min(b.len()), |((index, _), _)| index)
+}
+
+fn common_suffix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .rev()
+        .zip(b.chars().rev())
+        .find(|((_, a), b)| a != b)
+        .map_or(a.len().min(b.len()), |((index, c), _)| {
+            a.len() - index - c.len_utf8()
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn parse(template: &str) -> (String, Snippet) {
+        let (text, snippet) = Snippet::parse(template, 0);
+        (text, snippet.unwrap())
+    }
+
+    #[test]
+    fn placeholders_are_visited_by_number() {
+        let (text, mut snippet) = parse("SELECT ${2:*} FROM ${1:table} WHERE $3;$0");
+
+        assert_eq!(text, "SELECT * FROM table WHERE ;");
+        assert_eq!(snippet.current(), 14..19);
+        assert_eq!(snippet.next(), Some(7));
+        assert_eq!(snippet.current(), 7..8);
+        assert_eq!(snippet.next(), Some(26));
+        assert_eq!(snippet.next(), None);
+        assert_eq!(snippet.end(), 27);
+        assert_eq!(snippet.previous(), 7);
+    }
+
+    #[test]
+    fn template_without_placeholders_is_plain_text() {
+        assert_eq!(
+            Snippet::parse("echo \\$HOME $ ${x}", 3),
+            ("echo $HOME $ ${x}".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn snippet_is_placed_at_offset() {
+        let (text, snippet) = Snippet::parse("(${1:a}, ${2:b})", 4);
+
+        assert_eq!(text, "(a, b)");
+        let snippet = snippet.unwrap();
+        assert_eq!(snippet.current(), 5..6);
+        assert_eq!(snippet.end(), 10);
+    }
+
+    #[test]
+    fn typing_in_a_placeholder_moves_the_following_ones() {
+        let (text, mut snippet) = parse("f(${1}, ${2:b})");
+        assert_eq!(text, "f(, b)");
+
+        snippet.adjust("f(, b)", "f(abc, b)");
+        assert_eq!(snippet.current(), 2..5);
+        assert_eq!(snippet.next(), Some(7));
+        assert_eq!(snippet.current(), 7..8);
+
+        snippet.adjust("f(abc, b)", "f(abc, )");
+        assert_eq!(snippet.current(), 7..7);
+        snippet.adjust("f(abc, )", "f(abc, ü)");
+        assert_eq!(snippet.current(), 7..9);
+        assert_eq!(snippet.end(), 10);
+    }
+
+    #[test]
+    fn default_text_is_replaced_until_edited() {
+        let (_, mut snippet) = parse("${1:x} ${2:y}");
+
+        assert!(snippet.replaces_default(0));
+        assert!(!snippet.replaces_default(1));
+        snippet.adjust("x y", "x z");
+        assert!(!snippet.replaces_default(0));
+        assert_eq!(snippet.next(), Some(2));
+        assert!(snippet.replaces_default(2));
+    }
+}
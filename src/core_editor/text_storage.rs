@@ -0,0 +1,410 @@
+#[cfg(feature = "rope_buffer")]
+use {ropey::Rope, std::cell::OnceCell, unicode_segmentation::GraphemeIncomplete};
+use {
+    std::{borrow::Cow, ops::Range},
+    unicode_segmentation::GraphemeCursor,
+};
+
+/// Size in bytes from which the text is kept in a rope
+#[cfg(feature = "rope_buffer")]
+const ROPE_THRESHOLD: usize = 64 * 1024;
+
+/// The text of a [`LineBuffer`](super::LineBuffer)
+///
+/// Kept in a `String`. With the `rope_buffer` feature a text growing beyond
+/// `ROPE_THRESHOLD` bytes moves into a rope, so edits of a large buffer, e.g. a pasted
+/// script, no longer shift everything behind them, and it moves back into a `String` once
+/// it shrank to half the threshold.
+///
+/// Edits, char searches and grapheme boundaries work on the chunks of the rope. Only
+/// [`TextStorage::as_str()`] flattens it into one string, which is cached until the next edit.
+#[derive(Debug)]
+pub(crate) enum TextStorage {
+    Flat(String),
+    #[cfg(feature = "rope_buffer")]
+    Rope {
+        rope: Rope,
+        // The text of the rope, built on demand
+        flat: OnceCell<String>,
+    },
+}
+
+impl Default for TextStorage {
+    fn default() -> Self {
+        TextStorage::Flat(String::new())
+    }
+}
+
+impl Clone for TextStorage {
+    fn clone(&self) -> Self {
+        match self {
+            TextStorage::Flat(text) => TextStorage::Flat(text.clone()),
+            // Cloning a rope shares its chunks, copying the flattened text would undo that
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => TextStorage::Rope {
+                rope: rope.clone(),
+                flat: OnceCell::new(),
+            },
+        }
+    }
+}
+
+impl TextStorage {
+    pub fn new(text: String) -> Self {
+        #[cfg(feature = "rope_buffer")]
+        if text.len() >= ROPE_THRESHOLD {
+            return TextStorage::Rope {
+                rope: Rope::from_str(&text),
+                flat: OnceCell::from(text),
+            };
+        }
+
+        TextStorage::Flat(text)
+    }
+
+    /// The whole text, flattening a rope
+    pub fn as_str(&self) -> &str {
+        match self {
+            TextStorage::Flat(text) => text,
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, flat } => flat.get_or_init(|| rope.to_string()),
+        }
+    }
+
+    /// Length in bytes
+    pub fn len(&self) -> usize {
+        match self {
+            TextStorage::Flat(text) => text.len(),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => rope.len_bytes(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether `offset` is a valid byte offset between two chars
+    pub fn is_char_boundary(&self, offset: usize) -> bool {
+        match self {
+            TextStorage::Flat(text) => text.is_char_boundary(offset),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => {
+                offset <= rope.len_bytes() && rope.char_to_byte(rope.byte_to_char(offset)) == offset
+            }
+        }
+    }
+
+    /// The text in the byte `range`, only copied if it spans several chunks of a rope
+    pub fn slice(&self, range: Range<usize>) -> Cow<'_, str> {
+        match self {
+            TextStorage::Flat(text) => Cow::Borrowed(&text[range]),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => {
+                let slice = rope.byte_slice(range);
+                slice
+                    .as_str()
+                    .map_or_else(|| Cow::Owned(slice.to_string()), Cow::Borrowed)
+            }
+        }
+    }
+
+    /// The pieces of the text in order
+    pub fn chunks(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        match self {
+            TextStorage::Flat(text) => Box::new(std::iter::once(text.as_str())),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => Box::new(rope.chunks()),
+        }
+    }
+
+    /// The char starting at byte `offset`
+    pub fn char_after(&self, offset: usize) -> Option<char> {
+        match self {
+            TextStorage::Flat(text) => text[offset..].chars().next(),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => rope.chars_at(rope.byte_to_char(offset)).next(),
+        }
+    }
+
+    /// The char ending at byte `offset`
+    pub fn char_before(&self, offset: usize) -> Option<char> {
+        match self {
+            TextStorage::Flat(text) => text[..offset].chars().next_back(),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => rope.chars_at(rope.byte_to_char(offset)).prev(),
+        }
+    }
+
+    /// Offset of the first `c` at or behind byte `from`
+    pub fn find_char(&self, from: usize, c: char) -> Option<usize> {
+        match self {
+            TextStorage::Flat(text) => text[from..].find(c).map(|index| from + index),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => {
+                let mut offset = from;
+                for next in rope.chars_at(rope.byte_to_char(from)) {
+                    if next == c {
+                        return Some(offset);
+                    }
+                    offset += next.len_utf8();
+                }
+                None
+            }
+        }
+    }
+
+    /// Offset of the last `c` in front of byte `to`
+    pub fn rfind_char(&self, to: usize, c: char) -> Option<usize> {
+        match self {
+            TextStorage::Flat(text) => text[..to].rfind(c),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => {
+                let mut chars = rope.chars_at(rope.byte_to_char(to));
+                let mut offset = to;
+                while let Some(previous) = chars.prev() {
+                    offset -= previous.len_utf8();
+                    if previous == c {
+                        return Some(offset);
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// End of the extended grapheme cluster behind `offset`, the length at the end of the text
+    pub fn next_grapheme_boundary(&self, offset: usize) -> usize {
+        match self {
+            TextStorage::Flat(text) => GraphemeCursor::new(offset, text.len(), true)
+                .next_boundary(text, 0)
+                .ok()
+                .flatten()
+                .unwrap_or(text.len()),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => {
+                let (mut chunk, mut chunk_start, _, _) = rope.chunk_at_byte(offset);
+                let mut cursor = GraphemeCursor::new(offset, rope.len_bytes(), true);
+                loop {
+                    match cursor.next_boundary(chunk, chunk_start) {
+                        Ok(boundary) => return boundary.unwrap_or(rope.len_bytes()),
+                        Err(GraphemeIncomplete::NextChunk) => {
+                            chunk_start += chunk.len();
+                            chunk = rope.chunk_at_byte(chunk_start).0;
+                        }
+                        Err(GraphemeIncomplete::PreContext(end)) => {
+                            provide_context(&mut cursor, rope, end)
+                        }
+                        Err(_) => return rope.len_bytes(),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Start of the extended grapheme cluster in front of `offset`, 0 at the start of the text
+    pub fn prev_grapheme_boundary(&self, offset: usize) -> usize {
+        match self {
+            TextStorage::Flat(text) => GraphemeCursor::new(offset, text.len(), true)
+                .prev_boundary(text, 0)
+                .ok()
+                .flatten()
+                .unwrap_or(0),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, .. } => {
+                let (mut chunk, mut chunk_start, _, _) = rope.chunk_at_byte(offset);
+                let mut cursor = GraphemeCursor::new(offset, rope.len_bytes(), true);
+                loop {
+                    match cursor.prev_boundary(chunk, chunk_start) {
+                        Ok(boundary) => return boundary.unwrap_or(0),
+                        Err(GraphemeIncomplete::PrevChunk) => {
+                            let (previous, previous_start, _, _) =
+                                rope.chunk_at_byte(chunk_start - 1);
+                            chunk = previous;
+                            chunk_start = previous_start;
+                        }
+                        Err(GraphemeIncomplete::PreContext(end)) => {
+                            provide_context(&mut cursor, rope, end)
+                        }
+                        Err(_) => return 0,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replace the bytes in `range` by `text`
+    ///
+    /// Panics if `range` does not start and end on char boundaries.
+    pub fn replace_range(&mut self, range: Range<usize>, text: &str) {
+        match self {
+            TextStorage::Flat(flat) => {
+                flat.replace_range(range, text);
+                #[cfg(feature = "rope_buffer")]
+                if flat.len() >= ROPE_THRESHOLD {
+                    *self = TextStorage::new(std::mem::take(flat));
+                }
+            }
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope { rope, flat } => {
+                assert!(range.start <= range.end);
+                let start = rope.try_byte_to_char(range.start).ok();
+                let end = rope.try_byte_to_char(range.end).ok();
+                let (start, end) = match (start, end) {
+                    (Some(start), Some(end))
+                        if rope.char_to_byte(start) == range.start
+                            && rope.char_to_byte(end) == range.end =>
+                    {
+                        (start, end)
+                    }
+                    _ => panic!("byte range {:?} is not on char boundaries", range),
+                };
+                rope.remove(start..end);
+                rope.insert(start, text);
+                *flat = OnceCell::new();
+                if rope.len_bytes() < ROPE_THRESHOLD / 2 {
+                    *self = TextStorage::Flat(rope.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Hand the chunk ending at `end` to a grapheme `cursor` needing the text in front of it
+#[cfg(feature = "rope_buffer")]
+fn provide_context(cursor: &mut GraphemeCursor, rope: &Rope, end: usize) {
+    let (chunk, chunk_start, _, _) = rope.chunk_at_byte(end - 1);
+    cursor.provide_context(chunk, chunk_start);
+}
+
+impl PartialEq for TextStorage {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (TextStorage::Flat(text), TextStorage::Flat(other)) => text == other,
+            #[cfg(feature = "rope_buffer")]
+            (TextStorage::Rope { rope, .. }, TextStorage::Rope { rope: other, .. }) => {
+                rope == other
+            }
+            #[cfg(feature = "rope_buffer")]
+            (TextStorage::Flat(text), TextStorage::Rope { rope, .. })
+            | (TextStorage::Rope { rope, .. }, TextStorage::Flat(text)) => rope == text,
+        }
+    }
+}
+
+impl Eq for TextStorage {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    /// `text` in every kind of storage, also in a rope below the threshold
+    fn storages(text: &str) -> Vec<TextStorage> {
+        vec![
+            TextStorage::Flat(text.to_string()),
+            #[cfg(feature = "rope_buffer")]
+            TextStorage::Rope {
+                rope: Rope::from_str(text),
+                flat: OnceCell::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn replacing_edits_the_text() {
+        for mut text in storages("echo hello") {
+            text.replace_range(5..10, "wörld");
+            text.replace_range(0..0, "> ");
+
+            assert_eq!(text.as_str(), "> echo wörld");
+        }
+    }
+
+    #[test]
+    fn chars_are_found_around_an_offset() {
+        for text in storages("ä\nbc\nd") {
+            assert_eq!(text.len(), 7);
+            assert_eq!(text.char_after(2), Some('\n'));
+            assert_eq!(text.char_before(2), Some('ä'));
+            assert_eq!(text.char_before(0), None);
+            assert_eq!(text.char_after(7), None);
+            assert_eq!(text.find_char(3, '\n'), Some(5));
+            assert_eq!(text.find_char(6, '\n'), None);
+            assert_eq!(text.rfind_char(5, '\n'), Some(2));
+            assert_eq!(text.rfind_char(2, '\n'), None);
+            assert_eq!(text.slice(3..5), "bc");
+            assert_eq!(text.chunks().collect::<String>(), "ä\nbc\nd");
+            assert!(!text.is_char_boundary(1));
+        }
+    }
+
+    #[rstest]
+    #[case("abc", 1, 0, 2)]
+    #[case("e\u{301}x", 0, 0, 3)]
+    #[case("e\u{301}x", 3, 0, 4)]
+    #[case("a\r\nb", 1, 0, 3)]
+    #[case("🇩🇪🇫🇷", 8, 0, 16)]
+    #[case("", 0, 0, 0)]
+    fn grapheme_boundaries_are_found(
+        #[case] input: &str,
+        #[case] offset: usize,
+        #[case] prev: usize,
+        #[case] next: usize,
+    ) {
+        for text in storages(input) {
+            assert_eq!(text.prev_grapheme_boundary(offset), prev);
+            assert_eq!(text.next_grapheme_boundary(offset), next);
+        }
+    }
+
+    #[cfg(feature = "rope_buffer")]
+    #[test]
+    fn large_text_moves_into_a_rope_and_back() {
+        let line = "echo 'ä line of a pasted script'\n";
+        let mut text = TextStorage::default();
+        let mut expected = String::new();
+        while expected.len() < ROPE_THRESHOLD {
+            text.replace_range(expected.len()..expected.len(), line);
+            expected.push_str(line);
+        }
+        assert!(matches!(text, TextStorage::Rope { .. }));
+        assert_eq!(text.as_str(), expected);
+
+        text.replace_range(6..8, "");
+        expected.replace_range(6..8, "");
+        assert_eq!(text.as_str(), expected);
+
+        let first_line = expected.find('\n').unwrap() + 1;
+        text.replace_range(first_line..text.len(), "");
+        assert!(matches!(text, TextStorage::Flat(_)));
+        assert_eq!(text.as_str(), "echo ' line of a pasted script'\n");
+    }
+
+    #[cfg(feature = "rope_buffer")]
+    #[test]
+    fn graphemes_spanning_chunks_of_a_rope_are_found() {
+        // Combining marks and flags across the chunk boundaries of a large rope
+        let text = TextStorage::new("e\u{301}🇩🇪".repeat(ROPE_THRESHOLD / 8));
+        let flat = TextStorage::Flat(text.as_str().to_string());
+        assert!(matches!(text, TextStorage::Rope { .. }));
+
+        let mut offset = 0;
+        while offset < text.len() {
+            let next = text.next_grapheme_boundary(offset);
+            assert_eq!(next, flat.next_grapheme_boundary(offset));
+            assert_eq!(text.prev_grapheme_boundary(next), offset);
+            offset = next;
+        }
+        assert_eq!(text, flat);
+    }
+
+    #[cfg(feature = "rope_buffer")]
+    #[test]
+    #[should_panic]
+    fn replacing_inside_a_char_of_a_rope_panics() {
+        let mut text = TextStorage::new("ä".repeat(ROPE_THRESHOLD));
+        text.replace_range(1..1, "x");
+    }
+}
@@ -0,0 +1,306 @@
+use {
+    crate::{
+        CircularCompletionHandler, DefaultCompleter, DefaultPrompt, EditCommand, EditMode, Emacs,
+        FileBackedHistory, History, Keybindings, LineBuffer, PromptEditMode, PromptViMode,
+        Reedline, ReedlineEvent, Signal, Vi, Widget, WidgetArea,
+    },
+    crossterm::event::Event,
+    nu_ansi_term::{Color, Style},
+    std::{cell::RefCell, io, rc::Rc},
+};
+
+/// Walk the user through the keybindings of the editor, one key at a time
+///
+/// Runs the editor with a tutor beneath the input that asks to try a key, like `Ctrl-R` to
+/// search the history or `Tab` to complete, and moves on to the next lesson once the edit
+/// mode reports the expected event. The first part teaches the Emacs bindings, the second one
+/// switches to the Vi mode. `Enter` continues with the next part once all lessons of a part
+/// are done, `Ctrl-D` ends the tutorial early.
+///
+/// Only available with the `demo` feature, meant for shells to ship as e.g. a
+/// `tutor keybindings` command.
+///
+/// ## Example
+/// ```no_run
+/// reedline::demo()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn demo() -> io::Result<()> {
+    let mut history = FileBackedHistory::default();
+    for entry in SAMPLE_HISTORY {
+        history.append(entry);
+    }
+    let completer = DefaultCompleter::new(SAMPLE_COMMANDS.iter().map(|c| c.to_string()).collect());
+
+    let mut line_editor = Reedline::create()?
+        .with_history(Box::new(history))?
+        .with_completion_action_handler(Box::new(
+            CircularCompletionHandler::default().with_completer(Box::new(completer)),
+        ));
+    let prompt = DefaultPrompt::default();
+
+    let parts: [(&str, Box<dyn EditMode>, &[Lesson]); 2] = [
+        ("Emacs", Box::new(Emacs::default()), EMACS_LESSONS),
+        ("Vi", Box::new(Vi::default()), VI_LESSONS),
+    ];
+    for (name, edit_mode, lessons) in parts {
+        let tutor = Rc::new(RefCell::new(Tutor::new(name, lessons)));
+        line_editor = line_editor
+            .with_edit_mode(Box::new(Recorder {
+                inner: edit_mode,
+                tutor: tutor.clone(),
+            }))
+            .with_widget(Some(Box::new(TutorWidget(tutor.clone()))));
+
+        loop {
+            match line_editor.read_line(&prompt)? {
+                Signal::CtrlD => return Ok(()),
+                _ if tutor.borrow().is_done() => break,
+                _ => {}
+            }
+        }
+    }
+
+    line_editor = line_editor.with_widget(None);
+    line_editor.print_line("That's it, you know the most important keys now.")
+}
+
+/// Commands found by searching the history in the tutorial
+const SAMPLE_HISTORY: &[&str] = &[
+    "cargo build --release",
+    "git commit -m \"first steps\"",
+    "cargo test -- --nocapture",
+    "git push origin main",
+];
+
+/// Commands completed with `Tab` in the tutorial
+const SAMPLE_COMMANDS: &[&str] = &["cargo", "clear", "git", "grep", "history"];
+
+/// A key to try and the check whether it was pressed
+struct Lesson {
+    instruction: &'static str,
+    is_done: fn(&ReedlineEvent, &PromptEditMode) -> bool,
+}
+
+const EMACS_LESSONS: &[Lesson] = &[
+    Lesson {
+        instruction: "Type a few words, then move one word to the left with Alt-b or Ctrl-Left",
+        is_done: |event, _| runs(event, &EditCommand::MoveWordLeft),
+    },
+    Lesson {
+        instruction: "Delete the word before the cursor with Ctrl-w",
+        is_done: |event, _| runs(event, &EditCommand::CutSelection),
+    },
+    Lesson {
+        instruction: "Bring the word back with Ctrl-z",
+        is_done: |event, _| runs(event, &EditCommand::Undo),
+    },
+    Lesson {
+        instruction: "Type \"g\" at the start of a word and complete it with Tab",
+        is_done: |event, _| matches!(event, ReedlineEvent::HandleTab),
+    },
+    Lesson {
+        instruction: "Search the history for \"cargo\" with Ctrl-r",
+        is_done: |event, _| matches!(event, ReedlineEvent::SearchHistory),
+    },
+];
+
+const VI_LESSONS: &[Lesson] = &[
+    Lesson {
+        instruction: "Type a few words, then press Esc to switch to normal mode",
+        is_done: |_, mode| *mode == PromptEditMode::Vi(PromptViMode::Normal),
+    },
+    Lesson {
+        instruction: "Jump to the start of the line with 0 and move one word to the right with w",
+        is_done: |event, _| runs(event, &EditCommand::MoveWordRight),
+    },
+    Lesson {
+        instruction: "Delete the word under the cursor with dw",
+        is_done: |event, _| runs(event, &EditCommand::CutWordRight),
+    },
+    Lesson {
+        instruction: "Undo the deletion with u",
+        is_done: |event, _| runs(event, &EditCommand::Undo),
+    },
+    Lesson {
+        instruction: "Press i to insert text again",
+        is_done: |_, mode| *mode == PromptEditMode::Vi(PromptViMode::Insert),
+    },
+];
+
+/// Whether `event` runs `command`, also as part of several events or commands
+fn runs(event: &ReedlineEvent, command: &EditCommand) -> bool {
+    match event {
+        ReedlineEvent::Edit(commands) => commands.contains(command),
+        ReedlineEvent::Multiple(events) => events.iter().any(|event| runs(event, command)),
+        _ => false,
+    }
+}
+
+/// Progress through the lessons of one part of the tutorial
+struct Tutor {
+    name: &'static str,
+    lessons: &'static [Lesson],
+    current: usize,
+}
+
+impl Tutor {
+    fn new(name: &'static str, lessons: &'static [Lesson]) -> Self {
+        Tutor {
+            name,
+            lessons,
+            current: 0,
+        }
+    }
+
+    /// Continue with the next lesson if `event` completes the current one
+    fn observe(&mut self, event: &ReedlineEvent, edit_mode: &PromptEditMode) {
+        if let Some(lesson) = self.lessons.get(self.current) {
+            if (lesson.is_done)(event, edit_mode) {
+                self.current += 1;
+            }
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.current >= self.lessons.len()
+    }
+
+    /// The instruction of the current lesson and a line about the progress
+    fn rows(&self) -> [String; 2] {
+        match self.lessons.get(self.current) {
+            Some(lesson) => [
+                lesson.instruction.to_string(),
+                format!(
+                    "{} lesson {} of {}, Ctrl-D ends the tutorial",
+                    self.name,
+                    self.current + 1,
+                    self.lessons.len()
+                ),
+            ],
+            None => [
+                format!("All {} lessons done!", self.name),
+                "Press Enter to continue".to_string(),
+            ],
+        }
+    }
+}
+
+/// Edit mode passing every parsed event to the tutor
+struct Recorder {
+    inner: Box<dyn EditMode>,
+    tutor: Rc<RefCell<Tutor>>,
+}
+
+impl EditMode for Recorder {
+    fn parse_event(&mut self, event: Event) -> ReedlineEvent {
+        let event = self.inner.parse_event(event);
+        self.tutor
+            .borrow_mut()
+            .observe(&event, &self.inner.edit_mode());
+        event
+    }
+
+    fn edit_mode(&self) -> PromptEditMode {
+        self.inner.edit_mode()
+    }
+
+    fn keybindings(&self) -> Option<&Keybindings> {
+        self.inner.keybindings()
+    }
+
+    fn all_keybindings(&self) -> Vec<&Keybindings> {
+        self.inner.all_keybindings()
+    }
+}
+
+/// Shows the current lesson beneath the input
+struct TutorWidget(Rc<RefCell<Tutor>>);
+
+impl Widget for TutorWidget {
+    fn height(&self) -> u16 {
+        2
+    }
+
+    fn render(
+        &mut self,
+        area: WidgetArea,
+        _line_buffer: &LineBuffer,
+        use_ansi_coloring: bool,
+    ) -> Vec<String> {
+        let [instruction, progress] = self.0.borrow().rows();
+        let instruction = truncate(&instruction, area.width);
+        let progress = truncate(&progress, area.width);
+
+        if use_ansi_coloring {
+            vec![
+                Color::Green.bold().paint(instruction).to_string(),
+                Style::new().dimmed().paint(progress).to_string(),
+            ]
+        } else {
+            vec![instruction, progress]
+        }
+    }
+}
+
+fn truncate(row: &str, columns: u16) -> String {
+    row.chars().take(columns as usize).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn edit(command: EditCommand) -> ReedlineEvent {
+        ReedlineEvent::Edit(vec![command])
+    }
+
+    #[test]
+    fn lessons_advance_on_their_event_only() {
+        let mut tutor = Tutor::new("Emacs", EMACS_LESSONS);
+        tutor.observe(&edit(EditCommand::MoveWordRight), &PromptEditMode::Emacs);
+        assert_eq!(tutor.current, 0);
+
+        tutor.observe(&edit(EditCommand::MoveWordLeft), &PromptEditMode::Emacs);
+        tutor.observe(&ReedlineEvent::HandleTab, &PromptEditMode::Emacs);
+        assert_eq!(tutor.current, 1);
+        assert_eq!(
+            tutor.rows()[1],
+            "Emacs lesson 2 of 5, Ctrl-D ends the tutorial"
+        );
+    }
+
+    #[test]
+    fn vi_lessons_follow_the_mode() {
+        let mut tutor = Tutor::new("Vi", VI_LESSONS);
+        tutor.observe(
+            &ReedlineEvent::Repaint,
+            &PromptEditMode::Vi(PromptViMode::Insert),
+        );
+        assert_eq!(tutor.current, 0);
+
+        tutor.observe(
+            &ReedlineEvent::Repaint,
+            &PromptEditMode::Vi(PromptViMode::Normal),
+        );
+        let motion = ReedlineEvent::Multiple(vec![edit(EditCommand::MoveWordRight)]);
+        tutor.observe(&motion, &PromptEditMode::Vi(PromptViMode::Normal));
+        assert_eq!(tutor.current, 2);
+    }
+
+    #[test]
+    fn finished_part_asks_to_continue() {
+        let mut tutor = Tutor::new("Emacs", &EMACS_LESSONS[..1]);
+        tutor.observe(&edit(EditCommand::MoveWordLeft), &PromptEditMode::Emacs);
+
+        assert!(tutor.is_done());
+        assert_eq!(
+            tutor.rows(),
+            [
+                "All Emacs lessons done!".to_string(),
+                "Press Enter to continue".to_string()
+            ]
+        );
+    }
+}
@@ -1,6 +1,9 @@
-use crossterm::event::Event;
+use crossterm::event::{Event, MouseButton, MouseEvent, MouseEventKind};
 
-use crate::{enums::ReedlineEvent, PromptEditMode};
+use crate::{
+    enums::{EditCommand, ReedlineEvent},
+    Keybindings, PromptEditMode,
+};
 
 /// Define the style of parsing for the edit events
 /// Available default options:
@@ -12,4 +15,33 @@ pub trait EditMode {
 
     /// What to display in the prompt indicator
     fn edit_mode(&self) -> PromptEditMode;
+
+    /// The keybindings currently in effect, to show them e.g. in the command palette
+    ///
+    /// Edit modes without a keybinding table can rely on the default, which reports none.
+    fn keybindings(&self) -> Option<&Keybindings> {
+        None
+    }
+
+    /// Every keybinding table of the edit mode, also the ones of currently inactive modes
+    ///
+    /// Used to check the bindings for inconsistencies, defaults to [`EditMode::keybindings()`].
+    fn all_keybindings(&self) -> Vec<&Keybindings> {
+        self.keybindings().into_iter().collect()
+    }
+}
+
+/// Translate a mouse event, a left click moves the cursor to the clicked position
+/// and the wheel scrolls over the row it is turned on
+///
+/// Mouse events are only reported with [`Reedline::with_mouse_capture()`](crate::Reedline::with_mouse_capture).
+pub(crate) fn parse_mouse_event(event: MouseEvent) -> ReedlineEvent {
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            ReedlineEvent::Edit(vec![EditCommand::MoveToPosition(event.row, event.column)])
+        }
+        MouseEventKind::ScrollUp => ReedlineEvent::ScrollUp(event.row),
+        MouseEventKind::ScrollDown => ReedlineEvent::ScrollDown(event.row),
+        _ => ReedlineEvent::Mouse,
+    }
 }
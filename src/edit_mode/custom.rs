@@ -0,0 +1,197 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    enums::{EditCommand, ReedlineEvent},
+    PromptEditMode,
+};
+
+use super::{base::parse_mouse_event, keybindings::Keybindings, EditMode};
+
+/// An edit mode defined purely by a keybinding table
+///
+/// Lets hosts define bespoke modes (e.g. a "notebook mode") without reimplementing
+/// the event dispatch. Keys are resolved in this order:
+/// 1. The keybinding table, so any key including printable characters can be rebound
+/// 2. Printable characters insert themselves (can be disabled)
+/// 3. `Enter` submits the buffer
+/// 4. Everything else results in the fallback event, [`ReedlineEvent::None`] by default
+///
+/// ## Example
+/// ```rust
+/// use crossterm::event::{KeyCode, KeyModifiers};
+/// use reedline::{CustomEditMode, EditCommand, Keybindings, Reedline, ReedlineEvent};
+///
+/// let mut keybindings = Keybindings::new();
+/// keybindings.add_binding(
+///     KeyModifiers::CONTROL,
+///     KeyCode::Char('x'),
+///     ReedlineEvent::Edit(vec![EditCommand::Clear]),
+/// );
+///
+/// let edit_mode = Box::new(CustomEditMode::new("notebook", keybindings));
+/// let mut line_editor = Reedline::create()?.with_edit_mode(edit_mode);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct CustomEditMode {
+    name: String,
+    keybindings: Keybindings,
+    insert_printable: bool,
+    fallback: ReedlineEvent,
+}
+
+impl EditMode for CustomEditMode {
+    fn parse_event(&mut self, event: Event) -> ReedlineEvent {
+        match event {
+            Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) => {
+                if let Some(event) = self.keybindings.find_binding(modifiers, code) {
+                    return event;
+                }
+
+                match (modifiers, code) {
+                    (modifiers, KeyCode::Char(c))
+                        if self.insert_printable && is_printable_input(modifiers) =>
+                    {
+                        let c = if modifiers == KeyModifiers::SHIFT {
+                            c.to_ascii_uppercase()
+                        } else {
+                            c
+                        };
+                        ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])
+                    }
+                    (KeyModifiers::NONE, KeyCode::Enter) => ReedlineEvent::Enter,
+                    _ => self.fallback.clone(),
+                }
+            }
+
+            Event::Mouse(event) => parse_mouse_event(event),
+            Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
+            // Focus reporting and bracketed paste are not enabled
+            Event::FocusGained | Event::FocusLost | Event::Paste(_) => ReedlineEvent::None,
+        }
+    }
+
+    fn edit_mode(&self) -> PromptEditMode {
+        PromptEditMode::Custom(self.name.clone())
+    }
+
+    fn keybindings(&self) -> Option<&Keybindings> {
+        Some(&self.keybindings)
+    }
+}
+
+impl CustomEditMode {
+    /// Create an edit mode named `name` (shown by the prompt) from the given keybindings
+    pub fn new(name: impl Into<String>, keybindings: Keybindings) -> Self {
+        CustomEditMode {
+            name: name.into(),
+            keybindings,
+            insert_printable: true,
+            fallback: ReedlineEvent::None,
+        }
+    }
+
+    /// A builder to configure if printable characters without a binding insert themselves
+    pub fn with_printable_insertion(mut self, insert_printable: bool) -> CustomEditMode {
+        self.insert_printable = insert_printable;
+        self
+    }
+
+    /// A builder to configure the event for keys that are neither bound nor handled otherwise
+    pub fn with_fallback(mut self, fallback: ReedlineEvent) -> CustomEditMode {
+        self.fallback = fallback;
+        self
+    }
+}
+
+/// Modifier combinations that still produce a printable character
+///
+/// `CONTROL | ALT` is reported for 'alt gr' on non american keyboards.
+fn is_printable_input(modifiers: KeyModifiers) -> bool {
+    modifiers == KeyModifiers::NONE
+        || modifiers == KeyModifiers::SHIFT
+        || modifiers == KeyModifiers::CONTROL | KeyModifiers::ALT
+        || modifiers == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn key(modifiers: KeyModifiers, code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    fn notebook_mode() -> CustomEditMode {
+        let mut keybindings = Keybindings::new();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('r'),
+            ReedlineEvent::Enter,
+        );
+        keybindings.add_binding(
+            KeyModifiers::NONE,
+            KeyCode::Char('#'),
+            ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart]),
+        );
+        CustomEditMode::new("notebook", keybindings)
+    }
+
+    #[rstest]
+    #[case(KeyModifiers::NONE, KeyCode::Char('a'), ReedlineEvent::Edit(vec![EditCommand::InsertChar('a')]))]
+    #[case(KeyModifiers::SHIFT, KeyCode::Char('a'), ReedlineEvent::Edit(vec![EditCommand::InsertChar('A')]))]
+    #[case(KeyModifiers::CONTROL | KeyModifiers::ALT, KeyCode::Char('@'), ReedlineEvent::Edit(vec![EditCommand::InsertChar('@')]))]
+    #[case(KeyModifiers::CONTROL, KeyCode::Char('r'), ReedlineEvent::Enter)]
+    #[case(KeyModifiers::NONE, KeyCode::Char('#'), ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart]))]
+    #[case(KeyModifiers::NONE, KeyCode::Enter, ReedlineEvent::Enter)]
+    #[case(KeyModifiers::CONTROL, KeyCode::Char('q'), ReedlineEvent::None)]
+    #[case(KeyModifiers::NONE, KeyCode::F(1), ReedlineEvent::None)]
+    fn dispatches_keys(
+        #[case] modifiers: KeyModifiers,
+        #[case] code: KeyCode,
+        #[case] expected: ReedlineEvent,
+    ) {
+        let mut edit_mode = notebook_mode();
+
+        assert_eq!(edit_mode.parse_event(key(modifiers, code)), expected);
+    }
+
+    #[test]
+    fn printable_insertion_can_be_disabled() {
+        let mut edit_mode = notebook_mode()
+            .with_printable_insertion(false)
+            .with_fallback(ReedlineEvent::Repaint);
+
+        assert_eq!(
+            edit_mode.parse_event(key(KeyModifiers::NONE, KeyCode::Char('a'))),
+            ReedlineEvent::Repaint
+        );
+        assert_eq!(
+            edit_mode.parse_event(key(KeyModifiers::NONE, KeyCode::Char('#'))),
+            ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart])
+        );
+    }
+
+    #[test]
+    fn resize_is_forwarded() {
+        let mut edit_mode = notebook_mode();
+
+        assert_eq!(
+            edit_mode.parse_event(Event::Resize(80, 24)),
+            ReedlineEvent::Resize(80, 24)
+        );
+    }
+
+    #[test]
+    fn prompt_shows_mode_name() {
+        let edit_mode = notebook_mode();
+
+        assert!(matches!(
+            edit_mode.edit_mode(),
+            PromptEditMode::Custom(name) if name == "notebook"
+        ));
+    }
+}
@@ -6,17 +6,55 @@ use crate::{
     PromptEditMode,
 };
 
-use super::{keybindings::Keybindings, EditMode};
+use super::{base::parse_mouse_event, keybindings::Keybindings, EditMode};
 
 /// This parses the incoming Events like a emacs style-editor
+///
+/// A key bound to [`ReedlineEvent::UniversalArgument`] repeats the next edit or movement,
+/// e.g. `C-u 4 C-f` moves four characters to the right once `C-u` is bound to it:
+///
+/// ```rust
+/// use crossterm::event::{KeyCode, KeyModifiers};
+/// use reedline::{default_emacs_keybindings, Emacs, ReedlineEvent};
+///
+/// let mut keybindings = default_emacs_keybindings();
+/// keybindings.add_binding(
+///     KeyModifiers::CONTROL,
+///     KeyCode::Char('u'),
+///     ReedlineEvent::UniversalArgument,
+/// );
+/// let edit_mode = Emacs::new(keybindings);
+/// ```
+///
+/// Unless `C-x` is bound to something else, it starts a key sequence: `C-x C-x` swaps the
+/// cursor and the selection anchor set with `C-space`, like `exchange-point-and-mark`.
 pub struct Emacs {
     keybindings: Keybindings,
+    // Count of the universal argument, until the key it applies to
+    count: Option<Count>,
+    // `C-x` was pressed and the next key completes the sequence
+    after_ctrl_x: bool,
+}
+
+/// Count given by the universal argument and the digits typed after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Count {
+    times: usize,
+    digits: Option<usize>,
+}
+
+impl Count {
+    fn value(self) -> usize {
+        self.digits.unwrap_or(self.times)
+    }
 }
 
 impl Default for Emacs {
     fn default() -> Self {
         Emacs {
             keybindings: default_emacs_keybindings(),
+            count: None,
+            after_ctrl_x: false,
         }
     }
 }
@@ -24,48 +62,126 @@ impl Default for Emacs {
 impl EditMode for Emacs {
     fn parse_event(&mut self, event: Event) -> ReedlineEvent {
         match event {
-            Event::Key(KeyEvent { code, modifiers }) => match (modifiers, code) {
-                (KeyModifiers::NONE, KeyCode::Char(c)) => {
-                    ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])
-                }
-                // This combination of modifiers (CONTROL | ALT) is needed for non american keyboards.
-                // There is a special key called 'alt gr' that is captured with the combination
-                // of those two modifiers
-                (m, KeyCode::Char(c)) if m == KeyModifiers::CONTROL | KeyModifiers::ALT => {
-                    ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])
-                }
-
-                (m, KeyCode::Char(c))
-                    if m == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT =>
+            Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) => {
+                if let (Some(count), KeyModifiers::NONE, KeyCode::Char(c)) =
+                    (&mut self.count, modifiers, code)
                 {
-                    ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])
+                    if let Some(digit) = c.to_digit(10) {
+                        let digits = count.digits.unwrap_or_default();
+                        count.digits =
+                            Some(digits.saturating_mul(10).saturating_add(digit as usize));
+                        return ReedlineEvent::None;
+                    }
                 }
 
-                (KeyModifiers::SHIFT, KeyCode::Char(c)) => {
-                    ReedlineEvent::Edit(vec![EditCommand::InsertChar(c.to_ascii_uppercase())])
+                let is_ctrl_x = modifiers == KeyModifiers::CONTROL && code == KeyCode::Char('x');
+                if std::mem::take(&mut self.after_ctrl_x) {
+                    if is_ctrl_x {
+                        return ReedlineEvent::Edit(vec![EditCommand::SwapCursorAndAnchor]);
+                    }
+                } else if is_ctrl_x && self.keybindings.find_binding(modifiers, code).is_none() {
+                    self.after_ctrl_x = true;
+                    return ReedlineEvent::None;
                 }
 
-                (KeyModifiers::NONE, KeyCode::Enter) => ReedlineEvent::Enter,
-                _ => self
-                    .keybindings
-                    .find_binding(modifiers, code)
-                    .unwrap_or(ReedlineEvent::None),
-            },
+                match self.parse_key(modifiers, code) {
+                    ReedlineEvent::UniversalArgument => {
+                        self.count = Some(match self.count {
+                            Some(Count {
+                                times,
+                                digits: None,
+                            }) => Count {
+                                times: times.saturating_mul(4),
+                                digits: None,
+                            },
+                            Some(count) => count,
+                            None => Count {
+                                times: 4,
+                                digits: None,
+                            },
+                        });
+                        ReedlineEvent::None
+                    }
+                    event => match self.count.take() {
+                        Some(count) => repeat(event, count.value()),
+                        None => event,
+                    },
+                }
+            }
 
-            Event::Mouse(_) => ReedlineEvent::Mouse,
+            Event::Mouse(event) => parse_mouse_event(event),
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
+            // Focus reporting and bracketed paste are not enabled
+            Event::FocusGained | Event::FocusLost | Event::Paste(_) => ReedlineEvent::None,
         }
     }
 
     fn edit_mode(&self) -> PromptEditMode {
         PromptEditMode::Emacs
     }
+
+    fn keybindings(&self) -> Option<&Keybindings> {
+        Some(&self.keybindings)
+    }
 }
 
 impl Emacs {
     /// Emacs style input parsing constructor if you want to use custom keybindings
     pub fn new(keybindings: Keybindings) -> Self {
-        Emacs { keybindings }
+        Emacs {
+            keybindings,
+            count: None,
+            after_ctrl_x: false,
+        }
+    }
+
+    fn parse_key(&self, modifiers: KeyModifiers, code: KeyCode) -> ReedlineEvent {
+        match (modifiers, code) {
+            (KeyModifiers::NONE, KeyCode::Char(c)) => {
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])
+            }
+            // This combination of modifiers (CONTROL | ALT) is needed for non american keyboards.
+            // There is a special key called 'alt gr' that is captured with the combination
+            // of those two modifiers
+            (m, KeyCode::Char(c)) if m == KeyModifiers::CONTROL | KeyModifiers::ALT => {
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])
+            }
+
+            (m, KeyCode::Char(c))
+                if m == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT =>
+            {
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])
+            }
+
+            (KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar(c.to_ascii_uppercase())])
+            }
+
+            (KeyModifiers::NONE, KeyCode::Enter) => ReedlineEvent::Enter,
+            _ => self
+                .keybindings
+                .find_binding(modifiers, code)
+                .unwrap_or(ReedlineEvent::None),
+        }
+    }
+}
+
+/// `event` repeated `count` times, if it is an edit or a movement
+fn repeat(event: ReedlineEvent, count: usize) -> ReedlineEvent {
+    match event {
+        ReedlineEvent::Edit(commands) => {
+            ReedlineEvent::Edit((0..count).flat_map(|_| commands.iter().cloned()).collect())
+        }
+        ReedlineEvent::Up
+        | ReedlineEvent::Down
+        | ReedlineEvent::PreviousHistory
+        | ReedlineEvent::NextHistory
+        | ReedlineEvent::Multiple(_) => {
+            ReedlineEvent::Multiple((0..count).map(|_| event.clone()).collect())
+        }
+        event => event,
     }
 }
 
@@ -77,15 +193,93 @@ mod test {
     #[test]
     fn ctrl_l_leads_to_clear_screen_event() {
         let mut emacs = Emacs::default();
-        let ctrl_l = Event::Key(KeyEvent {
-            modifiers: KeyModifiers::CONTROL,
-            code: KeyCode::Char('l'),
-        });
+        let ctrl_l = Event::Key(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::CONTROL));
         let result = emacs.parse_event(ctrl_l);
 
         assert_eq!(result, ReedlineEvent::ClearScreen);
     }
 
+    #[test]
+    fn jump_list_is_bound() {
+        let mut emacs = Emacs::default();
+        let ctrl_o = Event::Key(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL));
+        let alt_i = Event::Key(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::ALT));
+
+        assert_eq!(
+            emacs.parse_event(ctrl_o),
+            ReedlineEvent::Edit(vec![EditCommand::JumpBack])
+        );
+        assert_eq!(
+            emacs.parse_event(alt_i),
+            ReedlineEvent::Edit(vec![EditCommand::JumpForward])
+        );
+    }
+
+    #[test]
+    fn ctrl_x_ctrl_x_swaps_cursor_and_anchor() {
+        let mut emacs = Emacs::default();
+        let ctrl_x = || Event::Key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL));
+
+        assert_eq!(emacs.parse_event(ctrl_x()), ReedlineEvent::None);
+        assert_eq!(
+            emacs.parse_event(ctrl_x()),
+            ReedlineEvent::Edit(vec![EditCommand::SwapCursorAndAnchor])
+        );
+
+        // Any other key ends the sequence and is handled as usual
+        emacs.parse_event(ctrl_x());
+        let ctrl_l = Event::Key(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::CONTROL));
+        assert_eq!(emacs.parse_event(ctrl_l), ReedlineEvent::ClearScreen);
+        assert_eq!(emacs.parse_event(ctrl_x()), ReedlineEvent::None);
+    }
+
+    #[test]
+    fn shift_or_alt_enter_inserts_newline() {
+        let mut emacs = Emacs::default();
+        let shift_enter = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT));
+        let alt_enter = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT));
+
+        assert_eq!(emacs.parse_event(shift_enter), ReedlineEvent::InsertNewline);
+        assert_eq!(emacs.parse_event(alt_enter), ReedlineEvent::InsertNewline);
+    }
+
+    #[test]
+    fn left_click_moves_to_position() {
+        use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+
+        let mut emacs = Emacs::default();
+        let mouse = |kind| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column: 7,
+                row: 3,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+
+        assert_eq!(
+            emacs.parse_event(mouse(MouseEventKind::Down(MouseButton::Left))),
+            ReedlineEvent::Edit(vec![EditCommand::MoveToPosition(3, 7)])
+        );
+        assert_eq!(
+            emacs.parse_event(mouse(MouseEventKind::ScrollUp)),
+            ReedlineEvent::ScrollUp(3)
+        );
+        assert_eq!(
+            emacs.parse_event(mouse(MouseEventKind::Moved)),
+            ReedlineEvent::Mouse
+        );
+    }
+
+    #[test]
+    fn alt_digit_leads_to_menu_select_event() {
+        let mut emacs = Emacs::default();
+        let alt_1 = Event::Key(KeyEvent::new(KeyCode::Char('1'), KeyModifiers::ALT));
+        let result = emacs.parse_event(alt_1);
+
+        assert_eq!(result, ReedlineEvent::MenuSelect(1));
+    }
+
     #[test]
     fn overriding_default_keybindings_works() {
         let mut keybindings = default_emacs_keybindings();
@@ -96,10 +290,7 @@ mod test {
         );
 
         let mut emacs = Emacs::new(keybindings);
-        let ctrl_l = Event::Key(KeyEvent {
-            modifiers: KeyModifiers::CONTROL,
-            code: KeyCode::Char('l'),
-        });
+        let ctrl_l = Event::Key(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::CONTROL));
         let result = emacs.parse_event(ctrl_l);
 
         assert_eq!(result, ReedlineEvent::HandleTab);
@@ -108,10 +299,7 @@ mod test {
     #[test]
     fn inserting_character_works() {
         let mut emacs = Emacs::default();
-        let l = Event::Key(KeyEvent {
-            modifiers: KeyModifiers::NONE,
-            code: KeyCode::Char('l'),
-        });
+        let l = Event::Key(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE));
         let result = emacs.parse_event(l);
 
         assert_eq!(
@@ -124,10 +312,7 @@ mod test {
     fn inserting_capital_character_works() {
         let mut emacs = Emacs::default();
 
-        let uppercase_l = Event::Key(KeyEvent {
-            modifiers: KeyModifiers::SHIFT,
-            code: KeyCode::Char('l'),
-        });
+        let uppercase_l = Event::Key(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::SHIFT));
         let result = emacs.parse_event(uppercase_l);
 
         assert_eq!(
@@ -141,10 +326,7 @@ mod test {
         let keybindings = Keybindings::default();
 
         let mut emacs = Emacs::new(keybindings);
-        let ctrl_l = Event::Key(KeyEvent {
-            modifiers: KeyModifiers::CONTROL,
-            code: KeyCode::Char('l'),
-        });
+        let ctrl_l = Event::Key(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::CONTROL));
         let result = emacs.parse_event(ctrl_l);
 
         assert_eq!(result, ReedlineEvent::None);
@@ -154,10 +336,7 @@ mod test {
     fn inserting_capital_character_for_non_ascii_remains_as_is() {
         let mut emacs = Emacs::default();
 
-        let uppercase_l = Event::Key(KeyEvent {
-            modifiers: KeyModifiers::SHIFT,
-            code: KeyCode::Char('😀'),
-        });
+        let uppercase_l = Event::Key(KeyEvent::new(KeyCode::Char('😀'), KeyModifiers::SHIFT));
         let result = emacs.parse_event(uppercase_l);
 
         assert_eq!(
@@ -165,4 +344,76 @@ mod test {
             ReedlineEvent::Edit(vec![EditCommand::InsertChar('😀')])
         );
     }
+
+    fn emacs_with_universal_argument() -> Emacs {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('u'),
+            ReedlineEvent::UniversalArgument,
+        );
+        Emacs::new(keybindings)
+    }
+
+    fn press(emacs: &mut Emacs, modifiers: KeyModifiers, c: char) -> ReedlineEvent {
+        emacs.parse_event(Event::Key(KeyEvent::new(KeyCode::Char(c), modifiers)))
+    }
+
+    #[test]
+    fn universal_argument_repeats_next_edit_by_typed_count() {
+        let mut emacs = emacs_with_universal_argument();
+
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::CONTROL, 'u'),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::NONE, '1'),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::NONE, '2'),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::CONTROL, 'f'),
+            ReedlineEvent::Edit(vec![EditCommand::MoveRight; 12])
+        );
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::CONTROL, 'f'),
+            ReedlineEvent::Edit(vec![EditCommand::MoveRight])
+        );
+    }
+
+    #[test]
+    fn universal_argument_without_digits_multiplies_by_four() {
+        let mut emacs = emacs_with_universal_argument();
+
+        press(&mut emacs, KeyModifiers::CONTROL, 'u');
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::NONE, 'a'),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('a'); 4])
+        );
+
+        press(&mut emacs, KeyModifiers::CONTROL, 'u');
+        press(&mut emacs, KeyModifiers::CONTROL, 'u');
+        assert_eq!(
+            press(&mut emacs, KeyModifiers::CONTROL, 'p'),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::PreviousHistory; 16])
+        );
+    }
+
+    #[test]
+    fn universal_argument_does_not_repeat_submit() {
+        let mut emacs = emacs_with_universal_argument();
+
+        press(&mut emacs, KeyModifiers::CONTROL, 'u');
+        assert_eq!(
+            emacs.parse_event(Event::Key(KeyEvent::new(
+                KeyCode::Enter,
+                KeyModifiers::NONE
+            ))),
+            ReedlineEvent::Enter
+        );
+    }
 }
@@ -1,21 +1,254 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, str::FromStr};
 
 use crate::enums::ReedlineEvent;
 
 use {
-    crate::EditCommand,
-    crossterm::event::{KeyCode, KeyModifiers},
-    serde::{Deserialize, Serialize},
+    crate::{menu::CommandPalette, EditCommand},
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    serde::{de, Deserialize, Deserializer, Serialize, Serializer},
 };
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+/// A key together with the modifiers held while pressing it
+///
+/// Combinations are written in a notation like `Ctrl-Alt-Left`, `F5` or `Shift-Tab`, see
+/// [`KeyCombination::from_str()`]. Display prints the same notation and (de)serializing uses
+/// it, so keybindings can be loaded from configuration files.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct KeyCombination {
     modifier: KeyModifiers,
     key_code: KeyCode,
 }
 
+/// Modifiers in the order they are printed
+const MODIFIER_NAMES: [(KeyModifiers, &str); 6] = [
+    (KeyModifiers::CONTROL, "Ctrl"),
+    (KeyModifiers::ALT, "Alt"),
+    (KeyModifiers::SHIFT, "Shift"),
+    (KeyModifiers::SUPER, "Super"),
+    (KeyModifiers::HYPER, "Hyper"),
+    (KeyModifiers::META, "Meta"),
+];
+
+/// Names of the keys that are not written as their character, the first name of a key is the
+/// one it is printed with
+const KEY_NAMES: [(&str, KeyCode); 46] = [
+    ("Space", KeyCode::Char(' ')),
+    ("Enter", KeyCode::Enter),
+    ("Return", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Backspace", KeyCode::Backspace),
+    ("Esc", KeyCode::Esc),
+    ("Escape", KeyCode::Esc),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PgUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("PgDn", KeyCode::PageDown),
+    ("Insert", KeyCode::Insert),
+    ("Ins", KeyCode::Insert),
+    ("Delete", KeyCode::Delete),
+    ("Del", KeyCode::Delete),
+    ("Null", KeyCode::Null),
+    ("CapsLock", KeyCode::CapsLock),
+    ("ScrollLock", KeyCode::ScrollLock),
+    ("NumLock", KeyCode::NumLock),
+    ("PrintScreen", KeyCode::PrintScreen),
+    ("Pause", KeyCode::Pause),
+    ("Menu", KeyCode::Menu),
+    // Keypad keys are reported like the main keys producing the same input, only the
+    // keypad center has a code of its own
+    ("KeypadBegin", KeyCode::KeypadBegin),
+    ("Kp0", KeyCode::Char('0')),
+    ("Kp1", KeyCode::Char('1')),
+    ("Kp2", KeyCode::Char('2')),
+    ("Kp3", KeyCode::Char('3')),
+    ("Kp4", KeyCode::Char('4')),
+    ("Kp5", KeyCode::Char('5')),
+    ("Kp6", KeyCode::Char('6')),
+    ("Kp7", KeyCode::Char('7')),
+    ("Kp8", KeyCode::Char('8')),
+    ("Kp9", KeyCode::Char('9')),
+    ("KpDecimal", KeyCode::Char('.')),
+    ("KpDivide", KeyCode::Char('/')),
+    ("KpMultiply", KeyCode::Char('*')),
+    ("KpSubtract", KeyCode::Char('-')),
+    ("KpAdd", KeyCode::Char('+')),
+    ("KpEnter", KeyCode::Enter),
+];
+
+/// Error of parsing a [`KeyCombination`] from its notation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyNotationError {
+    /// The notation names no key
+    Empty,
+    /// A part before the key is not a modifier
+    UnknownModifier(String),
+    /// The key is neither a single character nor a known key name
+    UnknownKey(String),
+}
+
+impl fmt::Display for KeyNotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyNotationError::Empty => f.write_str("no key given"),
+            KeyNotationError::UnknownModifier(modifier) => {
+                write!(f, "unknown modifier '{}'", modifier)
+            }
+            KeyNotationError::UnknownKey(key) => write!(f, "unknown key '{}'", key),
+        }
+    }
+}
+
+impl std::error::Error for KeyNotationError {}
+
+impl KeyCombination {
+    /// Combination of the key and modifiers as reported by crossterm
+    pub fn new(modifier: KeyModifiers, key_code: KeyCode) -> Self {
+        KeyCombination { modifier, key_code }
+    }
+
+    /// The modifiers of the combination
+    pub fn modifier(&self) -> KeyModifiers {
+        self.modifier
+    }
+
+    /// The key of the combination
+    pub fn key_code(&self) -> KeyCode {
+        self.key_code
+    }
+}
+
+impl fmt::Display for KeyCombination {
+    /// Human readable notation like `Ctrl-a` or `Alt-Left`, parsed back by
+    /// [`KeyCombination::from_str()`]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in MODIFIER_NAMES {
+            if self.modifier.contains(modifier) {
+                write!(f, "{}-", name)?;
+            }
+        }
+        match self.key_code {
+            // Terminals report Shift-Tab as its own key
+            KeyCode::BackTab if self.modifier.contains(KeyModifiers::SHIFT) => f.write_str("Tab"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            key_code => match KEY_NAMES.iter().find(|(_, code)| *code == key_code) {
+                // Digits and operators are named by the keypad entries
+                Some((name, _)) if !name.starts_with("Kp") => f.write_str(name),
+                _ => match key_code {
+                    KeyCode::Char(c) => write!(f, "{}", c),
+                    key_code => write!(f, "{:?}", key_code),
+                },
+            },
+        }
+    }
+}
+
+impl FromStr for KeyCombination {
+    type Err = KeyNotationError;
+
+    /// Parse a notation like `ctrl-alt-left`, `F5` or `Shift+Tab`
+    ///
+    /// Modifiers (`Ctrl`/`Control`, `Alt`, `Shift`, `Super`, `Hyper`, `Meta`) come first,
+    /// separated from each other and from the key by `-` or `+`. The key is a single character,
+    /// `F1` to `F255` or a key name like `Enter`, `Esc`, `PageUp` or `Space`. Names are not case
+    /// sensitive, characters are.
+    ///
+    /// The notation is normalized to the events terminals report: `Shift-Tab` is the `BackTab`
+    /// key with `Shift` held, `Shift` together with a lowercase letter gives the uppercase
+    /// letter, and the keypad keys `Kp0` to `Kp9`, `KpAdd`, `KpEnter`, ... are the keys producing
+    /// the same input.
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        let mut modifier = KeyModifiers::NONE;
+        let mut rest = notation;
+        // A separator at the start or the end belongs to the key, as in `Ctrl--`
+        while let Some(separator) = rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| *c == '-' || *c == '+')
+            .map(|(idx, _)| idx)
+            .filter(|idx| idx + 1 < rest.len())
+        {
+            let name = &rest[..separator];
+            let (flag, _) = MODIFIER_NAMES
+                .iter()
+                .chain(&[(KeyModifiers::CONTROL, "Control")])
+                .find(|(_, known)| known.eq_ignore_ascii_case(name))
+                .ok_or_else(|| KeyNotationError::UnknownModifier(name.to_string()))?;
+            modifier |= *flag;
+            rest = &rest[separator + 1..];
+        }
+
+        let key_code = parse_key(rest)?;
+        Ok(match key_code {
+            KeyCode::Tab if modifier.contains(KeyModifiers::SHIFT) => {
+                KeyCombination::new(modifier, KeyCode::BackTab)
+            }
+            KeyCode::BackTab => KeyCombination::new(modifier | KeyModifiers::SHIFT, key_code),
+            KeyCode::Char(c) if modifier.contains(KeyModifiers::SHIFT) => {
+                KeyCombination::new(modifier, KeyCode::Char(c.to_ascii_uppercase()))
+            }
+            key_code => KeyCombination::new(modifier, key_code),
+        })
+    }
+}
+
+fn parse_key(key: &str) -> Result<KeyCode, KeyNotationError> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => return Err(KeyNotationError::Empty),
+        (Some(c), None) => return Ok(KeyCode::Char(c)),
+        _ => {}
+    }
+
+    if let Some((_, key_code)) = KEY_NAMES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+    {
+        return Ok(*key_code);
+    }
+
+    key.strip_prefix(|c: char| c == 'f' || c == 'F')
+        .and_then(|number| number.parse::<u8>().ok())
+        .filter(|number| *number > 0)
+        .map(KeyCode::F)
+        .ok_or_else(|| KeyNotationError::UnknownKey(key.to_string()))
+}
+
+impl Serialize for KeyCombination {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyCombination {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let notation = String::deserialize(deserializer)?;
+        notation.parse().map_err(de::Error::custom)
+    }
+}
+
+impl From<KeyEvent> for KeyCombination {
+    fn from(event: KeyEvent) -> Self {
+        KeyCombination::new(event.modifiers, event.code)
+    }
+}
+
+impl From<KeyCombination> for KeyEvent {
+    fn from(key_combo: KeyCombination) -> Self {
+        KeyEvent::new(key_combo.key_code, key_combo.modifier)
+    }
+}
+
+/// Mapping of key combinations to the [`ReedlineEvent`] they trigger
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Keybindings {
+    /// The bound key combinations
     pub bindings: HashMap<KeyCombination, ReedlineEvent>,
 }
 
@@ -26,16 +259,19 @@ impl Default for Keybindings {
 }
 
 impl Keybindings {
+    /// Create a table without any bindings
     pub fn new() -> Self {
         Self {
             bindings: HashMap::new(),
         }
     }
 
+    /// Create a table without any bindings
     pub fn empty() -> Self {
         Self::new()
     }
 
+    /// Bind `command` to the key combination, replacing a previous binding
     pub fn add_binding(
         &mut self,
         modifier: KeyModifiers,
@@ -46,10 +282,38 @@ impl Keybindings {
         self.bindings.insert(key_combo, command);
     }
 
+    /// Bind `modifier` together with the digits `1` to `9` to select the value listed at that
+    /// position of an open menu, see [`ReedlineEvent::MenuSelect`]
+    ///
+    /// The default Emacs and Vi insert keybindings use `Alt`.
+    pub fn add_menu_quick_select(&mut self, modifier: KeyModifiers) {
+        for position in 1..=9 {
+            let digit = std::char::from_digit(position, 10).unwrap_or_default();
+            self.add_binding(
+                modifier,
+                KeyCode::Char(digit),
+                ReedlineEvent::MenuSelect(position as usize),
+            );
+        }
+    }
+
+    /// The event bound to the key combination, if any
     pub fn find_binding(&self, modifier: KeyModifiers, key_code: KeyCode) -> Option<ReedlineEvent> {
         let key_combo = KeyCombination { modifier, key_code };
         self.bindings.get(&key_combo).cloned()
     }
+
+    /// All key combinations bound to `event`, sorted by their notation
+    pub fn find_keys(&self, event: &ReedlineEvent) -> Vec<&KeyCombination> {
+        let mut keys: Vec<&KeyCombination> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| *bound == event)
+            .map(|(key_combo, _)| key_combo)
+            .collect();
+        keys.sort_by_cached_key(|key_combo| key_combo.to_string());
+        keys
+    }
 }
 
 fn edit_bind(command: EditCommand) -> ReedlineEvent {
@@ -87,18 +351,33 @@ pub fn default_emacs_keybindings() -> Keybindings {
     kb.add_binding(KM::CONTROL, KC::Char('b'), edit_bind(EC::MoveLeft));
     kb.add_binding(KM::CONTROL, KC::Char('f'), edit_bind(EC::MoveRight));
     kb.add_binding(KM::CONTROL, KC::Char('h'), edit_bind(EC::Backspace));
-    kb.add_binding(KM::CONTROL, KC::Char('w'), edit_bind(EC::CutWordLeft));
+    kb.add_binding(KM::CONTROL, KC::Char('w'), edit_bind(EC::CutSelection));
+    kb.add_binding(KM::CONTROL, KC::Char(' '), edit_bind(EC::SetAnchor));
     kb.add_binding(KM::CONTROL, KC::Char('p'), ReedlineEvent::PreviousHistory);
     kb.add_binding(KM::CONTROL, KC::Char('n'), ReedlineEvent::NextHistory);
     kb.add_binding(KM::CONTROL, KC::Char('r'), ReedlineEvent::SearchHistory);
+    kb.add_binding(
+        KM::CONTROL,
+        KC::Char('s'),
+        ReedlineEvent::SearchHistoryForward,
+    );
     kb.add_binding(KM::CONTROL, KC::Char('t'), edit_bind(EC::SwapGraphemes));
     kb.add_binding(KM::CONTROL, KC::Char('l'), ReedlineEvent::ClearScreen);
+    kb.add_binding(KM::CONTROL, KC::Char('o'), edit_bind(EC::JumpBack));
+    kb.add_binding(KM::ALT, KC::Char('i'), edit_bind(EC::JumpForward));
     kb.add_binding(KM::ALT, KC::Char('b'), edit_bind(EC::MoveWordLeft));
     kb.add_binding(KM::ALT, KC::Char('f'), edit_bind(EC::MoveWordRight));
     kb.add_binding(KM::ALT, KC::Char('d'), edit_bind(EC::CutWordRight));
+    kb.add_binding(KM::ALT, KC::Char('w'), edit_bind(EC::CopySelection));
     kb.add_binding(KM::ALT, KC::Char('u'), edit_bind(EC::UppercaseWord));
     kb.add_binding(KM::ALT, KC::Char('l'), edit_bind(EC::LowercaseWord));
     kb.add_binding(KM::ALT, KC::Char('c'), edit_bind(EC::CapitalizeChar));
+    kb.add_binding(KM::ALT, KC::Char('%'), ReedlineEvent::SearchReplace);
+    kb.add_binding(
+        KM::ALT,
+        KC::Char('x'),
+        ReedlineEvent::Menu(CommandPalette::NAME.to_string()),
+    );
     kb.add_binding(KM::ALT, KC::Left, edit_bind(EC::MoveWordLeft));
     kb.add_binding(KM::ALT, KC::Right, edit_bind(EC::MoveWordRight));
     kb.add_binding(KM::ALT, KC::Delete, edit_bind(EC::DeleteWord));
@@ -106,18 +385,37 @@ pub fn default_emacs_keybindings() -> Keybindings {
     kb.add_binding(KM::NONE, KC::End, edit_bind(EC::MoveToLineEnd));
     kb.add_binding(KM::NONE, KC::Home, edit_bind(EC::MoveToLineStart));
     kb.add_binding(KM::NONE, KC::Tab, ReedlineEvent::HandleTab);
+    kb.add_binding(KM::SHIFT, KC::BackTab, ReedlineEvent::SnippetPrevious);
+    kb.add_binding(KM::ALT, KC::Enter, ReedlineEvent::InsertNewline);
+    // Only reported by terminals with the kitty keyboard protocol
+    kb.add_binding(KM::SHIFT, KC::Enter, ReedlineEvent::InsertNewline);
     kb.add_binding(KM::NONE, KC::Up, ReedlineEvent::Up);
     kb.add_binding(KM::NONE, KC::Down, ReedlineEvent::Down);
     kb.add_binding(KM::NONE, KC::Left, edit_bind(EC::MoveLeft));
     kb.add_binding(KM::NONE, KC::Right, edit_bind(EC::MoveRight));
     kb.add_binding(KM::NONE, KC::Delete, edit_bind(EC::Delete));
     kb.add_binding(KM::NONE, KC::Backspace, edit_bind(EC::Backspace));
+    kb.add_binding(KM::NONE, KC::PageDown, ReedlineEvent::MenuPageNext);
+    kb.add_binding(KM::NONE, KC::PageUp, ReedlineEvent::MenuPagePrevious);
+    kb.add_menu_quick_select(KM::ALT);
 
     kb
 }
 
 pub fn default_vi_normal_keybindings() -> Keybindings {
-    Keybindings::new()
+    use EditCommand as EC;
+    use KeyCode as KC;
+    use KeyModifiers as KM;
+
+    let mut keybindings = Keybindings::new();
+
+    keybindings.add_binding(KM::CONTROL, KC::Char('o'), edit_bind(EC::JumpBack));
+    keybindings.add_binding(KM::CONTROL, KC::Char('l'), ReedlineEvent::ClearScreen);
+    // Most terminals report `Ctrl-I` as `Tab`, which keeps completing, `Alt-I` reaches it there
+    keybindings.add_binding(KM::CONTROL, KC::Char('i'), edit_bind(EC::JumpForward));
+    keybindings.add_binding(KM::ALT, KC::Char('i'), edit_bind(EC::JumpForward));
+
+    keybindings
 }
 
 pub fn default_vi_insert_keybindings() -> Keybindings {
@@ -135,6 +433,135 @@ pub fn default_vi_insert_keybindings() -> Keybindings {
     keybindings.add_binding(KM::NONE, KC::Delete, edit_bind(EC::Delete));
     keybindings.add_binding(KM::NONE, KC::End, edit_bind(EC::MoveToLineEnd));
     keybindings.add_binding(KM::NONE, KC::Home, edit_bind(EC::MoveToLineStart));
+    keybindings.add_binding(KM::CONTROL, KC::Char('l'), ReedlineEvent::ClearScreen);
+    keybindings.add_binding(KM::ALT, KC::Enter, ReedlineEvent::InsertNewline);
+    // Only reported by terminals with the kitty keyboard protocol
+    keybindings.add_binding(KM::SHIFT, KC::Enter, ReedlineEvent::InsertNewline);
+    keybindings.add_binding(KM::NONE, KC::PageDown, ReedlineEvent::MenuPageNext);
+    keybindings.add_binding(KM::NONE, KC::PageUp, ReedlineEvent::MenuPagePrevious);
+    keybindings.add_binding(KM::SHIFT, KC::BackTab, ReedlineEvent::SnippetPrevious);
+    keybindings.add_menu_quick_select(KM::ALT);
 
     keybindings
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde::de::{value::Error, IntoDeserializer};
+
+    #[rstest]
+    #[case("a", KeyModifiers::NONE, KeyCode::Char('a'))]
+    #[case("ctrl-a", KeyModifiers::CONTROL, KeyCode::Char('a'))]
+    #[case("Control+a", KeyModifiers::CONTROL, KeyCode::Char('a'))]
+    #[case("ctrl-alt-left", KeyModifiers::CONTROL | KeyModifiers::ALT, KeyCode::Left)]
+    #[case("ALT+ENTER", KeyModifiers::ALT, KeyCode::Enter)]
+    #[case("f5", KeyModifiers::NONE, KeyCode::F(5))]
+    #[case("Shift-F12", KeyModifiers::SHIFT, KeyCode::F(12))]
+    #[case("F", KeyModifiers::NONE, KeyCode::Char('F'))]
+    #[case("space", KeyModifiers::NONE, KeyCode::Char(' '))]
+    #[case("Ctrl--", KeyModifiers::CONTROL, KeyCode::Char('-'))]
+    #[case("alt-+", KeyModifiers::ALT, KeyCode::Char('+'))]
+    #[case("-", KeyModifiers::NONE, KeyCode::Char('-'))]
+    #[case("pgdn", KeyModifiers::NONE, KeyCode::PageDown)]
+    #[case("escape", KeyModifiers::NONE, KeyCode::Esc)]
+    #[case("ctrl-ä", KeyModifiers::CONTROL, KeyCode::Char('ä'))]
+    fn notation_is_parsed(
+        #[case] notation: &str,
+        #[case] modifier: KeyModifiers,
+        #[case] key_code: KeyCode,
+    ) {
+        assert_eq!(
+            notation.parse(),
+            Ok(KeyCombination::new(modifier, key_code))
+        );
+    }
+
+    #[rstest]
+    #[case("shift-tab", KeyModifiers::SHIFT, KeyCode::BackTab)]
+    #[case("BackTab", KeyModifiers::SHIFT, KeyCode::BackTab)]
+    #[case("ctrl-shift-tab", KeyModifiers::CONTROL | KeyModifiers::SHIFT, KeyCode::BackTab)]
+    #[case("shift-a", KeyModifiers::SHIFT, KeyCode::Char('A'))]
+    #[case("kp5", KeyModifiers::NONE, KeyCode::Char('5'))]
+    #[case("ctrl-KpAdd", KeyModifiers::CONTROL, KeyCode::Char('+'))]
+    #[case("kpenter", KeyModifiers::NONE, KeyCode::Enter)]
+    #[case("keypadbegin", KeyModifiers::NONE, KeyCode::KeypadBegin)]
+    fn notation_is_normalized_to_reported_events(
+        #[case] notation: &str,
+        #[case] modifier: KeyModifiers,
+        #[case] key_code: KeyCode,
+    ) {
+        assert_eq!(
+            notation.parse(),
+            Ok(KeyCombination::new(modifier, key_code))
+        );
+    }
+
+    #[rstest]
+    #[case("", KeyNotationError::Empty)]
+    #[case("hyperspace-a", KeyNotationError::UnknownModifier("hyperspace".into()))]
+    #[case("ctrl-", KeyNotationError::UnknownKey("ctrl-".into()))]
+    #[case("ctrl-foo", KeyNotationError::UnknownKey("foo".into()))]
+    #[case("f0", KeyNotationError::UnknownKey("f0".into()))]
+    #[case("f256", KeyNotationError::UnknownKey("f256".into()))]
+    fn invalid_notation_is_rejected(#[case] notation: &str, #[case] error: KeyNotationError) {
+        assert_eq!(notation.parse::<KeyCombination>(), Err(error));
+    }
+
+    #[rstest]
+    #[case(KeyModifiers::CONTROL, KeyCode::Char('a'), "Ctrl-a")]
+    #[case(KeyModifiers::ALT, KeyCode::Left, "Alt-Left")]
+    #[case(KeyModifiers::SHIFT, KeyCode::BackTab, "Shift-Tab")]
+    #[case(KeyModifiers::SHIFT, KeyCode::Char('A'), "Shift-A")]
+    #[case(KeyModifiers::NONE, KeyCode::Char(' '), "Space")]
+    #[case(KeyModifiers::NONE, KeyCode::Char('5'), "5")]
+    #[case(KeyModifiers::CONTROL, KeyCode::Char('-'), "Ctrl--")]
+    #[case(KeyModifiers::CONTROL | KeyModifiers::ALT, KeyCode::Enter, "Ctrl-Alt-Enter")]
+    #[case(KeyModifiers::SUPER | KeyModifiers::META, KeyCode::F(24), "Super-Meta-F24")]
+    #[case(KeyModifiers::NONE, KeyCode::KeypadBegin, "KeypadBegin")]
+    #[case(KeyModifiers::NONE, KeyCode::PageDown, "PageDown")]
+    fn notation_is_printed_and_parsed_back(
+        #[case] modifier: KeyModifiers,
+        #[case] key_code: KeyCode,
+        #[case] notation: &str,
+    ) {
+        let key_combo = KeyCombination::new(modifier, key_code);
+
+        assert_eq!(key_combo.to_string(), notation);
+        assert_eq!(notation.parse(), Ok(key_combo));
+    }
+
+    #[test]
+    fn default_bindings_are_printed_and_parsed_back() {
+        for key_combo in default_emacs_keybindings().bindings.keys() {
+            assert_eq!(key_combo.to_string().parse().as_ref(), Ok(key_combo));
+        }
+    }
+
+    #[test]
+    fn deserialized_from_notation() {
+        let deserializer = IntoDeserializer::<Error>::into_deserializer("ctrl-alt-left");
+
+        assert_eq!(
+            KeyCombination::deserialize(deserializer),
+            Ok(KeyCombination::new(
+                KeyModifiers::CONTROL | KeyModifiers::ALT,
+                KeyCode::Left
+            ))
+        );
+
+        let deserializer = IntoDeserializer::<Error>::into_deserializer("ctrl-nope");
+        assert!(KeyCombination::deserialize(deserializer).is_err());
+    }
+
+    #[test]
+    fn converts_from_and_to_key_events() {
+        let event = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        let key_combo = KeyCombination::from(event);
+
+        assert_eq!(key_combo.to_string(), "Shift-Tab");
+        assert_eq!(KeyEvent::from(key_combo), event);
+    }
+}
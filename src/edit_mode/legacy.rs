@@ -0,0 +1,117 @@
+use {crate::EditCommand, std::fmt};
+
+/// Names of [`EditCommand`]s of earlier versions with the command replacing them
+///
+/// Each name is also a serde alias of its replacement, so keybinding configs using it keep
+/// working. New renames belong here and as an alias on the variant.
+const LEGACY_EDIT_COMMANDS: &[(&str, EditCommand)] = &[
+    ("InsertCutBuffer", EditCommand::PasteCutBufferBefore),
+    ("PasteCutBuffer", EditCommand::PasteCutBufferBefore),
+    ("DeleteToLineEnd", EditCommand::ClearToLineEnd),
+];
+
+/// A deprecated [`EditCommand`] name used by a keybinding config, see [`legacy_edit_commands()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyEditCommand {
+    /// The deprecated name as written in the config
+    pub name: &'static str,
+    /// The command the name is read as
+    pub replacement: EditCommand,
+}
+
+impl fmt::Display for LegacyEditCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the edit command '{}' is deprecated, use '{:?}' instead",
+            self.name, self.replacement
+        )
+    }
+}
+
+/// The deprecated [`EditCommand`] names used in the serialized keybinding `config`, each once
+/// in the order of their first use
+///
+/// Deprecated names are still read as their replacement, hosts can show the returned items as
+/// warnings to ask users to migrate their config. Works on the text of any serde format, as
+/// the commands are written by their name.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{legacy_edit_commands, EditCommand};
+///
+/// let config = r#"{ "bindings": { "Ctrl-y": { "Edit": ["InsertCutBuffer"] } } }"#;
+/// let legacy = legacy_edit_commands(config);
+///
+/// assert_eq!(legacy[0].replacement, EditCommand::PasteCutBufferBefore);
+/// ```
+pub fn legacy_edit_commands(config: &str) -> Vec<LegacyEditCommand> {
+    let mut found: Vec<LegacyEditCommand> = vec![];
+    for word in config.split(|c: char| !c.is_alphanumeric() && c != '_') {
+        let legacy = LEGACY_EDIT_COMMANDS
+            .iter()
+            .find(|(name, _)| *name == word)
+            .filter(|(name, _)| found.iter().all(|legacy| legacy.name != *name));
+        if let Some((name, replacement)) = legacy {
+            found.push(LegacyEditCommand {
+                name,
+                replacement: replacement.clone(),
+            });
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde::{
+        de::{value::Error, IntoDeserializer},
+        Deserialize,
+    };
+
+    #[test]
+    fn legacy_names_deserialize_as_replacement() {
+        for (name, replacement) in LEGACY_EDIT_COMMANDS {
+            let deserializer = IntoDeserializer::<Error>::into_deserializer(*name);
+            assert_eq!(
+                EditCommand::deserialize(deserializer).unwrap(),
+                *replacement
+            );
+        }
+    }
+
+    #[test]
+    fn config_reports_each_legacy_name_once() {
+        let config = "
+            [bindings]
+            Ctrl-k = { Edit = ['DeleteToLineEnd'] }
+            Ctrl-y = { Edit = ['InsertCutBuffer'] }
+            Alt-y = { Edit = ['DeleteToLineEnd', 'MoveToLineEnd'] }
+        ";
+
+        assert_eq!(
+            legacy_edit_commands(config),
+            vec![
+                LegacyEditCommand {
+                    name: "DeleteToLineEnd",
+                    replacement: EditCommand::ClearToLineEnd
+                },
+                LegacyEditCommand {
+                    name: "InsertCutBuffer",
+                    replacement: EditCommand::PasteCutBufferBefore
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn current_names_are_not_reported() {
+        assert_eq!(
+            legacy_edit_commands(r#"["PasteCutBufferBefore", "ClearToLineEnd"]"#),
+            vec![]
+        );
+    }
+}
@@ -1,9 +1,13 @@
 mod base;
+mod custom;
 mod emacs;
 mod keybindings;
+mod legacy;
 mod vi;
 
 pub use base::EditMode;
+pub use custom::CustomEditMode;
 pub use emacs::Emacs;
-pub use keybindings::{default_emacs_keybindings, Keybindings};
+pub use keybindings::{default_emacs_keybindings, KeyCombination, KeyNotationError, Keybindings};
+pub use legacy::{legacy_edit_commands, LegacyEditCommand};
 pub use vi::Vi;
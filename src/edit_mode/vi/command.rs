@@ -1,4 +1,4 @@
-use super::motion::Motion;
+use super::motion::{Motion, TextObject};
 use super::parser::ReedlineOption;
 use crate::{EditCommand, ReedlineEvent};
 use std::iter::Peekable;
@@ -44,6 +44,18 @@ where
             let _ = input.next();
             Some(Command::MoveWordLeft)
         }
+        Some('W') => {
+            let _ = input.next();
+            Some(Command::MoveBigWordRight)
+        }
+        Some('B') => {
+            let _ = input.next();
+            Some(Command::MoveBigWordLeft)
+        }
+        Some('E') => {
+            let _ = input.next();
+            Some(Command::MoveBigWordEnd)
+        }
         Some('i') => {
             let _ = input.next();
             Some(Command::EnterViInsert)
@@ -68,6 +80,10 @@ where
             let _ = input.next();
             Some(Command::Change)
         }
+        Some('y') => {
+            let _ = input.next();
+            Some(Command::Yank)
+        }
         Some('x') => {
             let _ = input.next();
             Some(Command::DeleteChar)
@@ -80,6 +96,10 @@ where
             let _ = input.next();
             Some(Command::AppendToEnd)
         }
+        Some('.') => {
+            let _ = input.next();
+            Some(Command::RepeatLastChange)
+        }
         Some('f') => {
             let _ = input.next();
             match input.peek() {
@@ -108,6 +128,23 @@ where
                 None => Some(Command::Incomplete),
             }
         }
+        // Marks are named by a lower case letter
+        Some('m') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) if c.is_ascii_lowercase() => Some(Command::SetMark(*c)),
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('`') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) if c.is_ascii_lowercase() => Some(Command::JumpToMark(*c)),
+                Some(_) => None,
+                None => Some(Command::Incomplete),
+            }
+        }
         _ => None,
     }
 }
@@ -125,6 +162,9 @@ pub enum Command {
     MoveDown,
     MoveWordRight,
     MoveWordLeft,
+    MoveBigWordRight,
+    MoveBigWordLeft,
+    MoveBigWordEnd,
     MoveToLineStart,
     MoveToLineEnd,
     EnterViAppend,
@@ -133,10 +173,14 @@ pub enum Command {
     DeleteToEnd,
     AppendToEnd,
     Change,
+    Yank,
     MoveRightUntil(char),
     MoveRightBefore(char),
     MoveLeftUntil(char),
     MoveLeftBefore(char),
+    SetMark(char),
+    JumpToMark(char),
+    RepeatLastChange,
 }
 
 impl Command {
@@ -150,6 +194,14 @@ impl Command {
             Self::MoveToLineEnd => vec![ReedlineOption::Edit(EditCommand::MoveToLineEnd)],
             Self::MoveWordLeft => vec![ReedlineOption::Edit(EditCommand::MoveWordLeft)],
             Self::MoveWordRight => vec![ReedlineOption::Edit(EditCommand::MoveWordRight)],
+            Self::MoveBigWordLeft => vec![ReedlineOption::Edit(EditCommand::MoveBigWordLeft)],
+            Self::MoveBigWordRight => vec![ReedlineOption::Edit(EditCommand::MoveBigWordRight)],
+            // Onto the last character of the WORD, moving right first to leave the current end
+            Self::MoveBigWordEnd => vec![
+                ReedlineOption::Edit(EditCommand::MoveRight),
+                ReedlineOption::Edit(EditCommand::MoveBigWordRight),
+                ReedlineOption::Edit(EditCommand::MoveLeft),
+            ],
             Self::EnterViInsert => vec![ReedlineOption::Event(ReedlineEvent::Repaint)],
             Self::EnterViAppend => vec![ReedlineOption::Edit(EditCommand::MoveRight)],
             Self::PasteAfter => vec![ReedlineOption::Edit(EditCommand::PasteCutBufferAfter)],
@@ -164,10 +216,35 @@ impl Command {
             Self::MoveLeftUntil(c) => vec![ReedlineOption::Edit(EditCommand::MoveLeftUntil(*c))],
             Self::MoveLeftBefore(c) => vec![ReedlineOption::Edit(EditCommand::MoveLeftBefore(*c))],
             Self::DeleteChar => vec![ReedlineOption::Edit(EditCommand::Delete)],
-            Self::Delete | Self::Change | Self::Incomplete => vec![ReedlineOption::Incomplete],
+            Self::SetMark(name) => vec![ReedlineOption::Edit(EditCommand::SetMark(*name))],
+            Self::JumpToMark(name) => vec![ReedlineOption::Edit(EditCommand::MoveToMark(*name))],
+            // Replayed by the edit mode, which records the changes
+            Self::Delete
+            | Self::Change
+            | Self::Yank
+            | Self::RepeatLastChange
+            | Self::Incomplete => {
+                vec![ReedlineOption::Incomplete]
+            }
         }
     }
 
+    /// The command edits the buffer, possibly followed by text typed in insert mode
+    pub fn is_change(&self) -> bool {
+        matches!(
+            self,
+            Self::Delete
+                | Self::DeleteChar
+                | Self::DeleteToEnd
+                | Self::Change
+                | Self::PasteAfter
+                | Self::PasteBefore
+                | Self::EnterViInsert
+                | Self::EnterViAppend
+                | Self::AppendToEnd
+        )
+    }
+
     pub fn to_reedline_with_motion(
         &self,
         motion: &Motion,
@@ -178,6 +255,7 @@ impl Command {
                 Motion::End => Some(vec![ReedlineOption::Edit(EditCommand::CutToEnd)]),
                 Motion::Line => Some(vec![ReedlineOption::Edit(EditCommand::CutCurrentLine)]),
                 Motion::Word => Some(vec![ReedlineOption::Edit(EditCommand::CutWordRight)]),
+                Motion::BigWord => Some(vec![ReedlineOption::Edit(EditCommand::CutBigWordRight)]),
                 Motion::RightUntil(c) => {
                     Some(vec![ReedlineOption::Edit(EditCommand::CutRightUntil(*c))])
                 }
@@ -190,6 +268,8 @@ impl Command {
                 Motion::LeftBefore(c) => {
                     Some(vec![ReedlineOption::Edit(EditCommand::CutLeftBefore(*c))])
                 }
+                Motion::Inside(object) => Some(vec![ReedlineOption::Edit(cut_inside(object))]),
+                Motion::Around(object) => Some(vec![ReedlineOption::Edit(cut_around(object))]),
                 Motion::Start => None,
             },
             Self::Change => match motion {
@@ -206,6 +286,10 @@ impl Command {
                     ReedlineOption::Edit(EditCommand::CutWordRight),
                     ReedlineOption::Event(ReedlineEvent::Repaint),
                 ]),
+                Motion::BigWord => Some(vec![
+                    ReedlineOption::Edit(EditCommand::CutBigWordRight),
+                    ReedlineOption::Event(ReedlineEvent::Repaint),
+                ]),
                 Motion::RightUntil(c) => Some(vec![
                     ReedlineOption::Edit(EditCommand::CutRightUntil(*c)),
                     ReedlineOption::Event(ReedlineEvent::Repaint),
@@ -222,8 +306,39 @@ impl Command {
                     ReedlineOption::Edit(EditCommand::CutLeftBefore(*c)),
                     ReedlineOption::Event(ReedlineEvent::Repaint),
                 ]),
+                Motion::Inside(object) => Some(vec![
+                    ReedlineOption::Edit(cut_inside(object)),
+                    ReedlineOption::Event(ReedlineEvent::Repaint),
+                ]),
+                Motion::Around(object) => Some(vec![
+                    ReedlineOption::Edit(cut_around(object)),
+                    ReedlineOption::Event(ReedlineEvent::Repaint),
+                ]),
                 Motion::Start => None,
             },
+            Self::Yank => match motion {
+                Motion::End => Some(vec![ReedlineOption::Edit(EditCommand::CopyToLineEnd)]),
+                Motion::Line => Some(vec![ReedlineOption::Edit(EditCommand::CopyCurrentLine)]),
+                Motion::Word => Some(vec![ReedlineOption::Edit(EditCommand::CopyWordRight)]),
+                Motion::Inside(TextObject::Word) => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyInnerWord)])
+                }
+                Motion::Inside(TextObject::Pair(left, right)) => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CopyInsidePair(*left, *right),
+                )]),
+                Motion::Around(TextObject::Word) => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyAroundWord)])
+                }
+                Motion::Around(TextObject::Pair(left, right)) => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CopyAroundPair(*left, *right),
+                )]),
+                Motion::BigWord
+                | Motion::RightUntil(_)
+                | Motion::RightBefore(_)
+                | Motion::LeftUntil(_)
+                | Motion::LeftBefore(_)
+                | Motion::Start => None,
+            },
             _ => None,
         };
 
@@ -238,3 +353,17 @@ impl Command {
         }
     }
 }
+
+fn cut_inside(object: &TextObject) -> EditCommand {
+    match object {
+        TextObject::Word => EditCommand::CutInnerWord,
+        TextObject::Pair(left, right) => EditCommand::CutInsidePair(*left, *right),
+    }
+}
+
+fn cut_around(object: &TextObject) -> EditCommand {
+    match object {
+        TextObject::Word => EditCommand::CutAroundWord,
+        TextObject::Pair(left, right) => EditCommand::CutAroundPair(*left, *right),
+    }
+}
@@ -7,6 +7,7 @@ use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use super::EditMode;
 use crate::{
     edit_mode::{
+        base::parse_mouse_event,
         keybindings::{default_vi_insert_keybindings, default_vi_normal_keybindings, Keybindings},
         vi::parser::parse,
     },
@@ -21,12 +22,18 @@ enum Mode {
 }
 
 /// This parses incoming input `Event`s like a Vi-Style editor
+///
+/// `.` in normal mode repeats the last change: the command editing the buffer together with
+/// the edits typed in insert mode if the command started it, e.g. `cw` and the new word.
+/// A count like `3.` repeats it that many times.
 pub struct Vi {
     cache: Vec<char>,
     insert_keybindings: Keybindings,
     normal_keybindings: Keybindings,
     mode: Mode,
-    previous: Option<ReedlineEvent>,
+    // Events of the last change, extended by the edits in insert mode while `recording`
+    last_change: Vec<ReedlineEvent>,
+    recording: bool,
 }
 
 impl Default for Vi {
@@ -36,7 +43,8 @@ impl Default for Vi {
             normal_keybindings: default_vi_normal_keybindings(),
             cache: Vec::new(),
             mode: Mode::Insert,
-            previous: None,
+            last_change: vec![],
+            recording: false,
         }
     }
 }
@@ -44,16 +52,13 @@ impl Default for Vi {
 impl EditMode for Vi {
     fn parse_event(&mut self, event: Event) -> ReedlineEvent {
         match event {
-            Event::Key(KeyEvent { code, modifiers }) => match (self.mode, modifiers, code) {
-                (Mode::Normal, modifier, KeyCode::Char(c)) => {
-                    // The repeat character is the only character that is not managed
-                    // by the parser since the last event is stored in the editor
-                    if c == '.' {
-                        if let Some(event) = &self.previous {
-                            return event.clone();
-                        }
-                    }
-
+            Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) => match (self.mode, modifiers, code) {
+                // Alt and Control chords are looked up in the keybindings
+                (Mode::Normal, modifier, KeyCode::Char(c))
+                    if modifier != KeyModifiers::ALT && modifier != KeyModifiers::CONTROL =>
+                {
                     let char = if let KeyModifiers::SHIFT = modifier {
                         c.to_ascii_uppercase()
                     } else {
@@ -63,6 +68,11 @@ impl EditMode for Vi {
 
                     let res = parse(&mut self.cache.iter().peekable());
 
+                    if let Some(count) = res.repeat_count() {
+                        self.cache.clear();
+                        return self.repeat_last_change(count);
+                    }
+
                     if res.enter_insert_mode() {
                         self.mode = Mode::Insert;
                     }
@@ -79,11 +89,17 @@ impl EditMode for Vi {
                         }
                     };
 
-                    self.previous = Some(event.clone());
+                    if res.is_change() && event != ReedlineEvent::None {
+                        self.last_change = vec![event.clone()];
+                        self.recording = res.enter_insert_mode();
+                    }
 
                     event
                 }
-                (Mode::Insert, modifier, KeyCode::Char(c)) => {
+                // Alt and Control chords are looked up in the keybindings
+                (Mode::Insert, modifier, KeyCode::Char(c))
+                    if modifier != KeyModifiers::ALT && modifier != KeyModifiers::CONTROL =>
+                {
                     // Note. The modifier can also be a combination of modifiers, for
                     // example:
                     //     KeyModifiers::CONTROL | KeyModifiers::ALT
@@ -98,30 +114,37 @@ impl EditMode for Vi {
                         c
                     };
 
-                    ReedlineEvent::Edit(vec![EditCommand::InsertChar(char)])
+                    self.record(ReedlineEvent::Edit(vec![EditCommand::InsertChar(char)]))
                 }
                 (_, KeyModifiers::NONE, KeyCode::Tab) => ReedlineEvent::HandleTab,
                 (_, KeyModifiers::NONE, KeyCode::Esc) => {
                     self.cache.clear();
                     self.mode = Mode::Normal;
+                    self.recording = false;
                     ReedlineEvent::Repaint
                 }
                 (_, KeyModifiers::NONE, KeyCode::Enter) => {
                     self.mode = Mode::Insert;
+                    self.recording = false;
                     ReedlineEvent::Enter
                 }
                 (Mode::Normal, _, _) => self
                     .normal_keybindings
                     .find_binding(modifiers, code)
                     .unwrap_or(ReedlineEvent::None),
-                (Mode::Insert, _, _) => self
-                    .insert_keybindings
-                    .find_binding(modifiers, code)
-                    .unwrap_or(ReedlineEvent::None),
+                (Mode::Insert, _, _) => {
+                    let event = self
+                        .insert_keybindings
+                        .find_binding(modifiers, code)
+                        .unwrap_or(ReedlineEvent::None);
+                    self.record(event)
+                }
             },
 
-            Event::Mouse(_) => ReedlineEvent::Mouse,
+            Event::Mouse(event) => parse_mouse_event(event),
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
+            // Focus reporting and bracketed paste are not enabled
+            Event::FocusGained | Event::FocusLost | Event::Paste(_) => ReedlineEvent::None,
         }
     }
 
@@ -131,4 +154,179 @@ impl EditMode for Vi {
             Mode::Insert => PromptEditMode::Vi(PromptViMode::Insert),
         }
     }
+
+    fn keybindings(&self) -> Option<&Keybindings> {
+        match self.mode {
+            Mode::Normal => Some(&self.normal_keybindings),
+            Mode::Insert => Some(&self.insert_keybindings),
+        }
+    }
+
+    fn all_keybindings(&self) -> Vec<&Keybindings> {
+        vec![&self.insert_keybindings, &self.normal_keybindings]
+    }
+}
+
+impl Vi {
+    /// Add an edit made in insert mode to the last change, while the change is recorded
+    fn record(&mut self, event: ReedlineEvent) -> ReedlineEvent {
+        if self.recording && matches!(event, ReedlineEvent::Edit(_)) {
+            self.last_change.push(event.clone());
+        }
+        event
+    }
+
+    fn repeat_last_change(&self, count: usize) -> ReedlineEvent {
+        if self.last_change.is_empty() {
+            return ReedlineEvent::None;
+        }
+
+        ReedlineEvent::Multiple(
+            (0..count)
+                .flat_map(|_| self.last_change.iter().cloned())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn key(modifiers: KeyModifiers, code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn jump_list_is_bound_in_normal_mode() {
+        let mut vi = Vi::default();
+        vi.parse_event(key(KeyModifiers::NONE, KeyCode::Esc));
+
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::CONTROL, KeyCode::Char('o'))),
+            ReedlineEvent::Edit(vec![EditCommand::JumpBack])
+        );
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::CONTROL, KeyCode::Char('i'))),
+            ReedlineEvent::Edit(vec![EditCommand::JumpForward])
+        );
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::ALT, KeyCode::Char('i'))),
+            ReedlineEvent::Edit(vec![EditCommand::JumpForward])
+        );
+        assert_eq!(vi.edit_mode(), PromptEditMode::Vi(PromptViMode::Normal));
+    }
+
+    #[test]
+    fn tab_completes_in_both_modes() {
+        let mut vi = Vi::default();
+
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::NONE, KeyCode::Tab)),
+            ReedlineEvent::HandleTab
+        );
+        vi.parse_event(key(KeyModifiers::NONE, KeyCode::Esc));
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::NONE, KeyCode::Tab)),
+            ReedlineEvent::HandleTab
+        );
+    }
+
+    #[test]
+    fn ctrl_l_clears_screen_in_both_modes() {
+        let mut vi = Vi::default();
+
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::CONTROL, KeyCode::Char('l'))),
+            ReedlineEvent::ClearScreen
+        );
+        vi.parse_event(key(KeyModifiers::NONE, KeyCode::Esc));
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::CONTROL, KeyCode::Char('l'))),
+            ReedlineEvent::ClearScreen
+        );
+    }
+
+    #[test]
+    fn shift_or_alt_enter_inserts_newline_in_insert_mode() {
+        let mut vi = Vi::default();
+
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::SHIFT, KeyCode::Enter)),
+            ReedlineEvent::InsertNewline
+        );
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::ALT, KeyCode::Enter)),
+            ReedlineEvent::InsertNewline
+        );
+    }
+
+    #[test]
+    fn alt_digit_selects_menu_value_in_insert_mode() {
+        let mut vi = Vi::default();
+
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::ALT, KeyCode::Char('3'))),
+            ReedlineEvent::MenuSelect(3)
+        );
+        assert_eq!(
+            vi.parse_event(key(KeyModifiers::NONE, KeyCode::Char('3'))),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('3')])
+        );
+    }
+
+    fn type_keys(vi: &mut Vi, keys: &str) -> Vec<ReedlineEvent> {
+        keys.chars()
+            .map(|c| vi.parse_event(key(KeyModifiers::NONE, KeyCode::Char(c))))
+            .collect()
+    }
+
+    #[test]
+    fn repeat_replays_change_with_inserted_text() {
+        let mut vi = Vi::default();
+        vi.parse_event(key(KeyModifiers::NONE, KeyCode::Esc));
+        let change = type_keys(&mut vi, "cw").pop().unwrap();
+        type_keys(&mut vi, "ab");
+        vi.parse_event(key(KeyModifiers::NONE, KeyCode::Backspace));
+        vi.parse_event(key(KeyModifiers::NONE, KeyCode::Esc));
+
+        assert_eq!(
+            type_keys(&mut vi, "."),
+            vec![ReedlineEvent::Multiple(vec![
+                change,
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('a')]),
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('b')]),
+                ReedlineEvent::Edit(vec![EditCommand::Backspace]),
+            ])]
+        );
+        assert_eq!(vi.edit_mode(), PromptEditMode::Vi(PromptViMode::Normal));
+    }
+
+    #[test]
+    fn repeat_takes_count() {
+        let mut vi = Vi::default();
+        vi.parse_event(key(KeyModifiers::NONE, KeyCode::Esc));
+        let change = type_keys(&mut vi, "x").pop().unwrap();
+        type_keys(&mut vi, "yw");
+
+        assert_eq!(
+            type_keys(&mut vi, "3.").pop(),
+            Some(ReedlineEvent::Multiple(vec![
+                change.clone(),
+                change.clone(),
+                change
+            ]))
+        );
+    }
+
+    #[test]
+    fn repeat_without_change_does_nothing() {
+        let mut vi = Vi::default();
+        type_keys(&mut vi, "abc");
+        vi.parse_event(key(KeyModifiers::NONE, KeyCode::Esc));
+        type_keys(&mut vi, "w");
+
+        assert_eq!(type_keys(&mut vi, "."), vec![ReedlineEvent::None]);
+    }
 }
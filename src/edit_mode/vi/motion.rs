@@ -1,6 +1,7 @@
+use super::command::Command;
 use std::iter::Peekable;
 
-pub fn parse_motion<'iter, I>(input: &mut Peekable<I>) -> Option<Motion>
+pub fn parse_motion<'iter, I>(input: &mut Peekable<I>, command: Option<&Command>) -> Option<Motion>
 where
     I: Iterator<Item = &'iter char>,
 {
@@ -9,10 +10,36 @@ where
             let _ = input.next();
             Some(Motion::Word)
         }
-        Some('d') => {
+        Some('W') => {
+            let _ = input.next();
+            Some(Motion::BigWord)
+        }
+        // Doubling the operator applies it to the line, e.g. `dd` or `yy`
+        Some('d') if command == Some(&Command::Delete) => {
+            let _ = input.next();
+            Some(Motion::Line)
+        }
+        Some('c') if command == Some(&Command::Change) => {
             let _ = input.next();
             Some(Motion::Line)
         }
+        Some('y') if command == Some(&Command::Yank) => {
+            let _ = input.next();
+            Some(Motion::Line)
+        }
+        // Text objects, the object is left in the input if unknown
+        Some('i') => {
+            let _ = input.next();
+            let object = parse_text_object(input.peek()?)?;
+            let _ = input.next();
+            Some(Motion::Inside(object))
+        }
+        Some('a') => {
+            let _ = input.next();
+            let object = parse_text_object(input.peek()?)?;
+            let _ = input.next();
+            Some(Motion::Around(object))
+        }
         Some('0') => {
             let _ = input.next();
             Some(Motion::Start)
@@ -41,9 +68,30 @@ where
     }
 }
 
+fn parse_text_object(c: &char) -> Option<TextObject> {
+    match c {
+        'w' => Some(TextObject::Word),
+        '(' | ')' | 'b' => Some(TextObject::Pair('(', ')')),
+        '[' | ']' => Some(TextObject::Pair('[', ']')),
+        '{' | '}' | 'B' => Some(TextObject::Pair('{', '}')),
+        '<' | '>' => Some(TextObject::Pair('<', '>')),
+        '"' | '\'' | '`' => Some(TextObject::Pair(*c, *c)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TextObject {
+    Word,
+    Pair(char, char),
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Motion {
+    Inside(TextObject),
+    Around(TextObject),
     Word,
+    BigWord,
     Line,
     Start,
     End,
@@ -34,6 +34,21 @@ impl ParseResult {
         )
     }
 
+    /// The parsed command edits the buffer and can be repeated with `.`
+    pub fn is_change(&self) -> bool {
+        self.valid && matches!(&self.command, Some(command) if command.is_change())
+    }
+
+    /// How often to repeat the last change, if the input is `.` with an optional count
+    pub fn repeat_count(&self) -> Option<usize> {
+        match (&self.command, &self.count, &self.motion) {
+            (Some(Command::RepeatLastChange), None, None) if self.valid => {
+                Some(self.multiplier.unwrap_or(1))
+            }
+            _ => None,
+        }
+    }
+
     pub fn to_reedline_event(&self) -> ReedlineEvent {
         match (&self.multiplier, &self.command, &self.count, &self.motion) {
             // Movements with h,j,k,l are always single char or a number followed
@@ -115,7 +130,7 @@ where
     let multiplier = parse_number(input);
     let command = parse_command(input);
     let count = parse_number(input);
-    let motion = parse_motion(input);
+    let motion = parse_motion(input, command.as_ref());
 
     let valid =
         { multiplier.is_some() || command.is_some() || count.is_some() || motion.is_some() };
@@ -265,6 +280,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_text_object_waits_for_known_object() {
+        assert!(vi_parse(&['d', 'i']).is_valid());
+        assert!(vi_parse(&['y', 'a']).is_valid());
+        assert!(!vi_parse(&['d', 'i', 'x']).is_valid());
+        assert!(!vi_parse(&['d', 'y']).is_valid());
+    }
+
+    #[test]
+    fn test_change_inner_word_enters_insert_mode() {
+        assert!(vi_parse(&['c', 'i', 'w']).enter_insert_mode());
+        assert!(!vi_parse(&['y', 'i', 'w']).enter_insert_mode());
+    }
+
+    #[test]
+    fn test_repeat_takes_count() {
+        assert_eq!(vi_parse(&['.']).repeat_count(), Some(1));
+        assert_eq!(vi_parse(&['3', '.']).repeat_count(), Some(3));
+        assert_eq!(vi_parse(&['d', '.']).repeat_count(), None);
+        assert_eq!(vi_parse(&['x']).repeat_count(), None);
+    }
+
+    #[test]
+    fn test_only_edits_are_changes() {
+        assert!(vi_parse(&['d', 'w']).is_change());
+        assert!(vi_parse(&['2', 'x']).is_change());
+        assert!(vi_parse(&['A']).is_change());
+        assert!(!vi_parse(&['y', 'w']).is_change());
+        assert!(!vi_parse(&['w']).is_change());
+        assert!(!vi_parse(&['u']).is_change());
+        assert!(!vi_parse(&['d', 'y']).is_change());
+    }
+
+    #[test]
+    fn test_mark_requires_lowercase_name() {
+        assert!(vi_parse(&['m']).is_valid());
+        assert!(!vi_parse(&['m', 'A']).is_valid());
+        assert!(!vi_parse(&['`', '1']).is_valid());
+    }
+
     #[rstest]
     #[case(&['2', 'k'], ReedlineEvent::Multiple(vec![ReedlineEvent::Up, ReedlineEvent::Up]))]
     #[case(&['k'], ReedlineEvent::Multiple(vec![ReedlineEvent::Up]))]
@@ -296,6 +351,31 @@ mod tests {
     #[case(&['d', 'd'], ReedlineEvent::Multiple(vec![
         ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine])]))]
     #[case(&['d', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordRight])]))]
+    #[case(&['5', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveWordRight]); 5]))]
+    #[case(&['d', 'W'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutBigWordRight])]))]
+    #[case(&['2', 'B'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveBigWordLeft]); 2]))]
+    #[case(&['E'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveRight]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveBigWordRight]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveLeft])
+        ]))]
+    #[case(&['3', 'd', 'd'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine]); 3]))]
+    #[case(&['1', '0', 'x'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::Delete]); 10]))]
+    #[case(&['m', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::SetMark('a')])]))]
+    #[case(&['`', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToMark('a')])]))]
+    #[case(&['m'], ReedlineEvent::None)]
+    #[case(&['d', 'i', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutInnerWord])]))]
+    #[case(&['d', 'a', '('], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutAroundPair('(', ')')])]))]
+    #[case(&['c', 'i', '"'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutInsidePair('"', '"')]),
+        ReedlineEvent::Repaint
+        ]))]
+    #[case(&['y', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyWordRight])]))]
+    #[case(&['y', 'y'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyCurrentLine])]))]
+    #[case(&['y', 'i', 'B'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CopyInsidePair('{', '}')])]))]
+    #[case(&['d', 'i'], ReedlineEvent::None)]
     fn test_reedline_move(#[case] input: &[char], #[case] expected: ReedlineEvent) {
         let res = vi_parse(input);
         let output = res.to_reedline_event();
@@ -3,18 +3,56 @@ use std::borrow::Borrow;
 use {
     crate::{
         completion::{CircularCompletionHandler, CompletionActionHandler},
-        core_editor::Editor,
-        edit_mode::{EditMode, Emacs},
-        enums::{ReedlineEvent, UndoBehavior},
-        hinter::{DefaultHinter, Hinter},
-        history::{FileBackedHistory, History, HistoryNavigationQuery},
-        painter::Painter,
+        configuration::{ConfigurationIssue, IssueSeverity},
+        core_editor::{
+            Editor, LineBuffer, ScratchFile, SearchReplace, SearchReplaceStage, Snippet,
+            WordBoundary,
+        },
+        edit_mode::{EditMode, Emacs, KeyCombination},
+        enums::{CtrlCBehavior, CtrlDBehavior, InterruptBehavior, ReedlineEvent, UndoBehavior},
+        hinter::{CombinedHinter, DefaultHinter, HintDisplay, Hinter},
+        history::{
+            expand_history, FileBackedHistory, History, HistoryEntryMetadata,
+            HistoryNavigationQuery, HistorySessionId,
+        },
+        markup::MarkupWriter,
+        menu::{CommandPalette, Menu, QuickInsertMenu, QuickInserts},
+        painter::{HistoryPreview, MultilineGutter, Painter},
+        paste::{is_text_key, key_sequence, PasteSanitization},
         prompt::{PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus},
-        text_manipulation, DefaultHighlighter, DefaultValidator, EditCommand, Highlighter, Prompt,
-        Signal, ValidationResult, Validator,
+        text_manipulation::{
+            caret_notation, expanded_width, expanded_width_to_byte_offset, TabRendering,
+        },
+        widget::{self, Widget},
+        Abbreviations, BufferNormalizer, ByteOffset, DefaultHighlighter, DefaultValidator,
+        DisplayWidth, EditCommand, ExternalPrinter, Highlighter, Prompt, Signal, ValidationResult,
+        Validator,
+    },
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+        terminal, Result,
+    },
+    nu_ansi_term::Style,
+    std::{
+        io,
+        ops::Range,
+        path::PathBuf,
+        sync::mpsc::{channel, Receiver},
+        time::{Duration, Instant},
+    },
+};
+
+#[cfg(feature = "async")]
+use {
+    crossterm::{
+        event::{DisableMouseCapture, PopKeyboardEnhancementFlags},
+        ExecutableCommand,
+    },
+    futures_timer::Delay,
+    futures_util::{
+        future::{select, Either},
+        StreamExt,
     },
-    crossterm::{event, event::Event, terminal, Result},
-    std::{io, time::Duration},
 };
 
 // These two parameters define when an event is a Paste Event. The POLL_WAIT is used
@@ -28,6 +66,24 @@ const POLL_WAIT: u64 = 10;
 // a paste. 10 events in 10 milliseconds is conservative enough (unlikely somebody
 // will type more than 10 characters in 10 milliseconds)
 const EVENTS_THRESHOLD: usize = 10;
+// Paints are coalesced to at most this many frames per second by default
+const DEFAULT_MAX_FRAME_RATE: u32 = 60;
+// The animated prompt is repainted after this long without other paints, unless configured
+const DEFAULT_ANIMATION_INTERVAL: Duration = Duration::from_millis(1000);
+// How long to wait for input when nothing else is due
+const IDLE_TIMEOUT: Duration = Duration::from_millis(1000);
+// How often to check for job notifications while waiting for input
+const JOB_NOTIFICATION_INTERVAL: Duration = Duration::from_millis(100);
+
+// Called with each submitted line, see `Reedline::with_pre_execution_hook()`
+type PreExecutionHook = Box<dyn FnMut(&str)>;
+
+/// A paint skipped to respect the frame interval, a full repaint includes the buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum PendingPaint {
+    Buffer,
+    Full,
+}
 
 /// Determines if inputs should be used to extend the regular line buffer,
 /// traverse the history in the standard prompt or edit the search string in the
@@ -45,6 +101,12 @@ enum InputMode {
     /// Either bash style up/down history or fish style prefix search,
     /// Edits directly switch to [`InputMode::Regular`]
     HistoryTraversal,
+    /// Interactive search and replace within the buffer,
+    /// editing affects the query shown in the status row
+    SearchReplace,
+    /// A menu is shown below the buffer,
+    /// the menu decides if editing affects itself or the buffer
+    Menu,
 }
 
 /// Line editor engine
@@ -75,35 +137,182 @@ pub struct Reedline {
     history: Box<dyn History>,
     input_mode: InputMode,
 
+    // Query of the reverse history search, edited with its own cursor
+    history_search_query: LineBuffer,
+    // Style of the part of the found entry matching the query
+    history_search_match_style: Style,
+    control_char_style: Style,
+    paste_sanitization: PasteSanitization,
+    paste_confirmation: bool,
+
+    // Query of the search and replace within the buffer
+    search_replace: Option<SearchReplace>,
+
+    // The inserted snippet and the buffer its placeholders refer to
+    snippet: Option<(Snippet, String)>,
+
+    // The text of a paste awaiting confirmation and the buffer it is highlighted in
+    unconfirmed_paste: Option<(Range<usize>, String)>,
+
+    // Registered menus and the index of the open one
+    menus: Vec<Box<dyn Menu>>,
+    active_menu: Option<usize>,
+
+    // Strings provided by the host for single key insertion
+    quick_inserts: QuickInserts,
+
+    // Words expanded when typed as the first word of the line
+    abbreviations: Abbreviations,
+
     // Validator
     validator: Box<dyn Validator>,
 
     // Stdout
-    painter: Painter,
+    painter: Painter<MarkupWriter<io::Stdout>>,
 
     // Edit Mode: Vi, Emacs
     edit_mode: Box<dyn EditMode>,
 
     // Perform action when user hits tab
     tab_handler: Box<dyn CompletionActionHandler>,
+    // The status row shows the preview of the completion inserted by tab or why the history
+    // expansion failed, until the next event
+    transient_status_shown: bool,
+    // Expand history references like `!!` in the submitted line
+    history_expansion: bool,
 
     // Highlight the edit buffer
     highlighter: Box<dyn Highlighter>,
 
     // Showcase hints based on various strategies (history, language-completion, spellcheck, etc)
     hinter: Box<dyn Hinter>,
+    // Keep the hint at the end of the buffer while the cursor moves within the buffer
+    anchor_hint: bool,
+
+    // If Some(interval) read_line() repaints the prompt after `interval` without other paints
+    animation_interval: Option<Duration>,
 
-    // Is Some(n) read_line() should repaint prompt every `n` milliseconds
-    animate: bool,
+    // Host content painted in rows reserved beneath the input area
+    widget: Option<Box<dyn Widget>>,
+
+    // Builds the prompt from the live data of the host at the start of a line and every animation
+    prompt_refresher: Option<Box<dyn FnMut() -> Box<dyn Prompt>>>,
+    refreshed_prompt: Option<Box<dyn Prompt>>,
+
+    // Called before the prompt of a new line is painted and with each submitted line
+    pre_prompt_hook: Option<Box<dyn FnMut()>>,
+    pre_execution_hook: Option<PreExecutionHook>,
+
+    // Canonicalizes submitted lines before they enter the history
+    buffer_normalizer: Option<BufferNormalizer>,
+
+    // What `CtrlD` and `CtrlC` do
+    interrupt_behavior: InterruptBehavior,
 
     // Use ansi coloring or not
     use_ansi_coloring: bool,
+
+    // Show the last submitted command dimmed above the prompt
+    show_previous_command: bool,
+
+    // Show the adjacent entries dimmed around the buffer while traversing the history
+    show_history_preview: bool,
+
+    // Gutter in front of the lines of a multiline buffer
+    multiline_gutter: Option<MultilineGutter>,
+
+    // Tabs in the buffer reach up to the next multiple of `tab_stop` columns of their line
+    tab_stop: usize,
+    tab_rendering: TabRendering,
+
+    // Why the validator considered the buffer incomplete at the last attempt to submit it
+    incomplete_reason: Option<String>,
+
+    // Minimum time between two paints, paints requested in between are deferred
+    frame_interval: Option<Duration>,
+    last_paint: Option<Instant>,
+    pending_paint: Option<PendingPaint>,
+    // A paste or a batch of events is applied, paints wait for the repaint at its end
+    painting_batch: bool,
+
+    // The previous `read_line_with_timeout()` call timed out, the next call continues the line
+    resume_after_timeout: bool,
+
+    // Keeps the buffer on disk while editing to recover it after a crash
+    scratch_file: Option<ScratchFile>,
+
+    // Notifications of background jobs to print above the prompt
+    job_notifications: Option<Receiver<String>>,
+
+    // Output sent by the host through an `ExternalPrinter`, with the printer handed out
+    external_output: Option<(ExternalPrinter, Receiver<String>)>,
+
+    // Use the kitty keyboard protocol if the terminal supports it, which is only checked once
+    use_kitty_protocol: bool,
+    kitty_protocol_supported: Option<bool>,
+    // The keyboard enhancement is enabled while reading the current line
+    keyboard_enhanced: bool,
+
+    // Capture the mouse to move the cursor by clicking, captured while reading the current line
+    use_mouse_capture: bool,
+    mouse_captured: bool,
+    // Events run when the mouse wheel is turned up and down over the prompt area
+    scroll_wheel_events: (ReedlineEvent, ReedlineEvent),
+
+    // Render into the alternate screen, entered with the first read until the engine is dropped
+    use_alternate_screen: bool,
+    in_alternate_screen: bool,
+}
+
+/// Leaves raw mode and the keyboard enhancement when dropped
+#[cfg(feature = "async")]
+struct TerminalModesGuard {
+    keyboard_enhanced: bool,
+    mouse_captured: bool,
+}
+
+#[cfg(feature = "async")]
+impl Drop for TerminalModesGuard {
+    fn drop(&mut self) {
+        if self.keyboard_enhanced {
+            let _ = io::stdout().execute(PopKeyboardEnhancementFlags);
+        }
+        if self.mouse_captured {
+            let _ = io::stdout().execute(DisableMouseCapture);
+        }
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Lends the terminal to the host until dropped, see [`Reedline::suspend_guard()`]
+///
+/// Dropping the guard restores the terminal modes and repaints the suspended line.
+pub struct SuspendGuard<'engine, 'prompt> {
+    engine: &'engine mut Reedline,
+    prompt: &'prompt dyn Prompt,
+    raw_mode: bool,
+}
+
+impl Drop for SuspendGuard<'_, '_> {
+    fn drop(&mut self) {
+        // Nothing to report the error to, the next read repaints the line anyway
+        let _ = self.engine.resume(self.prompt, self.raw_mode);
+    }
 }
 
 impl Drop for Reedline {
     fn drop(&mut self) {
         // Ensures that the terminal is in a good state if we panic semigracefully
         // Calling `disable_raw_mode()` twice is fine with Linux
+        if self.keyboard_enhanced {
+            let _ = self.painter.disable_keyboard_enhancement();
+        }
+        if self.mouse_captured {
+            let _ = self.painter.disable_mouse_capture();
+        }
+        if self.in_alternate_screen {
+            let _ = self.painter.leave_alternate_screen();
+        }
         let _ = terminal::disable_raw_mode();
     }
 }
@@ -112,28 +321,76 @@ impl Reedline {
     /// Create a new [`Reedline`] engine with a local [`History`] that is not synchronized to a file.
     pub fn create() -> io::Result<Reedline> {
         let history = Box::new(FileBackedHistory::default());
-        let painter = Painter::new(io::stdout());
+        let painter = Painter::new(MarkupWriter::new(io::stdout()));
         let buffer_highlighter = Box::new(DefaultHighlighter::default());
         let hinter = Box::new(DefaultHinter::default());
         let validator = Box::new(DefaultValidator);
 
         let edit_mode = Box::new(Emacs::default());
+        let quick_inserts = QuickInserts::default();
 
         let reedline = Reedline {
             editor: Editor::default(),
             history,
             input_mode: InputMode::Regular,
+            history_search_query: LineBuffer::new(),
+            history_search_match_style: Style::new().bold().underline(),
+            control_char_style: Style::new().reverse(),
+            paste_sanitization: PasteSanitization::default(),
+            paste_confirmation: false,
+            unconfirmed_paste: None,
+            search_replace: None,
+            snippet: None,
+            menus: vec![
+                Box::new(CommandPalette::default()),
+                Box::new(QuickInsertMenu::new(quick_inserts.clone())),
+            ],
+            active_menu: None,
+            quick_inserts,
+            abbreviations: Abbreviations::default(),
             painter,
             edit_mode,
             tab_handler: Box::new(CircularCompletionHandler::default()),
+            transient_status_shown: false,
+            history_expansion: false,
             highlighter: buffer_highlighter,
             hinter,
+            anchor_hint: false,
             validator,
-            animate: true,
+            animation_interval: Some(DEFAULT_ANIMATION_INTERVAL),
+            widget: None,
+            prompt_refresher: None,
+            pre_prompt_hook: None,
+            pre_execution_hook: None,
+            buffer_normalizer: None,
+            interrupt_behavior: InterruptBehavior::default(),
+            refreshed_prompt: None,
             use_ansi_coloring: true,
+            show_previous_command: false,
+            show_history_preview: false,
+            multiline_gutter: None,
+            tab_stop: 8,
+            tab_rendering: TabRendering::default(),
+            incomplete_reason: None,
+            frame_interval: None,
+            last_paint: None,
+            pending_paint: None,
+            painting_batch: false,
+            resume_after_timeout: false,
+            scratch_file: None,
+            job_notifications: None,
+            external_output: None,
+            use_kitty_protocol: false,
+            kitty_protocol_supported: None,
+            keyboard_enhanced: false,
+            use_mouse_capture: false,
+            mouse_captured: false,
+            scroll_wheel_events: (ReedlineEvent::PreviousHistory, ReedlineEvent::NextHistory),
+            use_alternate_screen: false,
+            in_alternate_screen: false,
         };
 
-        Ok(reedline)
+        Ok(reedline.with_max_frame_rate(Some(DEFAULT_MAX_FRAME_RATE)))
     }
 
     /// A builder to include the hinter in your instance of the Reedline engine
@@ -158,7 +415,7 @@ impl Reedline {
     ///
     /// let mut line_editor = Reedline::create()?.with_hinter(Box::new(
     ///     DefaultHinter::default()
-    ///     .with_completer(completer) // or .with_history()
+    ///     .with_completer(completer) // or use `HistoryHinter` for hints from the history
     ///     // .with_inside_line()
     ///     .with_style(Style::new().italic().fg(Color::LightGray)),
     /// ));
@@ -169,6 +426,31 @@ impl Reedline {
         self
     }
 
+    /// A builder that asks several hinters, the first one with a non-empty hint wins
+    ///
+    /// The hinters are asked in the order of `hinters`. For explicit priorities or hints shown
+    /// side by side set up a [`CombinedHinter`] with [`Reedline::with_hinter()`].
+    pub fn with_hinters(self, hinters: Vec<Box<dyn Hinter>>) -> Reedline {
+        let count = hinters.len() as u32;
+        let hinter = hinters
+            .into_iter()
+            .zip((0..count).rev())
+            .fold(CombinedHinter::default(), |combined, (hinter, priority)| {
+                combined.with_hinter(hinter, priority)
+            });
+        self.with_hinter(Box::new(hinter))
+    }
+
+    /// A builder that keeps the hint at the end of the buffer while the cursor moves within it
+    ///
+    /// The hinter is always asked for the hint of the whole buffer, as if the cursor was at its
+    /// end, so the hint stays visible while moving left and editing in the middle of the line.
+    /// Accepting it with `Tab` appends it to the buffer and moves the cursor to the end.
+    pub fn with_anchored_hint(mut self, anchor_hint: bool) -> Reedline {
+        self.anchor_hint = anchor_hint;
+        self
+    }
+
     /// A builder to configure the completion action handler to use in your instance of the reedline engine
     /// # Example
     /// ```rust,no_run
@@ -198,6 +480,58 @@ impl Reedline {
         self
     }
 
+    /// A builder that sets the style of the part of the entry found by the reverse history
+    /// search that matches the search string
+    ///
+    /// Shows why the entry matched, defaults to bold and underlined. Only applied with ANSI
+    /// coloring, the histories report the match with [`History::match_at_cursor()`].
+    pub fn with_history_search_match_style(mut self, style: Style) -> Reedline {
+        self.history_search_match_style = style;
+        self
+    }
+
+    /// A builder that sets the style of the control characters in the buffer
+    ///
+    /// Control characters, e.g. an `ESC` pasted along with some text, are shown in caret
+    /// notation like `^[` instead of being sent to the terminal, defaults to reverse video.
+    pub fn with_control_char_style(mut self, style: Style) -> Reedline {
+        self.control_char_style = style;
+        self
+    }
+
+    /// A builder that sets how escape sequences and control characters in pasted text are
+    /// handled
+    ///
+    /// By default they are inserted into the buffer and shown in caret notation, instead of
+    /// running the commands bound to the keys they look like. [`PasteSanitization::Off`]
+    /// handles them like typed keys.
+    pub fn with_paste_sanitization(mut self, paste_sanitization: PasteSanitization) -> Reedline {
+        self.paste_sanitization = paste_sanitization;
+        self
+    }
+
+    /// A builder that makes a paste with line breaks wait for confirmation instead of
+    /// submitting its lines
+    ///
+    /// The line breaks are inserted into the buffer and the pasted text is highlighted until
+    /// it is edited. It is submitted by pressing Enter, like with the bracketed-paste-magic
+    /// of zsh.
+    pub fn with_paste_confirmation(mut self, paste_confirmation: bool) -> Reedline {
+        self.paste_confirmation = paste_confirmation;
+        self
+    }
+
+    /// A builder that expands bash style history references like `!!`, `!$` or `^old^new` in
+    /// the submitted line, see [`expand_history()`](crate::expand_history)
+    ///
+    /// The expansion is applied on `Enter` before the line is validated and added to the
+    /// history, the buffer shows the expanded line. A line referring to a missing entry is not
+    /// submitted, the error is shown beneath it instead. Disabled by default.
+    pub fn with_history_expansion(mut self, history_expansion: bool) -> Reedline {
+        self.history_expansion = history_expansion;
+        self
+    }
+
     /// A builder which enables or disables the use of ansi coloring in the prompt
     /// and in the command line syntax highlighting.
     pub fn with_ansi_colors(mut self, use_ansi_coloring: bool) -> Reedline {
@@ -205,10 +539,223 @@ impl Reedline {
         self
     }
 
-    /// A builder which enables or disables animations/automatic repainting of prompt.
-    /// If `repaint` is true, every second the prompt will be repainted and the clock updates
-    pub fn with_animation(mut self, repaint: bool) -> Reedline {
-        self.animate = repaint;
+    /// A builder that paints colors and styles as readable markup like `[fg=red]ls[/]`
+    /// instead of ANSI sequences, see [`ansi_to_markup()`](crate::ansi_to_markup)
+    ///
+    /// For debugging highlighters and prompts and for recording the output in snapshot tests.
+    /// Cursor movements are still written as escape sequences.
+    pub fn with_color_markup(mut self, color_markup: bool) -> Reedline {
+        self.painter.set_color_markup(color_markup);
+        self
+    }
+
+    /// A builder that marks the prompt, the input and the output of submitted commands with
+    /// semantic prompt sequences (OSC 133)
+    ///
+    /// Terminals like WezTerm, kitty and Windows Terminal use them to jump between prompts and
+    /// to select the output of a command. The end of a command is marked in front of the next
+    /// prompt. Terminals without support ignore the sequences.
+    pub fn with_semantic_prompt_markers(mut self, semantic_prompt_markers: bool) -> Reedline {
+        self.painter
+            .set_semantic_prompt_markers(semantic_prompt_markers);
+        self
+    }
+
+    /// A builder which configures how often the prompt is repainted automatically, e.g. to
+    /// update the clock of the prompt or a spinner
+    ///
+    /// By default the prompt is repainted every second, `None` disables the animation.
+    /// A repaint caused by input restarts the interval.
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    /// use std::time::Duration;
+    ///
+    /// let line_editor =
+    ///     Reedline::create()?.with_animation_interval(Some(Duration::from_millis(100)));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_animation_interval(mut self, interval: Option<Duration>) -> Reedline {
+        self.animation_interval = interval.filter(|interval| !interval.is_zero());
+        self
+    }
+
+    /// A builder that reserves rows beneath the input area for a host drawn [`Widget`]
+    ///
+    /// `None` removes the widget.
+    pub fn with_widget(mut self, widget: Option<Box<dyn Widget>>) -> Reedline {
+        self.widget = widget;
+        self
+    }
+
+    /// A builder that lets the host compute the prompt from live data, e.g. a clock, the
+    /// current git branch or the number of running jobs
+    ///
+    /// `refresher` is called when a line starts and before every repaint of
+    /// [`Reedline::with_animation_interval()`], the prompt it returns replaces the one passed to
+    /// [`Reedline::read_line()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::{DefaultPrompt, PromptEditMode, Reedline};
+    /// use std::sync::{
+    ///     atomic::{AtomicUsize, Ordering},
+    ///     Arc,
+    /// };
+    ///
+    /// let running_jobs = Arc::new(AtomicUsize::new(0));
+    /// let jobs = running_jobs.clone();
+    /// let line_editor = Reedline::create()?.with_prompt_refresher(Box::new(move || {
+    ///     let indicator = format!("[{} jobs]〉", jobs.load(Ordering::Relaxed));
+    ///     Box::new(DefaultPrompt::default().with_indicator(PromptEditMode::Emacs, indicator))
+    /// }));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_prompt_refresher(
+        mut self,
+        refresher: Box<dyn FnMut() -> Box<dyn Prompt>>,
+    ) -> Reedline {
+        self.prompt_refresher = Some(refresher);
+        self
+    }
+
+    /// A builder that sets a hook called before the prompt of a new line is painted
+    ///
+    /// Runs before the prompt refresher, e.g. to update the environment the prompt is built
+    /// from. A line continued after [`Reedline::read_line_with_timeout()`] doesn't call it again.
+    pub fn with_pre_prompt_hook(mut self, hook: Box<dyn FnMut()>) -> Reedline {
+        self.pre_prompt_hook = Some(hook);
+        self
+    }
+
+    /// A builder that sets a hook called with each submitted line, right before it is returned
+    ///
+    /// Only lines accepted by the validator are passed, e.g. to start timing the command.
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    /// use std::{cell::Cell, rc::Rc, time::Instant};
+    ///
+    /// let started = Rc::new(Cell::new(None));
+    /// let start = started.clone();
+    /// let line_editor = Reedline::create()?
+    ///     .with_pre_execution_hook(Box::new(move |_line| start.set(Some(Instant::now()))));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_pre_execution_hook(mut self, hook: Box<dyn FnMut(&str)>) -> Reedline {
+        self.pre_execution_hook = Some(hook);
+        self
+    }
+
+    /// A builder that sets how submitted lines are canonicalized
+    ///
+    /// The normalizer is applied to lines accepted by the validator, before they are added to
+    /// the history and returned with [`Signal::Success`]. The line on screen shows the result.
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::{collapse_whitespace, Reedline};
+    ///
+    /// let line_editor = Reedline::create()?.with_buffer_normalizer(Box::new(collapse_whitespace));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_buffer_normalizer(mut self, normalizer: BufferNormalizer) -> Reedline {
+        self.buffer_normalizer = Some(normalizer);
+        self
+    }
+
+    /// A builder that sets what [`ReedlineEvent::CtrlD`] and [`ReedlineEvent::CtrlC`] do
+    ///
+    /// By default `CtrlD` deletes the character under the cursor and returns [`Signal::CtrlD`]
+    /// on an empty buffer, `CtrlC` returns [`Signal::CtrlC`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::{CtrlCBehavior, CtrlDBehavior, InterruptBehavior, Reedline};
+    ///
+    /// let line_editor = Reedline::create()?.with_interrupt_behavior(InterruptBehavior {
+    ///     ctrl_d: CtrlDBehavior::Ignore,
+    ///     ctrl_c: CtrlCBehavior::ClearLine,
+    /// });
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_interrupt_behavior(mut self, interrupt_behavior: InterruptBehavior) -> Reedline {
+        self.interrupt_behavior = interrupt_behavior;
+        self
+    }
+
+    /// A builder that limits how often the screen is painted, by default to 60 frames per second
+    ///
+    /// Edits are still applied as soon as they arrive, only the paints in between are skipped.
+    /// This bounds the work on slow terminals flooded with events, e.g. while a key is held down.
+    /// `None` paints after every event.
+    pub fn with_max_frame_rate(mut self, frames_per_second: Option<u32>) -> Reedline {
+        self.frame_interval = frames_per_second
+            .filter(|frames_per_second| *frames_per_second > 0)
+            .map(|frames_per_second| Duration::from_secs(1) / frames_per_second);
+        self
+    }
+
+    /// A builder which enables or disables a dimmed, collapsed view of the previously
+    /// submitted command above the prompt.
+    ///
+    /// Useful for REPLs that clear the screen between entries but want to keep some context.
+    pub fn with_previous_command_view(mut self, show_previous_command: bool) -> Reedline {
+        self.show_previous_command = show_previous_command;
+        if !show_previous_command {
+            self.painter.set_previous_command(None);
+        }
+        self
+    }
+
+    /// A builder which enables or disables a preview of the adjacent history entries
+    ///
+    /// While traversing the history the older entry is shown dimmed above the prompt and the
+    /// newer one below the buffer, each collapsed to a single row.
+    pub fn with_history_preview(mut self, show_history_preview: bool) -> Reedline {
+        self.show_history_preview = show_history_preview;
+        self
+    }
+
+    /// A builder which configures the gutter in front of the lines of a multiline buffer
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::{MultilineGutter, Reedline};
+    ///
+    /// let mut line_editor =
+    ///     Reedline::create()?.with_multiline_gutter(Some(MultilineGutter::LineNumbers));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_multiline_gutter(mut self, multiline_gutter: Option<MultilineGutter>) -> Reedline {
+        self.multiline_gutter = multiline_gutter;
+        self
+    }
+
+    /// A builder which sets the columns between two tab stops, 8 by default
+    ///
+    /// A tab character in the buffer, e.g. from pasted code, is shown up to the next tab stop
+    /// of its line, counted from the start of the buffer line.
+    pub fn with_tab_stop(mut self, tab_stop: usize) -> Reedline {
+        self.tab_stop = tab_stop.max(1);
+        self
+    }
+
+    /// A builder which sets how tab characters in the buffer are shown
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::{Reedline, TabRendering};
+    ///
+    /// let mut line_editor = Reedline::create()?
+    ///     .with_tab_stop(4)
+    ///     .with_tab_rendering(TabRendering::Glyph('→'));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_tab_rendering(mut self, tab_rendering: TabRendering) -> Reedline {
+        self.tab_rendering = tab_rendering;
         self
     }
 
@@ -258,6 +805,105 @@ impl Reedline {
         Ok(self)
     }
 
+    /// A builder that keeps the buffer being edited in `scratch_file` to recover it after a crash
+    ///
+    /// The buffer is written every few edits and the file is removed once the line is submitted
+    /// or aborted. If the file already exists, a previous session ended while editing and its
+    /// buffer is offered as the initial buffer at the next prompt.
+    /// This protects long multi-line commands from terminal crashes.
+    ///
+    /// **Side effects:** creates all nested directories to the file
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::io;
+    /// use reedline::Reedline;
+    ///
+    /// let mut line_editor = Reedline::create()?.with_buffer_recovery("buffer.txt".into())?;
+    /// # Ok::<(), io::Error>(())
+    /// ```
+    pub fn with_buffer_recovery(mut self, scratch_file: PathBuf) -> std::io::Result<Reedline> {
+        let scratch_file = ScratchFile::new(scratch_file)?;
+        if let Some(buffer) = scratch_file.recover()? {
+            let offset = buffer.len();
+            self.editor.set_buffer(buffer);
+            self.set_offset(offset);
+        }
+        self.scratch_file = Some(scratch_file);
+
+        Ok(self)
+    }
+
+    /// A builder that prints notifications of background jobs while a line is read
+    ///
+    /// A notification sent to the channel, e.g. `[1]  Done  sleep 30`, is printed on its own
+    /// in place of the prompt, which is repainted below it with the buffer unchanged.
+    /// Notifications sent while no line is read are printed when the next one starts.
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    /// use std::{sync::mpsc, thread};
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    /// let mut line_editor = Reedline::create()?.with_job_notifications(receiver);
+    ///
+    /// thread::spawn(move || {
+    ///     // Wait for the job to finish
+    ///     sender.send("[1]  Done  sleep 30".to_string())
+    /// });
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_job_notifications(mut self, receiver: Receiver<String>) -> Reedline {
+        self.job_notifications = Some(receiver);
+        self
+    }
+
+    /// A builder that enables the kitty keyboard protocol on terminals supporting it
+    ///
+    /// The protocol reports key combinations that legacy terminals send like other keys, so
+    /// they can be bound separately: `Ctrl+I` is told apart from `Tab`, `Ctrl+M` from `Enter`,
+    /// and `Shift+Enter` inserts a newline with the default keybindings.
+    /// Support is detected when the first line is read, other terminals keep working as before.
+    pub fn with_kitty_keyboard_protocol(mut self, use_kitty_protocol: bool) -> Reedline {
+        self.use_kitty_protocol = use_kitty_protocol;
+        self
+    }
+
+    /// A builder that lets a left click move the cursor to the clicked position
+    ///
+    /// The mouse is captured while a line is read, which turns off the text selection of
+    /// most terminals. It usually stays available while holding `Shift`.
+    pub fn with_mouse_capture(mut self, use_mouse_capture: bool) -> Reedline {
+        self.use_mouse_capture = use_mouse_capture;
+        self
+    }
+
+    /// A builder that renders the prompt and the output printed through the engine into the
+    /// alternate screen of the terminal, for full screen hosts like pagers
+    ///
+    /// The alternate screen is entered when the first line is read and left when the engine is
+    /// dropped, or while the terminal is lent to the host with [`Reedline::suspend_guard()`].
+    /// It scrolls on its own, the normal screen and its scrollback are shown again unchanged
+    /// afterwards. Lines are painted inline on the normal screen by default.
+    pub fn with_alternate_screen(mut self, use_alternate_screen: bool) -> Reedline {
+        self.use_alternate_screen = use_alternate_screen;
+        self
+    }
+
+    /// A builder that configures the events run when the mouse wheel is turned over the prompt
+    ///
+    /// Requires [`Reedline::with_mouse_capture()`]. By default turning the wheel up and down
+    /// navigates the history, pass [`ReedlineEvent::None`] to ignore it.
+    pub fn with_scroll_wheel(
+        mut self,
+        scroll_up: ReedlineEvent,
+        scroll_down: ReedlineEvent,
+    ) -> Reedline {
+        self.scroll_wheel_events = (scroll_up, scroll_down);
+        self
+    }
+
     /// A builder that configures the validator for your instance of the Reedline engine
     /// # Example
     /// ```rust,no_run
@@ -275,6 +921,35 @@ impl Reedline {
         self
     }
 
+    /// A builder that expands abbreviations typed as the first word of the line, see
+    /// [`Abbreviations`]
+    pub fn with_abbreviations(mut self, abbreviations: Abbreviations) -> Reedline {
+        self.abbreviations = abbreviations;
+        self
+    }
+
+    /// A builder that sets which characters word-wise movements and edits treat as part of a
+    /// word, see [`WordBoundary`]
+    pub fn with_word_boundary(mut self, word_boundary: WordBoundary) -> Reedline {
+        self.editor.set_word_boundary(word_boundary);
+        self
+    }
+
+    /// A builder that registers a menu, replacing a registered menu with the same name
+    ///
+    /// The menu is opened by [`ReedlineEvent::Menu`] with its name, see [`Menu`] for an example.
+    pub fn with_menu(mut self, menu: Box<dyn Menu>) -> Reedline {
+        match self
+            .menus
+            .iter_mut()
+            .find(|registered| registered.name() == menu.name())
+        {
+            Some(registered) => *registered = menu,
+            None => self.menus.push(menu),
+        }
+        self
+    }
+
     /// A builder which configures the edit mode for your instance of the Reedline engine
     pub fn with_edit_mode(mut self, edit_mode: Box<dyn EditMode>) -> Reedline {
         self.edit_mode = edit_mode;
@@ -287,43 +962,505 @@ impl Reedline {
         self.edit_mode.edit_mode()
     }
 
-    /// Output the complete [`History`] chronologically with numbering to the terminal
-    pub fn print_history(&mut self) -> Result<()> {
-        let history: Vec<_> = self
-            .history
-            .iter_chronologic()
-            .cloned()
-            .enumerate()
-            .collect();
-
-        for (i, entry) in history {
-            self.print_line(&format!("{}\t{}", i + 1, entry))?;
-        }
-        Ok(())
+    /// Read access to the configured [`History`], e.g. to iterate over the current session
+    pub fn history(&self) -> &dyn History {
+        &*self.history
     }
 
-    /// Wait for input and provide the user with a specified [`Prompt`].
+    /// The registry of strings inserted by [`ReedlineEvent::QuickInsert`] and the [`QuickInsertMenu`]
     ///
-    /// Returns a [`crossterm::Result`] in which the `Err` type is [`crossterm::ErrorKind`]
-    /// to distinguish I/O errors and the `Ok` variant wraps a [`Signal`] which
-    /// handles user inputs.
-    pub fn read_line(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
-        terminal::enable_raw_mode()?;
+    /// The returned handle shares the entries with the engine, keep it to update them
+    /// between [`Reedline::read_line()`] calls.
+    pub fn quick_inserts(&self) -> QuickInserts {
+        self.quick_inserts.clone()
+    }
 
-        let result = self.read_line_helper(prompt);
+    /// Check the configured parts for inconsistencies, most serious first
+    ///
+    /// Reading a line fails with an [`io::ErrorKind::InvalidInput`] error if any issue is an
+    /// [`IssueSeverity::Error`]. Call this after configuring the engine to report all issues,
+    /// including the warnings.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use reedline::{
+    ///     default_emacs_keybindings, ConfigurationIssue, Emacs, Reedline, ReedlineEvent,
+    /// };
+    ///
+    /// let mut keybindings = default_emacs_keybindings();
+    /// keybindings.add_binding(
+    ///     KeyModifiers::CONTROL,
+    ///     KeyCode::Char('o'),
+    ///     ReedlineEvent::Menu("completions".into()),
+    /// );
+    /// let line_editor = Reedline::create()?.with_edit_mode(Box::new(Emacs::new(keybindings)));
+    ///
+    /// let issues = line_editor.validate_configuration();
+    /// assert!(matches!(
+    ///     &issues[..],
+    ///     [ConfigurationIssue::UnknownMenu { menu, .. }] if menu == "completions"
+    /// ));
+    /// for issue in issues {
+    ///     // Error: Ctrl-o opens the menu 'completions', which is not registered
+    ///     eprintln!("{:?}: {}", issue.severity(), issue);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn validate_configuration(&self) -> Vec<ConfigurationIssue> {
+        let mut issues = vec![];
+
+        let mut menu_names: Vec<&str> = vec![];
+        for menu in &self.menus {
+            let name = menu.name();
+            if !menu_names.contains(&name) {
+                menu_names.push(name);
+            } else if !issues.contains(&ConfigurationIssue::DuplicateMenu(name.to_string())) {
+                issues.push(ConfigurationIssue::DuplicateMenu(name.to_string()));
+            }
+        }
 
-        terminal::disable_raw_mode()?;
+        let mut bindings: Vec<(&KeyCombination, &ReedlineEvent)> = self
+            .edit_mode
+            .all_keybindings()
+            .into_iter()
+            .flat_map(|keybindings| keybindings.bindings.iter())
+            .collect();
+        bindings.sort_by_cached_key(|(key, _)| key.to_string());
+        for (key, event) in bindings {
+            self.check_binding(key, event, &mut issues);
+        }
 
-        result
+        // Stable sort, issues of the same severity stay ordered by key
+        issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity()));
+        issues
     }
 
-    /// Writes `msg` to the terminal with a following carriage return and newline
-    pub fn print_line(&mut self, msg: &str) -> Result<()> {
-        self.painter.paint_line(msg)
+    /// Collect the issues of the event bound to `key`, including the events it consists of
+    fn check_binding(
+        &self,
+        key: &KeyCombination,
+        event: &ReedlineEvent,
+        issues: &mut Vec<ConfigurationIssue>,
+    ) {
+        let issue = match event {
+            ReedlineEvent::Menu(menu) if !self.menus.iter().any(|m| m.name() == menu) => {
+                ConfigurationIssue::UnknownMenu {
+                    key: key.clone(),
+                    menu: menu.clone(),
+                }
+            }
+            ReedlineEvent::QuickInsert(entry) if self.quick_inserts.get(entry).is_none() => {
+                ConfigurationIssue::UnknownQuickInsert {
+                    key: key.clone(),
+                    entry: entry.clone(),
+                }
+            }
+            ReedlineEvent::Edit(commands) if commands.is_empty() => {
+                ConfigurationIssue::EmptyBinding(key.clone())
+            }
+            ReedlineEvent::Multiple(events) | ReedlineEvent::Paste(events) => {
+                if events.is_empty() {
+                    ConfigurationIssue::EmptyBinding(key.clone())
+                } else {
+                    for event in events {
+                        self.check_binding(key, event, issues);
+                    }
+                    return;
+                }
+            }
+            _ => return,
+        };
+
+        // The tables of different modes may contain the same binding
+        if !issues.contains(&issue) {
+            issues.push(issue);
+        }
+    }
+
+    /// Refuse to read a line if the configuration contains errors
+    fn check_configuration(&self) -> io::Result<()> {
+        let issues = self.validate_configuration();
+        match issues
+            .into_iter()
+            .find(|issue| issue.severity() == IssueSeverity::Error)
+        {
+            Some(issue) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                issue.to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Start a new history session, entries submitted from now on are tagged with its id
+    ///
+    /// See [`History::new_session_id()`]
+    pub fn new_history_session(&mut self) -> HistorySessionId {
+        self.history.new_session_id()
+    }
+
+    /// Write the history to its storage and pick up the entries of other instances sharing it
+    ///
+    /// Call this at safe points of the host, e.g. after a submitted command finished.
+    /// Browsing the history continues from the same entry, also after
+    /// [`Reedline::read_line_with_timeout()`] returned in the middle of it.
+    pub fn sync_history(&mut self) -> std::io::Result<()> {
+        self.history.sync()
+    }
+
+    /// Start the next read with `buffer` already typed and the cursor at byte offset `cursor`
+    ///
+    /// For workflows like editing the last failed command again or `fc`. The cursor is clamped
+    /// to the buffer and moved back to the start of a character. Undoing the first edit of the
+    /// next read returns to `buffer`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use reedline::{DefaultPrompt, Reedline, Signal};
+    ///
+    /// let mut line_editor = Reedline::create()?;
+    /// let prompt = DefaultPrompt::default();
+    ///
+    /// if let Signal::CtrlC { buffer, cursor } = line_editor.read_line(&prompt)? {
+    ///     // Offer the aborted line again
+    ///     line_editor.set_initial_buffer(buffer, cursor);
+    ///     line_editor.read_line(&prompt)?;
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn set_initial_buffer(&mut self, buffer: String, cursor: usize) {
+        let mut cursor = cursor.min(buffer.len());
+        while !buffer.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+
+        self.editor.set_buffer(buffer);
+        self.set_offset(cursor);
+        self.editor.reset_undo_stack();
+        self.editor.remember_undo_state(true);
+    }
+
+    /// The content of the line buffer
+    ///
+    /// Together with [`Reedline::current_insertion_point()`] and
+    /// [`Reedline::run_edit_commands()`] this lets hosts inspect and change the line between
+    /// reads, e.g. to expand abbreviations or correct a command.
+    pub fn current_buffer_contents(&self) -> &str {
+        self.editor.get_buffer()
+    }
+
+    /// The byte offset of the cursor in [`Reedline::current_buffer_contents()`]
+    pub fn current_insertion_point(&self) -> usize {
+        self.editor.offset()
+    }
+
+    /// Run `commands` over the line buffer like the bound keys do, the next read shows the
+    /// result
+    ///
+    /// The edits can be undone like those typed. [`EditCommand::MoveToPosition`] is ignored,
+    /// as it refers to the screen position of the line while it is read.
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::{EditCommand, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create()?;
+    /// line_editor.run_edit_commands(&[
+    ///     EditCommand::InsertString("gti status".into()),
+    ///     EditCommand::MoveToStart,
+    ///     EditCommand::CutWordRight,
+    ///     EditCommand::InsertString("git".into()),
+    /// ]);
+    ///
+    /// assert_eq!(line_editor.current_buffer_contents(), "git status");
+    /// assert_eq!(line_editor.current_insertion_point(), 3);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn run_edit_commands(&mut self, commands: &[EditCommand]) {
+        self.take_history_entry();
+        for command in commands {
+            self.apply_edit_command(command);
+        }
+    }
+
+    /// Output the complete [`History`] chronologically with numbering to the terminal
+    pub fn print_history(&mut self) -> Result<()> {
+        let history: Vec<_> = self
+            .history
+            .iter_chronologic()
+            .cloned()
+            .enumerate()
+            .collect();
+
+        for (i, entry) in history {
+            self.print_line(&format!("{}\t{}", i + 1, entry))?;
+        }
+        Ok(())
+    }
+
+    /// Wait for input and provide the user with a specified [`Prompt`].
+    ///
+    /// Returns a [`crossterm::Result`] in which the `Err` type is [`crossterm::ErrorKind`]
+    /// to distinguish I/O errors and the `Ok` variant wraps a [`Signal`] which
+    /// handles user inputs.
+    /// Fails right away if [`Reedline::validate_configuration()`] reports an error.
+    pub fn read_line(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
+        self.check_configuration()?;
+        self.enable_terminal_modes()?;
+
+        let result = self.read_line_helper(prompt, None);
+
+        self.disable_terminal_modes()?;
+
+        result
+    }
+
+    /// Wait for input like [`Reedline::read_line()`] without blocking the thread
+    ///
+    /// Reads the terminal events from a crossterm [`EventStream`](crossterm::event::EventStream),
+    /// so hosts running inside an async runtime like `tokio` or `async-std` can await the line
+    /// instead of spawning a blocking thread for the line editor. The timers use their own
+    /// thread and work with any runtime.
+    ///
+    /// The future borrows the engine and is not `Send`, await it on the task owning the engine.
+    /// Dropping it before completion restores the terminal and keeps the buffer for the next call.
+    ///
+    /// Requires the `async` feature.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use reedline::{DefaultPrompt, Reedline, Signal};
+    ///
+    /// async fn repl() -> std::io::Result<()> {
+    ///     let mut line_editor = Reedline::create()?;
+    ///     let prompt = DefaultPrompt::default();
+    ///
+    ///     while let Signal::Success(buffer) = line_editor.read_line_async(&prompt).await? {
+    ///         line_editor.print_line(&buffer)?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn read_line_async(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
+        self.check_configuration()?;
+        self.enable_terminal_modes()?;
+        // Also restore the terminal if the future is dropped while waiting for input
+        let _terminal_modes = TerminalModesGuard {
+            keyboard_enhanced: self.keyboard_enhanced,
+            mouse_captured: self.mouse_captured,
+        };
+
+        let result = self.read_line_async_helper(prompt).await;
+        self.keyboard_enhanced = false;
+        self.mouse_captured = false;
+
+        result
+    }
+
+    /// Wait for input like [`Reedline::read_line()`] but give up after `timeout`
+    ///
+    /// Returns [`Signal::Timeout`] if no line was completed in time. The buffer and edit state
+    /// are kept and the cursor is left at the start of the prompt, so the next call to
+    /// `read_line_with_timeout()` or [`Reedline::read_line()`] continues editing the same line.
+    /// Output printed in between with [`Reedline::print_line()`] appears above the prompt.
+    ///
+    /// Lets network REPLs and dashboards interleave other work with reading input.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use reedline::{DefaultPrompt, Reedline, Signal};
+    ///
+    /// let mut line_editor = Reedline::create()?;
+    /// let prompt = DefaultPrompt::default();
+    ///
+    /// loop {
+    ///     match line_editor.read_line_with_timeout(&prompt, Duration::from_millis(500))? {
+    ///         Signal::Success(buffer) => line_editor.print_line(&buffer)?,
+    ///         Signal::Timeout => {
+    ///             // Poll the network, update the dashboard, ...
+    ///         }
+    ///         _ => break,
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn read_line_with_timeout(
+        &mut self,
+        prompt: &dyn Prompt,
+        timeout: Duration,
+    ) -> Result<Signal> {
+        self.check_configuration()?;
+        self.enable_terminal_modes()?;
+
+        let result = self.read_line_helper(prompt, Some(Instant::now() + timeout));
+
+        self.disable_terminal_modes()?;
+
+        result
+    }
+
+    /// Hide the line and leave raw mode until the returned guard is dropped, to run a full
+    /// screen program like a pager or an editor
+    ///
+    /// Meant for a line suspended by [`Reedline::read_line_with_timeout()`]: its prompt and
+    /// buffer are cleared from the screen and painted again with `prompt` when the guard is
+    /// dropped, where the program left the cursor. The buffer and edit state are kept for the
+    /// next read. Without a suspended line only the terminal modes are changed.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use std::{process::Command, time::Duration};
+    /// use reedline::{DefaultPrompt, Reedline, Signal};
+    ///
+    /// let mut line_editor = Reedline::create()?;
+    /// let prompt = DefaultPrompt::default();
+    ///
+    /// loop {
+    ///     match line_editor.read_line_with_timeout(&prompt, Duration::from_secs(60))? {
+    ///         Signal::Success(buffer) => line_editor.print_line(&buffer)?,
+    ///         Signal::Timeout => {
+    ///             // Show the news while the user is idle, then continue editing
+    ///             let _suspended = line_editor.suspend_guard(&prompt)?;
+    ///             Command::new("less").arg("NEWS").status()?;
+    ///         }
+    ///         _ => break,
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn suspend_guard<'engine, 'prompt>(
+        &'engine mut self,
+        prompt: &'prompt dyn Prompt,
+    ) -> Result<SuspendGuard<'engine, 'prompt>> {
+        let raw_mode = terminal::is_raw_mode_enabled()?;
+        if self.resume_after_timeout {
+            self.painter.clear_from_prompt_start()?;
+        }
+        self.disable_terminal_modes()?;
+        self.leave_alternate_screen()?;
+
+        Ok(SuspendGuard {
+            engine: self,
+            prompt,
+            raw_mode,
+        })
+    }
+
+    /// Take the terminal back from the host after [`Reedline::suspend_guard()`]
+    fn resume(&mut self, prompt: &dyn Prompt, raw_mode: bool) -> Result<()> {
+        if raw_mode {
+            self.enable_terminal_modes()?;
+        }
+        self.enter_alternate_screen()?;
+        if !self.resume_after_timeout {
+            return Ok(());
+        }
+
+        // The program may have resized the terminal or left output behind
+        self.painter.init_terminal_size()?;
+        self.painter.initialize_prompt_position()?;
+        self.render_widget();
+        self.painter.adjust_prompt_position(&self.editor)?;
+        self.last_paint = None;
+        self.pending_paint = None;
+        self.with_live_prompt(prompt, |this, prompt| this.paint_from_origin(prompt))?;
+
+        // Where the next read expects the suspended line to start
+        self.painter.move_to_prompt_start()
+    }
+
+    /// Enter raw mode and the keyboard enhancement, if enabled, to read a line
+    fn enable_terminal_modes(&mut self) -> Result<()> {
+        terminal::enable_raw_mode()?;
+        self.enter_alternate_screen()?;
+
+        if self.use_kitty_protocol {
+            let supported = match self.kitty_protocol_supported {
+                Some(supported) => supported,
+                None => {
+                    let supported = terminal::supports_keyboard_enhancement()?;
+                    self.kitty_protocol_supported = Some(supported);
+                    supported
+                }
+            };
+            if supported {
+                self.painter.enable_keyboard_enhancement()?;
+                self.keyboard_enhanced = true;
+            }
+        }
+
+        if self.use_mouse_capture {
+            self.painter.enable_mouse_capture()?;
+            self.mouse_captured = true;
+        }
+
+        Ok(())
+    }
+
+    /// Restore the terminal modes changed by [`Reedline::enable_terminal_modes()`]
+    fn disable_terminal_modes(&mut self) -> Result<()> {
+        if std::mem::take(&mut self.keyboard_enhanced) {
+            self.painter.disable_keyboard_enhancement()?;
+        }
+        if std::mem::take(&mut self.mouse_captured) {
+            self.painter.disable_mouse_capture()?;
+        }
+
+        terminal::disable_raw_mode()
+    }
+
+    /// Switch to the alternate screen if enabled and not done yet
+    fn enter_alternate_screen(&mut self) -> Result<()> {
+        if self.use_alternate_screen && !self.in_alternate_screen {
+            self.painter.enter_alternate_screen()?;
+            self.in_alternate_screen = true;
+        }
+
+        Ok(())
+    }
+
+    /// Show the normal screen again, the alternate screen is entered again by the next read
+    fn leave_alternate_screen(&mut self) -> Result<()> {
+        if std::mem::take(&mut self.in_alternate_screen) {
+            self.painter.leave_alternate_screen()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `msg` to the terminal with a following carriage return and newline
+    pub fn print_line(&mut self, msg: &str) -> Result<()> {
+        self.painter.paint_line(msg)
+    }
+
+    /// A printer for output of the host that has to appear while a line is read
+    ///
+    /// All printers of one line editor share the same channel, see [`ExternalPrinter`].
+    pub fn external_printer(&mut self) -> ExternalPrinter {
+        let (printer, _) = self.external_output.get_or_insert_with(|| {
+            let (sender, receiver) = channel();
+            (ExternalPrinter::new(sender), receiver)
+        });
+        printer.clone()
+    }
+
+    /// Writes `output` to the terminal, each of its lines on its own row
+    ///
+    /// For use between reads, while a line is read an [`ExternalPrinter`] keeps the prompt intact.
+    pub fn print_external_output(&mut self, output: &str) -> Result<()> {
+        for line in output.lines() {
+            self.painter.paint_line(line)?;
+        }
+        Ok(())
     }
 
     /// Clear the screen by printing enough whitespace to start the prompt or
     /// other output back at the first line of the terminal.
+    ///
+    /// [`ReedlineEvent::ClearScreen`] does this while editing, without returning to the host.
     pub fn clear_screen(&mut self) -> Result<()> {
         self.painter.clear_screen()?;
 
@@ -332,100 +1469,507 @@ impl Reedline {
 
     /// Helper implemting the logic for [`Reedline::read_line()`] to be wrapped
     /// in a `raw_mode` context.
-    fn read_line_helper(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
+    ///
+    /// Returns [`Signal::Timeout`] once `deadline` passed without a completed line.
+    fn read_line_helper(
+        &mut self,
+        prompt: &dyn Prompt,
+        deadline: Option<Instant>,
+    ) -> Result<Signal> {
+        self.run_pre_prompt_hook();
+        self.refresh_prompt();
+        self.with_live_prompt(prompt, |this, prompt| this.begin_line(prompt))?;
+
+        loop {
+            if self.is_animation_due() {
+                self.refresh_prompt();
+            }
+
+            let signal = self
+                .with_live_prompt(prompt, |this, prompt| this.read_line_step(prompt, deadline))?;
+            if let Some(signal) = signal {
+                return Ok(signal);
+            }
+        }
+    }
+
+    /// Wait for the next events until `deadline` and handle them
+    fn read_line_step(
+        &mut self,
+        prompt: &dyn Prompt,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Signal>> {
+        self.print_pending_output(prompt)?;
+        self.poll_menu_values(prompt)?;
+        self.poll_tab_completions(prompt)?;
+        #[cfg(windows)]
+        self.poll_terminal_size(prompt)?;
+
+        let timeout = self.frame_timeout();
+        let timeout = match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    self.flush_pending_paint(prompt)?;
+                    self.painter.move_to_prompt_start()?;
+                    self.resume_after_timeout = true;
+                    if let Some(scratch_file) = &mut self.scratch_file {
+                        scratch_file.save(self.editor.get_buffer());
+                    }
+                    return Ok(Some(Signal::Timeout));
+                }
+                timeout.min(remaining)
+            }
+            None => timeout,
+        };
+
+        if event::poll(timeout)? {
+            let mut crossterm_events: Vec<Event> = vec![];
+
+            // There could be multiple events queued up!
+            // pasting text, resizes, blocking this thread (e.g. during debugging)
+            // We should be able to handle all of them as quickly as possible without causing unnecessary output steps.
+            while event::poll(Duration::from_millis(POLL_WAIT))? {
+                crossterm_events.push(event::read()?);
+            }
+
+            self.handle_crossterm_events(prompt, crossterm_events)
+        } else {
+            self.handle_idle(prompt)
+        }
+    }
+
+    /// Call the hook of [`Reedline::with_pre_prompt_hook()`] unless the line is continued
+    fn run_pre_prompt_hook(&mut self) {
+        if self.resume_after_timeout {
+            return;
+        }
+        if let Some(hook) = &mut self.pre_prompt_hook {
+            hook();
+        }
+    }
+
+    /// Ask the host for a new prompt with [`Reedline::with_prompt_refresher()`], if configured
+    fn refresh_prompt(&mut self) {
+        if let Some(prompt_refresher) = &mut self.prompt_refresher {
+            self.refreshed_prompt = Some(prompt_refresher());
+        }
+    }
+
+    /// Run `f` with the prompt of the last refresh, or `prompt` without a refresher
+    fn with_live_prompt<T>(
+        &mut self,
+        prompt: &dyn Prompt,
+        f: impl FnOnce(&mut Self, &dyn Prompt) -> T,
+    ) -> T {
+        let refreshed_prompt = self.refreshed_prompt.take();
+        let result = f(self, refreshed_prompt.as_deref().unwrap_or(prompt));
+        self.refreshed_prompt = refreshed_prompt;
+
+        result
+    }
+
+    /// Helper implementing [`Reedline::read_line_async()`], reading the events from a
+    /// crossterm [`EventStream`](event::EventStream) instead of polling
+    #[cfg(feature = "async")]
+    async fn read_line_async_helper(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
+        let mut event_stream = event::EventStream::new();
+        self.run_pre_prompt_hook();
+        self.refresh_prompt();
+        self.with_live_prompt(prompt, |this, prompt| this.begin_line(prompt))?;
+
+        loop {
+            if self.is_animation_due() {
+                self.refresh_prompt();
+            }
+
+            // The refreshed prompt cannot be borrowed by a closure across the await points
+            let refreshed_prompt = self.refreshed_prompt.take();
+            let signal = self
+                .read_line_async_step(
+                    refreshed_prompt.as_deref().unwrap_or(prompt),
+                    &mut event_stream,
+                )
+                .await;
+            self.refreshed_prompt = refreshed_prompt;
+
+            if let Some(signal) = signal? {
+                return Ok(signal);
+            }
+        }
+    }
+
+    /// Wait for the next events of `event_stream` and handle them
+    #[cfg(feature = "async")]
+    async fn read_line_async_step(
+        &mut self,
+        prompt: &dyn Prompt,
+        event_stream: &mut event::EventStream,
+    ) -> Result<Option<Signal>> {
+        self.print_pending_output(prompt)?;
+        self.poll_menu_values(prompt)?;
+        self.poll_tab_completions(prompt)?;
+        #[cfg(windows)]
+        self.poll_terminal_size(prompt)?;
+
+        let timeout = Delay::new(self.frame_timeout());
+        match select(event_stream.next(), timeout).await {
+            Either::Left((Some(event), _)) => {
+                let mut crossterm_events: Vec<Event> = vec![event?];
+
+                // Collect the events arriving right after, e.g. when pasting text
+                while let Either::Left((Some(event), _)) = select(
+                    event_stream.next(),
+                    Delay::new(Duration::from_millis(POLL_WAIT)),
+                )
+                .await
+                {
+                    crossterm_events.push(event?);
+                }
+
+                self.handle_crossterm_events(prompt, crossterm_events)
+            }
+            // The terminal input was closed
+            Either::Left((None, _)) => Ok(Some(Signal::CtrlD)),
+            Either::Right(_) => self.handle_idle(prompt),
+        }
+    }
+
+    /// Notice a resize without a resize event, which Windows doesn't reliably report
+    #[cfg(windows)]
+    fn poll_terminal_size(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if let Some((width, height)) = self.painter.changed_terminal_size()? {
+            self.painter.handle_reflowing_resize(width, height)?;
+            self.full_repaint(prompt)?;
+        }
+        Ok(())
+    }
+
+    /// Paint the prompt of a new or continued line, shared by all ways to read a line
+    fn begin_line(&mut self, prompt: &dyn Prompt) -> Result<()> {
         self.painter.init_terminal_size()?;
+        // A line continued after a timeout already follows the previous command
+        if !std::mem::take(&mut self.resume_after_timeout) {
+            self.painter
+                .paint_previous_command(self.use_ansi_coloring)?;
+        }
         self.painter.initialize_prompt_position()?;
+        // Make room for the widget before the first paint
+        self.render_widget();
+        self.painter.adjust_prompt_position(&self.editor)?;
+
+        // The prompt of a new line is painted right away
+        self.last_paint = None;
+        self.pending_paint = None;
 
-        // Redraw if Ctrl-L was used
+        self.paint_from_origin(prompt)
+    }
+
+    /// Paint the prompt and the state of the current mode from the prompt origin
+    fn paint_from_origin(&mut self, prompt: &dyn Prompt) -> Result<()> {
         if self.input_mode == InputMode::HistorySearch {
-            self.history_search_paint(prompt)?;
+            self.history_search_paint(prompt)
         } else {
+            self.full_repaint(prompt)
+        }
+    }
+
+    /// Clear the screen and continue editing with the prompt on the first line
+    fn clear_screen_and_repaint(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        self.painter.clear_screen()?;
+        self.painter.initialize_prompt_position()?;
+        self.paint_from_origin(prompt)
+    }
+
+    /// Repaint the prompt in place, erasing anything printed over it
+    fn redraw(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        self.painter.clear_from_prompt_start()?;
+        self.paint_from_origin(prompt)
+    }
+
+    /// How long to wait for input, waking up in time to perform a deferred paint,
+    /// to animate the prompt, to print job notifications or to show loaded menu values
+    fn frame_timeout(&self) -> Duration {
+        let timeout = match (self.pending_paint, self.frame_interval, self.last_paint) {
+            (Some(_), Some(interval), Some(last_paint)) => {
+                interval.saturating_sub(last_paint.elapsed())
+            }
+            (_, _, last_paint) => match self.animation_interval {
+                Some(interval) => interval.saturating_sub(
+                    last_paint
+                        .map(|last_paint| last_paint.elapsed())
+                        .unwrap_or_default(),
+                ),
+                None => IDLE_TIMEOUT,
+            },
+        };
+
+        if self.job_notifications.is_some()
+            || self.external_output.is_some()
+            || self.is_menu_loading()
+            || self.tab_handler.is_loading()
+        {
+            timeout.min(JOB_NOTIFICATION_INTERVAL)
+        } else {
+            timeout
+        }
+    }
+
+    /// Whether the open menu waits for values loaded in the background
+    fn is_menu_loading(&self) -> bool {
+        matches!(
+            self.active_menu.and_then(|index| self.menus.get(index)),
+            Some(menu) if menu.is_loading()
+        )
+    }
+
+    /// Repaint the open menu if values it loads in the background arrived
+    fn poll_menu_values(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        let arrived = match self.active_menu.and_then(|index| self.menus.get_mut(index)) {
+            Some(menu) => menu.poll_values(),
+            None => false,
+        };
+        if arrived {
+            self.menu_paint(prompt)?;
+        }
+
+        Ok(())
+    }
+
+    /// Insert the completions of the `Tab` handler if they arrived in the meantime
+    fn poll_tab_completions(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if !self.tab_handler.is_loading() {
+            return Ok(());
+        }
+        let before = self.editor.line_buffer().clone();
+        if self.tab_handler.poll(self.editor.line_buffer()) {
+            self.remember_tab_completion(&before);
+            self.show_tab_status()?;
             self.full_repaint(prompt)?;
         }
 
-        let mut crossterm_events: Vec<Event> = vec![];
+        Ok(())
+    }
+
+    /// Make the completion the `Tab` handler inserted into the line an edit that can be undone
+    fn remember_tab_completion(&mut self, before: &LineBuffer) {
+        if self.editor.line_buffer() == before {
+            return;
+        }
+        self.editor.remember_undo_state(true);
+        if let Some(scratch_file) = &mut self.scratch_file {
+            scratch_file.edited(self.editor.get_buffer());
+        }
+    }
+
+    /// Show the preview of the completion inserted by `Tab`, or that completions are loading
+    fn show_tab_status(&mut self) -> Result<()> {
+        let status = if self.tab_handler.is_loading() {
+            Some("(loading…)".to_string())
+        } else {
+            self.tab_handler.preview()
+        };
+        self.transient_status_shown = status.is_some();
+        self.painter.set_status_row(status);
+        self.painter.adjust_prompt_position(&self.editor)
+    }
+
+    /// Print the job notifications and external output received in the meantime where the
+    /// prompt is, then repaint the prompt and buffer below them
+    fn print_pending_output(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        let mut notifications: Vec<String> = match &self.job_notifications {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => vec![],
+        };
+        if let Some((_, receiver)) = &self.external_output {
+            notifications.extend(receiver.try_iter());
+        }
+        if notifications.is_empty() {
+            return Ok(());
+        }
+
+        // Also remove the history preview row above the prompt
+        self.painter
+            .set_history_preview(None, self.use_ansi_coloring)?;
+        self.painter.clear_from_prompt_start()?;
+        for line in notifications
+            .iter()
+            .flat_map(|notification| notification.lines())
+        {
+            self.painter.paint_line(line)?;
+        }
+        self.painter.initialize_prompt_position()?;
+
+        self.last_paint = None;
+        self.paint_from_origin(prompt)
+    }
+
+    /// Parse and handle a batch of terminal events that arrived together
+    fn handle_crossterm_events(
+        &mut self,
+        prompt: &dyn Prompt,
+        crossterm_events: Vec<Event>,
+    ) -> Result<Option<Signal>> {
         let mut reedline_events: Vec<ReedlineEvent> = vec![];
 
-        loop {
-            if event::poll(Duration::from_millis(1000))? {
-                let mut latest_resize = None;
-
-                // There could be multiple events queued up!
-                // pasting text, resizes, blocking this thread (e.g. during debugging)
-                // We should be able to handle all of them as quickly as possible without causing unnecessary output steps.
-                while event::poll(Duration::from_millis(POLL_WAIT))? {
-                    // TODO: Maybe replace with a separate function processing the buffered event
-                    match event::read()? {
-                        Event::Resize(x, y) => {
-                            latest_resize = Some((x, y));
-                        }
-                        x => crossterm_events.push(x),
-                    }
+        // Only the latest resize matters
+        let mut latest_resize = None;
+        let mut crossterm_events: Vec<Event> = crossterm_events
+            .into_iter()
+            .filter(|event| match event {
+                Event::Resize(x, y) => {
+                    latest_resize = Some((*x, *y));
+                    false
                 }
+                // Some terminals also report releasing a key
+                Event::Key(KeyEvent {
+                    kind: KeyEventKind::Release,
+                    ..
+                }) => false,
+                _ => true,
+            })
+            .collect();
 
-                if let Some((x, y)) = latest_resize {
-                    reedline_events.push(ReedlineEvent::Resize(x, y));
-                }
+        if let Some((x, y)) = latest_resize {
+            reedline_events.push(ReedlineEvent::Resize(x, y));
+        }
 
-                let mut last_edit_commands = None;
-                // If the size of crossterm_event vector is larger than threshold, we could assume
-                // that a lot of events were pasted into the prompt, indicating a paste
-                if crossterm_events.len() > EVENTS_THRESHOLD {
-                    reedline_events.push(self.handle_paste(&mut crossterm_events));
-                } else {
-                    for event in crossterm_events.drain(..) {
-                        match (&mut last_edit_commands, self.edit_mode.parse_event(event)) {
-                            (None, ReedlineEvent::Edit(ec)) => {
-                                last_edit_commands = Some(ec);
-                            }
-                            (None, other_event) => {
-                                reedline_events.push(other_event);
-                            }
-                            (Some(ref mut last_ecs), ReedlineEvent::Edit(ec)) => {
-                                last_ecs.extend(ec);
-                            }
-                            (ref mut a @ Some(_), other_event) => {
-                                reedline_events.push(ReedlineEvent::Edit(a.take().unwrap()));
+        let mut last_edit_commands = None;
+        // If the size of crossterm_event vector is larger than threshold, we could assume
+        // that a lot of events were pasted into the prompt, indicating a paste
+        if crossterm_events.len() > EVENTS_THRESHOLD {
+            reedline_events.push(self.handle_paste(&mut crossterm_events));
+        } else {
+            for event in crossterm_events.drain(..) {
+                match (&mut last_edit_commands, self.edit_mode.parse_event(event)) {
+                    (None, ReedlineEvent::Edit(ec)) => {
+                        last_edit_commands = Some(ec);
+                    }
+                    (None, other_event) => {
+                        reedline_events.push(other_event);
+                    }
+                    (Some(ref mut last_ecs), ReedlineEvent::Edit(ec)) => {
+                        last_ecs.extend(ec);
+                    }
+                    (ref mut a @ Some(_), other_event) => {
+                        reedline_events.push(ReedlineEvent::Edit(a.take().unwrap()));
 
-                                reedline_events.push(other_event);
-                            }
-                        }
+                        reedline_events.push(other_event);
                     }
                 }
-                if let Some(ec) = last_edit_commands {
-                    reedline_events.push(ReedlineEvent::Edit(ec));
-                }
-            } else if self.animate {
-                reedline_events.push(ReedlineEvent::Repaint);
-            };
+            }
+        }
+        if let Some(ec) = last_edit_commands {
+            reedline_events.push(ReedlineEvent::Edit(ec));
+        }
+
+        self.handle_events(prompt, reedline_events)
+    }
+
+    /// Checks if the animated prompt was not repainted for a whole interval
+    fn is_animation_due(&self) -> bool {
+        match (self.animation_interval, self.last_paint) {
+            (Some(interval), Some(last_paint)) => last_paint.elapsed() >= interval,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// No input arrived in time: perform a deferred paint or animate the prompt
+    fn handle_idle(&mut self, prompt: &dyn Prompt) -> Result<Option<Signal>> {
+        if self.pending_paint.is_some() {
+            self.flush_pending_paint(prompt)?;
+            Ok(None)
+        } else if self.is_animation_due() {
+            self.handle_events(prompt, vec![ReedlineEvent::Repaint])
+        } else {
+            Ok(None)
+        }
+    }
 
-            for event in reedline_events.drain(..) {
-                if let Some(signal) = self.handle_event(prompt, event)? {
-                    return Ok(signal);
+    fn handle_events(
+        &mut self,
+        prompt: &dyn Prompt,
+        reedline_events: Vec<ReedlineEvent>,
+    ) -> Result<Option<Signal>> {
+        for event in reedline_events {
+            if let Some(signal) = self.handle_event(prompt, event)? {
+                if let Some(scratch_file) = &mut self.scratch_file {
+                    match signal {
+                        Signal::Success(_) | Signal::CtrlC { .. } | Signal::CtrlD => {
+                            scratch_file.discard()
+                        }
+                        Signal::Timeout => {}
+                    }
                 }
+                return Ok(Some(signal));
             }
         }
+        Ok(None)
     }
 
     fn handle_paste(&mut self, crossterm_events: &mut Vec<Event>) -> ReedlineEvent {
         let reedline_events = crossterm_events
             .drain(..)
-            .map(|event| self.edit_mode.parse_event(event))
+            .filter_map(|event| self.parse_pasted_event(event))
             .collect::<Vec<ReedlineEvent>>();
 
         ReedlineEvent::Paste(reedline_events)
     }
 
+    /// Parse an event of a paste, sanitizing keys that do not insert text
+    fn parse_pasted_event(&mut self, event: Event) -> Option<ReedlineEvent> {
+        let key = match &event {
+            Event::Key(key) if self.paste_sanitization != PasteSanitization::Off => key,
+            _ => return Some(self.edit_mode.parse_event(event)),
+        };
+        if key.code == KeyCode::Tab && key.modifiers.is_empty() {
+            // A pasted tab is text, not a request for completions
+            return Some(ReedlineEvent::Edit(vec![EditCommand::InsertChar('\t')]));
+        }
+        if is_text_key(key) {
+            return Some(self.edit_mode.parse_event(event));
+        }
+
+        match self.paste_sanitization {
+            PasteSanitization::Visualize => key_sequence(key)
+                .map(|sequence| ReedlineEvent::Edit(vec![EditCommand::InsertString(sequence)])),
+            PasteSanitization::Strip | PasteSanitization::Off => None,
+        }
+    }
+
     fn handle_event(
         &mut self,
         prompt: &dyn Prompt,
         event: ReedlineEvent,
     ) -> Result<Option<Signal>> {
-        if self.input_mode == InputMode::HistorySearch {
-            self.handle_history_search_event(prompt, event)
+        let event = match event {
+            ReedlineEvent::ScrollUp(row) | ReedlineEvent::ScrollDown(row)
+                if !self.painter.is_in_prompt_area(row) =>
+            {
+                // Turning the wheel over the output above the prompt does nothing
+                return Ok(None);
+            }
+            ReedlineEvent::ScrollUp(_) => self.scroll_wheel_events.0.clone(),
+            ReedlineEvent::ScrollDown(_) => self.scroll_wheel_events.1.clone(),
+            event => event,
+        };
+
+        let signal = match self.input_mode {
+            InputMode::HistorySearch => self.handle_history_search_event(prompt, event),
+            InputMode::SearchReplace => self.handle_search_replace_event(prompt, event),
+            InputMode::Menu => self.handle_menu_event(prompt, event),
+            InputMode::Regular | InputMode::HistoryTraversal => {
+                self.handle_editor_event(prompt, event)
+            }
+        }?;
+
+        if signal.is_some() {
+            self.snippet = None;
         } else {
-            self.handle_editor_event(prompt, event)
+            self.follow_snippet();
         }
+        Ok(signal)
     }
 
     fn handle_history_search_event(
@@ -434,37 +1978,64 @@ impl Reedline {
         event: ReedlineEvent,
     ) -> io::Result<Option<Signal>> {
         match event {
-            ReedlineEvent::CtrlD => {
-                if self.editor.is_empty() {
+            ReedlineEvent::CtrlD => match self.interrupt_behavior.ctrl_d {
+                _ if self.editor.is_empty() => {
                     self.input_mode = InputMode::Regular;
                     self.editor.reset_undo_stack();
                     Ok(Some(Signal::CtrlD))
-                } else {
+                }
+                CtrlDBehavior::DeleteChar => {
                     self.run_history_commands(&[EditCommand::Delete]);
                     Ok(None)
                 }
-            }
+                CtrlDBehavior::Ignore => Ok(None),
+                CtrlDBehavior::ReturnSignal => {
+                    self.input_mode = InputMode::Regular;
+                    self.handle_editor_event(prompt, event)
+                }
+            },
             ReedlineEvent::CtrlC => {
                 self.input_mode = InputMode::Regular;
-                Ok(Some(Signal::CtrlC))
-            }
-            ReedlineEvent::ClearScreen => Ok(Some(Signal::CtrlL)),
-            ReedlineEvent::Enter | ReedlineEvent::HandleTab => {
-                if let Some(string) = self.history.string_at_cursor() {
-                    self.editor.set_buffer(string);
-                    self.editor.remember_undo_state(true);
+                match self.interrupt_behavior.ctrl_c {
+                    CtrlCBehavior::ReturnSignal => Ok(Some(Signal::CtrlC {
+                        buffer: self.editor.get_buffer().to_string(),
+                        cursor: self.editor.offset(),
+                    })),
+                    CtrlCBehavior::ClearLine => self.handle_editor_event(prompt, event),
                 }
-
-                self.input_mode = InputMode::Regular;
+            }
+            ReedlineEvent::ClearScreen => {
+                self.clear_screen_and_repaint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Redraw => {
+                self.redraw(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::CtrlZ => {
+                self.suspend(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Enter | ReedlineEvent::SubmitOrNewline | ReedlineEvent::HandleTab => {
+                self.accept_history_search();
                 self.full_repaint(prompt)?;
                 Ok(None)
             }
+            ReedlineEvent::InsertNewline
+            | ReedlineEvent::QuickInsert(_)
+            | ReedlineEvent::InsertSnippet(_) => {
+                // Continue editing the result
+                self.accept_history_search();
+                self.handle_editor_event(prompt, event)
+            }
             ReedlineEvent::Edit(commands) => {
                 self.run_history_commands(&commands);
                 self.repaint(prompt)?;
                 Ok(None)
             }
-            ReedlineEvent::Mouse => Ok(None),
+            ReedlineEvent::Mouse | ReedlineEvent::ScrollUp(_) | ReedlineEvent::ScrollDown(_) => {
+                Ok(None)
+            }
             ReedlineEvent::Resize(width, height) => {
                 self.painter.handle_resize(width, height);
                 self.full_repaint(prompt)?;
@@ -481,11 +2052,14 @@ impl Reedline {
                 self.repaint(prompt)?;
                 Ok(None)
             }
-            ReedlineEvent::NextHistory | ReedlineEvent::Down => {
+            ReedlineEvent::NextHistory
+            | ReedlineEvent::Down
+            | ReedlineEvent::SearchHistoryForward => {
+                let current = self.history.cursor();
                 self.history.forward();
-                // Hacky way to ensure that we don't fall of into failed search going forward
-                if self.history.string_at_cursor().is_none() {
-                    self.history.back();
+                // Stay on the current result instead of falling off into a failed search
+                if self.history.cursor().is_none() {
+                    self.history.set_cursor(current);
                 }
                 self.repaint(prompt)?;
                 Ok(None)
@@ -498,75 +2072,447 @@ impl Reedline {
                 // VI multiplier operations currently not supported in the history search
                 Ok(None)
             }
-            ReedlineEvent::None => {
+            ReedlineEvent::SearchReplace
+            | ReedlineEvent::Menu(_)
+            | ReedlineEvent::MenuNext
+            | ReedlineEvent::MenuPrevious
+            | ReedlineEvent::MenuPageNext
+            | ReedlineEvent::MenuPagePrevious
+            | ReedlineEvent::MenuAccept
+            | ReedlineEvent::MenuSelect(_)
+            | ReedlineEvent::SnippetPrevious
+            | ReedlineEvent::UniversalArgument
+            | ReedlineEvent::None => {
                 // Default no operation
                 Ok(None)
             }
-        }
-    }
-
-    fn handle_editor_event(
-        &mut self,
-        prompt: &dyn Prompt,
-        event: ReedlineEvent,
-    ) -> io::Result<Option<Signal>> {
-        match event {
+        }
+    }
+
+    fn handle_search_replace_event(
+        &mut self,
+        prompt: &dyn Prompt,
+        event: ReedlineEvent,
+    ) -> io::Result<Option<Signal>> {
+        let search_replace = match self.search_replace.as_mut() {
+            Some(search_replace) => search_replace,
+            None => {
+                self.input_mode = InputMode::Regular;
+                return self.handle_editor_event(prompt, event);
+            }
+        };
+
+        match event {
+            ReedlineEvent::CtrlC => {
+                self.exit_search_replace(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::ClearScreen => {
+                self.clear_screen_and_repaint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Redraw => {
+                self.redraw(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::CtrlZ => {
+                self.suspend(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Enter | ReedlineEvent::SubmitOrNewline => {
+                match search_replace.stage() {
+                    SearchReplaceStage::Pattern => search_replace.confirm_pattern(),
+                    SearchReplaceStage::Replacement => {
+                        if let Some(buffer) =
+                            search_replace.replace_current(self.editor.get_buffer())
+                        {
+                            let done = search_replace.current_match(&buffer).is_none();
+                            self.editor.set_buffer(buffer);
+                            self.editor.remember_undo_state(true);
+                            if done {
+                                // Nothing left to replace
+                                return self.exit_search_replace(prompt).map(|_| None);
+                            }
+                        }
+                    }
+                }
+                self.search_replace_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::HandleTab => {
+                if search_replace.stage() == SearchReplaceStage::Replacement {
+                    if let Some(buffer) = search_replace.replace_all(self.editor.get_buffer()) {
+                        self.editor.set_buffer(buffer);
+                        self.editor.remember_undo_state(true);
+                    }
+                    self.exit_search_replace(prompt)?;
+                }
+                Ok(None)
+            }
+            ReedlineEvent::Edit(commands) => {
+                for command in &commands {
+                    search_replace.edit(command);
+                }
+                self.search_replace_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Up | ReedlineEvent::PreviousHistory => {
+                search_replace.previous_match(self.editor.get_buffer());
+                self.search_replace_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Down | ReedlineEvent::NextHistory | ReedlineEvent::SearchReplace => {
+                search_replace.next_match(self.editor.get_buffer());
+                self.search_replace_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Resize(width, height) => {
+                self.painter.handle_resize(width, height);
+                self.full_repaint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Repaint => {
+                self.full_repaint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::CtrlD
+            | ReedlineEvent::Mouse
+            | ReedlineEvent::ScrollUp(_)
+            | ReedlineEvent::ScrollDown(_)
+            | ReedlineEvent::InsertNewline
+            | ReedlineEvent::QuickInsert(_)
+            | ReedlineEvent::InsertSnippet(_)
+            | ReedlineEvent::SnippetPrevious
+            | ReedlineEvent::SearchHistory
+            | ReedlineEvent::SearchHistoryForward
+            | ReedlineEvent::Menu(_)
+            | ReedlineEvent::MenuNext
+            | ReedlineEvent::MenuPrevious
+            | ReedlineEvent::MenuPageNext
+            | ReedlineEvent::MenuPagePrevious
+            | ReedlineEvent::MenuAccept
+            | ReedlineEvent::MenuSelect(_)
+            | ReedlineEvent::Paste(_)
+            | ReedlineEvent::Multiple(_)
+            | ReedlineEvent::UniversalArgument
+            | ReedlineEvent::None => Ok(None),
+        }
+    }
+
+    fn handle_menu_event(
+        &mut self,
+        prompt: &dyn Prompt,
+        event: ReedlineEvent,
+    ) -> io::Result<Option<Signal>> {
+        let menus = &mut self.menus;
+        let menu = match self.active_menu.and_then(|index| menus.get_mut(index)) {
+            Some(menu) => menu,
+            None => {
+                self.input_mode = InputMode::Regular;
+                return self.handle_editor_event(prompt, event);
+            }
+        };
+
+        match event {
+            ReedlineEvent::CtrlC => {
+                self.exit_menu(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Menu(name) if name == menu.name() => {
+                self.exit_menu(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::ClearScreen => {
+                self.clear_screen_and_repaint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Redraw => {
+                self.redraw(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::CtrlZ => {
+                self.suspend(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Enter | ReedlineEvent::SubmitOrNewline | ReedlineEvent::MenuAccept => {
+                self.accept_menu(prompt)
+            }
+            ReedlineEvent::MenuSelect(position) => {
+                if position > 0 && menu.select_visible(position - 1) {
+                    self.accept_menu(prompt)
+                } else {
+                    Ok(None)
+                }
+            }
+            ReedlineEvent::Edit(commands) => {
+                let buffer_commands: Vec<EditCommand> = commands
+                    .into_iter()
+                    .filter(|command| !menu.handle_edit(command))
+                    .collect();
+                if !buffer_commands.is_empty() {
+                    self.run_and_paint_edit_commands(&buffer_commands, prompt)?;
+                    if let Some(index) = self.active_menu {
+                        self.menus[index].update_values(self.editor.line_buffer());
+                    }
+                }
+                self.menu_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Up | ReedlineEvent::PreviousHistory | ReedlineEvent::MenuPrevious => {
+                menu.move_previous();
+                self.menu_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Down
+            | ReedlineEvent::NextHistory
+            | ReedlineEvent::HandleTab
+            | ReedlineEvent::MenuNext => {
+                menu.move_next();
+                self.menu_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::MenuPageNext => {
+                menu.next_page();
+                self.menu_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::MenuPagePrevious => {
+                menu.previous_page();
+                self.menu_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Resize(width, height) => {
+                self.painter.handle_resize(width, height);
+                self.full_repaint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Repaint => {
+                self.full_repaint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::CtrlD
+            | ReedlineEvent::Mouse
+            | ReedlineEvent::ScrollUp(_)
+            | ReedlineEvent::ScrollDown(_)
+            | ReedlineEvent::InsertNewline
+            | ReedlineEvent::QuickInsert(_)
+            | ReedlineEvent::InsertSnippet(_)
+            | ReedlineEvent::SnippetPrevious
+            | ReedlineEvent::SearchHistory
+            | ReedlineEvent::SearchHistoryForward
+            | ReedlineEvent::SearchReplace
+            | ReedlineEvent::Menu(_)
+            | ReedlineEvent::Paste(_)
+            | ReedlineEvent::Multiple(_)
+            | ReedlineEvent::UniversalArgument
+            | ReedlineEvent::None => Ok(None),
+        }
+    }
+
+    fn handle_editor_event(
+        &mut self,
+        prompt: &dyn Prompt,
+        event: ReedlineEvent,
+    ) -> io::Result<Option<Signal>> {
+        if self.transient_status_shown && event != ReedlineEvent::HandleTab {
+            self.transient_status_shown = false;
+            self.painter.set_status_row(None);
+        }
+
+        match event {
+            ReedlineEvent::HandleTab if self.snippet.is_some() => {
+                self.next_snippet_placeholder();
+                self.repaint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::SnippetPrevious => {
+                if let Some((snippet, _)) = &mut self.snippet {
+                    let offset = snippet.previous();
+                    self.set_offset(offset);
+                    self.repaint(prompt)?;
+                }
+                Ok(None)
+            }
+            ReedlineEvent::InsertSnippet(template) => {
+                self.take_history_entry();
+                self.snippet = None;
+                let (text, snippet) = Snippet::parse(&template, self.editor.offset());
+                self.run_and_paint_edit_commands(&[EditCommand::InsertString(text)], prompt)?;
+                if let Some(snippet) = snippet {
+                    self.set_offset(snippet.current().start);
+                    self.snippet = Some((snippet, self.editor.get_buffer().to_string()));
+                }
+                self.repaint(prompt)?;
+                Ok(None)
+            }
             ReedlineEvent::HandleTab => {
-                let line_buffer = self.editor.line_buffer();
-
                 let current_hint = self.hinter.current_hint();
                 if !current_hint.is_empty() && self.input_mode == InputMode::Regular {
-                    self.editor.clear_to_end();
-                    self.run_edit_commands(&[EditCommand::InsertString(current_hint)], prompt)?;
+                    if self.anchor_hint {
+                        self.editor.move_to_end();
+                    } else {
+                        self.editor.clear_to_end();
+                    }
+                    self.run_and_paint_edit_commands(
+                        &[EditCommand::InsertString(current_hint)],
+                        prompt,
+                    )?;
                 } else {
-                    self.tab_handler.handle(line_buffer);
+                    let before = self.editor.line_buffer().clone();
+                    self.tab_handler.handle(self.editor.line_buffer());
+                    self.remember_tab_completion(&before);
+                    self.show_tab_status()?;
                 }
 
                 self.full_repaint(prompt)?;
                 Ok(None)
             }
-            ReedlineEvent::CtrlD => {
-                if self.editor.is_empty() {
+            ReedlineEvent::CtrlD => match self.interrupt_behavior.ctrl_d {
+                _ if self.editor.is_empty() => {
                     self.editor.reset_undo_stack();
                     Ok(Some(Signal::CtrlD))
-                } else {
-                    self.run_edit_commands(&[EditCommand::Delete], prompt)?;
+                }
+                CtrlDBehavior::DeleteChar => {
+                    self.run_and_paint_edit_commands(&[EditCommand::Delete], prompt)?;
                     Ok(None)
                 }
+                CtrlDBehavior::Ignore => Ok(None),
+                CtrlDBehavior::ReturnSignal => {
+                    // The line is left like an aborted one
+                    self.incomplete_reason = None;
+                    self.leave_history_traversal(prompt)?;
+                    self.remove_widget(prompt)?;
+                    self.flush_pending_paint(prompt)?;
+                    self.run_and_paint_edit_commands(&[EditCommand::Clear], prompt)?;
+                    self.editor.reset_undo_stack();
+                    Ok(Some(Signal::CtrlD))
+                }
+            },
+            ReedlineEvent::CtrlC if self.interrupt_behavior.ctrl_c == CtrlCBehavior::ClearLine => {
+                self.incomplete_reason = None;
+                self.leave_history_traversal(prompt)?;
+                self.remove_widget(prompt)?;
+                self.run_and_paint_edit_commands(&[EditCommand::Clear], prompt)?;
+                Ok(None)
             }
             ReedlineEvent::CtrlC => {
-                self.run_edit_commands(&[EditCommand::Clear], prompt)?;
+                self.incomplete_reason = None;
+                self.leave_history_traversal(prompt)?;
+                self.remove_widget(prompt)?;
+                self.flush_pending_paint(prompt)?;
+                let buffer = self.editor.get_buffer().to_string();
+                let cursor = self.editor.offset();
+                self.run_and_paint_edit_commands(&[EditCommand::Clear], prompt)?;
                 self.editor.reset_undo_stack();
-                Ok(Some(Signal::CtrlC))
+                Ok(Some(Signal::CtrlC { buffer, cursor }))
+            }
+            ReedlineEvent::ClearScreen => {
+                self.clear_screen_and_repaint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Redraw => {
+                self.redraw(prompt)?;
+                Ok(None)
             }
-            ReedlineEvent::ClearScreen => Ok(Some(Signal::CtrlL)),
-            ReedlineEvent::Enter => {
+            ReedlineEvent::CtrlZ => {
+                self.suspend(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Enter | ReedlineEvent::SubmitOrNewline => {
+                self.expand_abbreviation();
+                // The confirmed paste is no longer highlighted on the submitted line
+                if self.unconfirmed_paste.take().is_some() {
+                    self.buffer_paint(prompt)?;
+                }
+                if self.history_expansion {
+                    match expand_history(self.editor.get_buffer(), self.history.as_ref()) {
+                        Ok(expanded) => {
+                            if expanded != self.editor.get_buffer() {
+                                self.editor.set_buffer(expanded);
+                                self.editor.remember_undo_state(true);
+                                self.repaint(prompt)?;
+                            }
+                        }
+                        Err(err) => {
+                            self.transient_status_shown = true;
+                            self.painter.set_status_row(Some(err.to_string()));
+                            self.full_repaint(prompt)?;
+                            return Ok(None);
+                        }
+                    }
+                }
+
                 let buffer = self.editor.get_buffer().to_string();
-                if matches!(self.validator.validate(&buffer), ValidationResult::Complete) {
+                if let ValidationResult::Incomplete { reason } = self.validator.validate(&buffer) {
+                    self.incomplete_reason = reason;
+                    self.insert_newline(prompt)?;
+                    Ok(None)
+                } else {
+                    // The submitted line stays on screen, it has to be painted completely
+                    self.incomplete_reason = None;
+                    self.leave_history_traversal(prompt)?;
+                    self.remove_widget(prompt)?;
+                    let buffer = match &self.buffer_normalizer {
+                        Some(normalizer) => {
+                            let normalized = normalizer(&buffer);
+                            if normalized != buffer {
+                                self.editor.set_buffer(normalized.clone());
+                                self.buffer_paint(prompt)?;
+                            }
+                            normalized
+                        }
+                        None => buffer,
+                    };
+                    self.flush_pending_paint(prompt)?;
                     self.append_to_history();
-                    self.run_edit_commands(&[EditCommand::Clear], prompt)?;
+                    self.run_and_paint_edit_commands(&[EditCommand::Clear], prompt)?;
                     self.painter.print_crlf()?;
+                    self.painter.mark_command_start()?;
                     self.editor.reset_undo_stack();
-
-                    Ok(Some(Signal::Success(buffer)))
-                } else {
-                    #[cfg(windows)]
-                    {
-                        self.run_edit_commands(&[EditCommand::InsertChar('\r')], prompt)?;
+                    if self.show_previous_command {
+                        self.painter.set_previous_command(Some(buffer.clone()));
+                    }
+                    if let Some(hook) = &mut self.pre_execution_hook {
+                        hook(&buffer);
                     }
-                    self.run_edit_commands(&[EditCommand::InsertChar('\n')], prompt)?;
-                    self.painter.adjust_prompt_position(&self.editor)?;
-                    self.full_repaint(prompt)?;
 
-                    Ok(None)
+                    Ok(Some(Signal::Success(buffer)))
+                }
+            }
+            ReedlineEvent::InsertNewline => {
+                self.insert_newline(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::QuickInsert(key) => {
+                if let Some(value) = self.quick_inserts.get(&key) {
+                    self.run_and_paint_edit_commands(&[EditCommand::InsertString(value)], prompt)?;
+                    self.repaint(prompt)?;
                 }
+                Ok(None)
             }
             ReedlineEvent::Edit(commands) => {
-                self.run_edit_commands(&commands, prompt)?;
+                self.clear_snippet_default(&commands);
+                // Abbreviations are expanded by a space typed behind them, also amid keys typed
+                // quickly enough to arrive together
+                let mut commands = commands.as_slice();
+                while let Some(space) = commands
+                    .iter()
+                    .position(|command| *command == EditCommand::InsertChar(' '))
+                {
+                    self.run_and_paint_edit_commands(&commands[..space], prompt)?;
+                    // The space is left out if the expansion placed the cursor
+                    if !self.expand_abbreviation() {
+                        self.run_and_paint_edit_commands(&commands[space..=space], prompt)?;
+                    }
+                    commands = &commands[space + 1..];
+                }
+                self.run_and_paint_edit_commands(commands, prompt)?;
                 self.repaint(prompt)?;
                 Ok(None)
             }
-            ReedlineEvent::Mouse => Ok(None),
+            ReedlineEvent::Mouse | ReedlineEvent::ScrollUp(_) | ReedlineEvent::ScrollDown(_) => {
+                Ok(None)
+            }
             ReedlineEvent::Resize(width, height) => {
                 self.painter.handle_resize(width, height);
                 self.full_repaint(prompt)?;
@@ -606,52 +2552,120 @@ impl Reedline {
                 self.full_repaint(prompt)?;
                 Ok(None)
             }
-            ReedlineEvent::SearchHistory => {
+            ReedlineEvent::SearchHistory | ReedlineEvent::SearchHistoryForward => {
                 // Make sure we are able to undo the result of a reverse history search
                 self.editor.remember_undo_state(true);
 
+                self.leave_history_traversal(prompt)?;
                 self.enter_history_search();
                 self.repaint(prompt)?;
                 Ok(None)
             }
-            ReedlineEvent::Paste(events) => {
+            ReedlineEvent::SearchReplace => {
+                // Make sure we are able to undo all replacements
+                self.editor.remember_undo_state(true);
+
+                self.input_mode = InputMode::SearchReplace;
+                self.search_replace = Some(SearchReplace::new(self.editor.offset()));
+                self.search_replace_paint(prompt)?;
+                Ok(None)
+            }
+            ReedlineEvent::Menu(name) => {
+                if let Some(index) = self.menus.iter().position(|menu| menu.name() == name) {
+                    self.menus[index]
+                        .activate(self.editor.line_buffer(), self.edit_mode.keybindings());
+                    self.active_menu = Some(index);
+                    self.input_mode = InputMode::Menu;
+                    self.menu_paint(prompt)?;
+                }
+                Ok(None)
+            }
+            ReedlineEvent::Paste(events) => self.handle_batch(prompt, |reedline| {
                 let mut latest_signal = None;
-                // Making sure that only InsertChars are handled during a paste event
+                let paste_start = reedline.editor.offset();
+                let mut line_break_pasted = false;
+                // Runs of pasted characters are inserted into the buffer at once
+                let mut pasted = String::new();
                 for event in events {
-                    if let ReedlineEvent::Edit(commands) = event {
+                    if reedline.paste_confirmation
+                        && matches!(event, ReedlineEvent::Enter | ReedlineEvent::SubmitOrNewline)
+                    {
+                        line_break_pasted = true;
+                        pasted.push('\n');
+                    } else if let ReedlineEvent::Edit(commands) = event {
                         for command in commands {
                             match command {
-                                EditCommand::InsertChar(c) => self.editor.insert_char(c),
+                                EditCommand::InsertChar(c) => pasted.push(c),
                                 x => {
-                                    self.run_edit_commands(&[x], prompt)?;
+                                    reedline.editor.insert_str(&pasted);
+                                    pasted.clear();
+                                    reedline.run_and_paint_edit_commands(&[x], prompt)?;
                                 }
                             }
                         }
                     } else {
-                        latest_signal = self.handle_editor_event(prompt, event)?;
+                        reedline.editor.insert_str(&pasted);
+                        pasted.clear();
+                        latest_signal = reedline.handle_editor_event(prompt, event)?;
                     }
                 }
-
-                self.painter.adjust_prompt_position(&self.editor)?;
-                self.full_repaint(prompt)?;
+                reedline.editor.insert_str(&pasted);
+                if line_break_pasted {
+                    let paste_end = reedline.editor.offset();
+                    reedline.unconfirmed_paste = Some((
+                        paste_start.min(paste_end)..paste_start.max(paste_end),
+                        reedline.editor.get_buffer().to_string(),
+                    ));
+                }
                 Ok(latest_signal)
-            }
-            ReedlineEvent::Multiple(events) => {
-                // Making sure that only InsertChars are handled during a paste event
-                let latest_signal = events
+            }),
+            ReedlineEvent::Multiple(events) => self.handle_batch(prompt, |reedline| {
+                events
                     .into_iter()
-                    .try_fold(None, |_, event| self.handle_editor_event(prompt, event))?;
+                    .try_fold(None, |_, event| reedline.handle_editor_event(prompt, event))
+            }),
+            ReedlineEvent::MenuNext
+            | ReedlineEvent::MenuPrevious
+            | ReedlineEvent::MenuPageNext
+            | ReedlineEvent::MenuPagePrevious
+            | ReedlineEvent::MenuAccept
+            | ReedlineEvent::MenuSelect(_)
+            | ReedlineEvent::UniversalArgument
+            | ReedlineEvent::None => Ok(None),
+        }
+    }
 
-                self.painter.adjust_prompt_position(&self.editor)?;
-                self.full_repaint(prompt)?;
-                Ok(latest_signal)
-            }
-            ReedlineEvent::None => Ok(None),
+    /// Stop the process group for job control and restore the editor once resumed
+    #[cfg(unix)]
+    fn suspend(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        // Leave the input intact above the shell output
+        self.painter.print_crlf()?;
+        self.disable_terminal_modes()?;
+        self.leave_alternate_screen()?;
+
+        // The default action of SIGTSTP stops the process, the call returns after SIGCONT
+        // SAFETY: `kill` has no memory safety requirements
+        unsafe {
+            libc::kill(0, libc::SIGTSTP);
         }
+
+        self.enable_terminal_modes()?;
+        self.painter.init_terminal_size()?;
+        self.painter.initialize_prompt_position()?;
+        self.full_repaint(prompt)
+    }
+
+    /// Suspending is only supported with Unix job control
+    #[cfg(not(unix))]
+    fn suspend(&mut self, _prompt: &dyn Prompt) -> Result<()> {
+        Ok(())
     }
 
     fn append_to_history(&mut self) {
-        self.history.append(self.editor.get_buffer());
+        self.history.append_with_metadata(
+            self.editor.get_buffer(),
+            HistoryEntryMetadata::from_current_dir(),
+        );
     }
 
     fn previous_history(&mut self) {
@@ -696,52 +2710,90 @@ impl Reedline {
         }
     }
 
+    /// The entries adjacent to the one in the buffer, if they are previewed
+    fn history_preview(&self) -> Option<HistoryPreview> {
+        if self.show_history_preview && self.input_mode == InputMode::HistoryTraversal {
+            let (older, newer) = self.history.strings_around_cursor();
+            Some(HistoryPreview { older, newer })
+        } else {
+            None
+        }
+    }
+
+    /// Stop traversing the history with the current entry in the buffer
+    ///
+    /// Repaints right away if the preview of the adjacent entries has to be removed.
+    fn leave_history_traversal(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if self.input_mode == InputMode::HistoryTraversal {
+            self.input_mode = InputMode::Regular;
+            if self.painter.is_history_preview_outdated(&None) {
+                self.last_paint = None;
+                self.full_repaint(prompt)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Switch into reverse history search mode
     ///
     /// This mode uses a separate prompt and handles keybindings sligthly differently!
     fn enter_history_search(&mut self) {
         self.input_mode = InputMode::HistorySearch;
+        self.history_search_query = LineBuffer::new();
         self.history
             .set_navigation(HistoryNavigationQuery::SubstringSearch("".to_string()));
     }
 
+    /// Leave the history search with its result in the buffer
+    fn accept_history_search(&mut self) {
+        if let Some(string) = self.history.string_at_cursor() {
+            self.editor.record_jump();
+            self.editor.set_buffer(string);
+            self.editor.remember_undo_state(true);
+        }
+
+        self.input_mode = InputMode::Regular;
+    }
+
     /// Dispatches the applicable [`EditCommand`] actions for editing the history search string.
     ///
     /// Only modifies internal state, does not perform regular output!
     fn run_history_commands(&mut self, commands: &[EditCommand]) {
         for command in commands {
+            let query = &mut self.history_search_query;
+            let previous_len = query.get_buffer().len();
             match command {
-                EditCommand::InsertChar(c) => {
-                    let navigation = self.history.get_navigation();
-                    if let HistoryNavigationQuery::SubstringSearch(mut substring) = navigation {
-                        substring.push(*c);
-                        self.history
-                            .set_navigation(HistoryNavigationQuery::SubstringSearch(substring));
-                    } else {
-                        self.history
-                            .set_navigation(HistoryNavigationQuery::SubstringSearch(String::from(
-                                *c,
-                            )));
-                    }
-                    self.history.back();
+                EditCommand::InsertChar(c) => query.insert_char(*c),
+                EditCommand::InsertString(s) => query.insert_str(s),
+                EditCommand::Backspace => query.delete_left_grapheme(),
+                EditCommand::Delete => query.delete_right_grapheme(),
+                EditCommand::BackspaceWord => query.delete_word_left(),
+                EditCommand::DeleteWord => query.delete_word_right(),
+                EditCommand::MoveLeft => query.move_left(),
+                EditCommand::MoveRight => query.move_right(),
+                EditCommand::MoveWordLeft => {
+                    query.move_word_left();
                 }
-                EditCommand::Backspace => {
-                    let navigation = self.history.get_navigation();
-
-                    if let HistoryNavigationQuery::SubstringSearch(substring) = navigation {
-                        let new_substring = text_manipulation::remove_last_grapheme(&substring);
-
-                        self.history
-                            .set_navigation(HistoryNavigationQuery::SubstringSearch(
-                                new_substring.to_string(),
-                            ));
-                        self.history.back();
-                    }
+                EditCommand::MoveWordRight => {
+                    query.move_word_right();
                 }
+                EditCommand::MoveToStart | EditCommand::MoveToLineStart => query.move_to_start(),
+                EditCommand::MoveToEnd | EditCommand::MoveToLineEnd => query.move_to_end(),
                 _ => {
                     self.input_mode = InputMode::Regular;
+                    continue;
                 }
             }
+
+            // Only insertions and deletions change the query, moving the cursor keeps the result
+            if query.get_buffer().len() != previous_len {
+                self.history
+                    .set_navigation(HistoryNavigationQuery::SubstringSearch(
+                        query.get_buffer().to_string(),
+                    ));
+                self.history.back();
+            }
         }
     }
 
@@ -750,6 +2802,7 @@ impl Reedline {
     /// When using the up/down traversal or fish/zsh style prefix search update the main line buffer accordingly.
     /// Not used for the separate modal reverse search!
     fn update_buffer_from_history(&mut self) {
+        self.editor.record_jump();
         match self.history.get_navigation() {
             HistoryNavigationQuery::Normal(original) => {
                 if let Some(buffer_to_paint) = self.history.string_at_cursor() {
@@ -773,12 +2826,107 @@ impl Reedline {
         }
     }
 
-    /// Executes [`EditCommand`] actions by modifying the internal state appropriately. Does not output itself.
-    fn run_edit_commands(
+    /// Executes [`EditCommand`] actions by modifying the internal state appropriately and
+    /// paints the inserted text right away
+    fn run_and_paint_edit_commands(
         &mut self,
         commands: &[EditCommand],
         prompt: &dyn Prompt,
     ) -> io::Result<()> {
+        self.take_history_entry();
+
+        for command in commands {
+            if let EditCommand::MoveToPosition(row, column) = command {
+                self.move_to_screen_position(*row, *column, prompt);
+            }
+            self.apply_edit_command(command);
+
+            // Performing mutation here might incur a perf hit down this line when
+            // we would like to do multiple inserts.
+            // A simple solution that we can do is to queue up these and perform the wrapping
+            // check after the loop finishes. Will need to sort out the details.
+            if matches!(
+                command,
+                EditCommand::InsertChar(_) | EditCommand::InsertString(_)
+            ) {
+                if self.painter.require_wrapping(&self.editor) {
+                    self.handle_wrap(prompt)?;
+                }
+
+                self.repaint(prompt)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Follow the edits of the buffer with the placeholders of the snippet
+    ///
+    /// The snippet ends once the cursor left its placeholders.
+    fn follow_snippet(&mut self) {
+        if let Some((snippet, buffer)) = &mut self.snippet {
+            let current = self.editor.get_buffer();
+            if current != buffer {
+                snippet.adjust(buffer, current);
+                *buffer = current.to_string();
+            }
+            if !snippet.contains(self.editor.offset()) {
+                self.snippet = None;
+            }
+        }
+    }
+
+    /// Move to the next placeholder of the snippet, ending it after the last one
+    fn next_snippet_placeholder(&mut self) {
+        if let Some((snippet, _)) = &mut self.snippet {
+            match snippet.next() {
+                Some(offset) => self.set_offset(offset),
+                None => {
+                    let end = snippet.end();
+                    self.snippet = None;
+                    self.set_offset(end);
+                }
+            }
+        }
+    }
+
+    /// Remove the default text of the current placeholder if `commands` start typing over it
+    fn clear_snippet_default(&mut self, commands: &[EditCommand]) {
+        if let Some((snippet, _)) = &self.snippet {
+            if snippet.replaces_default(self.editor.offset())
+                && matches!(
+                    commands.first(),
+                    Some(EditCommand::InsertChar(_) | EditCommand::InsertString(_))
+                )
+            {
+                self.editor.clear_range(snippet.current());
+            }
+        }
+    }
+
+    /// Expand the abbreviation in front of the cursor, if any
+    ///
+    /// Returns whether the cursor was placed at the marker of the expansion.
+    fn expand_abbreviation(&mut self) -> bool {
+        if self.input_mode != InputMode::Regular {
+            return false;
+        }
+        match self
+            .abbreviations
+            .expand(self.editor.get_buffer(), self.editor.offset())
+        {
+            Some((buffer, cursor, at_marker)) => {
+                self.editor.set_buffer(buffer);
+                self.set_offset(cursor);
+                self.editor.remember_undo_state(true);
+                at_marker
+            }
+            None => false,
+        }
+    }
+
+    /// Continue editing the history entry browsed to, if any, as the buffer
+    fn take_history_entry(&mut self) {
         if self.input_mode == InputMode::HistoryTraversal {
             if matches!(
                 self.history.get_navigation(),
@@ -790,86 +2938,157 @@ impl Reedline {
             }
             self.input_mode = InputMode::Regular;
         }
+    }
 
-        // Run the commands over the edit buffer
-        for command in commands {
-            match command {
-                EditCommand::MoveToStart => self.editor.move_to_start(),
-                EditCommand::MoveToEnd => self.editor.move_to_end(),
-                EditCommand::MoveToLineStart => self.editor.move_to_line_start(),
-                EditCommand::MoveToLineEnd => self.editor.move_to_line_end(),
-                EditCommand::MoveLeft => self.editor.move_left(),
-                EditCommand::MoveRight => self.editor.move_right(),
-                EditCommand::MoveWordLeft => self.editor.move_word_left(),
-                EditCommand::MoveWordRight => self.editor.move_word_right(),
-                // Performing mutation here might incur a perf hit down this line when
-                // we would like to do multiple inserts.
-                // A simple solution that we can do is to queue up these and perform the wrapping
-                // check after the loop finishes. Will need to sort out the details.
-                EditCommand::InsertChar(c) => {
-                    self.editor.insert_char(*c);
-
-                    if self.painter.require_wrapping(&self.editor) {
-                        self.handle_wrap(prompt)?;
-                    }
+    /// Run a single [`EditCommand`] over the edit buffer and record it for undo
+    fn apply_edit_command(&mut self, command: &EditCommand) {
+        match command {
+            EditCommand::MoveToStart => self.editor.move_to_start(),
+            EditCommand::MoveToEnd => self.editor.move_to_end(),
+            EditCommand::MoveToLineStart => self.editor.move_to_line_start(),
+            EditCommand::MoveToLineEnd => self.editor.move_to_line_end(),
+            EditCommand::MoveLeft => self.editor.move_left(),
+            EditCommand::MoveRight => self.editor.move_right(),
+            EditCommand::MoveWordLeft => self.editor.move_word_left(),
+            EditCommand::MoveWordRight => self.editor.move_word_right(),
+            EditCommand::MoveBigWordLeft => self.editor.move_big_word_left(),
+            EditCommand::MoveBigWordRight => self.editor.move_big_word_right(),
+            EditCommand::InsertChar(c) => self.editor.insert_char(*c),
+            EditCommand::InsertString(s) => self.editor.insert_str(s),
+            EditCommand::Backspace => self.editor.backspace(),
+            EditCommand::Delete => self.editor.delete(),
+            EditCommand::BackspaceWord => self.editor.backspace_word(),
+            EditCommand::DeleteWord => self.editor.delete_word(),
+            EditCommand::Clear => self.editor.clear(),
+            EditCommand::ClearToLineEnd => self.editor.clear_to_line_end(),
+            EditCommand::CutCurrentLine => self.editor.cut_current_line(),
+            EditCommand::CutFromStart => self.editor.cut_from_start(),
+            EditCommand::CutToEnd => self.editor.cut_from_end(),
+            EditCommand::CutWordLeft => self.editor.cut_word_left(),
+            EditCommand::CutWordRight => self.editor.cut_word_right(),
+            EditCommand::CutBigWordLeft => self.editor.cut_big_word_left(),
+            EditCommand::CutBigWordRight => self.editor.cut_big_word_right(),
+            EditCommand::PasteCutBufferBefore => self.editor.insert_cut_buffer_before(),
+            EditCommand::PasteCutBufferAfter => self.editor.insert_cut_buffer_after(),
+            EditCommand::UppercaseWord => self.editor.uppercase_word(),
+            EditCommand::LowercaseWord => self.editor.lowercase_word(),
+            EditCommand::CapitalizeChar => self.editor.capitalize_char(),
+            EditCommand::SwapWords => self.editor.swap_words(),
+            EditCommand::SwapGraphemes => self.editor.swap_graphemes(),
+            EditCommand::Undo => self.editor.undo(),
+            EditCommand::Redo => self.editor.redo(),
+            EditCommand::CutRightUntil(c) => self.editor.cut_right_until_char(*c, false),
+            EditCommand::CutRightBefore(c) => self.editor.cut_right_until_char(*c, true),
+            EditCommand::MoveRightUntil(c) => self.editor.move_right_until_char(*c, false),
+            EditCommand::MoveRightBefore(c) => self.editor.move_right_until_char(*c, true),
+            EditCommand::CutLeftUntil(c) => self.editor.cut_left_until_char(*c, false),
+            EditCommand::CutLeftBefore(c) => self.editor.cut_left_until_char(*c, true),
+            EditCommand::MoveLeftUntil(c) => self.editor.move_left_until_char(*c, false),
+            EditCommand::MoveLeftBefore(c) => self.editor.move_left_until_char(*c, true),
+            EditCommand::CutFromLineStart => self.editor.cut_from_line_start(),
+            EditCommand::CutToLineEnd => self.editor.cut_to_line_end(),
+            EditCommand::CutInnerWord => self.editor.cut_inner_word(),
+            EditCommand::CutAroundWord => self.editor.cut_around_word(),
+            EditCommand::CutInsidePair(left, right) => self.editor.cut_inside_pair(*left, *right),
+            EditCommand::CutAroundPair(left, right) => self.editor.cut_around_pair(*left, *right),
+            EditCommand::CopyInnerWord => self.editor.copy_inner_word(),
+            EditCommand::CopyAroundWord => self.editor.copy_around_word(),
+            EditCommand::CopyInsidePair(left, right) => self.editor.copy_inside_pair(*left, *right),
+            EditCommand::CopyAroundPair(left, right) => self.editor.copy_around_pair(*left, *right),
+            EditCommand::CopyWordRight => self.editor.copy_word_right(),
+            EditCommand::CopyToLineEnd => self.editor.copy_to_line_end(),
+            EditCommand::CopyCurrentLine => self.editor.copy_current_line(),
+            EditCommand::SetMark(name) => self.editor.set_mark(*name),
+            EditCommand::MoveToMark(name) => self.editor.move_to_mark(*name),
+            EditCommand::SetAnchor => self.editor.set_anchor(),
+            EditCommand::SwapCursorAndAnchor => self.editor.swap_cursor_and_anchor(),
+            EditCommand::CutSelection => self.editor.cut_selection(),
+            EditCommand::CopySelection => self.editor.copy_selection(),
+            EditCommand::JumpBack => self.editor.jump_back(),
+            EditCommand::JumpForward => self.editor.jump_forward(),
+            // Needs the painted frame, handled by `run_and_paint_edit_commands()`
+            EditCommand::MoveToPosition(..) => {}
+        }
 
-                    self.repaint(prompt)?;
-                }
-                EditCommand::InsertString(s) => {
-                    for c in s.chars() {
-                        self.editor.insert_char(c);
-                    }
+        match command.undo_behavior() {
+            UndoBehavior::Ignore => {}
+            UndoBehavior::Full => {
+                self.editor.remember_undo_state(true);
+            }
+            UndoBehavior::Coalesce => {
+                self.editor.remember_undo_state(false);
+            }
+        }
 
-                    if self.painter.require_wrapping(&self.editor) {
-                        self.handle_wrap(prompt)?;
-                    }
+        if !matches!(command.undo_behavior(), UndoBehavior::Ignore) {
+            if let Some(scratch_file) = &mut self.scratch_file {
+                scratch_file.edited(self.editor.get_buffer());
+            }
+        }
+    }
 
-                    self.repaint(prompt)?;
-                }
-                EditCommand::Backspace => self.editor.backspace(),
-                EditCommand::Delete => self.editor.delete(),
-                EditCommand::BackspaceWord => self.editor.backspace_word(),
-                EditCommand::DeleteWord => self.editor.delete_word(),
-                EditCommand::Clear => self.editor.clear(),
-                EditCommand::ClearToLineEnd => self.editor.clear_to_line_end(),
-                EditCommand::CutCurrentLine => self.editor.cut_current_line(),
-                EditCommand::CutFromStart => self.editor.cut_from_start(),
-                EditCommand::CutToEnd => self.editor.cut_from_end(),
-                EditCommand::CutWordLeft => self.editor.cut_word_left(),
-                EditCommand::CutWordRight => self.editor.cut_word_right(),
-                EditCommand::PasteCutBufferBefore => self.editor.insert_cut_buffer_before(),
-                EditCommand::PasteCutBufferAfter => self.editor.insert_cut_buffer_after(),
-                EditCommand::UppercaseWord => self.editor.uppercase_word(),
-                EditCommand::LowercaseWord => self.editor.lowercase_word(),
-                EditCommand::CapitalizeChar => self.editor.capitalize_char(),
-                EditCommand::SwapWords => self.editor.swap_words(),
-                EditCommand::SwapGraphemes => self.editor.swap_graphemes(),
-                EditCommand::Undo => self.editor.undo(),
-                EditCommand::Redo => self.editor.redo(),
-                EditCommand::CutRightUntil(c) => self.editor.cut_right_until_char(*c, false),
-                EditCommand::CutRightBefore(c) => self.editor.cut_right_until_char(*c, true),
-                EditCommand::MoveRightUntil(c) => self.editor.move_right_until_char(*c, false),
-                EditCommand::MoveRightBefore(c) => self.editor.move_right_until_char(*c, true),
-                EditCommand::CutLeftUntil(c) => self.editor.cut_left_until_char(*c, false),
-                EditCommand::CutLeftBefore(c) => self.editor.cut_left_until_char(*c, true),
-                EditCommand::MoveLeftUntil(c) => self.editor.move_left_until_char(*c, false),
-                EditCommand::MoveLeftBefore(c) => self.editor.move_left_until_char(*c, true),
-                EditCommand::CutFromLineStart => self.editor.cut_from_line_start(),
-                EditCommand::CutToLineEnd => self.editor.cut_to_line_end(),
-            }
-
-            match command.undo_behavior() {
-                UndoBehavior::Ignore => {}
-                UndoBehavior::Full => {
-                    self.editor.remember_undo_state(true);
-                }
-                UndoBehavior::Coalesce => {
-                    self.editor.remember_undo_state(false);
-                }
+    /// Move the cursor to the part of the buffer painted at the screen position
+    ///
+    /// Positions on the prompt, the gutter or the hint map to the closest buffer position,
+    /// positions outside of the lines of the buffer are ignored.
+    fn move_to_screen_position(&mut self, row: u16, column: u16, prompt: &dyn Prompt) {
+        let (line_idx, mut column) = match self.painter.frame_position(column, row) {
+            Some(position) => position,
+            None => return,
+        };
+
+        let buffer = self.editor.get_buffer();
+        let line_count = buffer.split('\n').count();
+        let line = match buffer.split('\n').nth(line_idx) {
+            Some(line) => line.trim_end_matches('\r'),
+            // Rows below the buffer, e.g. an open menu
+            None => return,
+        };
+        let line_start: usize = buffer
+            .split('\n')
+            .take(line_idx)
+            .map(|line| line.len() + 1)
+            .sum();
+
+        let mut prefix_width = self
+            .multiline_gutter
+            .as_ref()
+            .map(|multiline_gutter| multiline_gutter.width(line_count))
+            .unwrap_or_default();
+        if line_idx > 0 {
+            let indicator =
+                prompt.render_prompt_multiline_indicator(self.incomplete_reason.as_deref());
+            prefix_width = prefix_width + DisplayWidth::of(&indicator);
+        }
+        column = DisplayWidth(column.0.saturating_sub(prefix_width.0));
+
+        // An inline hint is painted at the cursor and takes no room in the buffer
+        let hint_offset = self.hint_offset();
+        if self.hinter.display() == HintDisplay::Inline
+            && (line_start..=line_start + line.len()).contains(&hint_offset)
+        {
+            let hint_column = expanded_width(&line[..hint_offset - line_start], self.tab_stop);
+            let hint_width = DisplayWidth::of(&self.hinter.current_hint());
+            if column >= hint_column + hint_width {
+                column = column - hint_width;
+            } else if column > hint_column {
+                column = hint_column;
             }
         }
 
-        Ok(())
+        let offset = expanded_width_to_byte_offset(line, column, self.tab_stop);
+        self.editor.set_insertion_point(line_start + offset.0);
+    }
+
+    /// Insert a line break at the cursor, as the platform expects it
+    fn insert_newline(&mut self, prompt: &dyn Prompt) -> io::Result<()> {
+        #[cfg(windows)]
+        {
+            self.run_and_paint_edit_commands(&[EditCommand::InsertChar('\r')], prompt)?;
+        }
+        self.run_and_paint_edit_commands(&[EditCommand::InsertChar('\n')], prompt)?;
+        self.painter.adjust_prompt_position(&self.editor)?;
+        self.full_repaint(prompt)
     }
 
     /// Set the cursor position as understood by the underlying [`LineBuffer`] for the current line
@@ -900,15 +3119,88 @@ impl Reedline {
     /// *Partial* repaint of either the buffer or the parts for reverse history search
     fn repaint(&mut self, prompt: &dyn Prompt) -> io::Result<()> {
         // Repainting
-        if self.input_mode == InputMode::HistorySearch {
-            self.history_search_paint(prompt)?;
-        } else {
-            self.buffer_paint(prompt)?;
+        match self.input_mode {
+            InputMode::HistorySearch => self.history_search_paint(prompt)?,
+            InputMode::SearchReplace => self.search_replace_paint(prompt)?,
+            InputMode::Menu => self.menu_paint(prompt)?,
+            InputMode::Regular | InputMode::HistoryTraversal => self.buffer_paint(prompt)?,
         }
 
         Ok(())
     }
 
+    /// Leave the search and replace mode and remove the status row
+    fn exit_search_replace(&mut self, prompt: &dyn Prompt) -> io::Result<()> {
+        self.search_replace = None;
+        self.input_mode = InputMode::Regular;
+        self.painter.set_status_row(None);
+        self.buffer_paint(prompt)
+    }
+
+    /// Repaint logic for the search and replace within the buffer
+    ///
+    /// Moves the cursor to the current match and shows the query in the status row.
+    fn search_replace_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if let Some(search_replace) = &self.search_replace {
+            let buffer = self.editor.get_buffer();
+            let status = search_replace.status(buffer);
+            if let Some(current) = search_replace.current_match(buffer) {
+                self.editor.set_insertion_point(current.start);
+            }
+            self.painter.set_status_row(Some(status));
+            self.painter.adjust_prompt_position(&self.editor)?;
+        }
+
+        self.buffer_paint(prompt)
+    }
+
+    /// Apply the selected value of the open menu, close it and run its accept event
+    fn accept_menu(&mut self, prompt: &dyn Prompt) -> io::Result<Option<Signal>> {
+        let menus = &self.menus;
+        let accept_event = match self.active_menu.and_then(|index| menus.get(index)) {
+            Some(menu) => {
+                menu.replace_in_buffer(self.editor.line_buffer());
+                menu.accept_event()
+            }
+            None => None,
+        };
+        self.editor.remember_undo_state(true);
+        self.exit_menu(prompt)?;
+
+        match accept_event {
+            Some(event) => self.handle_editor_event(prompt, event),
+            None => Ok(None),
+        }
+    }
+
+    /// Close the open menu and remove it from below the buffer
+    fn exit_menu(&mut self, prompt: &dyn Prompt) -> io::Result<()> {
+        self.active_menu = None;
+        self.input_mode = InputMode::Regular;
+        self.painter.set_status_row(None);
+        self.buffer_paint(prompt)
+    }
+
+    /// Repaint logic for the open menu, which is listed below the buffer
+    fn menu_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if let Some(menu) = self.active_menu.and_then(|index| self.menus.get(index)) {
+            let columns = self.painter.terminal_columns() as usize;
+            let mut rows = menu.menu_string(columns, self.use_ansi_coloring);
+            if let Some(preview) = menu.preview() {
+                rows.push('\n');
+                if self.use_ansi_coloring {
+                    rows.push_str(&Style::new().dimmed().paint(preview).to_string());
+                } else {
+                    rows.push_str(&preview);
+                }
+            }
+            self.painter.set_status_row(Some(rows));
+            self.painter.adjust_prompt_position(&self.editor)?;
+        }
+
+        self.buffer_paint(prompt)
+    }
+
     /// Repaint logic for the history reverse search
     ///
     /// Overwrites the prompt indicator and highlights the search string
@@ -928,13 +3220,22 @@ impl Reedline {
             self.painter.queue_history_search_indicator(
                 prompt,
                 prompt_history_search,
+                ByteOffset(self.history_search_query.offset()),
                 self.use_ansi_coloring,
             )?;
 
             match self.history.string_at_cursor() {
                 Some(string) => {
-                    self.painter
-                        .queue_history_search_result(&string, string.len())?;
+                    let matches = match self.history.match_at_cursor() {
+                        Some(found) if self.use_ansi_coloring => found.ranges(&string),
+                        _ => vec![],
+                    };
+                    self.painter.queue_history_search_result(
+                        &string,
+                        ByteOffset(string.len()),
+                        &matches,
+                        self.history_search_match_style,
+                    )?;
                     self.painter.flush()?;
                 }
 
@@ -942,6 +3243,7 @@ impl Reedline {
                     self.painter.clear_until_newline()?;
                 }
             }
+            self.painter.move_to_history_search_cursor()?;
         }
 
         Ok(())
@@ -952,22 +3254,64 @@ impl Reedline {
     /// # Returns:
     /// (highlighted_line, hint)
     fn prepare_buffer_content(&mut self, prompt: &dyn Prompt) -> ((String, String), String) {
+        self.render_widget();
+        self.follow_snippet();
+
         let cursor_position_in_buffer = self.editor.offset();
         let buffer_to_paint = self.editor.get_buffer();
 
-        let highlighted_line = self
-            .highlighter
-            .highlight(buffer_to_paint)
-            .render_around_insertion_point(
-                cursor_position_in_buffer,
-                prompt.render_prompt_multiline_indicator().borrow(),
-                self.use_ansi_coloring,
-            );
+        let mut styled_text = match (&self.input_mode, &self.search_replace) {
+            (InputMode::SearchReplace, Some(search_replace)) => search_replace.highlight_matches(
+                buffer_to_paint,
+                Style::new(),
+                Style::new().underline(),
+                Style::new().reverse(),
+            ),
+            _ => self
+                .highlighter
+                .highlight(buffer_to_paint, ByteOffset(cursor_position_in_buffer)),
+        };
+        if let Some((snippet, _)) = &self.snippet {
+            styled_text.overlay(snippet.current(), Style::new().underline());
+        }
+        match &self.unconfirmed_paste {
+            Some((pasted, buffer)) if buffer == buffer_to_paint => {
+                styled_text.overlay(pasted.clone(), Style::new().reverse())
+            }
+            Some(_) => self.unconfirmed_paste = None,
+            None => {}
+        }
+        let cursor_position_in_buffer =
+            if buffer_to_paint.chars().any(|c| caret_notation(c).is_some()) {
+                styled_text.show_control_chars(self.control_char_style, cursor_position_in_buffer)
+            } else {
+                cursor_position_in_buffer
+            };
+        let cursor_position_in_buffer = if buffer_to_paint.contains('\t') {
+            styled_text.expand_tabs(self.tab_stop, self.tab_rendering, cursor_position_in_buffer)
+        } else {
+            cursor_position_in_buffer
+        };
 
-        let hint: String = if self.input_mode == InputMode::Regular {
+        let highlighted_line = styled_text.render_around_insertion_point(
+            cursor_position_in_buffer,
+            prompt
+                .render_prompt_multiline_indicator(self.incomplete_reason.as_deref())
+                .borrow(),
+            self.use_ansi_coloring,
+        );
+        let mut highlighted_line = match &self.multiline_gutter {
+            Some(multiline_gutter) => {
+                multiline_gutter.apply(highlighted_line, self.use_ansi_coloring)
+            }
+            None => highlighted_line,
+        };
+
+        let hint_offset = self.hint_offset();
+        let mut hint: String = if self.input_mode == InputMode::Regular {
             self.hinter.handle(
                 buffer_to_paint,
-                cursor_position_in_buffer,
+                ByteOffset(hint_offset),
                 self.history.as_ref(),
                 self.use_ansi_coloring,
             )
@@ -975,18 +3319,89 @@ impl Reedline {
             String::new()
         };
 
+        // A hint below the line is painted with the other rows below the buffer
+        let hint_rows = if self.hinter.display() == HintDisplay::BelowLine && !hint.is_empty() {
+            Some(std::mem::take(&mut hint).replace("\r\n", "\n"))
+        } else {
+            None
+        };
+        self.painter.set_hint_rows(hint_rows);
+
+        // An anchored hint follows the rest of the buffer instead of the cursor
+        if hint_offset != cursor_position_in_buffer {
+            highlighted_line.1.push_str(&std::mem::take(&mut hint));
+        }
+
         (highlighted_line, hint)
     }
 
+    /// Let the widget render its rows for the next frame
+    fn render_widget(&mut self) {
+        let widget_rows = match &mut self.widget {
+            Some(widget) => widget::render_rows(
+                widget.as_mut(),
+                self.painter.terminal_columns(),
+                self.editor.line_buffer(),
+                self.use_ansi_coloring,
+            ),
+            None => None,
+        };
+        self.painter.set_widget_rows(widget_rows);
+    }
+
+    /// Remove the widget rows from the screen, e.g. to leave the submitted line behind
+    fn remove_widget(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if let Some(widget) = self.widget.take() {
+            self.last_paint = None;
+            self.full_repaint(prompt)?;
+            self.widget = Some(widget);
+        }
+
+        Ok(())
+    }
+
+    /// Buffer position the hint is computed for and painted at
+    fn hint_offset(&self) -> usize {
+        if self.anchor_hint {
+            self.editor.get_buffer().len()
+        } else {
+            self.editor.offset()
+        }
+    }
+
     /// Repaint logic for the normal input prompt buffer
     ///
     /// Requires coordinates where the input buffer begins after the prompt.
     /// Performs highlighting and hinting at the moment!
     fn buffer_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if self.defer_paint(PendingPaint::Buffer) {
+            return Ok(());
+        }
+
+        // A full repaint is pending or the edit mode changed,
+        // the prompt indicator has to reflect the live mode
+        if self.pending_paint == Some(PendingPaint::Full)
+            || self
+                .painter
+                .is_prompt_mode_outdated(&self.prompt_edit_mode())
+            || self
+                .painter
+                .is_history_preview_outdated(&self.history_preview())
+        {
+            return self.full_repaint(prompt);
+        }
+        self.pending_paint = None;
+
         let (highlighted_line, hint) = self.prepare_buffer_content(prompt);
 
+        // Nothing changed since the last paint, avoid flicker from redundant output
+        if self.painter.is_buffer_unchanged(&highlighted_line, &hint) {
+            return Ok(());
+        }
+
         self.painter.queue_buffer(highlighted_line, hint)?;
         self.painter.flush()?;
+        self.last_paint = Some(Instant::now());
 
         Ok(())
     }
@@ -995,6 +3410,18 @@ impl Reedline {
     ///
     /// Includes the highlighting and hinting calls.
     fn full_repaint(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if self.defer_paint(PendingPaint::Full) {
+            return Ok(());
+        }
+        self.pending_paint = None;
+
+        let history_preview = self.history_preview();
+        if self.painter.is_history_preview_outdated(&history_preview) {
+            self.painter
+                .set_history_preview(history_preview, self.use_ansi_coloring)?;
+            self.painter.adjust_prompt_position(&self.editor)?;
+        }
+
         let prompt_mode = self.prompt_edit_mode();
         let (highlighted_line, hint) = self.prepare_buffer_content(prompt);
 
@@ -1005,10 +3432,58 @@ impl Reedline {
             hint,
             self.use_ansi_coloring,
         )?;
+        self.last_paint = Some(Instant::now());
 
         Ok(())
     }
 
+    /// Apply the events of a paste or a batch with `handle` and repaint once afterwards
+    ///
+    /// The paints of the single events are deferred, so hints and highlighting are not
+    /// computed for every inserted character of a large paste.
+    fn handle_batch(
+        &mut self,
+        prompt: &dyn Prompt,
+        handle: impl FnOnce(&mut Self) -> Result<Option<Signal>>,
+    ) -> Result<Option<Signal>> {
+        // A batch within a batch is painted by the outer one
+        let painting_batch = std::mem::replace(&mut self.painting_batch, true);
+        let latest_signal = handle(self);
+        self.painting_batch = painting_batch;
+        let latest_signal = latest_signal?;
+
+        self.painter.adjust_prompt_position(&self.editor)?;
+        self.full_repaint(prompt)?;
+        Ok(latest_signal)
+    }
+
+    /// Check if a paint comes too early for the frame interval or during a batch and remember
+    /// it as pending
+    fn defer_paint(&mut self, paint: PendingPaint) -> bool {
+        let too_early = match (self.frame_interval, self.last_paint) {
+            (Some(interval), Some(last_paint)) => last_paint.elapsed() < interval,
+            _ => false,
+        };
+        if too_early || self.painting_batch {
+            self.pending_paint = self.pending_paint.max(Some(paint));
+        }
+
+        too_early || self.painting_batch
+    }
+
+    /// Perform the paint deferred by the frame interval or the current batch, if any
+    fn flush_pending_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        self.last_paint = None;
+        let painting_batch = std::mem::replace(&mut self.painting_batch, false);
+        let result = match self.pending_paint.take() {
+            Some(PendingPaint::Buffer) => self.buffer_paint(prompt),
+            Some(PendingPaint::Full) => self.full_repaint(prompt),
+            None => Ok(()),
+        };
+        self.painting_batch = painting_batch;
+        result
+    }
+
     fn handle_wrap(&mut self, prompt: &dyn Prompt) -> io::Result<()> {
         let (highlighted_line, hint) = self.prepare_buffer_content(prompt);
 
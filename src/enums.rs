@@ -5,12 +5,52 @@ use serde::{Deserialize, Serialize};
 pub enum Signal {
     /// Entry succeeded with the provided content
     Success(String),
-    /// Entry was aborted with `Ctrl+C`
-    CtrlC, // Interrupt current editing
+    /// Entry was aborted with `Ctrl+C`, the aborted line is cleared from the editor
+    CtrlC {
+        /// Content of the buffer when it was aborted, e.g. to offer it again later
+        buffer: String,
+        /// Byte offset of the cursor in `buffer`
+        cursor: usize,
+    },
     /// Abort with `Ctrl+D` signalling `EOF` or abort of a whole interactive session
     CtrlD, // End terminal session
-    /// Signal to clear the current screen. Buffer content remains untouched.
-    CtrlL, // FormFeed/Clear current screen
+    /// No line was completed within the timeout of `Reedline::read_line_with_timeout()`.
+    /// Buffer content remains untouched, call it again to continue editing.
+    Timeout,
+}
+
+/// What [`ReedlineEvent::CtrlD`] and [`ReedlineEvent::CtrlC`] do, bound to `Ctrl+D` and `Ctrl+C`
+///
+/// Set with `Reedline::with_interrupt_behavior()`. `CtrlD` on an empty buffer always returns
+/// [`Signal::CtrlD`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptBehavior {
+    /// What `CtrlD` does while the buffer holds text
+    pub ctrl_d: CtrlDBehavior,
+    /// What `CtrlC` does
+    pub ctrl_c: CtrlCBehavior,
+}
+
+/// What `CtrlD` does while the buffer holds text, see [`InterruptBehavior`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CtrlDBehavior {
+    /// Delete the character under the cursor, like readline
+    #[default]
+    DeleteChar,
+    /// Do nothing, so `Ctrl+D` doesn't edit a line that was meant to be left
+    Ignore,
+    /// Return [`Signal::CtrlD`] regardless of the buffer
+    ReturnSignal,
+}
+
+/// What `CtrlC` does, see [`InterruptBehavior`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CtrlCBehavior {
+    /// Clear the line and return [`Signal::CtrlC`] with the aborted buffer
+    #[default]
+    ReturnSignal,
+    /// Clear the line and keep reading, the cleared text can be brought back with undo
+    ClearLine,
 }
 
 /// Editing actions which can be mapped to key bindings.
@@ -42,6 +82,12 @@ pub enum EditCommand {
     /// Move one word to the right
     MoveWordRight,
 
+    /// Move one whitespace delimited word (vi WORD) to the left
+    MoveBigWordLeft,
+
+    /// Move one whitespace delimited word (vi WORD) to the right
+    MoveBigWordRight,
+
     /// Insert a character at the current insertion point
     InsertChar(char),
 
@@ -64,6 +110,7 @@ pub enum EditCommand {
     Clear,
 
     /// Clear the current buffer
+    #[serde(alias = "DeleteToLineEnd")]
     ClearToLineEnd,
 
     /// Cut the current line
@@ -87,7 +134,14 @@ pub enum EditCommand {
     /// Cut the word right of the insertion point
     CutWordRight,
 
+    /// Cut the whitespace delimited word (vi WORD) left of the insertion point
+    CutBigWordLeft,
+
+    /// Cut the whitespace delimited word (vi WORD) right of the insertion point
+    CutBigWordRight,
+
     /// Paste the cut buffer in front of the insertion point (Emacs, vi `P`)
+    #[serde(alias = "InsertCutBuffer", alias = "PasteCutBuffer")]
     PasteCutBufferBefore,
 
     /// Paste the cut buffer in front of the insertion point (vi `p`)
@@ -132,11 +186,76 @@ pub enum EditCommand {
     /// CutUntil left before char
     CutLeftBefore(char),
 
+    /// Cut the word or the blanks at the insertion point (vi `diw`)
+    CutInnerWord,
+
+    /// Cut the word at the insertion point with the adjacent blanks (vi `daw`)
+    CutAroundWord,
+
+    /// Cut between the enclosing left and right delimiters, e.g. brackets or quotes (vi `di(`)
+    CutInsidePair(char, char),
+
+    /// Cut the enclosing left and right delimiters and everything between them (vi `da(`)
+    CutAroundPair(char, char),
+
+    /// Copy the word or the blanks at the insertion point into the cut buffer (vi `yiw`)
+    CopyInnerWord,
+
+    /// Copy the word at the insertion point with the adjacent blanks (vi `yaw`)
+    CopyAroundWord,
+
+    /// Copy between the enclosing left and right delimiters (vi `yi(`)
+    CopyInsidePair(char, char),
+
+    /// Copy the enclosing left and right delimiters and everything between them (vi `ya(`)
+    CopyAroundPair(char, char),
+
+    /// Copy the word right of the insertion point (vi `yw`)
+    CopyWordRight,
+
+    /// Copy from the insertion point to the end of the current line (vi `y$`)
+    CopyToLineEnd,
+
+    /// Copy the current line (vi `yy`)
+    CopyCurrentLine,
+
     /// CutUntil left until char
     MoveLeftUntil(char),
 
     /// CutUntil left before char
     MoveLeftBefore(char),
+
+    /// Remember the cursor position as the named mark
+    SetMark(char),
+
+    /// Move the cursor to the named mark
+    MoveToMark(char),
+
+    /// Anchor the selection at the cursor (Emacs `set-mark-command`)
+    SetAnchor,
+
+    /// Swap the cursor and the selection anchor (Emacs `exchange-point-and-mark`)
+    SwapCursorAndAnchor,
+
+    /// Cut the text between the selection anchor and the cursor (Emacs `kill-region`)
+    ///
+    /// Cuts the word left of the cursor while no anchor is set, like `C-w` in readline.
+    CutSelection,
+
+    /// Copy the text between the selection anchor and the cursor (Emacs `kill-ring-save`)
+    CopySelection,
+
+    /// Move the cursor back to the previous position in the jump list
+    JumpBack,
+
+    /// Move the cursor forward to the next position in the jump list
+    JumpForward,
+
+    /// Move the cursor to the part of the buffer painted at the given screen row and column
+    ///
+    /// Sent for left clicks with [`Reedline::with_mouse_capture()`](crate::Reedline::with_mouse_capture),
+    /// positions outside of the buffer are ignored.
+    MoveToPosition(u16, u16),
 }
 
 impl EditCommand {
@@ -153,10 +272,17 @@ impl EditCommand {
             | EditCommand::MoveRight
             | EditCommand::MoveWordLeft
             | EditCommand::MoveWordRight
+            | EditCommand::MoveBigWordLeft
+            | EditCommand::MoveBigWordRight
             | EditCommand::MoveRightUntil(_)
             | EditCommand::MoveRightBefore(_)
             | EditCommand::MoveLeftUntil(_)
-            | EditCommand::MoveLeftBefore(_) => UndoBehavior::Full,
+            | EditCommand::MoveLeftBefore(_)
+            | EditCommand::MoveToMark(_)
+            | EditCommand::SwapCursorAndAnchor
+            | EditCommand::MoveToPosition(..)
+            | EditCommand::JumpBack
+            | EditCommand::JumpForward => UndoBehavior::Full,
 
             // Coalesceable insert
             EditCommand::InsertChar(_) => UndoBehavior::Coalesce,
@@ -176,6 +302,8 @@ impl EditCommand {
             | EditCommand::CutToEnd
             | EditCommand::CutWordLeft
             | EditCommand::CutWordRight
+            | EditCommand::CutBigWordLeft
+            | EditCommand::CutBigWordRight
             | EditCommand::PasteCutBufferBefore
             | EditCommand::PasteCutBufferAfter
             | EditCommand::UppercaseWord
@@ -186,9 +314,25 @@ impl EditCommand {
             | EditCommand::CutRightUntil(_)
             | EditCommand::CutRightBefore(_)
             | EditCommand::CutLeftUntil(_)
-            | EditCommand::CutLeftBefore(_) => UndoBehavior::Full,
-
-            EditCommand::Undo | EditCommand::Redo => UndoBehavior::Ignore,
+            | EditCommand::CutLeftBefore(_)
+            | EditCommand::CutInnerWord
+            | EditCommand::CutAroundWord
+            | EditCommand::CutInsidePair(..)
+            | EditCommand::CutAroundPair(..)
+            | EditCommand::CutSelection => UndoBehavior::Full,
+
+            EditCommand::Undo
+            | EditCommand::Redo
+            | EditCommand::SetMark(_)
+            | EditCommand::SetAnchor
+            | EditCommand::CopySelection
+            | EditCommand::CopyInnerWord
+            | EditCommand::CopyAroundWord
+            | EditCommand::CopyInsidePair(..)
+            | EditCommand::CopyAroundPair(..)
+            | EditCommand::CopyWordRight
+            | EditCommand::CopyToLineEnd
+            | EditCommand::CopyCurrentLine => UndoBehavior::Ignore,
         }
     }
 }
@@ -235,13 +379,79 @@ pub enum ReedlineEvent {
     CtrlC,
 
     /// Clears the screen and sets prompt to first line
+    ///
+    /// The buffer content remains untouched, bound to `Ctrl+L` by default.
     ClearScreen,
 
+    /// Repaints the prompt and buffer in place without clearing the screen
+    ///
+    /// Useful after output of the host or other processes was printed over the line.
+    /// Not bound by default, e.g. bind it to `Ctrl+L` instead of [`ReedlineEvent::ClearScreen`].
+    Redraw,
+
+    /// Suspend the process (Unix only)
+    ///
+    /// Expected behavior:
+    ///
+    /// Restore the terminal mode and stop the process group with `SIGTSTP`.
+    /// After resuming (`SIGCONT`) continue editing with a full repaint.
+    /// Not bound by default, as `Ctrl+Z` is used for undo in the Emacs mode.
+    CtrlZ,
+
     /// Handle enter event
+    ///
+    /// Accepts the open menu or history search, otherwise behaves like
+    /// [`ReedlineEvent::SubmitOrNewline`].
     Enter,
 
-    /// Mouse
-    Mouse, // Fill in details later
+    /// Submit the buffer if the validator considers it complete, otherwise insert a newline
+    SubmitOrNewline,
+
+    /// Insert a newline regardless of the validator, to compose multiline input deliberately
+    ///
+    /// Bound to `Alt+Enter` and, with the kitty keyboard protocol, `Shift+Enter` by default.
+    InsertNewline,
+
+    /// Insert the string registered under the given key in [`QuickInserts`](crate::QuickInserts)
+    /// at the cursor, nothing if the key is not registered
+    QuickInsert(String),
+
+    /// Insert a snippet at the cursor and select its first placeholder
+    ///
+    /// Placeholders are written as `${1}`, `${2:default text}` or `$3` and visited in the
+    /// order of their numbers with [`ReedlineEvent::HandleTab`], `$0` marks where the cursor
+    /// ends up after the last one. The current placeholder is underlined, typing at its start
+    /// replaces its default text. Moving the cursor out of the placeholders ends the snippet.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use reedline::{default_emacs_keybindings, ReedlineEvent};
+    ///
+    /// let mut keybindings = default_emacs_keybindings();
+    /// keybindings.add_binding(
+    ///     KeyModifiers::ALT,
+    ///     KeyCode::Char('s'),
+    ///     ReedlineEvent::InsertSnippet("SELECT ${2:*} FROM ${1:table}$0;".into()),
+    /// );
+    /// ```
+    InsertSnippet(String),
+
+    /// Go back to the previous placeholder of the inserted snippet, bound to `Shift+Tab`
+    SnippetPrevious,
+
+    /// Mouse events without an action of their own, clicks move the cursor
+    /// with [`EditCommand::MoveToPosition`]
+    Mouse,
+
+    /// The mouse wheel was turned up over the given screen row
+    ///
+    /// Runs the event configured with [`Reedline::with_scroll_wheel()`](crate::Reedline::with_scroll_wheel),
+    /// if the row is part of the prompt area.
+    ScrollUp(u16),
+
+    /// The mouse wheel was turned down over the given screen row, see [`ReedlineEvent::ScrollUp`]
+    ScrollDown(u16),
 
     /// trigger termimal resize
     Resize(u16, u16),
@@ -264,13 +474,53 @@ pub enum ReedlineEvent {
     /// Navigate to the next historic buffer
     NextHistory,
 
-    /// Search the history for a string
+    /// Search the history for a string, pressed again during the search it steps to the next
+    /// older match
     SearchHistory,
 
+    /// Search the history for a string like [`ReedlineEvent::SearchHistory`], pressed again
+    /// during the search it steps to the next newer match
+    ///
+    /// Outside a search it enters the search just like [`ReedlineEvent::SearchHistory`]: typing
+    /// the query finds the most recent match. Nothing is newer than the present yet, so it only
+    /// steps once a match was passed going back, and stays on the newest match at the end.
+    SearchHistoryForward,
+
+    /// Interactively search and replace within the current buffer
+    SearchReplace,
+
+    /// Open the menu registered with the given name, or close it if it is open
+    Menu(String),
+
+    /// Select the next value of the open menu
+    MenuNext,
+
+    /// Select the previous value of the open menu
+    MenuPrevious,
+
+    /// Show the next page of values of the open menu, if it lists them in pages
+    MenuPageNext,
+
+    /// Show the previous page of values of the open menu, if it lists them in pages
+    MenuPagePrevious,
+
+    /// Apply the selected value of the open menu and close it
+    MenuAccept,
+
+    /// Apply the value shown at the given position of the open menu, counting from 1
+    MenuSelect(usize),
+
     /// Paste event
     Paste(Vec<ReedlineEvent>),
 
     /// In vi mode multiple reedline events can be chained while parsing the
     /// command or movement characters
     Multiple(Vec<ReedlineEvent>),
+
+    /// Repeat the next edit or movement, like `C-u` of Emacs
+    ///
+    /// Handled by the [`Emacs`](crate::Emacs) edit mode: the digits typed next give the count,
+    /// without digits it is 4 and every further universal argument multiplies it by 4.
+    /// Not bound by default.
+    UniversalArgument,
 }
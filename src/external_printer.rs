@@ -0,0 +1,41 @@
+use std::sync::mpsc::Sender;
+
+/// Prints output of the host above the prompt while a line is read
+///
+/// Writing to stdout directly while [`Reedline`](crate::Reedline) paints the prompt and the
+/// buffer mixes both on screen. Output sent through the printer, e.g. from a completer, a
+/// background thread or a signal handler, is printed in place of the prompt by the next
+/// iteration of the editing loop instead, and the prompt is repainted below it.
+///
+/// Get one with [`Reedline::external_printer()`](crate::Reedline::external_printer). Printers
+/// can be cloned and sent to other threads, output sent while no line is read is printed when
+/// the next one starts.
+///
+/// ## Example
+/// ```rust
+/// use reedline::Reedline;
+/// use std::thread;
+///
+/// let mut line_editor = Reedline::create()?;
+/// let printer = line_editor.external_printer();
+///
+/// thread::spawn(move || printer.print_external_output("build finished"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExternalPrinter {
+    sender: Sender<String>,
+}
+
+impl ExternalPrinter {
+    pub(crate) fn new(sender: Sender<String>) -> Self {
+        ExternalPrinter { sender }
+    }
+
+    /// Print `output` above the prompt, each of its lines on its own row
+    ///
+    /// Does nothing once the [`Reedline`](crate::Reedline) the printer belongs to is dropped.
+    pub fn print_external_output(&self, output: &str) {
+        let _ = self.sender.send(output.to_string());
+    }
+}
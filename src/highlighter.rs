@@ -1,16 +1,112 @@
 use nu_ansi_term::Style;
 
-use {crate::styled_text::StyledText, nu_ansi_term::Color};
+use {
+    crate::{styled_text::StyledText, text_manipulation::ByteOffset},
+    nu_ansi_term::Color,
+    std::ops::Range,
+};
 
 pub static DEFAULT_BUFFER_MATCH_COLOR: Color = Color::Green;
 pub static DEFAULT_BUFFER_NEUTRAL_COLOR: Color = Color::White;
 pub static DEFAULT_BUFFER_NOTMATCH_COLOR: Color = Color::Red;
 
+pub static DEFAULT_MATCHING_BRACKET_COLOR: Color = Color::Cyan;
+
 /// The syntax highlighting trait. Implementers of this trait will take in the current string and then
 /// return a `StyledText` object, which represents the contents of the original line as styled strings
 pub trait Highlighter {
     /// The action that will handle the current buffer as a line and return the corresponding `StyledText` for the buffer
-    fn highlight(&self, line: &str) -> StyledText;
+    ///
+    /// `cursor` is the insertion point within `line`, allowing cursor dependent emphasis
+    fn highlight(&self, line: &str, cursor: ByteOffset) -> StyledText;
+}
+
+/// A highlighter annotating byte ranges of the line instead of rebuilding it as [`StyledText`]
+///
+/// Ranges may overlap and leave parts of the line out, later ones are layered over earlier
+/// ones. Combined in a [`LayeredHighlighter`], several of them style the same line, e.g.
+/// the matching brackets over the syntax highlighting.
+pub trait RangeHighlighter {
+    /// The styled byte ranges of `line`
+    ///
+    /// `cursor` is the insertion point within `line`, allowing cursor dependent emphasis
+    fn highlight_ranges(&self, line: &str, cursor: ByteOffset) -> Vec<(Range<usize>, Style)>;
+}
+
+/// A highlighter layering the ranges of [`RangeHighlighter`]s over the output of a base
+/// [`Highlighter`]
+///
+/// The layers are applied in the order they were added, colors of a later layer replace
+/// those below and its attributes are added to them.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{DefaultHighlighter, LayeredHighlighter, MatchingBracketHighlighter, Reedline};
+///
+/// let commands = vec!["ls".to_string(), "echo".to_string()];
+/// let highlighter = LayeredHighlighter::new(Box::new(DefaultHighlighter::new(commands)))
+///     .with_layer(Box::new(MatchingBracketHighlighter::default()));
+/// let line_editor = Reedline::create()?.with_highlighter(Box::new(highlighter));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct LayeredHighlighter {
+    base: Option<Box<dyn Highlighter>>,
+    layers: Vec<Box<dyn RangeHighlighter>>,
+    base_style: Style,
+}
+
+impl Default for LayeredHighlighter {
+    /// Layers over the unstyled line
+    fn default() -> Self {
+        LayeredHighlighter {
+            base: None,
+            layers: vec![],
+            base_style: Style::new(),
+        }
+    }
+}
+
+impl LayeredHighlighter {
+    /// Layer over the output of `base`
+    pub fn new(base: Box<dyn Highlighter>) -> Self {
+        LayeredHighlighter {
+            base: Some(base),
+            ..LayeredHighlighter::default()
+        }
+    }
+
+    /// A builder that adds a layer on top of the previous ones
+    pub fn with_layer(mut self, layer: Box<dyn RangeHighlighter>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// A builder that sets the style of the line without a base highlighter, unstyled by default
+    pub fn with_base_style(mut self, base_style: Style) -> Self {
+        self.base_style = base_style;
+        self
+    }
+}
+
+impl Highlighter for LayeredHighlighter {
+    fn highlight(&self, line: &str, cursor: ByteOffset) -> StyledText {
+        let ranges: Vec<(Range<usize>, Style)> = self
+            .layers
+            .iter()
+            .flat_map(|layer| layer.highlight_ranges(line, cursor))
+            .collect();
+
+        match &self.base {
+            Some(base) => {
+                let mut styled_text = base.highlight(line, cursor);
+                for (range, style) in ranges {
+                    styled_text.overlay(range, style);
+                }
+                styled_text
+            }
+            None => StyledText::from_ranges(line, self.base_style, &ranges),
+        }
+    }
 }
 
 /// A simple, example highlighter that shows how to highlight keywords
@@ -22,7 +118,7 @@ pub struct DefaultHighlighter {
 }
 
 impl Highlighter for DefaultHighlighter {
-    fn highlight(&self, line: &str) -> StyledText {
+    fn highlight(&self, line: &str, _cursor: ByteOffset) -> StyledText {
         let mut styled_text = StyledText::new();
 
         if self
@@ -92,3 +188,227 @@ impl Default for DefaultHighlighter {
         DefaultHighlighter::new(vec![])
     }
 }
+
+/// A highlighter emphasizing the bracket at the cursor together with its matching counterpart
+///
+/// The bracket directly under the cursor takes precedence over the one right before it.
+/// Supports `()`, `[]` and `{}`.
+pub struct MatchingBracketHighlighter {
+    neutral_style: Style,
+    match_style: Style,
+}
+
+impl Default for MatchingBracketHighlighter {
+    fn default() -> Self {
+        MatchingBracketHighlighter {
+            neutral_style: Style::new().fg(DEFAULT_BUFFER_NEUTRAL_COLOR),
+            match_style: Style::new().bold().fg(DEFAULT_MATCHING_BRACKET_COLOR),
+        }
+    }
+}
+
+impl MatchingBracketHighlighter {
+    /// Configure the style used for the pair of matching brackets
+    pub fn with_match_style(mut self, match_style: Style) -> MatchingBracketHighlighter {
+        self.match_style = match_style;
+        self
+    }
+
+    /// Configure the style used for the rest of the buffer
+    pub fn with_neutral_style(mut self, neutral_style: Style) -> MatchingBracketHighlighter {
+        self.neutral_style = neutral_style;
+        self
+    }
+}
+
+impl Highlighter for MatchingBracketHighlighter {
+    fn highlight(&self, line: &str, cursor: ByteOffset) -> StyledText {
+        let mut styled_text = StyledText::new();
+
+        match matching_bracket_pair(line, cursor.0) {
+            Some((first, second)) => {
+                // Brackets are all single byte characters
+                styled_text.push((self.neutral_style, line[..first].to_string()));
+                styled_text.push((self.match_style, line[first..=first].to_string()));
+                styled_text.push((self.neutral_style, line[first + 1..second].to_string()));
+                styled_text.push((self.match_style, line[second..=second].to_string()));
+                styled_text.push((self.neutral_style, line[second + 1..].to_string()));
+            }
+            None => styled_text.push((self.neutral_style, line.to_string())),
+        }
+
+        styled_text
+    }
+}
+
+impl RangeHighlighter for MatchingBracketHighlighter {
+    /// Only the matching brackets, the neutral style is left to the layers below
+    fn highlight_ranges(&self, line: &str, cursor: ByteOffset) -> Vec<(Range<usize>, Style)> {
+        match matching_bracket_pair(line, cursor.0) {
+            Some((first, second)) => vec![
+                (first..first + 1, self.match_style),
+                (second..second + 1, self.match_style),
+            ],
+            None => vec![],
+        }
+    }
+}
+
+const BRACKET_PAIRS: [(u8, u8); 3] = [(b'(', b')'), (b'[', b']'), (b'{', b'}')];
+
+/// Find the byte indices of the bracket at (or right before) `cursor` and its counterpart,
+/// ordered from left to right
+fn matching_bracket_pair(line: &str, cursor: usize) -> Option<(usize, usize)> {
+    let bytes = line.as_bytes();
+    let is_bracket = |idx: usize| matches!(bytes.get(idx), Some(b) if BRACKET_PAIRS.iter().any(|(o, c)| o == b || c == b));
+
+    let bracket_idx = if is_bracket(cursor) {
+        cursor
+    } else if cursor > 0 && is_bracket(cursor - 1) {
+        cursor - 1
+    } else {
+        return None;
+    };
+
+    let bracket = bytes[bracket_idx];
+    for &(open, close) in &BRACKET_PAIRS {
+        if bracket == open {
+            return find_closing(bytes, bracket_idx, open, close).map(|idx| (bracket_idx, idx));
+        } else if bracket == close {
+            return find_opening(bytes, bracket_idx, open, close).map(|idx| (idx, bracket_idx));
+        }
+    }
+
+    None
+}
+
+fn find_closing(bytes: &[u8], start: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, &b) in bytes.iter().enumerate().skip(start) {
+        if b == open {
+            depth += 1;
+        } else if b == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(idx);
+            }
+        }
+    }
+    None
+}
+
+fn find_opening(bytes: &[u8], start: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, &b) in bytes.iter().enumerate().take(start + 1).rev() {
+        if b == close {
+            depth += 1;
+        } else if b == open {
+            depth -= 1;
+            if depth == 0 {
+                return Some(idx);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("(a)", 0, Some((0, 2)))]
+    #[case("(a)", 3, Some((0, 2)))]
+    #[case("f(a[b]c)", 3, Some((3, 5)))]
+    #[case("f(a[b]c)", 8, Some((1, 7)))]
+    #[case("{(})", 0, Some((0, 2)))]
+    #[case("((a)", 0, None)]
+    #[case("abc", 1, None)]
+    #[case("", 0, None)]
+    #[case("ä(ö)", 2, Some((2, 5)))]
+    fn finds_matching_bracket(
+        #[case] line: &str,
+        #[case] cursor: usize,
+        #[case] expected: Option<(usize, usize)>,
+    ) {
+        assert_eq!(matching_bracket_pair(line, cursor), expected);
+    }
+
+    struct KeywordHighlighter(&'static str, Style);
+
+    impl RangeHighlighter for KeywordHighlighter {
+        fn highlight_ranges(&self, line: &str, _: ByteOffset) -> Vec<(Range<usize>, Style)> {
+            line.match_indices(self.0)
+                .map(|(start, keyword)| (start..start + keyword.len(), self.1))
+                .collect()
+        }
+    }
+
+    fn render(highlighter: &dyn Highlighter, line: &str, cursor: usize) -> String {
+        let (left, right) = highlighter
+            .highlight(line, ByteOffset(cursor))
+            .render_around_insertion_point(line.len(), "", true);
+        left + &right
+    }
+
+    #[test]
+    fn layers_are_applied_over_each_other() {
+        let red = Style::new().fg(Color::Red);
+        let highlighter = LayeredHighlighter::default()
+            .with_layer(Box::new(KeywordHighlighter("if", red)))
+            .with_layer(Box::new(
+                MatchingBracketHighlighter::default().with_match_style(Style::new().bold()),
+            ));
+
+        let expected = format!(
+            "{}{}{}{}{}",
+            red.paint("if"),
+            Style::new().paint(" "),
+            Style::new().bold().paint("("),
+            Style::new().paint("x"),
+            Style::new().bold().paint(")"),
+        );
+        assert_eq!(render(&highlighter, "if (x)", 6), expected);
+    }
+
+    #[test]
+    fn layers_keep_the_base_colors_they_do_not_set() {
+        let green = Style::new().fg(Color::Green);
+        let base = MatchingBracketHighlighter::default()
+            .with_neutral_style(green)
+            .with_match_style(green);
+        let highlighter = LayeredHighlighter::new(Box::new(base))
+            .with_layer(Box::new(KeywordHighlighter("b", Style::new().underline())));
+
+        let expected = format!(
+            "{}{}{}",
+            green.paint("a"),
+            green.underline().paint("b"),
+            green.paint("c"),
+        );
+        assert_eq!(render(&highlighter, "abc", 0), expected);
+    }
+
+    #[test]
+    fn matching_bracket_highlighter_styles_pair() {
+        let highlighter = MatchingBracketHighlighter::default()
+            .with_neutral_style(Style::new())
+            .with_match_style(Style::new().bold());
+        let (left, right) = highlighter
+            .highlight("x(y)", ByteOffset(1))
+            .render_around_insertion_point(4, "", true);
+
+        let bold = Style::new().bold();
+        let expected = format!(
+            "{}{}{}{}{}",
+            Style::new().paint("x"),
+            bold.paint("("),
+            Style::new().paint("y"),
+            bold.paint(")"),
+            Style::new().paint(""),
+        );
+        assert_eq!(left + &right, expected);
+    }
+}
@@ -0,0 +1,34 @@
+use crate::{ByteOffset, History};
+
+/// A trait that's responsible for returning the hint for the current line and position
+/// Hints are often shown in-line as part of the buffer, showing the user text they can accept or ignore
+pub trait Hinter {
+    /// Handle the hinting duty by using the line, position, and current history
+    ///
+    /// `pos` is the cursor position as a byte offset into `line`
+    fn handle(
+        &mut self,
+        line: &str,
+        pos: ByteOffset,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String;
+
+    /// Return the current hint being shown to the user
+    fn current_hint(&self) -> String;
+
+    /// Where the hint is shown, inline at the cursor by default
+    fn display(&self) -> HintDisplay {
+        HintDisplay::Inline
+    }
+}
+
+/// Where the hint of a [`Hinter`] is shown
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HintDisplay {
+    /// As ghost text at the cursor, continuing the line
+    #[default]
+    Inline,
+    /// On its own rows below the buffer, keeping the text of a multi-line buffer apart
+    BelowLine,
+}
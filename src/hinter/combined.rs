@@ -0,0 +1,145 @@
+use {
+    super::{HintDisplay, Hinter},
+    crate::{ByteOffset, History},
+};
+
+/// A hinter asking several others, e.g. for hints from the history and spelling corrections
+///
+/// Each hinter is added with a priority and the hinters are asked from the highest priority
+/// down, those of the same priority in the order they were added. By default the first
+/// non-empty hint is shown. With [`CombinedHinter::with_concatenation()`] the hints of all
+/// hinters are shown one after the other, accepting the hint still inserts the first one.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{CombinedHinter, DefaultCompleter, DefaultHinter, HistoryHinter};
+///
+/// let commands = DefaultCompleter::new(vec!["cargo".into(), "clear".into()]);
+/// let hinter = CombinedHinter::default()
+///     .with_hinter(Box::new(HistoryHinter::default()), 2)
+///     .with_hinter(
+///         Box::new(DefaultHinter::default().with_completer(Box::new(commands))),
+///         1,
+///     );
+/// ```
+#[derive(Default)]
+pub struct CombinedHinter {
+    hinters: Vec<(u32, Box<dyn Hinter>)>,
+    concatenate: bool,
+    // Index of the hinter whose hint is accepted
+    current: Option<usize>,
+}
+
+impl CombinedHinter {
+    /// A builder that adds `hinter` with a `priority`, higher priorities are asked first
+    pub fn with_hinter(mut self, hinter: Box<dyn Hinter>, priority: u32) -> Self {
+        self.hinters.push((priority, hinter));
+        // The sort is stable, hinters of the same priority keep their order
+        self.hinters
+            .sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+        self
+    }
+
+    /// A builder that shows the hints of all hinters instead of the first one only
+    pub fn with_concatenation(mut self, concatenate: bool) -> Self {
+        self.concatenate = concatenate;
+        self
+    }
+}
+
+impl Hinter for CombinedHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        pos: ByteOffset,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.current = None;
+        let mut output = String::new();
+        for (index, (_, hinter)) in self.hinters.iter_mut().enumerate() {
+            let hint = hinter.handle(line, pos, history, use_ansi_coloring);
+            if hint.is_empty() {
+                continue;
+            }
+            if self.current.is_none() {
+                self.current = Some(index);
+            } else if !self.concatenate {
+                // Later hinters are still asked, so their current hints stay up to date
+                continue;
+            } else {
+                output.push(' ');
+            }
+            output.push_str(&hint);
+        }
+
+        output
+    }
+
+    fn current_hint(&self) -> String {
+        self.current
+            .map(|index| self.hinters[index].1.current_hint())
+            .unwrap_or_default()
+    }
+
+    fn display(&self) -> HintDisplay {
+        let index = self.current.unwrap_or_default();
+        self.hinters
+            .get(index)
+            .map_or(HintDisplay::Inline, |(_, hinter)| hinter.display())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FileBackedHistory;
+    use pretty_assertions::assert_eq;
+
+    struct FixedHinter(&'static str);
+
+    impl Hinter for FixedHinter {
+        fn handle(&mut self, _: &str, _: ByteOffset, _: &dyn History, _: bool) -> String {
+            self.0.to_string()
+        }
+
+        fn current_hint(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    fn hint(hinter: &mut CombinedHinter) -> String {
+        hinter.handle("ca", ByteOffset(2), &FileBackedHistory::default(), false)
+    }
+
+    #[test]
+    fn first_non_empty_hint_by_priority_wins() {
+        let mut hinter = CombinedHinter::default()
+            .with_hinter(Box::new(FixedHinter("t")), 1)
+            .with_hinter(Box::new(FixedHinter("")), 3)
+            .with_hinter(Box::new(FixedHinter("rgo")), 2);
+
+        assert_eq!(hint(&mut hinter), "rgo");
+        assert_eq!(hinter.current_hint(), "rgo");
+    }
+
+    #[test]
+    fn concatenated_hints_accept_the_first() {
+        let mut hinter = CombinedHinter::default()
+            .with_hinter(Box::new(FixedHinter("rgo")), 0)
+            .with_hinter(Box::new(FixedHinter("")), 0)
+            .with_hinter(Box::new(FixedHinter("(did you mean cat?)")), 0)
+            .with_concatenation(true);
+
+        assert_eq!(hint(&mut hinter), "rgo (did you mean cat?)");
+        assert_eq!(hinter.current_hint(), "rgo");
+    }
+
+    #[test]
+    fn no_hinter_hints_nothing() {
+        let mut hinter = CombinedHinter::default();
+
+        assert_eq!(hint(&mut hinter), "");
+        assert_eq!(hinter.current_hint(), "");
+    }
+}
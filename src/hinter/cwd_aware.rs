@@ -0,0 +1,130 @@
+use {
+    super::Hinter,
+    crate::{ByteOffset, History},
+    nu_ansi_term::{Color, Style},
+    std::path::Path,
+};
+
+/// A hinter that prefers history entries previously executed in the current working directory
+///
+/// Requires a [`History`] that records [`HistoryEntryMetadata`](crate::HistoryEntryMetadata).
+/// Falls back to the most recent matching entry of the global history.
+///
+/// [`FileBackedHistory`](crate::FileBackedHistory) writes the directories to its file only with
+/// [`FileBackedHistory::with_persisted_metadata()`](crate::FileBackedHistory::with_persisted_metadata),
+/// otherwise the entries loaded after a restart have none and are hinted like in the plain
+/// history.
+pub struct CwdAwareHinter {
+    style: Style,
+    current_hint: String,
+}
+
+impl Hinter for CwdAwareHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        pos: ByteOffset,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.current_hint = if line.is_empty() || pos.0 != line.len() {
+            String::new()
+        } else {
+            let cwd = std::env::current_dir().ok();
+            cwd_aware_hint(line, cwd.as_deref(), history).unwrap_or_default()
+        };
+
+        if use_ansi_coloring && !self.current_hint.is_empty() {
+            self.style.paint(&self.current_hint).to_string()
+        } else {
+            self.current_hint.clone()
+        }
+    }
+
+    fn current_hint(&self) -> String {
+        self.current_hint.clone()
+    }
+}
+
+impl Default for CwdAwareHinter {
+    fn default() -> Self {
+        CwdAwareHinter {
+            style: Style::new().fg(Color::LightGray),
+            current_hint: String::new(),
+        }
+    }
+}
+
+impl CwdAwareHinter {
+    /// A builder that sets the style applied to the hint as part of the buffer
+    pub fn with_style(mut self, style: Style) -> CwdAwareHinter {
+        self.style = style;
+        self
+    }
+}
+
+/// Most recent entry extending `line`, preferring the ones executed in `cwd`
+fn cwd_aware_hint(line: &str, cwd: Option<&Path>, history: &dyn History) -> Option<String> {
+    let mut global_match = None;
+
+    for (entry, metadata) in history.iter_chronologic_with_metadata().rev() {
+        if entry.len() > line.len() && entry.starts_with(line) {
+            if cwd.is_some() && metadata.cwd.as_deref() == cwd {
+                return Some(entry[line.len()..].to_string());
+            }
+            if global_match.is_none() {
+                global_match = Some(entry);
+            }
+        }
+    }
+
+    global_match.map(|entry| entry[line.len()..].to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FileBackedHistory, HistoryEntryMetadata};
+    use pretty_assertions::assert_eq;
+
+    fn history_with(entries: &[(&str, &str)]) -> FileBackedHistory {
+        let mut history = FileBackedHistory::default();
+        for (entry, cwd) in entries {
+            history.append_with_metadata(
+                entry,
+                HistoryEntryMetadata {
+                    cwd: Some(cwd.into()),
+                    ..HistoryEntryMetadata::default()
+                },
+            );
+        }
+        history
+    }
+
+    #[test]
+    fn cwd_aware_hint_prefers_entries_from_cwd() {
+        let history = history_with(&[("cargo test", "/project"), ("cargo build", "/other")]);
+
+        let hint = cwd_aware_hint("cargo ", Some(Path::new("/project")), &history);
+
+        assert_eq!(hint, Some("test".to_string()));
+    }
+
+    #[test]
+    fn cwd_aware_hint_falls_back_to_global_history() {
+        let history = history_with(&[("cargo test", "/project"), ("cargo build", "/other")]);
+
+        let hint = cwd_aware_hint("cargo ", Some(Path::new("/elsewhere")), &history);
+
+        assert_eq!(hint, Some("build".to_string()));
+    }
+
+    #[test]
+    fn cwd_aware_hint_ignores_identical_entries() {
+        let history = history_with(&[("ls", "/project")]);
+
+        let hint = cwd_aware_hint("ls", Some(Path::new("/project")), &history);
+
+        assert_eq!(hint, None);
+    }
+}
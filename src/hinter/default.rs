@@ -0,0 +1,248 @@
+use {
+    super::{HintDisplay, Hinter},
+    crate::{ByteOffset, Completer, History, Span, Suggestion},
+    nu_ansi_term::{Color, Style},
+};
+
+/// A default example hinter that use the completions or the history to show a hint to the user
+pub struct DefaultHinter {
+    completer: Option<Box<dyn Completer>>,
+    history: bool,
+    style: Style,
+    inside_line: bool,
+    skip_identical: bool,
+    display: HintDisplay,
+    current_hint: String,
+}
+
+impl Hinter for DefaultHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        pos: ByteOffset,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String {
+        let mut completions = vec![];
+        let mut output = String::new();
+
+        if pos.0 == line.len() || self.inside_line {
+            if let Some(c) = &self.completer {
+                completions = c.complete(line, pos.0);
+            } else if self.history {
+                // The most recent entry starting with the text in front of the cursor,
+                // one repeating the whole line would leave the hint empty
+                completions = history
+                    .iter_chronologic()
+                    .rev()
+                    .filter(|entry| !self.skip_identical || entry.as_str() != line)
+                    .find(|entry| matches!(line.get(..pos.0), Some(prefix) if entry.starts_with(prefix)))
+                    .map(|entry| Suggestion::new(&entry[pos.0..], Span::new(pos.0, line.len())))
+                    .into_iter()
+                    .collect();
+            }
+
+            // Completions that would not add anything to the buffer are skipped,
+            // so the hint area doesn't toggle on every keystroke
+            let hint = completions
+                .iter()
+                .filter_map(|suggestion| suggestion.value.get(suggestion.span.len()..))
+                .find(|hint| !hint.is_empty())
+                .map(|hint| hint.replace("\n", "\r\n"));
+
+            if let Some(hint) = hint {
+                self.current_hint = hint.clone();
+
+                output = self.style.paint(hint).to_string();
+            } else {
+                self.current_hint = String::new();
+            }
+        } else {
+            self.current_hint = String::new();
+        }
+
+        if use_ansi_coloring {
+            output
+        } else if let Ok(bytes) = strip_ansi_escapes::strip(&output) {
+            String::from_utf8_lossy(&bytes).to_string()
+        } else {
+            output
+        }
+    }
+
+    fn current_hint(&self) -> String {
+        self.current_hint.clone()
+    }
+
+    fn display(&self) -> HintDisplay {
+        self.display
+    }
+}
+
+impl Default for DefaultHinter {
+    fn default() -> Self {
+        DefaultHinter {
+            completer: None,
+            history: false,
+            style: Style::new().fg(Color::LightGray),
+            inside_line: false,
+            skip_identical: true,
+            display: HintDisplay::Inline,
+            current_hint: String::new(),
+        }
+    }
+}
+
+impl DefaultHinter {
+    /// A builder for the default hinter that configures if the hint is shown inside the current line
+    pub fn with_inside_line(mut self) -> DefaultHinter {
+        self.inside_line = true;
+        self
+    }
+
+    /// A builder that will configure the completer used by this hinter
+    pub fn with_completer(mut self, completer: Box<dyn Completer>) -> DefaultHinter {
+        self.completer = Some(completer);
+        self
+    }
+
+    /// A builder that configures the history the hinter will use to hint, if in history mode
+    ///
+    /// For hints from the history alone prefer the dedicated [`HistoryHinter`](crate::HistoryHinter).
+    pub fn with_history(mut self) -> DefaultHinter {
+        self.history = true;
+        self
+    }
+
+    /// A builder that sets whether history entries identical to the line are passed over for
+    /// the next older one, on by default
+    ///
+    /// Without it no hint is shown while the line repeats the most recent matching entry.
+    pub fn with_skip_identical(mut self, skip_identical: bool) -> DefaultHinter {
+        self.skip_identical = skip_identical;
+        self
+    }
+
+    /// A builder that sets the style applied to the hint as part of the buffer
+    pub fn with_style(mut self, style: Style) -> DefaultHinter {
+        self.style = style;
+        self
+    }
+
+    /// A builder that sets where the hint is shown, inline at the cursor by default
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::{DefaultHinter, HintDisplay};
+    /// use nu_ansi_term::{Color, Style};
+    ///
+    /// let hinter = DefaultHinter::default()
+    ///     .with_history()
+    ///     .with_display(HintDisplay::BelowLine)
+    ///     .with_style(Style::new().italic().fg(Color::DarkGray));
+    /// ```
+    pub fn with_display(mut self, display: HintDisplay) -> DefaultHinter {
+        self.display = display;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FileBackedHistory, HistoryCompleter};
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    struct FixedCompleter(Vec<Suggestion>);
+
+    impl Completer for FixedCompleter {
+        fn complete(&self, _line: &str, _pos: usize) -> Vec<Suggestion> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn default_hinter_suppresses_completion_identical_to_buffer() {
+        let completer = FixedCompleter(vec![Suggestion::new("test", Span::new(0, 4))]);
+        let mut hinter = DefaultHinter::default().with_completer(Box::new(completer));
+
+        let output = hinter.handle("test", ByteOffset(4), &FileBackedHistory::default(), true);
+
+        assert_eq!(output, "");
+        assert_eq!(hinter.current_hint(), "");
+    }
+
+    #[test]
+    fn default_hinter_skips_to_first_completion_adding_text() {
+        let completer = FixedCompleter(vec![
+            Suggestion::new("te", Span::new(0, 2)),
+            Suggestion::new("test", Span::new(0, 2)),
+        ]);
+        let mut hinter = DefaultHinter::default().with_completer(Box::new(completer));
+
+        let output = hinter.handle("te", ByteOffset(2), &FileBackedHistory::default(), false);
+
+        assert_eq!(output, "st");
+        assert_eq!(hinter.current_hint(), "st");
+    }
+
+    #[rstest]
+    #[case(true, " --release")]
+    #[case(false, "")]
+    fn history_hint_skips_entry_identical_to_line(
+        #[case] skip_identical: bool,
+        #[case] expected: &str,
+    ) {
+        let mut history = FileBackedHistory::default();
+        history.append("cargo build --release");
+        history.append("cargo build");
+        let mut hinter = DefaultHinter::default()
+            .with_history()
+            .with_skip_identical(skip_identical);
+
+        let output = hinter.handle("cargo build", ByteOffset(11), &history, false);
+
+        assert_eq!(output, expected);
+    }
+
+    // The completer lists the older matches as well, an identical entry only adds an empty
+    // hint which is passed over
+    #[rstest]
+    #[case(true, " --release")]
+    #[case(false, " --release")]
+    fn history_completer_hints_past_entry_identical_to_line(
+        #[case] skip_identical: bool,
+        #[case] expected: &str,
+    ) {
+        let completer = HistoryCompleter::new(vec![
+            "cargo build --release".to_string(),
+            "cargo build".to_string(),
+        ])
+        .with_skip_identical(skip_identical);
+        let mut hinter = DefaultHinter::default().with_completer(Box::new(completer));
+
+        let output = hinter.handle(
+            "cargo build",
+            ByteOffset(11),
+            &FileBackedHistory::default(),
+            false,
+        );
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn history_hint_inside_multibyte_line() {
+        let mut history = FileBackedHistory::default();
+        history.append("привет мир");
+        let mut hinter = DefaultHinter::default().with_history().with_inside_line();
+
+        // A position inside a character hints nothing instead of panicking
+        assert_eq!(hinter.handle("привет", ByteOffset(3), &history, false), "");
+        assert_eq!(
+            hinter.handle("привет", ByteOffset(12), &history, false),
+            " мир"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use {
+    super::Hinter,
+    crate::{ByteOffset, History, Matcher, PrefixMatcher},
+    nu_ansi_term::Style,
+};
+
+/// A hinter showing the rest of the most recent history entry starting with the line
+///
+/// The history is scanned from the most recent entry backwards and the scan stops at the
+/// first match, so typing stays cheap even with a large history. The hint is only shown
+/// while the cursor is at the end of the line.
+///
+/// With a different [`Matcher`] than the default [`PrefixMatcher`], the hint is the rest of the
+/// entry after its last matched character.
+pub struct HistoryHinter {
+    style: Style,
+    matcher: Box<dyn Matcher>,
+    current_hint: String,
+}
+
+impl Hinter for HistoryHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        pos: ByteOffset,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.current_hint = if line.is_empty() || pos.0 != line.len() {
+            String::new()
+        } else {
+            let matcher = &self.matcher;
+            history
+                .iter_chronologic()
+                .rev()
+                .find_map(|entry| {
+                    let rest = &entry[matcher.find_match(entry, line)?.end(entry)..];
+                    (!rest.is_empty()).then(|| rest.replace("\n", "\r\n"))
+                })
+                .unwrap_or_default()
+        };
+
+        if use_ansi_coloring && !self.current_hint.is_empty() {
+            self.style.paint(&self.current_hint).to_string()
+        } else {
+            self.current_hint.clone()
+        }
+    }
+
+    fn current_hint(&self) -> String {
+        self.current_hint.clone()
+    }
+}
+
+impl Default for HistoryHinter {
+    fn default() -> Self {
+        HistoryHinter {
+            style: Style::new().dimmed(),
+            matcher: Box::new(PrefixMatcher),
+            current_hint: String::new(),
+        }
+    }
+}
+
+impl HistoryHinter {
+    /// A builder that sets the style applied to the hint as part of the buffer
+    pub fn with_style(mut self, style: Style) -> HistoryHinter {
+        self.style = style;
+        self
+    }
+
+    /// A builder that sets how the line is matched against the history entries
+    pub fn with_matcher(mut self, matcher: Box<dyn Matcher>) -> HistoryHinter {
+        self.matcher = matcher;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ExactMatcher, FileBackedHistory};
+    use pretty_assertions::assert_eq;
+
+    fn history_with(entries: &[&str]) -> FileBackedHistory {
+        let mut history = FileBackedHistory::default();
+        for entry in entries {
+            history.append(entry);
+        }
+        history
+    }
+
+    #[test]
+    fn hints_most_recent_match() {
+        let history = history_with(&["cargo build", "cargo test", "git status"]);
+        let mut hinter = HistoryHinter::default();
+
+        assert_eq!(
+            hinter.handle("cargo ", ByteOffset(6), &history, false),
+            "test"
+        );
+        assert_eq!(hinter.current_hint(), "test");
+        assert_eq!(
+            hinter.handle("git", ByteOffset(3), &history, false),
+            " status"
+        );
+    }
+
+    #[test]
+    fn skips_entries_identical_to_line() {
+        let history = history_with(&["ls -la", "ls"]);
+        let mut hinter = HistoryHinter::default();
+
+        assert_eq!(hinter.handle("ls", ByteOffset(2), &history, false), " -la");
+    }
+
+    #[test]
+    fn no_hint_without_match_or_inside_line() {
+        let history = history_with(&["cargo build"]);
+        let mut hinter = HistoryHinter::default();
+
+        assert_eq!(hinter.handle("git", ByteOffset(3), &history, false), "");
+        assert_eq!(hinter.handle("cargo", ByteOffset(2), &history, false), "");
+        assert_eq!(hinter.handle("", ByteOffset(0), &history, false), "");
+    }
+
+    #[test]
+    fn hints_rest_after_match_of_configured_matcher() {
+        let history = history_with(&["git checkout main"]);
+        let mut hinter = HistoryHinter::default().with_matcher(Box::new(ExactMatcher));
+
+        assert_eq!(
+            hinter.handle("checkout", ByteOffset(8), &history, false),
+            " main"
+        );
+    }
+
+    #[test]
+    fn hint_is_styled_with_ansi_coloring() {
+        let history = history_with(&["cargo build"]);
+        let mut hinter = HistoryHinter::default().with_style(Style::new().italic());
+
+        assert_eq!(
+            hinter.handle("cargo", ByteOffset(5), &history, true),
+            Style::new().italic().paint(" build").to_string()
+        );
+        assert_eq!(hinter.current_hint(), " build");
+    }
+}
@@ -0,0 +1,13 @@
+mod base;
+mod combined;
+mod cwd_aware;
+mod default;
+mod history;
+mod next_token;
+
+pub use base::{HintDisplay, Hinter};
+pub use combined::CombinedHinter;
+pub use cwd_aware::CwdAwareHinter;
+pub use default::DefaultHinter;
+pub use history::HistoryHinter;
+pub use next_token::NextTokenHinter;
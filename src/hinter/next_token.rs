@@ -0,0 +1,313 @@
+use {
+    super::Hinter,
+    crate::{ByteOffset, Completer, History, Span, Suggestion},
+    nu_ansi_term::{Color, Style},
+    std::collections::HashMap,
+};
+
+/// Number of preceding tokens used as context for a suggestion
+const CONTEXT_LEN: usize = 2;
+
+/// Usage statistics of a token following a given context
+#[derive(Debug, Default, Clone, Copy)]
+struct TokenStats {
+    count: usize,
+    // Sequence number of the last entry the token was used in
+    last_used: usize,
+}
+
+/// A hinter suggesting the most likely next token of the current command
+///
+/// Suggestions are based on n-gram statistics mined from the history: for the tokens in
+/// front of the cursor the most recently used continuation is hinted, e.g. after `git checkout`
+/// the branch checked out last. Two tokens of context are preferred, falling back to one.
+///
+/// The statistics are updated incrementally with the entries appended to the history.
+/// As a [`Completer`] it offers all known continuations, most recent first.
+pub struct NextTokenHinter {
+    style: Style,
+    current_hint: String,
+    // Context of preceding tokens mapped to the tokens that followed them.
+    // The start of an entry is represented by empty tokens.
+    ngrams: HashMap<Vec<String>, HashMap<String, TokenStats>>,
+    // Number of entries learned, used as sequence number for recency
+    learned_entries: usize,
+    // History length and last entry at the previous sync
+    synced_len: usize,
+    synced_last: Option<String>,
+}
+
+impl Hinter for NextTokenHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        pos: ByteOffset,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.sync(history);
+
+        self.current_hint = if line.trim().is_empty() || pos.0 != line.len() {
+            String::new()
+        } else {
+            let (context, partial) = split_context(line);
+            self.candidates(&context, partial)
+                .into_iter()
+                .next()
+                .map(|token| token[partial.len()..].to_string())
+                .unwrap_or_default()
+        };
+
+        if use_ansi_coloring && !self.current_hint.is_empty() {
+            self.style.paint(&self.current_hint).to_string()
+        } else {
+            self.current_hint.clone()
+        }
+    }
+
+    fn current_hint(&self) -> String {
+        self.current_hint.clone()
+    }
+}
+
+impl Completer for NextTokenHinter {
+    fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let (context, partial) = split_context(&line[..pos]);
+        let span = Span::new(pos - partial.len(), pos);
+
+        self.candidates(&context, partial)
+            .into_iter()
+            .map(|token| Suggestion::new(token, span).with_append_whitespace(true))
+            .collect()
+    }
+}
+
+impl Default for NextTokenHinter {
+    fn default() -> Self {
+        NextTokenHinter {
+            style: Style::new().fg(Color::LightGray),
+            current_hint: String::new(),
+            ngrams: HashMap::new(),
+            learned_entries: 0,
+            synced_len: 0,
+            synced_last: None,
+        }
+    }
+}
+
+impl NextTokenHinter {
+    /// A builder that sets the style applied to the hint as part of the buffer
+    pub fn with_style(mut self, style: Style) -> NextTokenHinter {
+        self.style = style;
+        self
+    }
+
+    /// Update the statistics with the tokens of `entry`
+    ///
+    /// Entries of the history passed to [`Hinter::handle()`] are learned automatically.
+    pub fn learn(&mut self, entry: &str) {
+        self.learned_entries += 1;
+
+        let mut tokens = vec![""; CONTEXT_LEN];
+        tokens.extend(entry.split_whitespace());
+        for window in tokens.windows(CONTEXT_LEN + 1) {
+            let (context, token) = window.split_at(CONTEXT_LEN);
+            for start in 0..CONTEXT_LEN {
+                let key = context[start..].iter().map(|t| t.to_string()).collect();
+                let stats = self
+                    .ngrams
+                    .entry(key)
+                    .or_default()
+                    .entry(token[0].to_string())
+                    .or_default();
+                stats.count += 1;
+                stats.last_used = self.learned_entries;
+            }
+        }
+    }
+
+    /// Learn the entries appended to `history` since the last call
+    fn sync(&mut self, history: &dyn History) {
+        let len = history.iter_chronologic().len();
+        let last = history.iter_chronologic().next_back().cloned();
+        if len == self.synced_len && last == self.synced_last {
+            return;
+        }
+
+        let new_entries = match &self.synced_last {
+            Some(_) if len > self.synced_len => len - self.synced_len,
+            // The history dropped old entries to make room, find the last one already learned
+            Some(synced_last) => history
+                .iter_chronologic()
+                .rev()
+                .position(|entry| entry == synced_last)
+                .unwrap_or(len),
+            None => len,
+        };
+
+        let entries: Vec<String> = history
+            .iter_chronologic()
+            .skip(len - new_entries)
+            .cloned()
+            .collect();
+        for entry in &entries {
+            self.learn(entry);
+        }
+
+        self.synced_len = len;
+        self.synced_last = last;
+    }
+
+    /// Known tokens following `context` that start with `partial`, most recent first
+    ///
+    /// Uses the longest context with any matching continuation.
+    fn candidates(&self, context: &[&str], partial: &str) -> Vec<&str> {
+        for start in 0..CONTEXT_LEN {
+            let key: Vec<String> = context[start..].iter().map(|t| t.to_string()).collect();
+            if let Some(followers) = self.ngrams.get(&key) {
+                let mut candidates: Vec<(&String, &TokenStats)> = followers
+                    .iter()
+                    .filter(|(token, _)| token.len() > partial.len() && token.starts_with(partial))
+                    .collect();
+                if !candidates.is_empty() {
+                    candidates.sort_by(|(a_token, a), (b_token, b)| {
+                        b.last_used
+                            .cmp(&a.last_used)
+                            .then(b.count.cmp(&a.count))
+                            .then(a_token.cmp(b_token))
+                    });
+                    return candidates
+                        .into_iter()
+                        .map(|(token, _)| token.as_str())
+                        .collect();
+                }
+            }
+        }
+
+        vec![]
+    }
+}
+
+/// Split `line` into the context of the last completed tokens and the partially typed token
+///
+/// The context is padded with empty tokens at the start of the line.
+fn split_context(line: &str) -> (Vec<&str>, &str) {
+    let mut tokens: Vec<&str> = line.split_whitespace().collect();
+    let partial = if line.ends_with(char::is_whitespace) {
+        ""
+    } else {
+        tokens.pop().unwrap_or_default()
+    };
+
+    let mut context = vec![""; CONTEXT_LEN];
+    context.extend(tokens);
+    let context = context.split_off(context.len() - CONTEXT_LEN);
+
+    (context, partial)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FileBackedHistory;
+    use pretty_assertions::assert_eq;
+
+    fn history_with(entries: &[&str]) -> FileBackedHistory {
+        let mut history = FileBackedHistory::default();
+        for entry in entries {
+            history.append(entry);
+        }
+        history
+    }
+
+    fn hint(hinter: &mut NextTokenHinter, history: &dyn History, line: &str) -> String {
+        hinter.handle(line, ByteOffset(line.len()), history, false)
+    }
+
+    #[test]
+    fn split_context_separates_partial_token() {
+        assert_eq!(
+            split_context("git checkout ma"),
+            (vec!["git", "checkout"], "ma")
+        );
+        assert_eq!(
+            split_context("git checkout "),
+            (vec!["git", "checkout"], "")
+        );
+        assert_eq!(split_context("gi"), (vec!["", ""], "gi"));
+        assert_eq!(split_context("a b c "), (vec!["b", "c"], ""));
+    }
+
+    #[test]
+    fn hints_most_recent_next_token() {
+        let history = history_with(&[
+            "git checkout main",
+            "git checkout feature",
+            "git commit -m wip",
+        ]);
+        let mut hinter = NextTokenHinter::default();
+
+        assert_eq!(hint(&mut hinter, &history, "git checkout "), "feature");
+        assert_eq!(hint(&mut hinter, &history, "git checkout ma"), "in");
+        assert_eq!(hint(&mut hinter, &history, "git c"), "ommit");
+    }
+
+    #[test]
+    fn falls_back_to_shorter_context() {
+        let history = history_with(&["sudo git checkout main"]);
+        let mut hinter = NextTokenHinter::default();
+
+        assert_eq!(hint(&mut hinter, &history, "cd ; git checkout "), "main");
+        assert_eq!(hint(&mut hinter, &history, "unknown "), "");
+    }
+
+    #[test]
+    fn learns_entries_appended_later() {
+        let mut history = history_with(&["cargo build"]);
+        let mut hinter = NextTokenHinter::default();
+        assert_eq!(hint(&mut hinter, &history, "cargo "), "build");
+
+        history.append("cargo test");
+
+        assert_eq!(hint(&mut hinter, &history, "cargo "), "test");
+        assert_eq!(hinter.learned_entries, 2);
+    }
+
+    #[test]
+    fn learns_new_entries_when_history_is_full() {
+        let mut history = FileBackedHistory::new(2);
+        history.append("cargo build");
+        history.append("cargo check");
+        let mut hinter = NextTokenHinter::default();
+        assert_eq!(hint(&mut hinter, &history, "cargo "), "check");
+
+        history.append("cargo test");
+
+        assert_eq!(hint(&mut hinter, &history, "cargo "), "test");
+        assert_eq!(hinter.learned_entries, 3);
+    }
+
+    #[test]
+    fn no_hint_for_empty_line_or_cursor_inside_line() {
+        let history = history_with(&["ls -la"]);
+        let mut hinter = NextTokenHinter::default();
+
+        assert_eq!(hint(&mut hinter, &history, ""), "");
+        assert_eq!(hinter.handle("ls ", ByteOffset(1), &history, false), "");
+    }
+
+    #[test]
+    fn completes_partial_token() {
+        let mut hinter = NextTokenHinter::default();
+        hinter.learn("git checkout main");
+        hinter.learn("git checkout master");
+
+        assert_eq!(
+            hinter.complete("git checkout ma", 15),
+            vec![
+                Suggestion::new("master", Span::new(13, 15)).with_append_whitespace(true),
+                Suggestion::new("main", Span::new(13, 15)).with_append_whitespace(true),
+            ]
+        );
+    }
+}
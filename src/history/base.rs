@@ -1,6 +1,10 @@
-use std::collections::vec_deque::Iter;
+use std::{
+    collections::vec_deque::Iter,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::core_editor::LineBuffer;
+use crate::{core_editor::LineBuffer, HistoryFormat, Match};
 
 /// Browsing modes for a [`History`]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -15,14 +19,163 @@ pub enum HistoryNavigationQuery {
     // Fuzzy Search
 }
 
+/// Identifies the session a history entry was added in
+///
+/// Several instances (e.g. shells in different terminals) can share a history file,
+/// every instance adds its entries in a session of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HistorySessionId(i64);
+
+impl HistorySessionId {
+    /// Wrap a session id chosen by the host
+    pub const fn new(id: i64) -> Self {
+        HistorySessionId(id)
+    }
+
+    /// A session id distinct from the ones of other instances, derived from the current time
+    pub fn from_current_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos());
+        HistorySessionId(nanos as i64)
+    }
+
+    /// The numerical value of the id
+    pub fn as_i64(&self) -> i64 {
+        self.0
+    }
+}
+
+/// Identifies an entry of a [`History`] for as long as it is part of it
+///
+/// Ids are assigned when an entry is added, by appending it or by picking it up with
+/// [`History::sync()`]. They never change and are not reused, an id only becomes invalid
+/// when its entry is removed, e.g. to stay within the capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HistoryItemId(i64);
+
+impl HistoryItemId {
+    /// Wrap an id assigned by a [`History`] implementation
+    pub const fn new(id: i64) -> Self {
+        HistoryItemId(id)
+    }
+
+    /// The numerical value of the id
+    pub fn as_i64(&self) -> i64 {
+        self.0
+    }
+}
+
+/// Additional context recorded together with a history entry
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HistoryEntryMetadata {
+    /// The working directory the entry was executed in, if known
+    pub cwd: Option<PathBuf>,
+    /// The session the entry was added in, if known
+    pub session: Option<HistorySessionId>,
+    /// When the entry was executed, if known
+    pub timestamp: Option<SystemTime>,
+}
+
+impl HistoryEntryMetadata {
+    /// Metadata describing an entry submitted now from the current working directory of the
+    /// process
+    pub fn from_current_dir() -> Self {
+        HistoryEntryMetadata {
+            cwd: std::env::current_dir().ok(),
+            session: None,
+            timestamp: Some(SystemTime::now()),
+        }
+    }
+}
+
+static NO_METADATA: HistoryEntryMetadata = HistoryEntryMetadata {
+    cwd: None,
+    session: None,
+    timestamp: None,
+};
+
 /// Interface of a history datastructure that supports stateful navigation via [`HistoryNavigationQuery`].
+///
+/// ## Navigation
+/// The cursor refers to an entry by its [`HistoryItemId`]. Entries appended or picked up
+/// by [`History::sync()`] while browsing leave it on the same entry. The cursor returns to
+/// the default state in front of the most recent entry when a navigation query is set or
+/// when its entry is removed from the history, which callers holding an id can detect with
+/// [`History::set_cursor()`] or [`History::string_by_id()`].
 pub trait History {
     /// Append entry to the history, if capacity management is part of the implementation may perform that as well
     fn append(&mut self, entry: &str);
 
+    /// Append entry to the history together with [`HistoryEntryMetadata`]
+    ///
+    /// Implementations that don't track metadata can rely on the default, which drops it.
+    fn append_with_metadata(&mut self, entry: &str, _metadata: HistoryEntryMetadata) {
+        self.append(entry);
+    }
+
     /// Chronologic interation over all entries present in the history
     fn iter_chronologic(&self) -> Iter<'_, String>;
 
+    /// Chronologic iteration over all entries paired with their [`HistoryEntryMetadata`]
+    ///
+    /// The default implementation reports empty metadata for every entry.
+    fn iter_chronologic_with_metadata(
+        &self,
+    ) -> Box<dyn DoubleEndedIterator<Item = (&String, &HistoryEntryMetadata)> + '_> {
+        Box::new(self.iter_chronologic().map(|entry| (entry, &NO_METADATA)))
+    }
+
+    /// Append the entries of a history file of another shell, in the given format
+    ///
+    /// Entries are appended with the metadata the file has, like the time they were executed.
+    fn import(&mut self, format: HistoryFormat, data: &[u8]) {
+        for (entry, metadata) in format.parse(data) {
+            self.append_with_metadata(&entry, metadata);
+        }
+    }
+
+    /// All entries as the content of a history file of another shell, in the given format
+    fn export(&self, format: HistoryFormat) -> Vec<u8> {
+        format.write(
+            self.iter_chronologic_with_metadata()
+                .map(|(entry, metadata)| (entry.as_str(), metadata)),
+        )
+    }
+
+    /// Start a new session, entries appended from now on are tagged with the returned id
+    ///
+    /// Implementations that don't track sessions can rely on the default, which only creates an id.
+    fn new_session_id(&mut self) -> HistorySessionId {
+        HistorySessionId::from_current_time()
+    }
+
+    /// The id of the current session, if one was started
+    fn session_id(&self) -> Option<HistorySessionId> {
+        None
+    }
+
+    /// Chronologic iteration over the entries added in the current session
+    ///
+    /// Useful to browse only the commands of this instance when several share a history.
+    /// Empty if no session was started.
+    fn iter_session(&self) -> Box<dyn DoubleEndedIterator<Item = &String> + '_> {
+        let session = self.session_id();
+        Box::new(
+            self.iter_chronologic_with_metadata()
+                .filter(move |(_, metadata)| session.is_some() && metadata.session == session)
+                .map(|(entry, _)| entry),
+        )
+    }
+
+    /// Synchronize with the persistent storage, e.g. a file shared with other instances
+    ///
+    /// Writes the entries not yet persisted and picks up those added by others in the meantime.
+    /// Hosts can call this at safe points like after a command finished.
+    fn sync(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+
     /// This moves the cursor backwards respecting the navigation query that is set
     /// - Results in a no-op if the cursor is at the initial point
     fn back(&mut self);
@@ -34,6 +187,56 @@ pub trait History {
     /// Returns the string (if present) at the cursor
     fn string_at_cursor(&self) -> Option<String>;
 
+    /// Where the search string of [`HistoryNavigationQuery::SubstringSearch`] matched the
+    /// entry at the cursor, highlighted in the reverse search
+    ///
+    /// The default reports no match, the entry is then shown without highlight.
+    fn match_at_cursor(&self) -> Option<Match> {
+        None
+    }
+
+    /// The id of the entry at the cursor, `None` in the default state
+    fn cursor(&self) -> Option<HistoryItemId>;
+
+    /// Move the cursor to the entry with `id`, `None` returns to the default state
+    ///
+    /// Returns `false` and leaves the cursor in the default state if no entry has the id.
+    fn set_cursor(&mut self, id: Option<HistoryItemId>) -> bool;
+
+    /// Returns the string of the entry with `id`, if it is still part of the history
+    fn string_by_id(&self, id: HistoryItemId) -> Option<String>;
+
+    /// The id of the entry at position `index` of [`History::iter_chronologic()`]
+    ///
+    /// Lets hosts address entries by their number, e.g. for a `history delete <n>` command.
+    /// The default knows no ids.
+    fn id_at(&self, _index: usize) -> Option<HistoryItemId> {
+        None
+    }
+
+    /// Remove the entry with `id`, e.g. to scrub a secret typed by accident
+    ///
+    /// Persistent histories also remove it from their storage. Returns `false` if no entry has
+    /// the id, histories that can't edit their entries rely on the default, which always does.
+    fn delete(&mut self, _id: HistoryItemId) -> std::io::Result<bool> {
+        Ok(false)
+    }
+
+    /// Replace the text of the entry with `id` by `entry`, keeping its id and metadata
+    ///
+    /// An empty `entry` removes the entry like [`History::delete()`]. Returns `false` if no
+    /// entry has the id, the default changes nothing.
+    fn replace(&mut self, _id: HistoryItemId, _entry: &str) -> std::io::Result<bool> {
+        Ok(false)
+    }
+
+    /// Returns the strings [`History::back()`] and [`History::forward()`] would move the cursor to
+    ///
+    /// Used to preview the adjacent entries while browsing, the default provides none.
+    fn strings_around_cursor(&self) -> (Option<String>, Option<String>) {
+        (None, None)
+    }
+
     /// Set a new navigation mode for search based on input query defined in [`HistoryNavigationQuery`]
     ///
     /// By current convention, resets the position in the stateful browsing to the default.
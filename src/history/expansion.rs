@@ -0,0 +1,223 @@
+use {super::History, std::fmt};
+
+/// A history reference of [`expand_history()`] that could not be resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryExpansionError {
+    /// No entry matches the reference, like `!42` in a shorter history
+    EventNotFound(String),
+    /// The text to replace by a quick substitution `^old^new` is not part of the previous entry
+    SubstitutionFailed(String),
+}
+
+impl fmt::Display for HistoryExpansionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryExpansionError::EventNotFound(reference) => {
+                write!(f, "{}: event not found", reference)
+            }
+            HistoryExpansionError::SubstitutionFailed(substitution) => {
+                write!(f, "{}: substitution failed", substitution)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HistoryExpansionError {}
+
+/// Replace the bash style history references in `line` by the entries of `history`
+///
+/// - `!!` is the previous entry
+/// - `!n` is the entry number `n`, counting from 1 for the oldest one
+/// - `!-n` is the entry `n` positions back, `!-1` is the previous one
+/// - `!prefix` is the most recent entry starting with `prefix`
+/// - `!$` is the last word of the previous entry
+/// - `^old^new` at the start of the line repeats the previous entry with the first `old`
+///   replaced by `new`
+///
+/// A `!` followed by a blank, `=` or `(`, at the end of the line, escaped by a backslash or
+/// inside single quotes stays as it is.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{expand_history, FileBackedHistory, History};
+///
+/// let mut history = FileBackedHistory::default();
+/// history.append("git add src/lib.rs");
+///
+/// assert_eq!(expand_history("git diff !$", &history)?, "git diff src/lib.rs");
+/// assert_eq!(expand_history("^add^reset", &history)?, "git reset src/lib.rs");
+/// # Ok::<(), reedline::HistoryExpansionError>(())
+/// ```
+pub fn expand_history(line: &str, history: &dyn History) -> Result<String, HistoryExpansionError> {
+    if let Some(substitution) = line.strip_prefix('^') {
+        return quick_substitution(substitution, history)
+            .ok_or_else(|| HistoryExpansionError::SubstitutionFailed(line.to_string()));
+    }
+
+    let mut expanded = String::with_capacity(line.len());
+    let mut in_single_quotes = false;
+    let mut offset = 0;
+    while let Some(c) = line[offset..].chars().next() {
+        let next = offset + c.len_utf8();
+        match c {
+            '\'' => in_single_quotes = !in_single_quotes,
+            '\\' if !in_single_quotes => {
+                // Keep the escaped character with its backslash
+                let escaped = line[next..].chars().next().map_or(0, char::len_utf8);
+                expanded.push_str(&line[offset..next + escaped]);
+                offset = next + escaped;
+                continue;
+            }
+            '!' if !in_single_quotes => {
+                if let Some((reference, len)) = Reference::parse(&line[next..]) {
+                    let entry = reference.resolve(history).ok_or_else(|| {
+                        HistoryExpansionError::EventNotFound(line[offset..next + len].to_string())
+                    })?;
+                    expanded.push_str(&entry);
+                    offset = next + len;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        expanded.push(c);
+        offset = next;
+    }
+
+    Ok(expanded)
+}
+
+/// The entry a `!` refers to
+#[derive(Debug, PartialEq, Eq)]
+enum Reference<'line> {
+    Previous,
+    LastWord,
+    Number(usize),
+    FromEnd(usize),
+    Prefix(&'line str),
+}
+
+impl<'line> Reference<'line> {
+    /// The reference at the start of `text` following a `!` and its length in bytes, `None` if
+    /// the `!` is meant literally
+    fn parse(text: &'line str) -> Option<(Reference<'line>, usize)> {
+        let digits = |text: &str| text.bytes().take_while(u8::is_ascii_digit).count();
+
+        match text.chars().next()? {
+            '!' => Some((Reference::Previous, 1)),
+            '$' => Some((Reference::LastWord, 1)),
+            '-' => {
+                let len = digits(&text[1..]);
+                let n = text[1..len + 1].parse().ok()?;
+                Some((Reference::FromEnd(n), len + 1))
+            }
+            '0'..='9' => {
+                let len = digits(text);
+                Some((Reference::Number(text[..len].parse().ok()?), len))
+            }
+            c if c.is_whitespace() || c == '=' || c == '(' => None,
+            _ => {
+                let len = text
+                    .find(|c: char| c.is_whitespace() || matches!(c, ';' | '|' | '&' | '\'' | '"'))
+                    .unwrap_or(text.len());
+                Some((Reference::Prefix(&text[..len]), len))
+            }
+        }
+    }
+
+    fn resolve(&self, history: &dyn History) -> Option<String> {
+        let mut entries = history.iter_chronologic();
+        match self {
+            Reference::Previous => entries.next_back().cloned(),
+            Reference::LastWord => entries
+                .next_back()
+                .and_then(|entry| entry.split_whitespace().last())
+                .map(str::to_string),
+            Reference::Number(n) => entries.nth(n.checked_sub(1)?).cloned(),
+            Reference::FromEnd(n) => entries.nth_back(n.checked_sub(1)?).cloned(),
+            Reference::Prefix(prefix) => entries
+                .rev()
+                .find(|entry| entry.starts_with(prefix))
+                .cloned(),
+        }
+    }
+}
+
+/// The previous entry with the replacement of `substitution`, written as `old^new` or
+/// `old^new^` with an optional text to append after the closing `^`
+fn quick_substitution(substitution: &str, history: &dyn History) -> Option<String> {
+    let mut parts = substitution.splitn(3, '^');
+    let old = parts.next().filter(|old| !old.is_empty())?;
+    let new = parts.next().unwrap_or_default();
+    let appended = parts.next().unwrap_or_default();
+
+    let previous = history.iter_chronologic().next_back()?;
+    if !previous.contains(old) {
+        return None;
+    }
+
+    Some(previous.replacen(old, new, 1) + appended)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FileBackedHistory;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn history() -> FileBackedHistory {
+        let mut history = FileBackedHistory::default();
+        for entry in [
+            "cargo build",
+            "git add src/lib.rs",
+            "cargo test --lib",
+            "ls -la",
+        ] {
+            history.append(entry);
+        }
+        history
+    }
+
+    #[rstest]
+    #[case("sudo !!", "sudo ls -la")]
+    #[case("!1 --release", "cargo build --release")]
+    #[case("!-2", "cargo test --lib")]
+    #[case("!git; !car", "git add src/lib.rs; cargo test --lib")]
+    #[case("vim !$", "vim -la")]
+    #[case("echo !!!", "echo ls -la!")]
+    #[case("^-la^-lh", "ls -lh")]
+    #[case("^la^lh^ /tmp", "ls -lh /tmp")]
+    fn references_are_expanded(#[case] line: &str, #[case] expected: &str) {
+        assert_eq!(expand_history(line, &history()), Ok(expected.to_string()));
+    }
+
+    #[rstest]
+    #[case("echo hi!")]
+    #[case("echo ! x")]
+    #[case("[ != ]")]
+    #[case("echo \\!!")]
+    #[case("echo '!!'")]
+    #[case("echo a^b")]
+    fn literal_exclamation_marks_are_kept(#[case] line: &str) {
+        assert_eq!(expand_history(line, &history()), Ok(line.to_string()));
+    }
+
+    #[rstest]
+    #[case("!9", HistoryExpansionError::EventNotFound("!9".into()))]
+    #[case("!0", HistoryExpansionError::EventNotFound("!0".into()))]
+    #[case("echo !-10 x", HistoryExpansionError::EventNotFound("!-10".into()))]
+    #[case("!vim", HistoryExpansionError::EventNotFound("!vim".into()))]
+    #[case("^vim^nvim", HistoryExpansionError::SubstitutionFailed("^vim^nvim".into()))]
+    fn unresolved_references_fail(#[case] line: &str, #[case] expected: HistoryExpansionError) {
+        assert_eq!(expand_history(line, &history()), Err(expected));
+    }
+
+    #[test]
+    fn empty_history_has_no_previous_entry() {
+        assert_eq!(
+            expand_history("!!", &FileBackedHistory::default()),
+            Err(HistoryExpansionError::EventNotFound("!!".into()))
+        );
+    }
+}
@@ -1,17 +1,26 @@
 use std::{
-    collections::{vec_deque::Iter, VecDeque},
+    cell::RefCell,
+    collections::{vec_deque::Iter, BTreeMap, HashMap, VecDeque},
     fs::{File, OpenOptions},
-    io::{BufRead, BufReader, BufWriter, Write},
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
     path::PathBuf,
+    time::{Duration, UNIX_EPOCH},
 };
 
-use crate::core_editor::LineBuffer;
+use crate::{core_editor::LineBuffer, ExactMatcher, Match, Matcher};
 
-use super::{base::HistoryNavigationQuery, History};
+use super::{
+    base::{HistoryEntryMetadata, HistoryItemId, HistoryNavigationQuery, HistorySessionId},
+    paging::{self, BackwardLines},
+    History,
+};
 
 /// Default size of the [`FileBackedHistory`] used when calling [`FileBackedHistory::default()`]
 pub const HISTORY_SIZE: usize = 1000;
 pub const NEWLINE_ESCAPE: &str = "<\\n>";
+/// Encloses the [`HistoryEntryMetadata`] in front of an entry in the history file, see
+/// [`FileBackedHistory::with_persisted_metadata()`]
+pub const METADATA_DELIMITER: &str = "<\\m>";
 
 /// Stateful history that allows up/down-arrow browsing with an internal cursor.
 ///
@@ -19,15 +28,33 @@ pub const NEWLINE_ESCAPE: &str = "<\\n>";
 /// Similar to bash's behavior without HISTTIMEFORMAT.
 /// (See <https://www.gnu.org/software/bash/manual/html_node/Bash-History-Facilities.html>)
 /// If the history is associated to a file all new changes within a given history capacity will be written to disk when History is dropped.
+/// [`HistoryEntryMetadata`] is only kept in memory unless [`FileBackedHistory::with_persisted_metadata()`]
+/// is set, otherwise the file format stays plain text.
+/// Several instances can share a file by calling [`History::sync()`], which also picks up the entries of the others.
+///
+/// With [`FileBackedHistory::with_paged_file()`] only the most recent entries are kept in memory,
+/// older ones stay in the file and are read from it when browsing or searching reaches them.
 #[derive(Debug)]
 pub struct FileBackedHistory {
     capacity: usize,
     entries: VecDeque<String>,
-    cursor: usize, // If cursor == entries.len() outside history browsing
+    metadata: VecDeque<HistoryEntryMetadata>, // Kept in lockstep with `entries`
+    ids: VecDeque<HistoryItemId>,             // Kept in lockstep with `entries`
+    positions: HashMap<HistoryItemId, usize>, // Index in `ids` at the last reindex
+    dropped: usize, // Entries dropped from the front since the last reindex
+    next_id: i64,
+    cursor: Option<HistoryItemId>, // None outside history browsing
     file: Option<PathBuf>,
-    len_on_disk: usize,  // Keep track what was previously written to disk
+    len_on_disk: usize,   // Keep track what was previously written to disk
     truncate_file: bool, // as long as the file would not exceed capacity we can use appending writes
+    lines_in_file: usize, // Lines in the file at the last load or sync, to detect those added by others
     query: HistoryNavigationQuery,
+    session: Option<HistorySessionId>,
+    search_matcher: Box<dyn Matcher>, // Used for `HistoryNavigationQuery::SubstringSearch`
+    paged: bool,                      // Entries beyond capacity are kept in the file, not dropped
+    archive_end: u64,                 // Offset in the file of the first entry in memory when paged
+    archived_ids: RefCell<ArchivedIds>,
+    persisted_metadata: bool, // Metadata is written to the file in front of the entries
 }
 
 impl Default for FileBackedHistory {
@@ -47,11 +74,90 @@ fn decode_entry(s: &str) -> String {
     s.replace(NEWLINE_ESCAPE, "\n")
 }
 
+/// The line of the history file storing `entry`, preceded by `metadata` if there is some
+///
+/// `<\m><seconds>.<nanoseconds>;<session>;<cwd><\m><entry>`, unknown fields are left empty.
+fn encode_line(entry: &str, metadata: Option<&HistoryEntryMetadata>) -> String {
+    match metadata.filter(|metadata| **metadata != HistoryEntryMetadata::default()) {
+        Some(metadata) => {
+            let timestamp = metadata
+                .timestamp
+                .and_then(|timestamp| timestamp.duration_since(UNIX_EPOCH).ok())
+                .map_or_else(String::new, |since_epoch| {
+                    format!(
+                        "{}.{:09}",
+                        since_epoch.as_secs(),
+                        since_epoch.subsec_nanos()
+                    )
+                });
+            let session = metadata
+                .session
+                .map_or_else(String::new, |session| session.as_i64().to_string());
+            let cwd = metadata
+                .cwd
+                .as_ref()
+                .map_or_else(String::new, |cwd| cwd.to_string_lossy().into_owned());
+            encode_entry(&format!(
+                "{delimiter}{};{};{}{delimiter}{}",
+                timestamp,
+                session,
+                cwd,
+                entry,
+                delimiter = METADATA_DELIMITER
+            ))
+        }
+        None => encode_entry(entry),
+    }
+}
+
+/// The entry and its metadata stored in a line of the history file
+fn decode_line(line: &str) -> (String, HistoryEntryMetadata) {
+    let line = decode_entry(line);
+    let (fields, entry) = match line
+        .strip_prefix(METADATA_DELIMITER)
+        .and_then(|rest| rest.split_once(METADATA_DELIMITER))
+    {
+        Some(parts) => parts,
+        None => return (line, HistoryEntryMetadata::default()),
+    };
+    let mut fields = fields.splitn(3, ';');
+    let timestamp = fields.next().and_then(|timestamp| {
+        let (seconds, nanos) = timestamp.split_once('.')?;
+        let since_epoch = Duration::new(seconds.parse().ok()?, nanos.parse().ok()?);
+        UNIX_EPOCH.checked_add(since_epoch)
+    });
+    let session = fields
+        .next()
+        .and_then(|session| session.parse().ok())
+        .map(HistorySessionId::new);
+    let cwd = fields
+        .next()
+        .filter(|cwd| !cwd.is_empty())
+        .map(PathBuf::from);
+
+    (
+        entry.to_string(),
+        HistoryEntryMetadata {
+            cwd,
+            session,
+            timestamp,
+        },
+    )
+}
+
 impl History for FileBackedHistory {
     /// Appends an entry if non-empty and not repetition of the previous entry.
-    /// Resets the browsing cursor to the default state in front of the most recent entry.
+    /// The browsing cursor stays on its entry, unless it is the oldest one dropped to make room.
     ///
     fn append(&mut self, entry: &str) {
+        self.append_with_metadata(entry, HistoryEntryMetadata::default());
+    }
+
+    fn append_with_metadata(&mut self, entry: &str, mut metadata: HistoryEntryMetadata) {
+        if metadata.session.is_none() {
+            metadata.session = self.session;
+        }
+
         // Don't append if the preceding value is identical or the string empty
         if self
             .entries
@@ -62,53 +168,199 @@ impl History for FileBackedHistory {
             if self.entries.len() == self.capacity {
                 // History is "full", so we delete the oldest entry first,
                 // before adding a new one.
-                self.entries.pop_front();
+                let oldest = self.entries.pop_front();
+                let oldest_metadata = self.metadata.pop_front().unwrap_or_default();
+                let oldest_id = self.ids.pop_front();
+                if let Some(oldest_id) = oldest_id {
+                    self.positions.remove(&oldest_id);
+                    self.dropped += 1;
+                }
+                if !self.paged {
+                    self.truncate_file = true;
+                } else if let (Some(oldest), Some(oldest_id)) =
+                    (oldest.filter(|_| self.len_on_disk > 0), oldest_id)
+                {
+                    // The entry stays in the file, in front of those in memory, and keeps its id
+                    self.archived_ids
+                        .get_mut()
+                        .insert(self.archive_end, oldest_id);
+                    self.archive_end += self.line_of(&oldest, &oldest_metadata).len() as u64 + 1;
+                    self.lines_in_file = self.lines_in_file.saturating_sub(1);
+                }
                 self.len_on_disk = self.len_on_disk.saturating_sub(1);
-                self.truncate_file = true;
             }
+            let id = self.new_id();
             self.entries.push_back(entry.to_string());
+            self.metadata.push_back(metadata);
+            self.positions.insert(id, self.dropped + self.ids.len());
+            self.ids.push_back(id);
         }
-        self.reset_cursor();
     }
 
     fn iter_chronologic(&self) -> Iter<'_, String> {
         self.entries.iter()
     }
 
-    fn back(&mut self) {
-        match self.query.clone() {
-            HistoryNavigationQuery::Normal(_) => {
-                if self.cursor > 0 {
-                    self.cursor -= 1;
-                }
+    fn iter_chronologic_with_metadata(
+        &self,
+    ) -> Box<dyn DoubleEndedIterator<Item = (&String, &HistoryEntryMetadata)> + '_> {
+        Box::new(self.entries.iter().zip(self.metadata.iter()))
+    }
+
+    fn new_session_id(&mut self) -> HistorySessionId {
+        let session = HistorySessionId::from_current_time();
+        self.session = Some(session);
+        session
+    }
+
+    fn session_id(&self) -> Option<HistorySessionId> {
+        self.session
+    }
+
+    fn sync(&mut self) -> std::io::Result<()> {
+        if self.file.is_none() {
+            return Ok(());
+        }
+
+        let on_disk = self.read_file()?;
+        let foreign = self.foreign_lines(&on_disk);
+        // Ids and line lengths of the persisted entries dropped from memory
+        let mut dropped = vec![];
+
+        if !foreign.is_empty() {
+            // The entries of the others were added before the unwritten entries of this instance
+            let own_entries = self.entries.split_off(self.len_on_disk);
+            let own_metadata = self.metadata.split_off(self.len_on_disk);
+            let own_ids = self.ids.split_off(self.len_on_disk);
+            for _ in 0..foreign.len() {
+                let id = self.new_id();
+                self.ids.push_back(id);
             }
-            HistoryNavigationQuery::PrefixSearch(prefix) => {
-                self.back_with_criteria(&|entry| entry.starts_with(&prefix));
+            self.len_on_disk += foreign.len();
+            for (entry, metadata) in foreign {
+                self.entries.push_back(entry);
+                self.metadata.push_back(metadata);
             }
-            HistoryNavigationQuery::SubstringSearch(substring) => {
-                self.back_with_criteria(&|entry| entry.contains(&substring));
+            self.metadata.extend(own_metadata);
+            self.ids.extend(own_ids);
+            self.entries.extend(own_entries);
+
+            let excess = self.entries.len().saturating_sub(self.capacity);
+            let entries: Vec<_> = self.entries.drain(..excess).collect();
+            let metadata: Vec<_> = self.metadata.drain(..excess).collect();
+            let ids: Vec<_> = self.ids.drain(..excess).collect();
+            dropped = ids
+                .into_iter()
+                .zip(entries.iter().zip(&metadata))
+                .take(self.len_on_disk)
+                .map(|(id, (entry, metadata))| (id, self.line_of(entry, metadata).len() as u64 + 1))
+                .collect();
+            self.len_on_disk = self.len_on_disk.saturating_sub(excess);
+            self.reindex();
+        }
+
+        let unwritten = self.entries.len() - self.len_on_disk;
+        if self.paged {
+            self.flush()?;
+            // The entries dropped above are archived now, the others end the file
+            let mut offset = self.archive_end;
+            for (id, len) in dropped {
+                self.archived_ids.get_mut().insert(offset, id);
+                offset += len;
             }
+            let in_memory: u64 = self
+                .entries
+                .iter()
+                .zip(&self.metadata)
+                .map(|(entry, metadata)| self.line_of(entry, metadata).len() as u64 + 1)
+                .sum();
+            let file_len = std::fs::metadata(self.file.as_ref().unwrap())?.len();
+            self.archive_end = file_len.saturating_sub(in_memory);
+            self.lines_in_file = self.entries.len();
+            return Ok(());
+        }
+        if on_disk.len() + unwritten > self.capacity {
+            self.truncate_file = true;
+        }
+        self.flush()?;
+        self.lines_in_file = if self.truncate_file {
+            self.entries.len()
+        } else {
+            on_disk.len() + unwritten
+        };
+        self.truncate_file = false;
+
+        Ok(())
+    }
+
+    fn back(&mut self) {
+        if let Some(id) = self.back_id() {
+            self.cursor = Some(id);
         }
     }
 
     fn forward(&mut self) {
-        match self.query.clone() {
-            HistoryNavigationQuery::Normal(_) => {
-                if self.cursor < self.entries.len() {
-                    self.cursor += 1;
-                }
+        self.cursor = self.forward_id();
+    }
+
+    fn string_at_cursor(&self) -> Option<String> {
+        self.cursor().and_then(|id| self.string_by_id(id))
+    }
+
+    fn match_at_cursor(&self) -> Option<Match> {
+        match &self.query {
+            HistoryNavigationQuery::SubstringSearch(substring) => {
+                let entry = self.string_at_cursor()?;
+                self.search_matcher.find_match(&entry, substring)
+            }
+            _ => None,
+        }
+    }
+
+    fn strings_around_cursor(&self) -> (Option<String>, Option<String>) {
+        let string_of = |id: Option<HistoryItemId>| id.and_then(|id| self.string_by_id(id));
+
+        (string_of(self.back_id()), string_of(self.forward_id()))
+    }
+
+    fn cursor(&self) -> Option<HistoryItemId> {
+        self.cursor.filter(|id| self.contains(*id))
+    }
+
+    fn set_cursor(&mut self, id: Option<HistoryItemId>) -> bool {
+        match id {
+            Some(id) if !self.contains(id) => {
+                self.reset_cursor();
+                false
             }
-            HistoryNavigationQuery::PrefixSearch(prefix) => {
-                self.forward_with_criteria(&|entry| entry.starts_with(&prefix));
+            _ => {
+                self.cursor = id;
+                true
             }
-            HistoryNavigationQuery::SubstringSearch(substring) => {
-                self.forward_with_criteria(&|entry| entry.contains(&substring));
+        }
+    }
+
+    fn string_by_id(&self, id: HistoryItemId) -> Option<String> {
+        match (self.index_of(id), self.archived_offset(id)) {
+            (Some(index), _) => Some(self.entries[index].clone()),
+            (None, Some(offset)) => {
+                let (line, _) = paging::line_at(self.file.as_ref()?, offset).ok()?;
+                Some(decode_line(&line).0)
             }
+            (None, None) => None,
         }
     }
 
-    fn string_at_cursor(&self) -> Option<String> {
-        self.entries.get(self.cursor).cloned()
+    fn id_at(&self, index: usize) -> Option<HistoryItemId> {
+        self.ids.get(index).copied()
+    }
+
+    fn delete(&mut self, id: HistoryItemId) -> std::io::Result<bool> {
+        self.edit_entry(id, None)
+    }
+
+    fn replace(&mut self, id: HistoryItemId, entry: &str) -> std::io::Result<bool> {
+        self.edit_entry(id, Some(entry).filter(|entry| !entry.is_empty()))
     }
 
     fn set_navigation(&mut self, navigation: HistoryNavigationQuery) {
@@ -134,11 +386,23 @@ impl FileBackedHistory {
         FileBackedHistory {
             capacity,
             entries: VecDeque::with_capacity(capacity),
-            cursor: 0,
+            metadata: VecDeque::with_capacity(capacity),
+            ids: VecDeque::with_capacity(capacity),
+            positions: HashMap::with_capacity(capacity),
+            dropped: 0,
+            next_id: 0,
+            cursor: None,
             file: None,
             len_on_disk: 0,
             truncate_file: true,
+            lines_in_file: 0,
             query: HistoryNavigationQuery::Normal(LineBuffer::default()),
+            session: None,
+            search_matcher: Box::new(ExactMatcher),
+            paged: false,
+            archive_end: 0,
+            archived_ids: RefCell::default(),
+            persisted_metadata: false,
         }
     }
 
@@ -160,6 +424,50 @@ impl FileBackedHistory {
         Ok(hist)
     }
 
+    /// Creates a new history with an associated history file that is read only in parts
+    ///
+    /// Keeps the `capacity` most recent entries of the file in memory, unlike
+    /// [`FileBackedHistory::with_file()`] the older ones are not dropped from the file. Browsing
+    /// and searching past the entries in memory walk the file backwards, reading only the part
+    /// they pass. Meant for huge history files that take long to load in full.
+    ///
+    /// [`History::iter_chronologic()`] and [`History::id_at()`] cover the entries in memory.
+    /// Older entries get their id when browsing or searching reaches them, entries leaving the
+    /// memory for the file keep theirs. Like the ids of the entries in memory they stay valid
+    /// when other entries are deleted or replaced.
+    ///
+    /// **Side effects:** creates all nested directories to the file and the file itself
+    pub fn with_paged_file(capacity: usize, file: PathBuf) -> std::io::Result<Self> {
+        let mut hist = Self::new(capacity);
+        if let Some(base_dir) = file.parent() {
+            std::fs::create_dir_all(base_dir)?;
+        }
+        hist.file = Some(file);
+        hist.paged = true;
+        hist.load_paged_file()?;
+        Ok(hist)
+    }
+
+    /// A builder that sets how [`HistoryNavigationQuery::SubstringSearch`] matches entries
+    ///
+    /// Defaults to an [`ExactMatcher`], finding entries that contain the search string.
+    pub fn with_search_matcher(mut self, search_matcher: Box<dyn Matcher>) -> Self {
+        self.search_matcher = search_matcher;
+        self
+    }
+
+    /// A builder that sets whether the [`HistoryEntryMetadata`] of the entries is written to
+    /// the file, off by default
+    ///
+    /// Entries with metadata are stored as `<\m><seconds>.<nanoseconds>;<session>;<cwd><\m><entry>`,
+    /// which other programs reading the file, including older versions of this one, take for a
+    /// part of the entry. The metadata is read from the file in any case, so entries written by
+    /// another instance keep it, e.g. for the [`CwdAwareHinter`](crate::CwdAwareHinter).
+    pub fn with_persisted_metadata(mut self, persisted_metadata: bool) -> Self {
+        self.persisted_metadata = persisted_metadata;
+        self
+    }
+
     /// Loads history from the associated newline separated file
     ///
     /// Expects the [`History`] to be empty.
@@ -167,73 +475,271 @@ impl FileBackedHistory {
     ///
     /// **Side effect:** creates not yet existing file.
     fn load_file(&mut self) -> std::io::Result<()> {
-        let f = File::open(
-            self.file
-                .as_ref()
-                .expect("History::load_file should only be called if a filename is set"),
-        );
         assert!(
             self.entries.is_empty(),
             "History currently designed to load file once in the constructor"
         );
-        match f {
+        let mut from_file = self.read_file()?;
+        self.lines_in_file = from_file.len();
+        let from_file = if from_file.len() > self.capacity {
+            from_file.split_off(from_file.len() - self.capacity)
+        } else {
+            from_file
+        };
+        self.len_on_disk = from_file.len();
+        self.ids = from_file.iter().map(|_| self.new_id()).collect();
+        self.reindex();
+        (self.entries, self.metadata) = from_file.into_iter().unzip();
+        self.reset_cursor();
+        Ok(())
+    }
+
+    /// Loads the last `capacity` lines of the associated file, without reading the ones before
+    fn load_paged_file(&mut self) -> std::io::Result<()> {
+        let path = self
+            .file
+            .clone()
+            .expect("History::load_paged_file should only be called if a filename is set");
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)?;
+        let mut len = file.metadata()?.len();
+        if len > 0 {
+            // Appended entries have to start on a line of their own
+            let mut last = [0];
+            file.seek(SeekFrom::Start(len - 1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                file.write_all(b"\n")?;
+                len += 1;
+            }
+        }
+
+        let mut lines = BackwardLines::new(&path, len)?
+            .take(self.capacity)
+            .collect::<std::io::Result<Vec<_>>>()?;
+        lines.reverse();
+        self.archive_end = lines.first().map_or(len, |(offset, _)| *offset);
+        (self.entries, self.metadata) = lines
+            .into_iter()
+            .map(|(_, line)| decode_line(&line))
+            .unzip();
+        self.ids = (0..self.entries.len()).map(|_| self.new_id()).collect();
+        self.reindex();
+        self.archived_ids = RefCell::default();
+        self.len_on_disk = self.entries.len();
+        self.lines_in_file = self.entries.len();
+        self.truncate_file = false;
+        self.reset_cursor();
+        Ok(())
+    }
+
+    /// Lines of the file added by other instances since the last load or sync
+    fn foreign_lines(
+        &self,
+        on_disk: &VecDeque<(String, HistoryEntryMetadata)>,
+    ) -> VecDeque<(String, HistoryEntryMetadata)> {
+        let last_persisted = self
+            .len_on_disk
+            .checked_sub(1)
+            .and_then(|index| self.entries.get(index));
+        let start = match last_persisted {
+            // The file still ends with what was there at the last sync, others only appended
+            Some(last)
+                if self.lines_in_file > 0
+                    && on_disk.get(self.lines_in_file - 1).map(|(line, _)| line) == Some(last) =>
+            {
+                self.lines_in_file
+            }
+            // Another instance rewrote the file, continue after the last entry known to be persisted
+            Some(last) => on_disk
+                .iter()
+                .rposition(|(line, _)| line == last)
+                .map_or(on_disk.len(), |index| index + 1),
+            None => self.lines_in_file.min(on_disk.len()),
+        };
+
+        on_disk.range(start..).cloned().collect()
+    }
+
+    /// Reads all entries of the associated file with their metadata, when paged the ones
+    /// following the archived entries
+    ///
+    /// **Side effect:** creates not yet existing file.
+    fn read_file(&self) -> std::io::Result<VecDeque<(String, HistoryEntryMetadata)>> {
+        let path = self
+            .file
+            .as_ref()
+            .expect("History::read_file should only be called if a filename is set");
+        if self.paged {
+            return paging::lines_between(path, self.archive_end, u64::MAX)?
+                .map(|line| line.map(|(_, line)| decode_line(&line)))
+                .collect();
+        }
+        match File::open(path) {
             Err(e) => match e.kind() {
                 std::io::ErrorKind::NotFound => {
-                    File::create(self.file.as_ref().unwrap())?;
-                    Ok(())
+                    File::create(path)?;
+                    Ok(VecDeque::new())
                 }
                 _ => Err(e),
             },
-            Ok(file) => {
-                let reader = BufReader::new(file);
-                let mut from_file = reader
-                    .lines()
-                    .map(|o| o.map(|i| decode_entry(&i)))
-                    .collect::<Result<VecDeque<String>, _>>()?;
-                let from_file = if from_file.len() > self.capacity {
-                    from_file.split_off(from_file.len() - self.capacity)
-                } else {
-                    from_file
-                };
-                self.len_on_disk = from_file.len();
-                self.entries = from_file;
-                self.reset_cursor();
-                Ok(())
-            }
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .map(|o| o.map(|i| decode_line(&i)))
+                .collect(),
         }
     }
 
-    fn back_with_criteria(&mut self, criteria: &dyn Fn(&str) -> bool) {
-        if !self.entries.is_empty() {
-            let previous_match = self.entries.get(self.cursor);
-            if let Some((next_cursor, _)) = self
-                .entries
-                .iter()
-                .take(self.cursor)
-                .enumerate()
-                .rev()
-                .find(|(_, entry)| criteria(entry) && previous_match != Some(entry))
-            {
-                // set to entry
-                self.cursor = next_cursor;
+    /// Assign the id for a newly added entry
+    fn new_id(&mut self) -> HistoryItemId {
+        let id = HistoryItemId::new(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Position of the entry with `id` in `entries`
+    fn index_of(&self, id: HistoryItemId) -> Option<usize> {
+        self.positions
+            .get(&id)
+            .map(|position| position - self.dropped)
+    }
+
+    /// Rebuild the positions of the ids after entries were added or removed in between others
+    fn reindex(&mut self) {
+        self.positions = self
+            .ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index))
+            .collect();
+        self.dropped = 0;
+    }
+
+    /// Id of the archived entry starting at byte `offset` of the file
+    fn archived_id(&self, offset: u64) -> HistoryItemId {
+        self.archived_ids.borrow_mut().id_at(offset)
+    }
+
+    /// Offset in the file of the archived entry with `id`, if it is one
+    fn archived_offset(&self, id: HistoryItemId) -> Option<u64> {
+        let offset = self.archived_ids.borrow().offset_of(id)?;
+        Some(offset).filter(|offset| self.paged && *offset < self.archive_end)
+    }
+
+    /// Whether the entry with `id` is in memory or archived in the file
+    fn contains(&self, id: HistoryItemId) -> bool {
+        self.index_of(id).is_some() || self.archived_offset(id).is_some()
+    }
+
+    /// Position of the cursor in `entries`, `entries.len()` outside history browsing
+    fn cursor_index(&self) -> usize {
+        self.cursor
+            .and_then(|id| self.index_of(id))
+            .unwrap_or(self.entries.len())
+    }
+
+    /// Whether `entry` is part of the results of the navigation query
+    fn matches_query(&self, entry: &str) -> bool {
+        match &self.query {
+            HistoryNavigationQuery::Normal(_) => true,
+            HistoryNavigationQuery::PrefixSearch(prefix) => entry.starts_with(prefix.as_str()),
+            HistoryNavigationQuery::SubstringSearch(substring) => {
+                self.search_matcher.find_match(entry, substring).is_some()
             }
         }
     }
 
-    fn forward_with_criteria(&mut self, criteria: &dyn Fn(&str) -> bool) {
-        let previous_match = self.entries.get(self.cursor);
-        if let Some((next_cursor, _)) = self
-            .entries
+    /// Whether [`History::back()`] or [`History::forward()`] can move the cursor to `entry`
+    ///
+    /// Searches skip the results equal to the current one.
+    fn is_next_result(&self, entry: &str, current: Option<&str>) -> bool {
+        match &self.query {
+            HistoryNavigationQuery::Normal(_) => true,
+            _ => self.matches_query(entry) && current != Some(entry),
+        }
+    }
+
+    /// Id [`History::back()`] moves the cursor to according to the navigation query, if it moves
+    fn back_id(&self) -> Option<HistoryItemId> {
+        let in_memory = match self.cursor().and_then(|id| self.archived_offset(id)) {
+            Some(_) => None,
+            None => self.back_index(),
+        };
+        match in_memory {
+            Some(index) => Some(self.ids[index]),
+            None => self.back_in_archive(),
+        }
+    }
+
+    /// Id [`History::forward()`] moves the cursor to according to the navigation query
+    ///
+    /// `None` if there is no newer entry and the cursor returns to its default state.
+    fn forward_id(&self) -> Option<HistoryItemId> {
+        self.forward_in_archive()
+            .or_else(|| self.forward_index().map(|index| self.ids[index]))
+    }
+
+    /// Index of the closest older entry in memory that is a result of the navigation query
+    fn back_index(&self) -> Option<usize> {
+        let cursor = self.cursor_index();
+        let current = self.entries.get(cursor).map(String::as_str);
+        self.entries
             .iter()
+            .take(cursor)
             .enumerate()
-            .skip(self.cursor + 1)
-            .find(|(_, entry)| criteria(entry) && previous_match != Some(entry))
-        {
-            // set to entry
-            self.cursor = next_cursor;
-        } else {
-            self.reset_cursor();
+            .rev()
+            .find(|(_, entry)| self.is_next_result(entry, current))
+            .map(|(index, _)| index)
+    }
+
+    /// Index of the closest newer entry in memory that is a result of the navigation query
+    fn forward_index(&self) -> Option<usize> {
+        let current = self.string_at_cursor();
+        let start = match self.cursor().and_then(|id| self.archived_offset(id)) {
+            // All entries in memory are newer than the archived ones
+            Some(_) => 0,
+            None => self.cursor_index() + 1,
+        };
+        self.entries
+            .iter()
+            .enumerate()
+            .skip(start)
+            .find(|(_, entry)| self.is_next_result(entry, current.as_deref()))
+            .map(|(index, _)| index)
+    }
+
+    /// Id of the closest older archived entry that is a result of the navigation query
+    fn back_in_archive(&self) -> Option<HistoryItemId> {
+        if !self.paged {
+            return None;
         }
+        let current = self.string_at_cursor();
+        let end = self
+            .cursor()
+            .and_then(|id| self.archived_offset(id))
+            .unwrap_or(self.archive_end);
+
+        BackwardLines::new(self.file.as_ref()?, end)
+            .ok()?
+            .map_while(Result::ok)
+            .find(|(_, line)| self.is_next_result(&decode_line(line).0, current.as_deref()))
+            .map(|(offset, _)| self.archived_id(offset))
+    }
+
+    /// Id of the closest newer archived entry that is a result of the navigation query
+    fn forward_in_archive(&self) -> Option<HistoryItemId> {
+        let offset = self.cursor().and_then(|id| self.archived_offset(id))?;
+        let current = self.string_at_cursor();
+
+        paging::lines_between(self.file.as_ref()?, offset, self.archive_end)
+            .ok()?
+            .map_while(Result::ok)
+            .skip(1)
+            .find(|(_, line)| self.is_next_result(&decode_line(line).0, current.as_deref()))
+            .map(|(offset, _)| self.archived_id(offset))
     }
 
     /// Writes unwritten history contents to disk.
@@ -243,6 +749,22 @@ impl FileBackedHistory {
         if self.file.is_none() {
             return Ok(());
         }
+        if self.truncate_file && self.paged {
+            // Only the entries in memory are rewritten, the archived ones in front stay
+            let mut lines = vec![];
+            for (entry, metadata) in self.entries.iter().zip(&self.metadata) {
+                lines.extend(self.line_of(entry, metadata).bytes());
+                lines.push(b'\n');
+            }
+            paging::splice(
+                self.file.as_ref().unwrap(),
+                self.archive_end,
+                u64::MAX,
+                &lines,
+            )?;
+            self.len_on_disk = self.entries.len();
+            return Ok(());
+        }
         let file = if self.truncate_file {
             // Rewrite the whole file if we truncated the old output
             self.len_on_disk = 0;
@@ -259,8 +781,12 @@ impl FileBackedHistory {
                 .open(self.file.as_ref().unwrap())?
         };
         let mut writer = BufWriter::new(file);
-        for line in self.entries.range(self.len_on_disk..) {
-            writer.write_all(encode_entry(line).as_bytes())?;
+        let unwritten = self
+            .entries
+            .range(self.len_on_disk..)
+            .zip(self.metadata.range(self.len_on_disk..));
+        for (entry, metadata) in unwritten {
+            writer.write_all(self.line_of(entry, metadata).as_bytes())?;
             writer.write_all("\n".as_bytes())?;
         }
         writer.flush()?;
@@ -269,9 +795,117 @@ impl FileBackedHistory {
         Ok(())
     }
 
+    /// Replace the entry with `id` by `replacement` or remove it, then rewrite the file
+    fn edit_entry(
+        &mut self,
+        id: HistoryItemId,
+        replacement: Option<&str>,
+    ) -> std::io::Result<bool> {
+        // The file is rewritten from the entries, those of other instances must be part of them
+        self.sync()?;
+
+        if let Some(offset) = self.archived_offset(id) {
+            self.edit_archived_entry(offset, replacement)?;
+            return Ok(true);
+        }
+        let index = match self.index_of(id) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+        match replacement {
+            Some(entry) => self.entries[index] = entry.to_string(),
+            None => {
+                self.entries.remove(index);
+                self.metadata.remove(index);
+                self.ids.remove(index);
+                self.reindex();
+            }
+        }
+
+        self.truncate_file = true;
+        self.flush()?;
+        self.truncate_file = false;
+        self.lines_in_file = self.entries.len();
+
+        Ok(true)
+    }
+
+    /// Replace the archived line at `offset` by `replacement` or remove it
+    fn edit_archived_entry(
+        &mut self,
+        offset: u64,
+        replacement: Option<&str>,
+    ) -> std::io::Result<()> {
+        let path = self.file.as_ref().unwrap();
+        let (previous, line_end) = paging::line_at(path, offset)?;
+        // A replaced entry keeps its metadata
+        let (_, metadata) = decode_line(&previous);
+        let line =
+            replacement.map_or_else(String::new, |entry| self.line_of(entry, &metadata) + "\n");
+        paging::splice(path, offset, line_end, line.as_bytes())?;
+
+        self.archived_ids
+            .get_mut()
+            .replace(offset, line_end - offset, line.len() as u64);
+        self.archive_end = self.archive_end + line.len() as u64 - (line_end - offset);
+        Ok(())
+    }
+
+    /// The line of the file storing `entry`, with its metadata if that is persisted
+    fn line_of(&self, entry: &str, metadata: &HistoryEntryMetadata) -> String {
+        encode_line(entry, Some(metadata).filter(|_| self.persisted_metadata))
+    }
+
     /// Reset the internal browsing cursor
     fn reset_cursor(&mut self) {
-        self.cursor = self.entries.len();
+        self.cursor = None;
+    }
+}
+
+/// Ids of the archived entries of a paged file by their offset in it
+///
+/// Entries found in the file get an id when they are first reached, kept apart from the ids
+/// of the entries added to memory by being negative. The offsets follow the edits of the file.
+#[derive(Debug, Default)]
+struct ArchivedIds {
+    by_offset: BTreeMap<u64, HistoryItemId>,
+    offsets: HashMap<HistoryItemId, u64>,
+    last_id: i64,
+}
+
+impl ArchivedIds {
+    /// Id of the entry starting at `offset`, a new one for an entry not reached before
+    fn id_at(&mut self, offset: u64) -> HistoryItemId {
+        if let Some(id) = self.by_offset.get(&offset) {
+            return *id;
+        }
+        self.last_id -= 1;
+        let id = HistoryItemId::new(self.last_id);
+        self.insert(offset, id);
+        id
+    }
+
+    fn insert(&mut self, offset: u64, id: HistoryItemId) {
+        self.by_offset.insert(offset, id);
+        self.offsets.insert(id, offset);
+    }
+
+    fn offset_of(&self, id: HistoryItemId) -> Option<u64> {
+        self.offsets.get(&id).copied()
+    }
+
+    /// Follow the `len` bytes of the line at `offset` being replaced by `new_len` bytes,
+    /// dropping the id of the entry if it was removed
+    fn replace(&mut self, offset: u64, len: u64, new_len: u64) {
+        let behind = self.by_offset.split_off(&(offset + 1));
+        if new_len == 0 {
+            if let Some(id) = self.by_offset.remove(&offset) {
+                self.offsets.remove(&id);
+            }
+        }
+        for (old_offset, id) in behind {
+            self.insert(old_offset + new_len - len, id);
+        }
     }
 }
 
@@ -288,6 +922,7 @@ mod tests {
     use std::io::BufRead;
 
     use super::*;
+    use crate::FuzzyMatcher;
 
     #[test]
     fn accessing_empty_history_returns_nothing() {
@@ -366,6 +1001,65 @@ mod tests {
         assert_eq!(hist.string_at_cursor(), Some("find me as well".to_string()));
     }
 
+    #[test]
+    fn strings_around_cursor_follow_navigation() {
+        let mut hist = FileBackedHistory::default();
+        hist.append("find me as well");
+        hist.append("test");
+        hist.append("find me");
+
+        hist.set_navigation(HistoryNavigationQuery::Normal(LineBuffer::new()));
+        hist.back();
+        assert_eq!(
+            hist.strings_around_cursor(),
+            (Some("test".to_string()), None)
+        );
+
+        hist.set_navigation(HistoryNavigationQuery::PrefixSearch("find".to_string()));
+        hist.back();
+        hist.back();
+        assert_eq!(
+            hist.strings_around_cursor(),
+            (None, Some("find me".to_string()))
+        );
+    }
+
+    #[test]
+    fn cursor_stays_on_entry_when_appending() {
+        let mut hist = FileBackedHistory::default();
+        hist.append("first");
+        hist.append("second");
+        hist.back();
+        hist.back();
+        let id = hist.cursor().unwrap();
+
+        hist.append("third");
+
+        assert_eq!(hist.cursor(), Some(id));
+        assert_eq!(hist.string_at_cursor(), Some("first".to_string()));
+        hist.forward();
+        assert_eq!(hist.string_at_cursor(), Some("second".to_string()));
+        assert_eq!(hist.string_by_id(id), Some("first".to_string()));
+    }
+
+    #[test]
+    fn cursor_resets_when_entry_is_removed() {
+        let mut hist = FileBackedHistory::new(2);
+        hist.append("first");
+        hist.append("second");
+        hist.back();
+        hist.back();
+        let id = hist.cursor().unwrap();
+
+        hist.append("third");
+
+        assert_eq!(hist.cursor(), None);
+        assert_eq!(hist.string_by_id(id), None);
+        assert!(!hist.set_cursor(Some(id)));
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("third".to_string()));
+    }
+
     #[test]
     fn prefix_search_bottoms_out() {
         let mut hist = FileBackedHistory::default();
@@ -463,6 +1157,73 @@ mod tests {
         assert_eq!(hist.string_at_cursor(), Some("substring".to_string()));
     }
 
+    #[test]
+    fn substring_search_alternates_between_back_and_forward() {
+        let mut hist = FileBackedHistory::default();
+        hist.append("cargo build");
+        hist.append("ls");
+        hist.append("cargo test");
+        hist.append("pwd");
+        hist.append("cargo run");
+
+        hist.set_navigation(HistoryNavigationQuery::SubstringSearch("cargo".to_string()));
+        hist.back();
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("cargo test".to_string()));
+        hist.forward();
+        assert_eq!(hist.string_at_cursor(), Some("cargo run".to_string()));
+        hist.back();
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("cargo build".to_string()));
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("cargo build".to_string()));
+        hist.forward();
+        assert_eq!(hist.string_at_cursor(), Some("cargo test".to_string()));
+        hist.forward();
+        hist.forward();
+        assert_eq!(hist.string_at_cursor(), None);
+    }
+
+    #[test]
+    fn substring_search_forward_from_the_present_finds_nothing() {
+        let mut hist = FileBackedHistory::default();
+        hist.append("cargo build");
+
+        hist.set_navigation(HistoryNavigationQuery::SubstringSearch("cargo".to_string()));
+        hist.forward();
+        assert_eq!(hist.string_at_cursor(), None);
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("cargo build".to_string()));
+    }
+
+    #[test]
+    fn substring_search_uses_search_matcher() {
+        let mut hist = FileBackedHistory::default().with_search_matcher(Box::new(FuzzyMatcher));
+        hist.append("git checkout main");
+        hist.append("git commit");
+
+        hist.set_navigation(HistoryNavigationQuery::SubstringSearch("gco".to_string()));
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("git commit".to_string()));
+        hist.back();
+        assert_eq!(
+            hist.string_at_cursor(),
+            Some("git checkout main".to_string())
+        );
+    }
+
+    #[test]
+    fn match_at_cursor_locates_search_string() {
+        let mut hist = FileBackedHistory::default();
+        hist.append("prefix substring");
+        assert_eq!(hist.match_at_cursor(), None);
+
+        hist.set_navigation(HistoryNavigationQuery::SubstringSearch("sub".to_string()));
+        hist.back();
+        let found = hist.match_at_cursor().unwrap();
+        assert_eq!(found.ranges("prefix substring"), vec![7..10]);
+    }
+
     #[test]
     fn substring_search_with_empty_value_returns_none() {
         let mut hist = FileBackedHistory::default();
@@ -473,6 +1234,32 @@ mod tests {
         assert_eq!(hist.string_at_cursor(), None);
     }
 
+    #[test]
+    fn metadata_stays_aligned_with_entries() {
+        let mut hist = FileBackedHistory::new(2);
+        let metadata = |dir: &str| HistoryEntryMetadata {
+            cwd: Some(dir.into()),
+            ..HistoryEntryMetadata::default()
+        };
+        hist.append_with_metadata("first", metadata("/one"));
+        hist.append_with_metadata("second", metadata("/two"));
+        hist.append_with_metadata("second", metadata("/ignored"));
+        hist.append_with_metadata("third", metadata("/three"));
+
+        let actual: Vec<(String, HistoryEntryMetadata)> = hist
+            .iter_chronologic_with_metadata()
+            .map(|(entry, metadata)| (entry.clone(), metadata.clone()))
+            .collect();
+
+        assert_eq!(
+            actual,
+            vec![
+                ("second".to_string(), metadata("/two")),
+                ("third".to_string(), metadata("/three")),
+            ]
+        );
+    }
+
     #[test]
     fn writes_to_new_file() {
         use std::fs::File;
@@ -500,4 +1287,466 @@ mod tests {
 
         tmp.close().unwrap();
     }
+
+    #[test]
+    fn appended_entries_are_tagged_with_session() {
+        let mut hist = FileBackedHistory::default();
+        hist.append("before");
+        assert_eq!(hist.iter_session().count(), 0);
+
+        let session = hist.new_session_id();
+        hist.append("first");
+        hist.append("second");
+
+        assert_eq!(hist.session_id(), Some(session));
+        assert_eq!(
+            hist.iter_session().collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+        assert_eq!(
+            hist.iter_chronologic_with_metadata()
+                .next()
+                .map(|(_, metadata)| metadata.session),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn sync_shares_entries_between_instances() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut first = FileBackedHistory::with_file(5, histfile.clone()).unwrap();
+        let mut second = FileBackedHistory::with_file(5, histfile.clone()).unwrap();
+        first.new_session_id();
+
+        first.append("ls");
+        first.sync().unwrap();
+        second.append("pwd");
+        second.sync().unwrap();
+        first.append("cd");
+        first.sync().unwrap();
+
+        assert_eq!(
+            first.iter_chronologic().collect::<Vec<_>>(),
+            vec!["ls", "pwd", "cd"]
+        );
+        assert_eq!(first.iter_session().collect::<Vec<_>>(), vec!["ls", "cd"]);
+        assert_eq!(
+            second.iter_chronologic().collect::<Vec<_>>(),
+            vec!["ls", "pwd"]
+        );
+
+        drop(first);
+        drop(second);
+        let reloaded = FileBackedHistory::with_file(5, histfile).unwrap();
+        assert_eq!(
+            reloaded.iter_chronologic().collect::<Vec<_>>(),
+            vec!["ls", "pwd", "cd"]
+        );
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn entries_are_replaced_and_deleted_by_id() {
+        let mut hist = FileBackedHistory::default();
+        hist.append("first");
+        hist.append("export TOKEN=secret");
+        hist.append("third");
+        let secret = hist.id_at(1).unwrap();
+        let third = hist.id_at(2).unwrap();
+
+        assert!(hist.replace(secret, "export TOKEN=***").unwrap());
+        assert_eq!(
+            hist.string_by_id(secret),
+            Some("export TOKEN=***".to_string())
+        );
+
+        assert!(hist.delete(secret).unwrap());
+        assert!(!hist.delete(secret).unwrap());
+        assert_eq!(hist.string_by_id(secret), None);
+        assert_eq!(hist.id_at(1), Some(third));
+        assert_eq!(
+            hist.iter_chronologic().collect::<Vec<_>>(),
+            vec!["first", "third"]
+        );
+    }
+
+    #[test]
+    fn deleted_entries_are_removed_from_file() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut first = FileBackedHistory::with_file(5, histfile.clone()).unwrap();
+        let mut second = FileBackedHistory::with_file(5, histfile.clone()).unwrap();
+        first.append("ls");
+        first.append("export TOKEN=secret");
+        first.sync().unwrap();
+        second.append("pwd");
+        second.sync().unwrap();
+
+        let secret = first.id_at(1).unwrap();
+        assert!(first.delete(secret).unwrap());
+
+        let reloaded = FileBackedHistory::with_file(5, histfile).unwrap();
+        assert_eq!(
+            reloaded.iter_chronologic().collect::<Vec<_>>(),
+            vec!["ls", "pwd"]
+        );
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn sync_keeps_capacity() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut first = FileBackedHistory::with_file(2, histfile.clone()).unwrap();
+        let mut second = FileBackedHistory::with_file(2, histfile.clone()).unwrap();
+
+        first.append("a");
+        first.append("b");
+        first.sync().unwrap();
+        second.append("c");
+        second.sync().unwrap();
+
+        assert_eq!(
+            second.iter_chronologic().collect::<Vec<_>>(),
+            vec!["b", "c"]
+        );
+
+        first.append("d");
+        first.sync().unwrap();
+
+        assert_eq!(first.iter_chronologic().collect::<Vec<_>>(), vec!["c", "d"]);
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn sync_keeps_cursor_on_entry() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut first = FileBackedHistory::with_file(5, histfile.clone()).unwrap();
+        let mut second = FileBackedHistory::with_file(5, histfile.clone()).unwrap();
+        first.append("ls");
+        first.sync().unwrap();
+        first.append("cd");
+        first.back();
+        assert_eq!(first.string_at_cursor(), Some("cd".to_string()));
+
+        second.append("pwd");
+        second.sync().unwrap();
+        first.sync().unwrap();
+
+        assert_eq!(
+            first.iter_chronologic().collect::<Vec<_>>(),
+            vec!["ls", "pwd", "cd"]
+        );
+        assert_eq!(first.string_at_cursor(), Some("cd".to_string()));
+        first.back();
+        assert_eq!(first.string_at_cursor(), Some("pwd".to_string()));
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn paged_file_keeps_recent_entries_in_memory() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        std::fs::write(&histfile, "cargo build\nls\ncargo test\npwd\ncd").unwrap();
+
+        let mut hist = FileBackedHistory::with_paged_file(2, histfile.clone()).unwrap();
+        assert_eq!(
+            hist.iter_chronologic().collect::<Vec<_>>(),
+            vec!["pwd", "cd"]
+        );
+
+        let mut browsed = vec![];
+        for _ in 0..6 {
+            hist.back();
+            browsed.push(hist.string_at_cursor().unwrap());
+        }
+        assert_eq!(
+            browsed,
+            vec![
+                "cd",
+                "pwd",
+                "cargo test",
+                "ls",
+                "cargo build",
+                "cargo build"
+            ]
+        );
+
+        hist.forward();
+        assert_eq!(hist.string_at_cursor(), Some("ls".to_string()));
+        assert_eq!(
+            hist.strings_around_cursor(),
+            (
+                Some("cargo build".to_string()),
+                Some("cargo test".to_string())
+            )
+        );
+        hist.forward();
+        hist.forward();
+        assert_eq!(hist.string_at_cursor(), Some("pwd".to_string()));
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn paged_search_walks_the_file_backwards() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        std::fs::write(
+            &histfile,
+            "cargo build\ncargo test\nls\ncargo test\npwd\ncd\n",
+        )
+        .unwrap();
+
+        let mut hist = FileBackedHistory::with_paged_file(2, histfile.clone()).unwrap();
+        hist.set_navigation(HistoryNavigationQuery::SubstringSearch("cargo".to_string()));
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("cargo test".to_string()));
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("cargo build".to_string()));
+        assert_eq!(
+            hist.match_at_cursor().unwrap().ranges("cargo build"),
+            vec![0..5]
+        );
+        hist.forward();
+        assert_eq!(hist.string_at_cursor(), Some("cargo test".to_string()));
+        hist.forward();
+        assert_eq!(hist.string_at_cursor(), None);
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn paged_file_keeps_entries_beyond_capacity() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        std::fs::write(&histfile, "a\nb\nc\n").unwrap();
+
+        let mut hist = FileBackedHistory::with_paged_file(2, histfile.clone()).unwrap();
+        hist.append("d");
+        hist.sync().unwrap();
+        hist.append("e");
+        hist.append("f");
+        hist.sync().unwrap();
+
+        assert_eq!(hist.iter_chronologic().collect::<Vec<_>>(), vec!["e", "f"]);
+        assert_eq!(
+            std::fs::read_to_string(&histfile).unwrap(),
+            "a\nb\nc\nd\ne\nf\n"
+        );
+        hist.set_navigation(HistoryNavigationQuery::PrefixSearch("d".to_string()));
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("d".to_string()));
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn archived_entries_are_replaced_and_deleted() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        std::fs::write(&histfile, "ls\nexport TOKEN=secret\npwd\ncd\n").unwrap();
+
+        let mut hist = FileBackedHistory::with_paged_file(1, histfile.clone()).unwrap();
+        hist.back();
+        hist.back();
+        hist.back();
+        let secret = hist.cursor().unwrap();
+        assert_eq!(
+            hist.string_by_id(secret),
+            Some("export TOKEN=secret".to_string())
+        );
+
+        assert!(hist.replace(secret, "export TOKEN=***").unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&histfile).unwrap(),
+            "ls\nexport TOKEN=***\npwd\ncd\n"
+        );
+        assert!(hist.delete(secret).unwrap());
+        assert_eq!(std::fs::read_to_string(&histfile).unwrap(), "ls\npwd\ncd\n");
+
+        hist.append("echo");
+        hist.sync().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&histfile).unwrap(),
+            "ls\npwd\ncd\necho\n"
+        );
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn ids_stay_valid_when_other_entries_are_deleted() {
+        use tempfile::tempdir;
+
+        let mut hist = FileBackedHistory::new(5);
+        hist.append("a");
+        hist.append("b");
+        hist.append("c");
+        let (a, c) = (hist.id_at(0).unwrap(), hist.id_at(2).unwrap());
+        assert!(hist.delete(hist.id_at(1).unwrap()).unwrap());
+        assert_eq!(hist.string_by_id(a), Some("a".to_string()));
+        assert_eq!(hist.string_by_id(c), Some("c".to_string()));
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        std::fs::write(&histfile, "a\nb\nc\nd\n").unwrap();
+
+        let mut hist = FileBackedHistory::with_paged_file(1, histfile.clone()).unwrap();
+        let d = hist.id_at(0).unwrap();
+        hist.back();
+        assert_eq!(hist.cursor(), Some(d));
+        let ids: Vec<_> = (0..3)
+            .map(|_| {
+                hist.back();
+                hist.cursor().unwrap()
+            })
+            .collect();
+        let (c, b, a) = (ids[0], ids[1], ids[2]);
+
+        assert!(hist.delete(b).unwrap());
+        assert!(hist.replace(a, "a longer a").unwrap());
+        assert_eq!(hist.string_by_id(b), None);
+        assert_eq!(hist.string_by_id(a), Some("a longer a".to_string()));
+        assert_eq!(hist.string_by_id(c), Some("c".to_string()));
+
+        hist.append("e");
+        hist.sync().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&histfile).unwrap(),
+            "a longer a\nc\nd\ne\n"
+        );
+        assert!(hist.delete(c).unwrap());
+        assert_eq!(hist.string_by_id(d), Some("d".to_string()));
+        assert_eq!(hist.string_by_id(a), Some("a longer a".to_string()));
+
+        tmp.close().unwrap();
+    }
+
+    fn with_metadata(hist: &FileBackedHistory) -> Vec<(String, HistoryEntryMetadata)> {
+        hist.iter_chronologic_with_metadata()
+            .map(|(entry, metadata)| (entry.clone(), metadata.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn persisted_metadata_is_read_by_a_reopened_history() {
+        use std::time::{Duration, UNIX_EPOCH};
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        let metadata = HistoryEntryMetadata {
+            cwd: Some("/home/user/a;b".into()),
+            session: Some(HistorySessionId::new(-42)),
+            timestamp: Some(UNIX_EPOCH + Duration::new(1_625_000_000, 123_456_789)),
+        };
+        let expected = vec![
+            ("git status".to_string(), metadata.clone()),
+            ("plain".to_string(), HistoryEntryMetadata::default()),
+            ("echo 'a\nb'".to_string(), metadata.clone()),
+        ];
+
+        let mut hist = FileBackedHistory::with_file(5, histfile.clone())
+            .unwrap()
+            .with_persisted_metadata(true);
+        for (entry, metadata) in &expected {
+            hist.append_with_metadata(entry, metadata.clone());
+        }
+        drop(hist);
+
+        assert_eq!(
+            std::fs::read_to_string(&histfile).unwrap().lines().nth(1),
+            Some("plain")
+        );
+        let reopened = FileBackedHistory::with_file(5, histfile.clone()).unwrap();
+        assert_eq!(with_metadata(&reopened), expected);
+        let reopened = FileBackedHistory::with_paged_file(2, histfile).unwrap();
+        assert_eq!(with_metadata(&reopened), expected[1..].to_vec());
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn sync_picks_up_the_metadata_of_other_instances() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        let mut first = FileBackedHistory::with_file(5, histfile.clone())
+            .unwrap()
+            .with_persisted_metadata(true);
+        let mut second = FileBackedHistory::with_file(5, histfile).unwrap();
+        let session = first.new_session_id();
+        second.new_session_id();
+        let metadata = HistoryEntryMetadata {
+            cwd: Some("/tmp".into()),
+            session: Some(session),
+            timestamp: None,
+        };
+
+        first.append_with_metadata("ls", metadata.clone());
+        first.sync().unwrap();
+        second.append("pwd");
+        second.sync().unwrap();
+
+        assert_eq!(
+            with_metadata(&second)[0],
+            ("ls".to_string(), metadata.clone())
+        );
+        assert_eq!(second.iter_session().collect::<Vec<_>>(), vec!["pwd"]);
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn archived_entries_are_read_without_their_metadata() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        std::fs::write(&histfile, "<\\m>;7;/tmp<\\m>ls\npwd\n").unwrap();
+
+        let mut hist = FileBackedHistory::with_paged_file(1, histfile.clone())
+            .unwrap()
+            .with_persisted_metadata(true);
+        hist.back();
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("ls".to_string()));
+
+        let ls = hist.cursor().unwrap();
+        assert!(hist.replace(ls, "ls -a").unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&histfile).unwrap(),
+            "<\\m>;7;/tmp<\\m>ls -a\npwd\n"
+        );
+
+        tmp.close().unwrap();
+    }
 }
@@ -0,0 +1,331 @@
+use {
+    super::HistoryEntryMetadata,
+    std::time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// History file formats of other shells, to move a history between them and a
+/// [`History`](super::History)
+///
+/// Timestamps are read into and written from [`HistoryEntryMetadata::timestamp`] where the
+/// format has them, other metadata is not part of these formats. Entries spanning several
+/// lines are kept together as far as the format allows.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{FileBackedHistory, History, HistoryFormat};
+///
+/// let bash_history = b"#1625000000\ngit status\n#1625000060\ncargo test\n";
+///
+/// let mut history = FileBackedHistory::default();
+/// history.import(HistoryFormat::Bash, bash_history);
+///
+/// assert_eq!(history.export(HistoryFormat::Bash), bash_history.to_vec());
+/// assert_eq!(
+///     history.export(HistoryFormat::Zsh),
+///     b": 1625000000:0;git status\n: 1625000060:0;cargo test\n".to_vec()
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// `~/.bash_history`, one command per line, preceded by a `#<seconds>` line if bash
+    /// records timestamps (`HISTTIMEFORMAT`)
+    Bash,
+    /// `~/.zsh_history`, plain lines or the extended format `: <seconds>:<duration>;<command>`
+    /// with lines of multiline commands ending in `\`
+    Zsh,
+    /// `~/.local/share/fish/fish_history`, entries of `- cmd: <command>` and `when: <seconds>`
+    Fish,
+}
+
+impl HistoryFormat {
+    /// The entries stored in `data`, in chronologic order
+    pub fn parse(&self, data: &[u8]) -> Vec<(String, HistoryEntryMetadata)> {
+        match self {
+            HistoryFormat::Bash => parse_bash(data),
+            HistoryFormat::Zsh => parse_zsh(data),
+            HistoryFormat::Fish => parse_fish(data),
+        }
+    }
+
+    /// The history file content storing `entries`
+    pub fn write<'a>(
+        &self,
+        entries: impl IntoIterator<Item = (&'a str, &'a HistoryEntryMetadata)>,
+    ) -> Vec<u8> {
+        let mut data = vec![];
+        for (entry, metadata) in entries {
+            let seconds = metadata.timestamp.map(unix_seconds);
+            match self {
+                HistoryFormat::Bash => {
+                    if let Some(seconds) = seconds {
+                        data.extend(format!("#{}\n", seconds).bytes());
+                    }
+                    data.extend(entry.bytes());
+                }
+                HistoryFormat::Zsh => {
+                    if let Some(seconds) = seconds {
+                        data.extend(format!(": {}:0;", seconds).bytes());
+                    }
+                    data.extend(metafy(&entry.replace('\n', "\\\n")));
+                }
+                HistoryFormat::Fish => {
+                    data.extend(format!("- cmd: {}", escape_fish(entry)).bytes());
+                    if let Some(seconds) = seconds {
+                        data.extend(format!("\n  when: {}", seconds).bytes());
+                    }
+                }
+            }
+            data.push(b'\n');
+        }
+
+        data
+    }
+}
+
+fn metadata_at(seconds: Option<u64>) -> HistoryEntryMetadata {
+    HistoryEntryMetadata {
+        timestamp: seconds.map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds)),
+        ..HistoryEntryMetadata::default()
+    }
+}
+
+fn unix_seconds(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Lines of a history file, also the ones not in UTF-8
+fn lines(data: &[u8]) -> impl Iterator<Item = String> + '_ {
+    // No line follows the final newline
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+    data.split(|byte| *byte == b'\n').map(|line| {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        String::from_utf8_lossy(line).into_owned()
+    })
+}
+
+/// Entries separated by `#<seconds>` lines keep all their lines, otherwise every line is one
+fn parse_bash(data: &[u8]) -> Vec<(String, HistoryEntryMetadata)> {
+    let timestamp = |line: &str| line.strip_prefix('#').and_then(|s| s.parse::<u64>().ok());
+
+    let mut entries: Vec<(String, HistoryEntryMetadata)> = vec![];
+    let mut entry_has_timestamp = false;
+    for line in lines(data) {
+        if let Some(seconds) = timestamp(&line) {
+            entries.push((String::new(), metadata_at(Some(seconds))));
+            entry_has_timestamp = true;
+        } else {
+            match entries.last_mut() {
+                Some((entry, _)) if entry_has_timestamp && !entry.is_empty() => {
+                    entry.push('\n');
+                    entry.push_str(&line);
+                }
+                Some((entry, _)) if entry_has_timestamp => entry.push_str(&line),
+                _ => entries.push((line, metadata_at(None))),
+            }
+        }
+    }
+
+    entries.retain(|(entry, _)| !entry.is_empty());
+    entries
+}
+
+fn parse_zsh(data: &[u8]) -> Vec<(String, HistoryEntryMetadata)> {
+    let data = unmetafy(data);
+    let mut entries: Vec<(String, HistoryEntryMetadata)> = vec![];
+    let mut continued = false;
+    for line in lines(&data) {
+        let (line, continues) = match line.strip_suffix('\\') {
+            Some(line) => (line.to_string(), true),
+            None => (line, false),
+        };
+
+        match entries.last_mut() {
+            Some((entry, _)) if continued => {
+                entry.push('\n');
+                entry.push_str(&line);
+            }
+            _ => {
+                let (seconds, command) = split_zsh_extended(&line);
+                entries.push((command.to_string(), metadata_at(seconds)));
+            }
+        }
+        continued = continues;
+    }
+
+    entries.retain(|(entry, _)| !entry.is_empty());
+    entries
+}
+
+/// The timestamp and command of a line in the extended format `: <seconds>:<duration>;<command>`
+fn split_zsh_extended(line: &str) -> (Option<u64>, &str) {
+    let extended = line.strip_prefix(": ").and_then(|rest| {
+        let (header, command) = rest.split_once(';')?;
+        let (seconds, _duration) = header.split_once(':')?;
+        Some((seconds.parse().ok()?, command))
+    });
+
+    match extended {
+        Some((seconds, command)) => (Some(seconds), command),
+        None => (None, line),
+    }
+}
+
+/// zsh stores the bytes it uses as tokens as the byte 0x83 followed by the byte xor 0x20
+fn unmetafy(data: &[u8]) -> Vec<u8> {
+    let mut bytes = data.iter();
+    let mut unmetafied = Vec::with_capacity(data.len());
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            0x83 => unmetafied.extend(bytes.next().map(|byte| byte ^ 0x20)),
+            _ => unmetafied.push(byte),
+        }
+    }
+    unmetafied
+}
+
+fn metafy(entry: &str) -> Vec<u8> {
+    let mut metafied = Vec::with_capacity(entry.len());
+    for &byte in entry.as_bytes() {
+        if (0x83..=0xa2).contains(&byte) {
+            metafied.extend([0x83, byte ^ 0x20]);
+        } else {
+            metafied.push(byte);
+        }
+    }
+    metafied
+}
+
+fn parse_fish(data: &[u8]) -> Vec<(String, HistoryEntryMetadata)> {
+    let mut entries: Vec<(String, HistoryEntryMetadata)> = vec![];
+    for line in lines(data) {
+        if let Some(command) = line.strip_prefix("- cmd: ") {
+            entries.push((unescape_fish(command), metadata_at(None)));
+        } else if let Some(seconds) = line.trim_start().strip_prefix("when: ") {
+            if let Some((_, metadata)) = entries.last_mut() {
+                *metadata = metadata_at(seconds.trim().parse().ok());
+            }
+        }
+    }
+
+    entries
+}
+
+/// fish writes backslashes as `\\` and newlines as `\n`
+fn escape_fish(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape_fish(command: &str) -> String {
+    let mut unescaped = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn at(seconds: u64) -> HistoryEntryMetadata {
+        metadata_at(Some(seconds))
+    }
+
+    fn commands(entries: &[(String, HistoryEntryMetadata)]) -> Vec<&str> {
+        entries.iter().map(|(entry, _)| entry.as_str()).collect()
+    }
+
+    #[test]
+    fn bash_lines_are_entries() {
+        let entries = HistoryFormat::Bash.parse(b"ls -la\n\ncd /tmp\n");
+
+        assert_eq!(commands(&entries), vec!["ls -la", "cd /tmp"]);
+        assert_eq!(entries[0].1.timestamp, None);
+    }
+
+    #[test]
+    fn bash_timestamps_group_lines() {
+        let entries =
+            HistoryFormat::Bash.parse(b"#100\nfor i in 1 2\ndo echo $i; done\n#160\nls\n");
+
+        assert_eq!(
+            entries,
+            vec![
+                ("for i in 1 2\ndo echo $i; done".to_string(), at(100)),
+                ("ls".to_string(), at(160)),
+            ]
+        );
+    }
+
+    #[test]
+    fn zsh_extended_lines_carry_timestamps() {
+        let entries =
+            HistoryFormat::Zsh.parse(b": 100:0;git status\nls\n: 160:3;echo a\\\necho b\n");
+
+        assert_eq!(
+            entries,
+            vec![
+                ("git status".to_string(), at(100)),
+                ("ls".to_string(), metadata_at(None)),
+                ("echo a\necho b".to_string(), at(160)),
+            ]
+        );
+    }
+
+    #[test]
+    fn zsh_metafied_bytes_are_decoded() {
+        let entry = "echo grüße";
+        let metafied = metafy(entry);
+        assert_ne!(metafied, entry.as_bytes());
+
+        let entries = HistoryFormat::Zsh.parse(&metafied);
+        assert_eq!(commands(&entries), vec![entry]);
+    }
+
+    #[test]
+    fn fish_entries_are_unescaped() {
+        let data = b"- cmd: echo a\\nb \\\\n\n  when: 100\n  paths:\n    - /tmp\n- cmd: ls\n";
+        let entries = HistoryFormat::Fish.parse(data);
+
+        assert_eq!(
+            entries,
+            vec![
+                ("echo a\nb \\n".to_string(), at(100)),
+                ("ls".to_string(), metadata_at(None)),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(HistoryFormat::Bash)]
+    #[case(HistoryFormat::Zsh)]
+    #[case(HistoryFormat::Fish)]
+    fn written_entries_are_parsed_back(#[case] format: HistoryFormat) {
+        let entries = vec![
+            ("git commit -m \"grüße\"".to_string(), at(100)),
+            ("echo a\\\necho b".to_string(), at(160)),
+        ];
+        let written = format.write(
+            entries
+                .iter()
+                .map(|(entry, metadata)| (entry.as_str(), metadata)),
+        );
+
+        assert_eq!(format.parse(&written), entries);
+    }
+}
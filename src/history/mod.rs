@@ -1,5 +1,12 @@
 mod base;
+mod expansion;
 mod file_backed;
+mod interop;
+mod paging;
 
-pub use base::{History, HistoryNavigationQuery};
+pub use base::{
+    History, HistoryEntryMetadata, HistoryItemId, HistoryNavigationQuery, HistorySessionId,
+};
+pub use expansion::{expand_history, HistoryExpansionError};
 pub use file_backed::{FileBackedHistory, HISTORY_SIZE};
+pub use interop::HistoryFormat;
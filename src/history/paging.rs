@@ -0,0 +1,245 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Bytes read from the file at once when walking it backwards
+const PAGE_SIZE: u64 = 64 * 1024;
+
+/// The lines of a file in front of a byte offset, from the last one to the first one
+///
+/// Yields each line with the offset of its first byte, without its newline. Only the pages
+/// holding the lines walked over are read, the memory use does not depend on the file size.
+pub(super) struct BackwardLines {
+    file: File,
+    // Offset in the file of the first byte in `pending`
+    start: u64,
+    // Bytes from `start` to the end of the lines not yet yielded, ending in a newline
+    pending: Vec<u8>,
+}
+
+impl BackwardLines {
+    /// Walk the lines of the file at `path` starting before byte offset `end`
+    ///
+    /// `end` is expected at the start of a line or at the end of the file, a last line without
+    /// newline is yielded as well.
+    pub(super) fn new(path: &Path, end: u64) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut pending = vec![];
+        if end > 0 {
+            let mut last = [0];
+            file.seek(SeekFrom::Start(end - 1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                pending.push(b'\n');
+            }
+        }
+
+        Ok(BackwardLines {
+            file,
+            start: end,
+            pending,
+        })
+    }
+
+    /// Prepend the page in front of `start` to `pending`
+    fn read_page(&mut self) -> io::Result<()> {
+        let page_start = self.start.saturating_sub(PAGE_SIZE);
+        let mut page = vec![0; (self.start - page_start) as usize];
+        self.file.seek(SeekFrom::Start(page_start))?;
+        self.file.read_exact(&mut page)?;
+
+        page.append(&mut self.pending);
+        self.pending = page;
+        self.start = page_start;
+        Ok(())
+    }
+}
+
+impl Iterator for BackwardLines {
+    type Item = io::Result<(u64, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(newline) = self.pending.len().checked_sub(1) {
+                // A line is complete once the newline in front of it or the file start is read
+                let line_start = match self.pending[..newline].iter().rposition(|b| *b == b'\n') {
+                    Some(previous_newline) => Some(previous_newline + 1),
+                    None if self.start == 0 => Some(0),
+                    None => None,
+                };
+                if let Some(line_start) = line_start {
+                    let line =
+                        String::from_utf8_lossy(&self.pending[line_start..newline]).into_owned();
+                    self.pending.truncate(line_start);
+                    return Some(Ok((self.start + line_start as u64, line)));
+                }
+            }
+
+            if self.start == 0 {
+                return None;
+            }
+            if let Err(err) = self.read_page() {
+                // Don't retry the failed page
+                self.start = 0;
+                self.pending.clear();
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+/// The lines of the file at `path` from byte offset `start` up to `end`, in file order with
+/// the offset of their first byte
+pub(super) fn lines_between(
+    path: &Path,
+    start: u64,
+    end: u64,
+) -> io::Result<impl Iterator<Item = io::Result<(u64, String)>>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::new(file.take(end.saturating_sub(start)));
+    let mut offset = start;
+
+    Ok(std::iter::from_fn(move || {
+        let mut line = vec![];
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => None,
+            Ok(len) => {
+                let line_offset = offset;
+                offset += len as u64;
+                if line.last() == Some(&b'\n') {
+                    line.pop();
+                }
+                Some(Ok((
+                    line_offset,
+                    String::from_utf8_lossy(&line).into_owned(),
+                )))
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }))
+}
+
+/// The line starting at byte offset `offset` and the offset following its newline
+pub(super) fn line_at(path: &Path, offset: u64) -> io::Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut line = vec![];
+    let len = BufReader::new(file).read_until(b'\n', &mut line)?;
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+
+    Ok((
+        String::from_utf8_lossy(&line).into_owned(),
+        offset + len as u64,
+    ))
+}
+
+/// Replace the bytes from `start` up to `end` of the file at `path` by `replacement`
+///
+/// The file is copied to a temporary file next to it, which then replaces it. An `end` past
+/// the end of the file replaces everything from `start` on.
+pub(super) fn splice(path: &Path, start: u64, end: u64, replacement: &[u8]) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    let mut original = File::open(path)?;
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    io::copy(&mut Read::by_ref(&mut original).take(start), &mut writer)?;
+    writer.write_all(replacement)?;
+    original.seek(SeekFrom::Start(end.min(original.metadata()?.len())))?;
+    io::copy(&mut original, &mut writer)?;
+    writer.flush()?;
+    drop(writer);
+
+    fs::rename(&temporary, path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn lines_of(path: &Path, end: u64) -> Vec<(u64, String)> {
+        BackwardLines::new(path, end)
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn lines_are_walked_backwards_with_offsets() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("lines");
+        std::fs::write(&path, "ab\n\ncd\nlast").unwrap();
+
+        assert_eq!(
+            lines_of(&path, 11),
+            vec![
+                (7, "last".to_string()),
+                (4, "cd".to_string()),
+                (3, String::new()),
+                (0, "ab".to_string()),
+            ]
+        );
+        assert_eq!(
+            lines_of(&path, 4),
+            vec![(3, String::new()), (0, "ab".to_string())]
+        );
+        assert_eq!(lines_of(&path, 0), vec![]);
+    }
+
+    #[test]
+    fn lines_spanning_pages_are_joined() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("lines");
+        let long = "x".repeat(PAGE_SIZE as usize + 10);
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "first").unwrap();
+        writeln!(file, "{}", long).unwrap();
+        writeln!(file, "last").unwrap();
+        let len = file.metadata().unwrap().len();
+
+        let lines: Vec<String> = lines_of(&path, len)
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect();
+        assert_eq!(lines, vec!["last".to_string(), long, "first".to_string()]);
+    }
+
+    #[test]
+    fn lines_between_offsets_are_read_forward() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("lines");
+        std::fs::write(&path, "ab\ncd\nef\n").unwrap();
+
+        let lines: Vec<(u64, String)> = lines_between(&path, 3, 9)
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(lines, vec![(3, "cd".to_string()), (6, "ef".to_string())]);
+        assert_eq!(line_at(&path, 3).unwrap(), ("cd".to_string(), 6));
+    }
+
+    #[test]
+    fn splice_replaces_a_range() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("lines");
+        std::fs::write(&path, "ab\ncd\nef\n").unwrap();
+
+        splice(&path, 3, 6, b"replaced\n").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "ab\nreplaced\nef\n"
+        );
+
+        splice(&path, 3, u64::MAX, b"").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "ab\n");
+    }
+}
@@ -23,13 +23,10 @@
 //!          Ok(Signal::Success(buffer)) => {
 //!              println!("We processed: {}", buffer);
 //!          }
-//!          Ok(Signal::CtrlD) | Ok(Signal::CtrlC) => {
+//!          Ok(Signal::CtrlD) | Ok(Signal::CtrlC { .. }) => {
 //!              println!("\nAborted!");
 //!              break;
 //!          }
-//!          Ok(Signal::CtrlL) => {
-//!              line_editor.clear_screen();
-//!          }
 //!          x => {
 //!              println!("Event: {:?}", x);
 //!          }
@@ -135,22 +132,13 @@
 //! use std::io;
 //! use {
 //!   nu_ansi_term::{Color, Style},
-//!   reedline::{DefaultCompleter, DefaultHinter, Reedline},
+//!   reedline::{HistoryHinter, Reedline},
 //! };
 //!
-//! let commands = vec![
-//!   "test".into(),
-//!   "hello world".into(),
-//!   "hello world reedline".into(),
-//!   "this is the reedline crate".into(),
-//! ];
-//! let completer = Box::new(DefaultCompleter::new_with_wordlen(commands.clone(), 2));
-//!
+//! // Shows the rest of the most recent matching history entry,
+//! // `DefaultHinter::with_completer()` hints from a completer instead
 //! let mut line_editor = Reedline::create()?.with_hinter(Box::new(
-//!   DefaultHinter::default()
-//!   .with_completer(completer) // or .with_history()
-//!   // .with_inside_line()
-//!   .with_style(Style::new().italic().fg(Color::LightGray)),
+//!   HistoryHinter::default().with_style(Style::new().italic().fg(Color::LightGray)),
 //! ));
 //! # Ok::<(), io::Error>(())
 //! ```
@@ -187,44 +175,99 @@
 #![warn(missing_docs)]
 // #![deny(warnings)]
 mod core_editor;
-pub use core_editor::LineBuffer;
+pub use core_editor::{LineBuffer, WordBoundary};
 
 mod text_manipulation;
+pub use text_manipulation::{ByteOffset, DisplayWidth, TabRendering};
 
 mod enums;
-pub use enums::{EditCommand, ReedlineEvent, Signal, UndoBehavior};
+pub use enums::{
+    CtrlCBehavior, CtrlDBehavior, EditCommand, InterruptBehavior, ReedlineEvent, Signal,
+    UndoBehavior,
+};
 
 mod painter;
+pub use painter::MultilineGutter;
+
+mod markup;
+pub use markup::ansi_to_markup;
+
+mod widget;
+pub use widget::{Widget, WidgetArea};
+
+mod configuration;
+pub use configuration::{ConfigurationIssue, IssueSeverity};
 
 mod engine;
-pub use engine::Reedline;
+pub use engine::{Reedline, SuspendGuard};
 
 mod history;
-pub use history::{FileBackedHistory, History, HistoryNavigationQuery, HISTORY_SIZE};
+pub use history::{
+    expand_history, FileBackedHistory, History, HistoryEntryMetadata, HistoryExpansionError,
+    HistoryFormat, HistoryItemId, HistoryNavigationQuery, HistorySessionId, HISTORY_SIZE,
+};
 
 mod prompt;
 pub use prompt::{
     DefaultPrompt, Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus,
-    PromptViMode, DEFAULT_PROMPT_COLOR, DEFAULT_PROMPT_INDICATOR,
+    PromptSegments, PromptViMode, DEFAULT_PROMPT_COLOR, DEFAULT_PROMPT_INDICATOR,
 };
 
 mod edit_mode;
-pub use edit_mode::{default_emacs_keybindings, EditMode, Emacs, Vi};
+pub use edit_mode::{
+    default_emacs_keybindings, legacy_edit_commands, CustomEditMode, EditMode, Emacs,
+    KeyCombination, KeyNotationError, Keybindings, LegacyEditCommand, Vi,
+};
 
 mod highlighter;
-pub use highlighter::{DefaultHighlighter, Highlighter};
+pub use highlighter::{
+    DefaultHighlighter, Highlighter, LayeredHighlighter, MatchingBracketHighlighter,
+    RangeHighlighter,
+};
+
+#[cfg(feature = "tree-sitter")]
+mod tree_sitter_highlighter;
+#[cfg(feature = "tree-sitter")]
+pub use tree_sitter_highlighter::TreeSitterHighlighter;
 
 mod styled_text;
 pub use styled_text::StyledText;
 
 mod completion;
 pub use completion::{
-    CircularCompletionHandler, Completer, CompletionActionHandler, DefaultCompleter,
-    ListCompletionHandler, Span,
+    AsyncSuggestions, CachedCompleter, CircularCompletionHandler, CombinedCompleter, Completer,
+    CompletionActionHandler, DefaultCompleter, HistoryCompleter, ListCompletionHandler, Span,
+    Suggestion, SuggestionSorter,
 };
 
 mod hinter;
-pub use hinter::{DefaultHinter, Hinter};
+pub use hinter::{
+    CombinedHinter, CwdAwareHinter, DefaultHinter, HintDisplay, Hinter, HistoryHinter,
+    NextTokenHinter,
+};
+
+mod matcher;
+pub use matcher::{ExactMatcher, FuzzyMatcher, Match, Matcher, PrefixMatcher, RegexMatcher};
+
+mod menu;
+pub use menu::{CommandPalette, Menu, MultiSelectMenu, QuickInsertMenu, QuickInserts};
+
+mod abbreviations;
+pub use abbreviations::Abbreviations;
+
+mod normalizer;
+pub use normalizer::{collapse_whitespace, trim_trailing_whitespace, BufferNormalizer};
+
+mod paste;
+pub use paste::PasteSanitization;
+
+mod external_printer;
+pub use external_printer::ExternalPrinter;
 
 mod validator;
 pub use validator::{DefaultValidator, ValidationResult, Validator};
+
+#[cfg(feature = "demo")]
+mod demo;
+#[cfg(feature = "demo")]
+pub use demo::demo;
@@ -7,8 +7,8 @@ use {
     },
     nu_ansi_term::{Color, Style},
     reedline::{
-        default_emacs_keybindings, DefaultCompleter, DefaultHighlighter, DefaultHinter,
-        DefaultPrompt, EditCommand, FileBackedHistory, Reedline, ReedlineEvent, Signal,
+        default_emacs_keybindings, DefaultCompleter, DefaultHighlighter, DefaultPrompt,
+        EditCommand, FileBackedHistory, HistoryHinter, Reedline, ReedlineEvent, Signal,
     },
     std::{
         io::{stdout, Write},
@@ -27,6 +27,11 @@ fn main() -> Result<()> {
         println!();
         return Ok(());
     };
+    // with the `demo` feature, --demo runs the keybinding tutorial
+    #[cfg(feature = "demo")]
+    if args.len() > 1 && args[1] == "--demo" {
+        return reedline::demo();
+    }
 
     let history = Box::new(FileBackedHistory::with_file(50, "history.txt".into())?);
     let commands = vec![
@@ -40,7 +45,9 @@ fn main() -> Result<()> {
         "this is the reedline crate".into(),
     ];
 
-    let completer = Box::new(DefaultCompleter::new_with_wordlen(commands.clone(), 2));
+    let completer = Box::new(
+        DefaultCompleter::new_with_wordlen(commands.clone(), 2).with_append_whitespace(true),
+    );
 
     let edit_mode: Box<dyn EditMode> = if vi_mode {
         Box::new(Vi::default())
@@ -62,13 +69,10 @@ fn main() -> Result<()> {
             ListCompletionHandler::default().with_completer(completer),
         ))
         .with_hinter(Box::new(
-            DefaultHinter::default()
-                .with_history()
-                // .with_completer(completer) // or .with_history()
-                // .with_inside_line()
-                .with_style(Style::new().fg(Color::DarkGray)),
+            HistoryHinter::default().with_style(Style::new().fg(Color::DarkGray)),
         ))
         .with_ansi_colors(true);
+    line_editor.new_history_session();
 
     let prompt = DefaultPrompt::new(1);
 
@@ -80,6 +84,7 @@ fn main() -> Result<()> {
                 break;
             }
             Ok(Signal::Success(buffer)) => {
+                line_editor.sync_history()?;
                 if (buffer.trim() == "exit") || (buffer.trim() == "logout") {
                     break;
                 }
@@ -93,11 +98,11 @@ fn main() -> Result<()> {
                 }
                 line_editor.print_line(&format!("Our buffer: {}", buffer))?;
             }
-            Ok(Signal::CtrlC) => {
+            Ok(Signal::CtrlC { .. }) => {
                 // Prompt has been cleared and should start on the next line
             }
-            Ok(Signal::CtrlL) => {
-                line_editor.clear_screen()?;
+            Ok(Signal::Timeout) => {
+                // Only returned by `read_line_with_timeout()`
             }
             Err(err) => {
                 println!("Error: {:?}", err);
@@ -131,7 +136,10 @@ fn print_events_helper() -> Result<()> {
             // It's guaranteed that read() wont block if `poll` returns `Ok(true)`
             let event = crossterm::event::read()?;
 
-            if let Event::Key(KeyEvent { code, modifiers }) = event {
+            if let Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) = event
+            {
                 match code {
                     KeyCode::Char(c) => {
                         println!(
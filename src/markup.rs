@@ -0,0 +1,212 @@
+use std::io::{self, Write};
+
+/// Replace the ANSI color and style sequences (SGR) of `text` by a readable markup
+///
+/// Every sequence becomes a tag listing its parts, e.g. `\x1b[1;31m` becomes `[bold fg=red]`
+/// and the reset `\x1b[0m` becomes `[/]`. Colors are written by name, as `fg=208` for the
+/// 256 color palette and as `fg=#ff8700` for true colors, `bg=` sets the background. Other
+/// escape sequences and the text are kept as they are.
+///
+/// Makes styled output comparable in tests and readable in logs, independent of the
+/// capabilities of the terminal. [`Reedline::with_color_markup()`](crate::Reedline::with_color_markup)
+/// paints the editor with it.
+///
+/// ## Example
+/// ```rust
+/// use nu_ansi_term::Color;
+/// use reedline::ansi_to_markup;
+///
+/// let painted = Color::Red.bold().paint("error").to_string();
+/// assert_eq!(ansi_to_markup(&painted), "[bold fg=red]error[/]");
+/// ```
+pub fn ansi_to_markup(text: &str) -> String {
+    let mut markup = MarkupWriter::new(Vec::with_capacity(text.len()));
+    markup.set_enabled(true);
+    // Writing to a `Vec` does not fail and the text ends with a complete sequence
+    let _ = markup.write_all(text.as_bytes());
+    let _ = markup.flush();
+    String::from_utf8_lossy(&markup.inner).into_owned()
+}
+
+/// Writer converting the color and style sequences written to it into markup, see
+/// [`ansi_to_markup()`]
+///
+/// Passes everything through unchanged until enabled. Sequences may arrive split across
+/// writes, the start of an incomplete sequence is held back until its end is written.
+pub(crate) struct MarkupWriter<W: Write> {
+    inner: W,
+    enabled: bool,
+    // Start of an escape sequence whose final byte was not written yet
+    pending: Vec<u8>,
+}
+
+impl<W: Write> MarkupWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        MarkupWriter {
+            inner,
+            enabled: false,
+            pending: vec![],
+        }
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    #[cfg(test)]
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// The sequence in `pending` is complete once it ends in its final byte
+    fn is_pending_complete(&self) -> bool {
+        match self.pending.as_slice() {
+            [] | [0x1b] => false,
+            [0x1b, b'['] => false,
+            [0x1b, b'[', .., last] => (0x40..=0x7e).contains(last),
+            // A single character escape like `ESC 7`
+            _ => true,
+        }
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        let sequence = std::mem::take(&mut self.pending);
+        match sequence.as_slice() {
+            [0x1b, b'[', params @ .., b'm'] => {
+                let params = String::from_utf8_lossy(params);
+                write!(self.inner, "[{}]", sgr_markup(&params))
+            }
+            _ => self.inner.write_all(&sequence),
+        }
+    }
+}
+
+impl<W: Write> Write for MarkupWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.enabled {
+            return self.inner.write(buf);
+        }
+
+        let mut text_start = 0;
+        for (idx, &byte) in buf.iter().enumerate() {
+            if !self.pending.is_empty() {
+                self.pending.push(byte);
+                if self.is_pending_complete() {
+                    self.write_pending()?;
+                }
+                text_start = idx + 1;
+            } else if byte == 0x1b {
+                self.inner.write_all(&buf[text_start..idx])?;
+                self.pending.push(byte);
+                text_start = idx + 1;
+            }
+        }
+        self.inner.write_all(&buf[text_start..])?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The tag content of the SGR parameters `params`, like `bold fg=red`
+fn sgr_markup(params: &str) -> String {
+    let mut params = params.split(';').map(|param| param.parse::<u16>().ok());
+    let mut parts = vec![];
+    while let Some(param) = params.next() {
+        let part = match param {
+            None | Some(0) => "/".to_string(),
+            Some(1) => "bold".to_string(),
+            Some(2) => "dim".to_string(),
+            Some(3) => "italic".to_string(),
+            Some(4) => "underline".to_string(),
+            Some(5) => "blink".to_string(),
+            Some(7) => "reverse".to_string(),
+            Some(8) => "hidden".to_string(),
+            Some(9) => "strikethrough".to_string(),
+            Some(22) => "/bold".to_string(),
+            Some(23) => "/italic".to_string(),
+            Some(24) => "/underline".to_string(),
+            Some(25) => "/blink".to_string(),
+            Some(27) => "/reverse".to_string(),
+            Some(28) => "/hidden".to_string(),
+            Some(29) => "/strikethrough".to_string(),
+            Some(39) => "/fg".to_string(),
+            Some(49) => "/bg".to_string(),
+            Some(code @ 30..=37) => format!("fg={}", COLOR_NAMES[(code - 30) as usize]),
+            Some(code @ 40..=47) => format!("bg={}", COLOR_NAMES[(code - 40) as usize]),
+            Some(code @ 90..=97) => format!("fg=light_{}", COLOR_NAMES[(code - 90) as usize]),
+            Some(code @ 100..=107) => format!("bg=light_{}", COLOR_NAMES[(code - 100) as usize]),
+            Some(code @ 38) | Some(code @ 48) => {
+                let layer = if code == 38 { "fg" } else { "bg" };
+                match params.next().flatten() {
+                    Some(5) => format!("{}={}", layer, params.next().flatten().unwrap_or(0)),
+                    Some(2) => {
+                        let mut channel = || params.next().flatten().unwrap_or(0);
+                        let (r, g, b) = (channel(), channel(), channel());
+                        format!("{}=#{:02x}{:02x}{:02x}", layer, r, g, b)
+                    }
+                    _ => format!("{}=?", layer),
+                }
+            }
+            Some(code) => format!("sgr={}", code),
+        };
+        parts.push(part);
+    }
+
+    parts.join(" ")
+}
+
+/// Names of the 8 basic colors in the order of their codes
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "purple", "cyan", "white",
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nu_ansi_term::{Color, Style};
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Style::new().fg(Color::Red), "[fg=red]text[/]")]
+    #[case(Style::new().fg(Color::LightBlue).on(Color::Black), "[bg=black fg=light_blue]text[/]")]
+    #[case(Style::new().fg(Color::Fixed(208)), "[fg=208]text[/]")]
+    #[case(Style::new().on(Color::Rgb(255, 135, 0)), "[bg=#ff8700]text[/]")]
+    #[case(Style::new().bold().italic().underline(), "[bold italic underline]text[/]")]
+    #[case(Style::new().dimmed().reverse(), "[dim reverse]text[/]")]
+    #[case(Style::new(), "text")]
+    fn styles_are_written_as_markup(#[case] style: Style, #[case] expected: &str) {
+        assert_eq!(ansi_to_markup(&style.paint("text").to_string()), expected);
+    }
+
+    #[test]
+    fn other_sequences_are_kept() {
+        assert_eq!(
+            ansi_to_markup("\x1b[2;4Ha\x1b[Kb\x1b7\x1b[39m"),
+            "\x1b[2;4Ha\x1b[Kb\x1b7[/fg]"
+        );
+    }
+
+    #[test]
+    fn sequences_split_across_writes_are_converted() {
+        let mut writer = MarkupWriter::new(vec![]);
+        writer.set_enabled(true);
+        for part in ["ab\x1b", "[3", "1mc", "\x1b[0", "m"] {
+            writer.write_all(part.as_bytes()).unwrap();
+        }
+
+        assert_eq!(writer.inner, b"ab[fg=red]c[/]");
+    }
+
+    #[test]
+    fn disabled_writer_passes_sequences_through() {
+        let mut writer = MarkupWriter::new(vec![]);
+        writer.write_all(b"\x1b[31mc").unwrap();
+
+        assert_eq!(writer.inner, b"\x1b[31mc");
+    }
+}
@@ -0,0 +1,137 @@
+use {
+    nu_ansi_term::Style,
+    std::{fmt, ops::Range},
+};
+
+/// Where a pattern matched a candidate
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// Rank among the matches of the same pattern, lower is better
+    pub score: usize,
+
+    /// Byte offsets of the matched characters in the candidate, in ascending order
+    pub indices: Vec<usize>,
+}
+
+impl Match {
+    /// Byte offset right after the last matched character, `0` if no character was matched
+    pub fn end(&self, candidate: &str) -> usize {
+        self.indices
+            .last()
+            .map(|index| index + candidate[*index..].chars().next().map_or(0, char::len_utf8))
+            .unwrap_or(0)
+    }
+
+    /// Byte ranges of the runs of adjacent matched characters in `candidate`, in ascending order
+    pub fn ranges(&self, candidate: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = vec![];
+        for &index in &self.indices {
+            let end = index + candidate[index..].chars().next().map_or(0, char::len_utf8);
+            match ranges.last_mut() {
+                Some(last) if last.end == index => last.end = end,
+                _ => ranges.push(index..end),
+            }
+        }
+
+        ranges
+    }
+
+    /// Paint `candidate` with the matched characters in `match_style` and the rest in `style`
+    pub fn paint(&self, candidate: &str, style: Style, match_style: Style) -> String {
+        let paint_run = |matched: bool, run: &str| {
+            if matched {
+                match_style.paint(run).to_string()
+            } else {
+                style.paint(run).to_string()
+            }
+        };
+
+        let mut painted = String::new();
+        let mut run_start = 0;
+        let mut run_matched = false;
+        for (offset, _) in candidate.char_indices() {
+            let matched = self.indices.binary_search(&offset).is_ok();
+            if matched != run_matched {
+                if offset > run_start {
+                    painted.push_str(&paint_run(run_matched, &candidate[run_start..offset]));
+                }
+                run_start = offset;
+                run_matched = matched;
+            }
+        }
+        if run_start < candidate.len() {
+            painted.push_str(&paint_run(run_matched, &candidate[run_start..]));
+        }
+
+        painted
+    }
+}
+
+/// The strategy deciding if a typed pattern matches a candidate
+///
+/// Shared by the filter of [`CommandPalette`](crate::CommandPalette), the substring search
+/// of [`FileBackedHistory`](crate::FileBackedHistory) and [`HistoryHinter`](crate::HistoryHinter).
+/// The returned [`Match`] lists the matched characters, so every UI can highlight them the same
+/// way with [`Match::paint()`].
+///
+/// An empty pattern is expected to match every candidate.
+pub trait Matcher: Send {
+    /// Match `pattern` against `candidate`, `None` if it does not match
+    fn find_match(&self, candidate: &str, pattern: &str) -> Option<Match>;
+}
+
+impl fmt::Debug for dyn Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Matcher")
+    }
+}
+
+/// Byte offsets of all characters in `candidate[start..end]`
+pub(crate) fn char_indices_between(candidate: &str, start: usize, end: usize) -> Vec<usize> {
+    candidate[start..end]
+        .char_indices()
+        .map(|(offset, _)| start + offset)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn end_is_after_last_matched_character() {
+        let found = Match {
+            score: 0,
+            indices: vec![0, 1],
+        };
+
+        assert_eq!(found.end("añb"), 3);
+        assert_eq!(Match::default().end("añb"), 0);
+    }
+
+    #[test]
+    fn ranges_join_adjacent_characters() {
+        let found = Match {
+            score: 0,
+            indices: vec![0, 1, 4],
+        };
+
+        assert_eq!(found.ranges("añbcd"), vec![0..3, 4..5]);
+        assert!(Match::default().ranges("abc").is_empty());
+    }
+
+    #[test]
+    fn paint_groups_matched_runs() {
+        let found = Match {
+            score: 0,
+            indices: vec![1, 2],
+        };
+        let bold = Style::new().bold();
+
+        assert_eq!(
+            found.paint("abcd", Style::new(), bold),
+            format!("a{}d", bold.paint("bc"))
+        );
+    }
+}
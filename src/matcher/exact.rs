@@ -0,0 +1,72 @@
+use super::{
+    base::{char_indices_between, Match},
+    Matcher,
+};
+
+/// Matches candidates containing the pattern exactly as typed
+///
+/// Earlier occurrences score better.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExactMatcher;
+
+impl Matcher for ExactMatcher {
+    fn find_match(&self, candidate: &str, pattern: &str) -> Option<Match> {
+        candidate.find(pattern).map(|start| Match {
+            score: start,
+            indices: char_indices_between(candidate, start, start + pattern.len()),
+        })
+    }
+}
+
+/// Matches candidates starting with the pattern exactly as typed
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PrefixMatcher;
+
+impl Matcher for PrefixMatcher {
+    fn find_match(&self, candidate: &str, pattern: &str) -> Option<Match> {
+        if candidate.starts_with(pattern) {
+            Some(Match {
+                score: 0,
+                indices: char_indices_between(candidate, 0, pattern.len()),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn exact_matches_substring() {
+        assert_eq!(
+            ExactMatcher.find_match("git checkout", "ch"),
+            Some(Match {
+                score: 4,
+                indices: vec![4, 5]
+            })
+        );
+        assert_eq!(ExactMatcher.find_match("git checkout", "Ch"), None);
+    }
+
+    #[test]
+    fn prefix_matches_start_only() {
+        assert_eq!(
+            PrefixMatcher.find_match("über", "üb"),
+            Some(Match {
+                score: 0,
+                indices: vec![0, 2]
+            })
+        );
+        assert_eq!(PrefixMatcher.find_match("git checkout", "ch"), None);
+    }
+
+    #[test]
+    fn empty_pattern_matches_everything() {
+        assert_eq!(ExactMatcher.find_match("ls", ""), Some(Match::default()));
+        assert_eq!(PrefixMatcher.find_match("ls", ""), Some(Match::default()));
+    }
+}
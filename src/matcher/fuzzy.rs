@@ -0,0 +1,66 @@
+use super::{base::Match, Matcher};
+
+/// Matches candidates containing the characters of the pattern in the same order
+///
+/// Ignores case. Every character skipped before or between the matched characters adds one
+/// to the score, so `mwl` prefers "Move word left" over "Move to start of line".
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FuzzyMatcher;
+
+impl Matcher for FuzzyMatcher {
+    fn find_match(&self, candidate: &str, pattern: &str) -> Option<Match> {
+        let mut candidate_chars = candidate.char_indices().enumerate();
+        let mut score = 0;
+        let mut last_position = None;
+        let mut indices = vec![];
+
+        for pattern_char in pattern.chars() {
+            let (position, (offset, _)) = candidate_chars
+                .find(|(_, (_, c))| c.to_lowercase().eq(pattern_char.to_lowercase()))?;
+            score += match last_position {
+                Some(last) => position - last - 1,
+                None => position,
+            };
+            last_position = Some(position);
+            indices.push(offset);
+        }
+
+        Some(Match { score, indices })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("Undo", "", Some(0))]
+    #[case("Undo", "undo", Some(0))]
+    #[case("Undo", "UD", Some(1))]
+    #[case("Move word left", "mwl", Some(8))]
+    #[case("Undo", "odu", None)]
+    fn score_counts_skipped_characters(
+        #[case] candidate: &str,
+        #[case] pattern: &str,
+        #[case] expected: Option<usize>,
+    ) {
+        assert_eq!(
+            FuzzyMatcher
+                .find_match(candidate, pattern)
+                .map(|found| found.score),
+            expected
+        );
+    }
+
+    #[test]
+    fn indices_are_byte_offsets() {
+        assert_eq!(
+            FuzzyMatcher
+                .find_match("Größe ändern", "gä")
+                .map(|found| found.indices),
+            Some(vec![0, 8])
+        );
+    }
+}
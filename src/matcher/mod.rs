@@ -0,0 +1,9 @@
+mod base;
+mod exact;
+mod fuzzy;
+mod regex;
+
+pub use self::regex::RegexMatcher;
+pub use base::{Match, Matcher};
+pub use exact::{ExactMatcher, PrefixMatcher};
+pub use fuzzy::FuzzyMatcher;
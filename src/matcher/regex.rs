@@ -0,0 +1,69 @@
+use {
+    super::{
+        base::{char_indices_between, Match},
+        Matcher,
+    },
+    ::regex::Regex,
+    std::sync::Mutex,
+};
+
+/// Matches candidates with the pattern as a regular expression
+///
+/// The first match in the candidate counts, earlier matches score better.
+/// Invalid expressions match nothing, e.g. while a group is still being typed.
+#[derive(Debug, Default)]
+pub struct RegexMatcher {
+    // The pattern is compiled once and reused while it stays the same
+    compiled: Mutex<Option<(String, Option<Regex>)>>,
+}
+
+impl Matcher for RegexMatcher {
+    fn find_match(&self, candidate: &str, pattern: &str) -> Option<Match> {
+        let mut compiled = self
+            .compiled
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let is_current =
+            matches!(&*compiled, Some((compiled_pattern, _)) if compiled_pattern == pattern);
+        if !is_current {
+            *compiled = Some((pattern.to_string(), Regex::new(pattern).ok()));
+        }
+
+        let regex = compiled.as_ref().and_then(|(_, regex)| regex.as_ref())?;
+        regex.find(candidate).map(|found| Match {
+            score: found.start(),
+            indices: char_indices_between(candidate, found.start(), found.end()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn matches_expression() {
+        let matcher = RegexMatcher::default();
+
+        assert_eq!(
+            matcher.find_match("cargo test --release", "t[a-z]+t"),
+            Some(Match {
+                score: 6,
+                indices: vec![6, 7, 8, 9]
+            })
+        );
+        assert_eq!(matcher.find_match("cargo build", "t[a-z]+t"), None);
+    }
+
+    #[test]
+    fn invalid_expression_matches_nothing() {
+        let matcher = RegexMatcher::default();
+
+        assert_eq!(matcher.find_match("(a)", "(a"), None);
+        assert_eq!(
+            matcher.find_match("(a)", "a").map(|found| found.indices),
+            Some(vec![1])
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{EditCommand, Keybindings, LineBuffer, ReedlineEvent};
+
+/// A menu shown below the buffer to pick one of several values
+///
+/// Menus are registered with [`Reedline::with_menu()`](crate::Reedline::with_menu) and opened
+/// by [`ReedlineEvent::Menu`] with their name. While a menu is open the selection is moved by
+/// [`ReedlineEvent::MenuNext`] and [`ReedlineEvent::MenuPrevious`] (also the arrow keys and `Tab`)
+/// and [`ReedlineEvent::MenuAccept`] (also `Enter`) applies the selected value.
+/// [`ReedlineEvent::MenuPageNext`] and [`ReedlineEvent::MenuPagePrevious`] (`PageDown` and
+/// `PageUp` by default) turn the pages of menus listing more values than fit.
+/// [`ReedlineEvent::MenuSelect`] (`Alt+1` to `Alt+9` by default) applies a listed value by its
+/// position, if the menu implements [`Menu::select_visible()`].
+/// `Ctrl+C` closes the menu without changes.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{EditCommand, Keybindings, LineBuffer, Menu, Reedline, ReedlineEvent};
+///
+/// /// Inserts one of a few snippets at the cursor
+/// struct SnippetMenu {
+///     snippets: Vec<&'static str>,
+///     selected: usize,
+/// }
+///
+/// impl Menu for SnippetMenu {
+///     fn name(&self) -> &str {
+///         "snippets"
+///     }
+///
+///     fn activate(&mut self, _line_buffer: &LineBuffer, _keybindings: Option<&Keybindings>) {
+///         self.selected = 0;
+///     }
+///
+///     fn update_values(&mut self, _line_buffer: &LineBuffer) {}
+///
+///     fn move_next(&mut self) {
+///         self.selected = (self.selected + 1) % self.snippets.len();
+///     }
+///
+///     fn move_previous(&mut self) {
+///         self.selected = (self.selected + self.snippets.len() - 1) % self.snippets.len();
+///     }
+///
+///     fn menu_string(&self, _columns: usize, _use_ansi_coloring: bool) -> String {
+///         self.snippets
+///             .iter()
+///             .enumerate()
+///             .map(|(i, snippet)| {
+///                 let marker = if i == self.selected { "> " } else { "  " };
+///                 format!("{}{}", marker, snippet)
+///             })
+///             .collect::<Vec<_>>()
+///             .join("\n")
+///     }
+///
+///     fn replace_in_buffer(&self, line_buffer: &mut LineBuffer) {
+///         line_buffer.insert_str(self.snippets[self.selected]);
+///     }
+/// }
+///
+/// let menu = SnippetMenu {
+///     snippets: vec!["for i in 0..10 {}", "if true {}"],
+///     selected: 0,
+/// };
+/// let mut line_editor = Reedline::create()?.with_menu(Box::new(menu));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub trait Menu {
+    /// The name used to open the menu with [`ReedlineEvent::Menu`]
+    fn name(&self) -> &str;
+
+    /// Called when the menu is opened, e.g. to reset the selection
+    ///
+    /// `keybindings` are the ones of the current edit mode, if it has a keybinding table.
+    fn activate(&mut self, line_buffer: &LineBuffer, keybindings: Option<&Keybindings>);
+
+    /// Update the values after the buffer changed while the menu is open
+    fn update_values(&mut self, line_buffer: &LineBuffer);
+
+    /// Take the values that arrived since the last call, for menus loading them in the
+    /// background
+    ///
+    /// Called regularly while [`Menu::is_loading()`], returns `true` if the menu has to be
+    /// repainted. The default loads nothing in the background.
+    fn poll_values(&mut self) -> bool {
+        false
+    }
+
+    /// Whether values are still being loaded, e.g. from an asynchronous
+    /// [`Completer`](crate::Completer)
+    fn is_loading(&self) -> bool {
+        false
+    }
+
+    /// Apply an edit typed while the menu is open to the menu itself, e.g. to a filter
+    ///
+    /// Returns `false` if the edit should change the buffer instead, which is followed by
+    /// [`Menu::update_values()`]. The default leaves all edits to the buffer.
+    fn handle_edit(&mut self, _command: &EditCommand) -> bool {
+        false
+    }
+
+    /// Select the next value
+    fn move_next(&mut self);
+
+    /// Select the previous value
+    fn move_previous(&mut self);
+
+    /// Show the next page of values, for menus listing them in pages
+    ///
+    /// The default selects the next value.
+    fn next_page(&mut self) {
+        self.move_next();
+    }
+
+    /// Show the previous page of values, for menus listing them in pages
+    ///
+    /// The default selects the previous value.
+    fn previous_page(&mut self) {
+        self.move_previous();
+    }
+
+    /// Select the value shown at `index` among the listed rows, counting from 0
+    ///
+    /// Used to accept a value right away by its position. Returns `false` if no value is
+    /// shown there, the default does not support selecting by position.
+    fn select_visible(&mut self, _index: usize) -> bool {
+        false
+    }
+
+    /// The rows to show below the buffer, separated by `\n`
+    ///
+    /// Rows should fit into `columns`.
+    fn menu_string(&self, columns: usize, use_ansi_coloring: bool) -> String;
+
+    /// Apply the selected value to the buffer when it is accepted
+    fn replace_in_buffer(&self, line_buffer: &mut LineBuffer);
+
+    /// Details of the selected value, shown in a row below the menu
+    ///
+    /// E.g. the [`Suggestion::preview`](crate::Suggestion::preview) of a completion, the
+    /// default shows none.
+    fn preview(&self) -> Option<String> {
+        None
+    }
+
+    /// An event to run after the selected value was accepted and the menu closed
+    ///
+    /// Lets menus trigger editor actions, the default runs none.
+    fn accept_event(&self) -> Option<ReedlineEvent> {
+        None
+    }
+}
@@ -0,0 +1,495 @@
+use {
+    super::{Menu, QuickInsertMenu},
+    crate::{
+        enums::{EditCommand, ReedlineEvent},
+        FuzzyMatcher, Keybindings, LineBuffer, Match, Matcher,
+    },
+    nu_ansi_term::Style,
+};
+
+/// An action offered by the command palette
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PaletteAction {
+    name: String,
+    // Notation of the keys the event is bound to, empty if unbound
+    keys: String,
+    event: ReedlineEvent,
+}
+
+/// Menu listing the actions of the editor, filtered by the typed text
+///
+/// Accepting an entry runs its event as if its keys were pressed, which helps to discover
+/// features without knowing their bindings. Registered by default under [`CommandPalette::NAME`],
+/// the Emacs mode opens it with `Alt+x`.
+///
+/// Actions are filtered with a [`FuzzyMatcher`] unless configured otherwise, the matched
+/// characters of the names are highlighted.
+pub struct CommandPalette {
+    actions: Vec<PaletteAction>,
+    filter: String,
+    matcher: Box<dyn Matcher>,
+    match_style: Style,
+    // Indices of the actions matching the filter with the matched characters, best match first
+    matches: Vec<(usize, Match)>,
+    selected: usize,
+    max_rows: usize,
+}
+
+impl Default for CommandPalette {
+    fn default() -> Self {
+        CommandPalette {
+            actions: vec![],
+            filter: String::new(),
+            matcher: Box::new(FuzzyMatcher),
+            match_style: Style::new().bold(),
+            matches: vec![],
+            selected: 0,
+            max_rows: 8,
+        }
+    }
+}
+
+impl Menu for CommandPalette {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    /// Collect the built-in actions and everything bound in `keybindings`
+    fn activate(&mut self, _line_buffer: &LineBuffer, keybindings: Option<&Keybindings>) {
+        let mut actions: Vec<PaletteAction> = builtin_actions()
+            .into_iter()
+            .map(|(name, event)| PaletteAction {
+                name: name.to_string(),
+                keys: String::new(),
+                event,
+            })
+            .collect();
+
+        if let Some(keybindings) = keybindings {
+            // Custom bindings to events without a name of their own,
+            // navigating a menu makes no sense from within the palette
+            let mut unnamed: Vec<ReedlineEvent> = keybindings
+                .bindings
+                .values()
+                .filter(|event| match event {
+                    ReedlineEvent::Menu(name) => name != Self::NAME,
+                    ReedlineEvent::MenuNext
+                    | ReedlineEvent::MenuPrevious
+                    | ReedlineEvent::MenuPageNext
+                    | ReedlineEvent::MenuPagePrevious
+                    | ReedlineEvent::MenuAccept
+                    | ReedlineEvent::MenuSelect(_) => false,
+                    event => !actions.iter().any(|action| action.event == **event),
+                })
+                .cloned()
+                .collect();
+            unnamed.sort_by_cached_key(|event| format!("{:?}", event));
+            unnamed.dedup();
+            actions.extend(unnamed.into_iter().map(|event| PaletteAction {
+                name: format!("{:?}", event),
+                keys: String::new(),
+                event,
+            }));
+
+            for action in &mut actions {
+                action.keys = keybindings
+                    .find_keys(&action.event)
+                    .iter()
+                    .map(|key_combo| key_combo.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+            }
+        }
+
+        self.actions = actions;
+        self.filter.clear();
+        self.update_matches();
+    }
+
+    /// The palette does not depend on the buffer
+    fn update_values(&mut self, _line_buffer: &LineBuffer) {}
+
+    /// Edits change the filter text
+    ///
+    /// Only simple insertions and deletions are supported, other commands are ignored.
+    fn handle_edit(&mut self, command: &EditCommand) -> bool {
+        match command {
+            EditCommand::InsertChar(c) => self.filter.push(*c),
+            EditCommand::InsertString(s) => self.filter.push_str(s),
+            EditCommand::Backspace => {
+                self.filter.pop();
+            }
+            EditCommand::Clear | EditCommand::CutFromStart | EditCommand::CutCurrentLine => {
+                self.filter.clear()
+            }
+            _ => return true,
+        }
+        self.update_matches();
+        true
+    }
+
+    /// Select the next matching action, wrapping around to the first one
+    fn move_next(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + 1) % self.matches.len();
+        }
+    }
+
+    /// Select the previous matching action, wrapping around to the last one
+    fn move_previous(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.matches.len() - 1);
+        }
+    }
+
+    /// Move the selection down by the listed rows, stopping at the last match
+    fn next_page(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + self.max_rows).min(self.matches.len() - 1);
+        }
+    }
+
+    /// Move the selection up by the listed rows, stopping at the first match
+    fn previous_page(&mut self) {
+        self.selected = self.selected.saturating_sub(self.max_rows);
+    }
+
+    /// Select one of the listed actions, the rows below the filter
+    fn select_visible(&mut self, index: usize) -> bool {
+        let selected = self.first_visible() + index;
+        if index < self.max_rows && selected < self.matches.len() {
+            self.selected = selected;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Rows showing the filter and the matching actions around the selection
+    fn menu_string(&self, columns: usize, use_ansi_coloring: bool) -> String {
+        let mut rows = vec![truncate(
+            &format!("(command palette): {}", self.filter),
+            columns,
+        )];
+
+        if self.matches.is_empty() {
+            rows.push(truncate("(no matching action)", columns));
+            return rows.join("\n");
+        }
+
+        let first = self.first_visible();
+        let shown = &self.matches[first..self.matches.len().min(first + self.max_rows)];
+        let name_width = shown
+            .iter()
+            .map(|(index, _)| self.actions[*index].name.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        for (offset, (index, found)) in shown.iter().enumerate() {
+            let action = &self.actions[*index];
+            let is_selected = first + offset == self.selected;
+            let marker = if is_selected && !use_ansi_coloring {
+                "> "
+            } else {
+                "  "
+            };
+            let row = truncate(
+                format!(
+                    "{}{:<width$}  {}",
+                    marker,
+                    action.name,
+                    action.keys,
+                    width = name_width
+                )
+                .trim_end(),
+                columns,
+            );
+
+            rows.push(if use_ansi_coloring {
+                // The name follows the marker
+                let found = Match {
+                    score: found.score,
+                    indices: found.indices.iter().map(|i| i + marker.len()).collect(),
+                };
+                if is_selected {
+                    found.paint(&row, Style::new().reverse(), self.match_style.reverse())
+                } else {
+                    found.paint(&row, Style::new(), self.match_style)
+                }
+            } else {
+                row
+            });
+        }
+
+        rows.join("\n")
+    }
+
+    /// Actions are run as events, the buffer is left to them
+    fn replace_in_buffer(&self, _line_buffer: &mut LineBuffer) {}
+
+    /// The event of the selected action, if any action matches
+    fn accept_event(&self) -> Option<ReedlineEvent> {
+        self.matches
+            .get(self.selected)
+            .map(|(index, _)| self.actions[*index].event.clone())
+    }
+}
+
+impl CommandPalette {
+    /// The name the palette is registered under
+    pub const NAME: &'static str = "command_palette";
+
+    /// A builder that sets the number of actions listed at once
+    pub fn with_max_rows(mut self, max_rows: usize) -> CommandPalette {
+        self.max_rows = max_rows.max(1);
+        self
+    }
+
+    /// A builder that sets how the typed text is matched against the action names
+    pub fn with_matcher(mut self, matcher: Box<dyn Matcher>) -> CommandPalette {
+        self.matcher = matcher;
+        self
+    }
+
+    /// A builder that sets the style of the matched characters in the action names
+    pub fn with_match_style(mut self, match_style: Style) -> CommandPalette {
+        self.match_style = match_style;
+        self
+    }
+
+    /// Index into the matches of the first listed action, the list scrolls with the selection
+    fn first_visible(&self) -> usize {
+        (self.selected + 1).saturating_sub(self.max_rows)
+    }
+
+    fn update_matches(&mut self) {
+        let matcher = &self.matcher;
+        let filter = &self.filter;
+        let mut matches: Vec<(usize, Match)> = self
+            .actions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, action)| {
+                matcher
+                    .find_match(&action.name, filter)
+                    .map(|found| (index, found))
+            })
+            .collect();
+        // Stable sort, equally good matches stay in catalog order
+        matches.sort_by_key(|(_, found)| found.score);
+
+        self.matches = matches;
+        self.selected = 0;
+    }
+}
+
+fn truncate(row: &str, columns: usize) -> String {
+    row.chars().take(columns).collect()
+}
+
+/// The actions available in every edit mode, with a readable name
+fn builtin_actions() -> Vec<(&'static str, ReedlineEvent)> {
+    use EditCommand as EC;
+
+    let edit = |command| ReedlineEvent::Edit(vec![command]);
+
+    vec![
+        ("Move to start of buffer", edit(EC::MoveToStart)),
+        ("Move to end of buffer", edit(EC::MoveToEnd)),
+        ("Move to start of line", edit(EC::MoveToLineStart)),
+        ("Move to end of line", edit(EC::MoveToLineEnd)),
+        ("Move left", edit(EC::MoveLeft)),
+        ("Move right", edit(EC::MoveRight)),
+        ("Move word left", edit(EC::MoveWordLeft)),
+        ("Move word right", edit(EC::MoveWordRight)),
+        ("Move up", ReedlineEvent::Up),
+        ("Move down", ReedlineEvent::Down),
+        ("Jump back", edit(EC::JumpBack)),
+        ("Jump forward", edit(EC::JumpForward)),
+        ("Delete character", edit(EC::Delete)),
+        ("Delete character before cursor", edit(EC::Backspace)),
+        ("Delete word", edit(EC::DeleteWord)),
+        ("Delete word before cursor", edit(EC::BackspaceWord)),
+        ("Clear buffer", edit(EC::Clear)),
+        ("Clear to end of line", edit(EC::ClearToLineEnd)),
+        ("Cut current line", edit(EC::CutCurrentLine)),
+        ("Cut from start of buffer", edit(EC::CutFromStart)),
+        ("Cut from start of line", edit(EC::CutFromLineStart)),
+        ("Cut to end of buffer", edit(EC::CutToEnd)),
+        ("Cut to end of line", edit(EC::CutToLineEnd)),
+        ("Cut word left", edit(EC::CutWordLeft)),
+        ("Cut word right", edit(EC::CutWordRight)),
+        ("Set selection anchor", edit(EC::SetAnchor)),
+        (
+            "Swap cursor and selection anchor",
+            edit(EC::SwapCursorAndAnchor),
+        ),
+        ("Cut selection", edit(EC::CutSelection)),
+        ("Copy selection", edit(EC::CopySelection)),
+        ("Paste before cursor", edit(EC::PasteCutBufferBefore)),
+        ("Paste after cursor", edit(EC::PasteCutBufferAfter)),
+        ("Uppercase word", edit(EC::UppercaseWord)),
+        ("Lowercase word", edit(EC::LowercaseWord)),
+        ("Capitalize character", edit(EC::CapitalizeChar)),
+        ("Swap words", edit(EC::SwapWords)),
+        ("Swap characters", edit(EC::SwapGraphemes)),
+        ("Undo", edit(EC::Undo)),
+        ("Redo", edit(EC::Redo)),
+        ("Previous history entry", ReedlineEvent::PreviousHistory),
+        ("Next history entry", ReedlineEvent::NextHistory),
+        ("Search history", ReedlineEvent::SearchHistory),
+        (
+            "Search history forward",
+            ReedlineEvent::SearchHistoryForward,
+        ),
+        ("Search and replace", ReedlineEvent::SearchReplace),
+        ("Complete", ReedlineEvent::HandleTab),
+        ("Clear screen", ReedlineEvent::ClearScreen),
+        ("Redraw line", ReedlineEvent::Redraw),
+        ("Submit", ReedlineEvent::Enter),
+        ("Insert newline", ReedlineEvent::InsertNewline),
+        (
+            "Quick insert",
+            ReedlineEvent::Menu(QuickInsertMenu::NAME.into()),
+        ),
+        ("Abort line", ReedlineEvent::CtrlC),
+        ("End input", ReedlineEvent::CtrlD),
+        ("Suspend", ReedlineEvent::CtrlZ),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PrefixMatcher;
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use pretty_assertions::assert_eq;
+
+    fn palette_with_filter(filter: &str) -> CommandPalette {
+        let mut palette = CommandPalette::default();
+        palette.activate(&LineBuffer::new(), None);
+        palette.handle_edit(&EditCommand::InsertString(filter.to_string()));
+        palette
+    }
+
+    #[test]
+    fn filter_ranks_closest_match_first() {
+        let palette = palette_with_filter("redo");
+
+        assert_eq!(
+            palette.accept_event(),
+            Some(ReedlineEvent::Edit(vec![EditCommand::Redo]))
+        );
+    }
+
+    #[test]
+    fn filter_uses_configured_matcher() {
+        let mut palette = CommandPalette::default().with_matcher(Box::new(PrefixMatcher));
+        palette.activate(&LineBuffer::new(), None);
+        palette.handle_edit(&EditCommand::InsertString("Redo".to_string()));
+
+        assert_eq!(
+            palette.accept_event(),
+            Some(ReedlineEvent::Edit(vec![EditCommand::Redo]))
+        );
+        assert_eq!(palette.matches.len(), 1);
+    }
+
+    #[test]
+    fn highlights_matched_characters() {
+        let palette = palette_with_filter("redo").with_match_style(Style::new().underline());
+
+        assert_eq!(
+            palette.menu_string(80, true).lines().nth(1),
+            Some(
+                Match {
+                    score: 0,
+                    indices: vec![2, 3, 4, 5]
+                }
+                .paint(
+                    "  Redo",
+                    Style::new().reverse(),
+                    Style::new().underline().reverse()
+                )
+                .as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut palette = palette_with_filter("history");
+        assert_eq!(palette.matches.len(), 4);
+
+        palette.move_previous();
+        assert_eq!(palette.selected, 3);
+        palette.move_next();
+        assert_eq!(palette.selected, 0);
+    }
+
+    #[test]
+    fn no_match_has_no_event() {
+        let palette = palette_with_filter("xyzzy");
+
+        assert_eq!(palette.accept_event(), None);
+        assert_eq!(
+            palette.menu_string(80, false),
+            "(command palette): xyzzy\n(no matching action)"
+        );
+    }
+
+    #[test]
+    fn shows_bound_keys_and_custom_bindings() {
+        let mut keybindings = Keybindings::new();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('z'),
+            ReedlineEvent::Edit(vec![EditCommand::Undo]),
+        );
+        keybindings.add_binding(
+            KeyModifiers::ALT,
+            KeyCode::Char('i'),
+            ReedlineEvent::Edit(vec![EditCommand::InsertString("if".to_string())]),
+        );
+        let mut palette = CommandPalette::default();
+        palette.activate(&LineBuffer::new(), Some(&keybindings));
+        let keys_of = |name: &str| {
+            palette
+                .actions
+                .iter()
+                .find(|action| action.name == name)
+                .map(|action| action.keys.as_str())
+        };
+
+        assert_eq!(keys_of("Undo"), Some("Ctrl-z"));
+        assert_eq!(keys_of("Redo"), Some(""));
+        assert_eq!(keys_of("Edit([InsertString(\"if\")])"), Some("Alt-i"));
+    }
+
+    #[test]
+    fn render_scrolls_to_selection() {
+        let mut palette = palette_with_filter("move").with_max_rows(2);
+        palette.move_next();
+        palette.move_next();
+
+        assert_eq!(
+            palette.menu_string(80, false),
+            "(command palette): move\n  Move to end of buffer\n> Move to start of line"
+        );
+    }
+
+    #[test]
+    fn select_visible_picks_listed_rows_only() {
+        let mut palette = palette_with_filter("move").with_max_rows(2);
+        palette.move_next();
+        palette.move_next();
+
+        assert!(palette.select_visible(0));
+        assert_eq!(palette.selected, 1);
+        assert!(!palette.select_visible(2));
+        assert_eq!(palette.selected, 1);
+    }
+}
@@ -0,0 +1,9 @@
+mod base;
+mod command_palette;
+mod multi_select;
+mod quick_insert;
+
+pub use base::Menu;
+pub use command_palette::CommandPalette;
+pub use multi_select::MultiSelectMenu;
+pub use quick_insert::{QuickInsertMenu, QuickInserts};
@@ -0,0 +1,602 @@
+use {
+    super::Menu,
+    crate::{
+        AsyncSuggestions, Completer, EditCommand, Keybindings, LineBuffer, Span, Suggestion,
+        SuggestionSorter,
+    },
+    nu_ansi_term::Style,
+    std::sync::mpsc::{Receiver, TryRecvError},
+};
+
+/// Menu listing the completions at the cursor to insert several of them at once, e.g. the
+/// files passed to a command accepting multiple arguments
+///
+/// `Space` marks or unmarks the selected completion. Accepting the menu replaces the completed
+/// text with all marked values in the order they are listed, joined by the separator. Without
+/// marks the selected completion is inserted like by a single choice menu. Other typed text
+/// edits the buffer and updates the completions, marks of values that are no longer completed
+/// are dropped.
+///
+/// More completions than fit into [`MultiSelectMenu::with_max_rows()`] are listed in pages
+/// with a row like `12/40 (Tab for more)` below them. Moving the selection past the end of a
+/// page turns to the next one, `PageDown` and `PageUp` turn a whole page. The selection stays
+/// on its value while typing updates the completions, so does the page.
+///
+/// Completions are asked for with [`Completer::complete_async()`], a completer answering
+/// later is shown as a loading row until its suggestions arrive.
+///
+/// The menu is not registered by default, register it with
+/// [`Reedline::with_menu()`](crate::Reedline::with_menu) and bind a key to
+/// [`ReedlineEvent::Menu`](crate::ReedlineEvent::Menu) with its name.
+///
+/// ## Example
+/// ```rust
+/// use crossterm::event::{KeyCode, KeyModifiers};
+/// use reedline::{
+///     default_emacs_keybindings, DefaultCompleter, Emacs, MultiSelectMenu, Reedline,
+///     ReedlineEvent,
+/// };
+///
+/// let completer = DefaultCompleter::new(vec!["src/".into(), "tests/".into(), "benches/".into()]);
+/// let menu = MultiSelectMenu::new(Box::new(completer)).with_separator(" ");
+///
+/// let mut keybindings = default_emacs_keybindings();
+/// keybindings.add_binding(
+///     KeyModifiers::ALT,
+///     KeyCode::Char('m'),
+///     ReedlineEvent::Menu(MultiSelectMenu::DEFAULT_NAME.into()),
+/// );
+///
+/// let line_editor = Reedline::create()?
+///     .with_menu(Box::new(menu))
+///     .with_edit_mode(Box::new(Emacs::new(keybindings)));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct MultiSelectMenu {
+    name: String,
+    completer: Box<dyn Completer>,
+    sorter: Option<SuggestionSorter>,
+    separator: String,
+    // Completions of the current buffer
+    values: Vec<Suggestion>,
+    selected: usize,
+    max_rows: usize,
+    // Completions not known yet and the value to select once they arrive
+    pending: Option<(Receiver<Vec<Suggestion>>, Option<String>)>,
+    // Marked values in the order they were marked
+    marked: Vec<String>,
+}
+
+impl Menu for MultiSelectMenu {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Complete the buffer and clear the marks
+    fn activate(&mut self, line_buffer: &LineBuffer, _keybindings: Option<&Keybindings>) {
+        self.marked.clear();
+        self.update_values(line_buffer);
+    }
+
+    /// Complete the buffer again, keeping the marks and the selection of values that are still
+    /// completed
+    fn update_values(&mut self, line_buffer: &LineBuffer) {
+        let selected = match self.pending.take() {
+            Some((_, selected)) => selected,
+            None => self
+                .values
+                .get(self.selected)
+                .map(|suggestion| suggestion.value.clone()),
+        };
+        match self
+            .completer
+            .complete_async(line_buffer.get_buffer(), line_buffer.offset())
+        {
+            AsyncSuggestions::Ready(values) => self.set_values(values, selected),
+            AsyncSuggestions::Pending(receiver) => {
+                self.values.clear();
+                self.selected = 0;
+                self.pending = Some((receiver, selected));
+            }
+        }
+    }
+
+    fn poll_values(&mut self) -> bool {
+        let received = match &self.pending {
+            Some((receiver, _)) => receiver.try_recv(),
+            None => return false,
+        };
+        match received {
+            Ok(values) => {
+                let selected = self.pending.take().and_then(|(_, selected)| selected);
+                self.set_values(values, selected);
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => {
+                self.pending = None;
+                true
+            }
+        }
+    }
+
+    fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// `Space` toggles the mark of the selected value instead of editing the buffer
+    fn handle_edit(&mut self, command: &EditCommand) -> bool {
+        if *command != EditCommand::InsertChar(' ') {
+            return false;
+        }
+
+        if let Some(suggestion) = self.values.get(self.selected) {
+            match self
+                .marked
+                .iter()
+                .position(|marked| *marked == suggestion.value)
+            {
+                Some(index) => {
+                    self.marked.remove(index);
+                }
+                None => self.marked.push(suggestion.value.clone()),
+            }
+        }
+        true
+    }
+
+    /// Select the next value, wrapping around to the first one
+    fn move_next(&mut self) {
+        if !self.values.is_empty() {
+            self.selected = (self.selected + 1) % self.values.len();
+        }
+    }
+
+    /// Select the previous value, wrapping around to the last one
+    fn move_previous(&mut self) {
+        if !self.values.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.values.len() - 1);
+        }
+    }
+
+    /// Select the first value of the next page, wrapping around to the first page
+    fn next_page(&mut self) {
+        if !self.values.is_empty() {
+            let next = self.page_start() + self.max_rows;
+            self.selected = if next < self.values.len() { next } else { 0 };
+        }
+    }
+
+    /// Select the first value of the previous page, wrapping around to the last page
+    fn previous_page(&mut self) {
+        if !self.values.is_empty() {
+            self.selected = match self.page_start().checked_sub(self.max_rows) {
+                Some(previous) => previous,
+                None => (self.values.len() - 1) / self.max_rows * self.max_rows,
+            };
+        }
+    }
+
+    /// Select one of the values on the current page
+    fn select_visible(&mut self, index: usize) -> bool {
+        let selected = self.page_start() + index;
+        if index < self.max_rows && selected < self.values.len() {
+            self.selected = selected;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// One row per value of the current page with its mark and description
+    fn menu_string(&self, columns: usize, use_ansi_coloring: bool) -> String {
+        if self.pending.is_some() {
+            return truncate("(loading…)", columns);
+        }
+        if self.values.is_empty() {
+            return truncate("(no completions)", columns);
+        }
+
+        let value_width = self
+            .values
+            .iter()
+            .map(|suggestion| suggestion.value.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        let first = self.page_start();
+        let mut rows: Vec<String> = self
+            .values
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(self.max_rows)
+            .map(|(index, suggestion)| {
+                let is_selected = index == self.selected;
+                let marker = if is_selected && !use_ansi_coloring {
+                    "> "
+                } else {
+                    "  "
+                };
+                let mark = if self.is_marked(suggestion) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                let row = format!(
+                    "{}{} {:<width$}  {}",
+                    marker,
+                    mark,
+                    suggestion.value,
+                    suggestion.description.as_deref().unwrap_or_default(),
+                    width = value_width
+                );
+                let row = truncate(row.trim_end(), columns);
+                if is_selected && use_ansi_coloring {
+                    Style::new().reverse().paint(row).to_string()
+                } else {
+                    row
+                }
+            })
+            .collect();
+
+        if self.values.len() > self.max_rows {
+            let status = truncate(
+                &format!("{}/{} (Tab for more)", self.selected + 1, self.values.len()),
+                columns,
+            );
+            rows.push(if use_ansi_coloring {
+                Style::new().dimmed().paint(status).to_string()
+            } else {
+                status
+            });
+        }
+
+        rows.join("\n")
+    }
+
+    /// Replace the completed text with the marked values, or the selected one without marks
+    fn replace_in_buffer(&self, line_buffer: &mut LineBuffer) {
+        let chosen: Vec<&Suggestion> = if self.marked.is_empty() {
+            self.values.get(self.selected).into_iter().collect()
+        } else {
+            self.values
+                .iter()
+                .filter(|suggestion| self.is_marked(suggestion))
+                .collect()
+        };
+
+        if let Some(combined) = combine(&chosen, &self.separator) {
+            combined.apply(line_buffer);
+        }
+    }
+
+    fn preview(&self) -> Option<String> {
+        self.values
+            .get(self.selected)
+            .and_then(|suggestion| suggestion.preview.clone())
+    }
+}
+
+impl MultiSelectMenu {
+    /// The name the menu is registered under, unless changed with [`MultiSelectMenu::with_name()`]
+    pub const DEFAULT_NAME: &'static str = "multi_select";
+
+    /// Create a menu listing the completions of `completer`, joined by a space when inserted
+    pub fn new(completer: Box<dyn Completer>) -> Self {
+        MultiSelectMenu {
+            name: Self::DEFAULT_NAME.to_string(),
+            completer,
+            sorter: None,
+            separator: " ".to_string(),
+            values: vec![],
+            selected: 0,
+            max_rows: 10,
+            pending: None,
+            marked: vec![],
+        }
+    }
+
+    /// A builder that sets the name to open the menu with
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// A builder that sets the string inserted between the marked values, e.g. `","`
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// A builder that sets a hook reordering the completions before they are listed, e.g. to
+    /// rank them by how often they are used
+    pub fn with_sorter(mut self, sorter: SuggestionSorter) -> Self {
+        self.sorter = Some(sorter);
+        self
+    }
+
+    /// A builder that sets how many values are listed per page, 10 by default
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows.max(1);
+        self
+    }
+
+    /// Show `values`, keeping the marks of those still listed and selecting `selected` if it is
+    fn set_values(&mut self, mut values: Vec<Suggestion>, selected: Option<String>) {
+        if let Some(sorter) = &self.sorter {
+            sorter(&mut values);
+        }
+        self.marked
+            .retain(|marked| values.iter().any(|suggestion| suggestion.value == *marked));
+        self.selected = selected
+            .and_then(|selected| {
+                values
+                    .iter()
+                    .position(|suggestion| suggestion.value == selected)
+            })
+            .unwrap_or(0);
+        self.values = values;
+    }
+
+    /// Index into the values of the first one on the page of the selection
+    fn page_start(&self) -> usize {
+        self.selected / self.max_rows * self.max_rows
+    }
+
+    fn is_marked(&self, suggestion: &Suggestion) -> bool {
+        self.marked.contains(&suggestion.value)
+    }
+}
+
+/// A single suggestion replacing the spans of all `suggestions` with their joined values
+fn combine(suggestions: &[&Suggestion], separator: &str) -> Option<Suggestion> {
+    let start = suggestions
+        .iter()
+        .map(|suggestion| suggestion.span.start)
+        .min()?;
+    let end = suggestions
+        .iter()
+        .map(|suggestion| suggestion.span.end)
+        .max()?;
+    let value = suggestions
+        .iter()
+        .map(|suggestion| suggestion.value.as_str())
+        .collect::<Vec<_>>()
+        .join(separator);
+
+    Some(
+        Suggestion::new(value, Span::new(start.0, end.0)).with_append_whitespace(
+            suggestions
+                .iter()
+                .any(|suggestion| suggestion.append_whitespace),
+        ),
+    )
+}
+
+fn truncate(row: &str, columns: usize) -> String {
+    row.chars().take(columns).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::{cell::RefCell, rc::Rc, sync::mpsc};
+
+    /// Completes the word before the cursor with the words starting with it
+    struct WordCompleter(Vec<&'static str>);
+
+    impl Completer for WordCompleter {
+        fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+            let start = line[..pos].rfind(' ').map_or(0, |space| space + 1);
+            self.0
+                .iter()
+                .filter(|word| word.starts_with(&line[start..pos]))
+                .map(|word| Suggestion::new(*word, Span::new(start, pos)))
+                .collect()
+        }
+    }
+
+    fn menu_for(buffer: &str) -> (MultiSelectMenu, LineBuffer) {
+        let completer = WordCompleter(vec!["test-a", "test-b", "test-c"]);
+        let mut menu = MultiSelectMenu::new(Box::new(completer)).with_separator(",");
+        let mut line_buffer = LineBuffer::new();
+        line_buffer.insert_str(buffer);
+        menu.activate(&line_buffer, None);
+        (menu, line_buffer)
+    }
+
+    fn space(menu: &mut MultiSelectMenu) {
+        assert!(menu.handle_edit(&EditCommand::InsertChar(' ')));
+    }
+
+    #[test]
+    fn space_toggles_mark_of_selected_value() {
+        let (mut menu, _) = menu_for("run test");
+        space(&mut menu);
+        menu.move_next();
+        menu.move_next();
+        space(&mut menu);
+        menu.move_previous();
+
+        assert_eq!(
+            menu.menu_string(80, false),
+            "  [x] test-a\n> [ ] test-b\n  [x] test-c"
+        );
+
+        menu.move_previous();
+        space(&mut menu);
+        assert_eq!(menu.marked, vec!["test-c".to_string()]);
+    }
+
+    #[test]
+    fn accepting_joins_marked_values_in_listed_order() {
+        let (mut menu, mut line_buffer) = menu_for("run test");
+        menu.move_next();
+        menu.move_next();
+        space(&mut menu);
+        menu.move_previous();
+        menu.move_previous();
+        space(&mut menu);
+
+        menu.replace_in_buffer(&mut line_buffer);
+
+        assert_eq!(line_buffer.get_buffer(), "run test-a,test-c");
+        assert_eq!(line_buffer.offset(), line_buffer.get_buffer().len());
+    }
+
+    #[test]
+    fn accepting_without_marks_inserts_selected_value() {
+        let (mut menu, mut line_buffer) = menu_for("run test");
+        menu.move_next();
+
+        menu.replace_in_buffer(&mut line_buffer);
+
+        assert_eq!(line_buffer.get_buffer(), "run test-b");
+    }
+
+    #[test]
+    fn other_edits_go_to_buffer_and_drop_stale_marks() {
+        let (mut menu, mut line_buffer) = menu_for("run test");
+        space(&mut menu);
+        menu.move_next();
+        space(&mut menu);
+
+        assert!(!menu.handle_edit(&EditCommand::InsertChar('-')));
+        line_buffer.insert_str("-b");
+        menu.update_values(&line_buffer);
+
+        assert_eq!(menu.marked, vec!["test-b".to_string()]);
+        assert_eq!(menu.menu_string(80, false), "> [x] test-b");
+    }
+
+    #[test]
+    fn preview_of_selected_value_is_shown() {
+        let (mut menu, _) = menu_for("run test");
+        menu.values[1] = menu.values[1].clone().with_preview("/tmp/test-b");
+
+        assert_eq!(menu.preview(), None);
+        menu.move_next();
+        assert_eq!(menu.preview(), Some("/tmp/test-b".to_string()));
+    }
+
+    #[test]
+    fn combine_covers_all_spans() {
+        let first = Suggestion::new("alpha", Span::new(4, 6));
+        let second = Suggestion::new("beta", Span::new(2, 5)).with_append_whitespace(true);
+
+        assert_eq!(
+            combine(&[&first, &second], " "),
+            Some(Suggestion::new("alpha beta", Span::new(2, 6)).with_append_whitespace(true))
+        );
+        assert_eq!(combine(&[], " "), None);
+    }
+
+    #[test]
+    fn sorter_orders_listed_values() {
+        let completer = WordCompleter(vec!["test-a", "test-b", "test-c"]);
+        let mut menu = MultiSelectMenu::new(Box::new(completer))
+            .with_sorter(Box::new(|values: &mut Vec<Suggestion>| values.reverse()));
+        let mut line_buffer = LineBuffer::new();
+        line_buffer.insert_str("run test");
+        menu.activate(&line_buffer, None);
+
+        assert_eq!(
+            menu.menu_string(80, false),
+            "> [ ] test-c\n  [ ] test-b\n  [ ] test-a"
+        );
+    }
+
+    #[test]
+    fn values_beyond_max_rows_are_paged() {
+        let (menu, _) = menu_for("run test");
+        let mut menu = menu.with_max_rows(2);
+
+        assert_eq!(
+            menu.menu_string(80, false),
+            "> [ ] test-a\n  [ ] test-b\n1/3 (Tab for more)"
+        );
+
+        menu.move_next();
+        menu.move_next();
+        assert_eq!(
+            menu.menu_string(80, false),
+            "> [ ] test-c\n3/3 (Tab for more)"
+        );
+        assert!(menu.select_visible(0));
+        assert!(!menu.select_visible(1));
+    }
+
+    #[test]
+    fn pages_are_turned_with_wrap_around() {
+        let (menu, mut line_buffer) = menu_for("run test");
+        let mut menu = menu.with_max_rows(2);
+
+        menu.next_page();
+        assert_eq!(menu.selected, 2);
+        menu.next_page();
+        assert_eq!(menu.selected, 0);
+        menu.previous_page();
+        assert_eq!(menu.selected, 2);
+
+        // The page is kept while typing narrows down the completions
+        line_buffer.insert_str("-");
+        menu.update_values(&line_buffer);
+        assert_eq!(menu.selected, 2);
+    }
+
+    type SenderSlot = Rc<RefCell<Option<mpsc::Sender<Vec<Suggestion>>>>>;
+
+    /// Hands out the sender of the suggestions to the test instead of completing
+    struct DelayedCompleter(SenderSlot);
+
+    impl Completer for DelayedCompleter {
+        fn complete(&self, _line: &str, _pos: usize) -> Vec<Suggestion> {
+            vec![]
+        }
+
+        fn complete_async(&self, _line: &str, _pos: usize) -> AsyncSuggestions {
+            let (sender, receiver) = mpsc::channel();
+            *self.0.borrow_mut() = Some(sender);
+            AsyncSuggestions::Pending(receiver)
+        }
+    }
+
+    fn delayed_menu() -> (MultiSelectMenu, SenderSlot) {
+        let sender = Rc::new(RefCell::new(None));
+        let mut menu = MultiSelectMenu::new(Box::new(DelayedCompleter(sender.clone())));
+        menu.activate(&LineBuffer::new(), None);
+        (menu, sender)
+    }
+
+    #[test]
+    fn pending_completions_show_loading_row() {
+        let (mut menu, sender) = delayed_menu();
+
+        assert!(menu.is_loading());
+        assert!(!menu.poll_values());
+        assert_eq!(menu.menu_string(80, false), "(loading…)");
+
+        let values = vec![
+            Suggestion::new("test-a", Span::new(0, 0)),
+            Suggestion::new("test-b", Span::new(0, 0)),
+        ];
+        sender.borrow().as_ref().unwrap().send(values).unwrap();
+        assert!(menu.poll_values());
+        assert!(!menu.is_loading());
+        assert_eq!(menu.menu_string(80, false), "> [ ] test-a\n  [ ] test-b");
+    }
+
+    #[test]
+    fn dropped_sender_completes_nothing() {
+        let (mut menu, sender) = delayed_menu();
+
+        sender.borrow_mut().take();
+        assert!(menu.poll_values());
+        assert_eq!(menu.menu_string(80, false), "(no completions)");
+    }
+}
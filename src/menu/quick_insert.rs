@@ -0,0 +1,259 @@
+use {
+    super::Menu,
+    crate::{Keybindings, LineBuffer},
+    nu_ansi_term::Style,
+    std::{cell::RefCell, rc::Rc},
+};
+
+/// Strings provided by the host to insert at the cursor with a single key press
+///
+/// The registry is a shared handle, clones refer to the same entries. Update it between
+/// [`Reedline::read_line()`](crate::Reedline::read_line) calls, e.g. with the first path
+/// printed by the last command or the current git branch. Entries are inserted by
+/// [`ReedlineEvent::QuickInsert`](crate::ReedlineEvent::QuickInsert) with their key or picked
+/// from the [`QuickInsertMenu`].
+///
+/// ## Example
+/// ```rust
+/// use crossterm::event::{KeyCode, KeyModifiers};
+/// use reedline::{default_emacs_keybindings, Emacs, Reedline, ReedlineEvent};
+///
+/// let mut keybindings = default_emacs_keybindings();
+/// keybindings.add_binding(
+///     KeyModifiers::ALT,
+///     KeyCode::Char('b'),
+///     ReedlineEvent::QuickInsert("branch".into()),
+/// );
+///
+/// let line_editor =
+///     Reedline::create()?.with_edit_mode(Box::new(Emacs::new(keybindings)));
+/// line_editor.quick_inserts().set("branch", "main");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct QuickInserts {
+    // Entries in the order they were first set
+    entries: Rc<RefCell<Vec<(String, String)>>>,
+}
+
+impl QuickInserts {
+    /// Set the string inserted for `key`, replacing its previous value
+    pub fn set(&self, key: impl Into<String>, value: impl Into<String>) {
+        let key = key.into();
+        let value = value.into();
+        let mut entries = self.entries.borrow_mut();
+        match entries.iter_mut().find(|(existing, _)| *existing == key) {
+            Some(entry) => entry.1 = value,
+            None => entries.push((key, value)),
+        }
+    }
+
+    /// Remove the entry of `key`, returning its value
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries.borrow_mut();
+        let index = entries.iter().position(|(existing, _)| existing == key)?;
+        Some(entries.remove(index).1)
+    }
+
+    /// The string inserted for `key`, if any
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.entries
+            .borrow()
+            .iter()
+            .find(|(existing, _)| existing == key)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Remove all entries
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    fn entries(&self) -> Vec<(String, String)> {
+        self.entries.borrow().clone()
+    }
+}
+
+/// Menu listing the entries of the [`QuickInserts`] to insert one at the cursor
+///
+/// Registered by default under [`QuickInsertMenu::NAME`], sharing the registry returned by
+/// [`Reedline::quick_inserts()`](crate::Reedline::quick_inserts). It is not bound to a key,
+/// open it with [`ReedlineEvent::Menu`](crate::ReedlineEvent::Menu) or the command palette.
+pub struct QuickInsertMenu {
+    quick_inserts: QuickInserts,
+    // Entries at the time the menu was opened
+    entries: Vec<(String, String)>,
+    selected: usize,
+}
+
+impl Menu for QuickInsertMenu {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    /// Take the current entries of the registry
+    fn activate(&mut self, _line_buffer: &LineBuffer, _keybindings: Option<&Keybindings>) {
+        self.entries = self.quick_inserts.entries();
+        self.selected = 0;
+    }
+
+    /// The entries do not depend on the buffer
+    fn update_values(&mut self, _line_buffer: &LineBuffer) {}
+
+    /// Select the next entry, wrapping around to the first one
+    fn move_next(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = (self.selected + 1) % self.entries.len();
+        }
+    }
+
+    /// Select the previous entry, wrapping around to the last one
+    fn move_previous(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.entries.len() - 1);
+        }
+    }
+
+    fn select_visible(&mut self, index: usize) -> bool {
+        if index < self.entries.len() {
+            self.selected = index;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// One row per entry with its key and the first line of its value
+    fn menu_string(&self, columns: usize, use_ansi_coloring: bool) -> String {
+        if self.entries.is_empty() {
+            return truncate("(nothing to insert)", columns);
+        }
+
+        let key_width = self
+            .entries
+            .iter()
+            .map(|(key, _)| key.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, (key, value))| {
+                let is_selected = index == self.selected;
+                let marker = if is_selected && !use_ansi_coloring {
+                    "> "
+                } else {
+                    "  "
+                };
+                let value = value.lines().next().unwrap_or_default();
+                let row = truncate(
+                    &format!("{}{:<width$}  {}", marker, key, value, width = key_width),
+                    columns,
+                );
+                if is_selected && use_ansi_coloring {
+                    Style::new().reverse().paint(row).to_string()
+                } else {
+                    row
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Insert the value of the selected entry at the cursor
+    fn replace_in_buffer(&self, line_buffer: &mut LineBuffer) {
+        if let Some((_, value)) = self.entries.get(self.selected) {
+            line_buffer.insert_str(value);
+        }
+    }
+}
+
+impl QuickInsertMenu {
+    /// The name the menu is registered under
+    pub const NAME: &'static str = "quick_insert";
+
+    /// Create a menu listing the entries of `quick_inserts`
+    pub fn new(quick_inserts: QuickInserts) -> Self {
+        QuickInsertMenu {
+            quick_inserts,
+            entries: vec![],
+            selected: 0,
+        }
+    }
+}
+
+fn truncate(row: &str, columns: usize) -> String {
+    row.chars().take(columns).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn set_replaces_value_in_place() {
+        let quick_inserts = QuickInserts::default();
+        quick_inserts.set("path", "/tmp/out.txt");
+        quick_inserts.set("branch", "main");
+        quick_inserts.set("path", "/tmp/other.txt");
+
+        assert_eq!(
+            quick_inserts.entries(),
+            vec![
+                ("path".to_string(), "/tmp/other.txt".to_string()),
+                ("branch".to_string(), "main".to_string())
+            ]
+        );
+        assert_eq!(quick_inserts.remove("path"), Some("/tmp/other.txt".into()));
+        assert_eq!(quick_inserts.get("path"), None);
+    }
+
+    #[test]
+    fn menu_sees_updates_of_shared_registry() {
+        let quick_inserts = QuickInserts::default();
+        let mut menu = QuickInsertMenu::new(quick_inserts.clone());
+        quick_inserts.set("branch", "main");
+        quick_inserts.set("path", "/tmp/out.txt");
+
+        menu.activate(&LineBuffer::new(), None);
+        menu.move_next();
+
+        assert_eq!(
+            menu.menu_string(80, false),
+            "  branch  main\n> path    /tmp/out.txt"
+        );
+    }
+
+    #[test]
+    fn accepting_inserts_at_cursor() {
+        let quick_inserts = QuickInserts::default();
+        quick_inserts.set("branch", "main");
+        let mut menu = QuickInsertMenu::new(quick_inserts);
+        let mut line_buffer = LineBuffer::new();
+        line_buffer.insert_str("git checkout ");
+
+        menu.activate(&line_buffer, None);
+        menu.replace_in_buffer(&mut line_buffer);
+
+        assert_eq!(line_buffer.get_buffer(), "git checkout main");
+    }
+
+    #[test]
+    fn empty_registry_inserts_nothing() {
+        let mut menu = QuickInsertMenu::new(QuickInserts::default());
+        let mut line_buffer = LineBuffer::new();
+
+        menu.activate(&line_buffer, None);
+        menu.move_next();
+        menu.replace_in_buffer(&mut line_buffer);
+
+        assert_eq!(menu.menu_string(80, false), "(nothing to insert)");
+        assert!(!menu.select_visible(0));
+        assert_eq!(line_buffer.get_buffer(), "");
+    }
+}
@@ -0,0 +1,94 @@
+/// Canonicalizes a submitted line before it is added to the history and returned
+///
+/// See [`Reedline::with_buffer_normalizer()`](crate::Reedline::with_buffer_normalizer).
+/// [`trim_trailing_whitespace`] and [`collapse_whitespace`] cover the common cases.
+pub type BufferNormalizer = Box<dyn Fn(&str) -> String>;
+
+/// Remove the whitespace at the end of every line of `buffer`, including trailing newlines
+///
+/// ## Example
+/// ```rust
+/// use reedline::trim_trailing_whitespace;
+///
+/// assert_eq!(trim_trailing_whitespace("ls -la  \n  pwd \n"), "ls -la\n  pwd");
+/// ```
+pub fn trim_trailing_whitespace(buffer: &str) -> String {
+    buffer
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_string()
+}
+
+/// Collapse every run of spaces and tabs between words to a single space and trim the lines
+///
+/// The indentation of the lines and text inside single or double quotes are kept as they are,
+/// so arguments like `"a  b"` keep their meaning for a shell.
+///
+/// ## Example
+/// ```rust
+/// use reedline::collapse_whitespace;
+///
+/// assert_eq!(collapse_whitespace("git  commit -m 'a  b'   "), "git commit -m 'a  b'");
+/// ```
+pub fn collapse_whitespace(buffer: &str) -> String {
+    let mut normalized = String::with_capacity(buffer.len());
+    let mut quote = None;
+    for (index, line) in trim_trailing_whitespace(buffer).split('\n').enumerate() {
+        if index > 0 {
+            normalized.push('\n');
+        }
+        let content = line.trim_start_matches([' ', '\t']);
+        normalized.push_str(&line[..line.len() - content.len()]);
+
+        let mut in_blank = false;
+        for c in content.chars() {
+            if quote.is_none() && (c == ' ' || c == '\t') {
+                in_blank = true;
+                continue;
+            }
+            if in_blank {
+                normalized.push(' ');
+                in_blank = false;
+            }
+            match quote {
+                Some(open) if open == c => quote = None,
+                None if c == '\'' || c == '"' => quote = Some(c),
+                _ => {}
+            }
+            normalized.push(c);
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("ls  ", "ls")]
+    #[case("ls\t\n\n", "ls")]
+    #[case("  cd ..", "  cd ..")]
+    #[case("echo a  \n  b ", "echo a\n  b")]
+    #[case("   ", "")]
+    fn trailing_whitespace_is_trimmed(#[case] buffer: &str, #[case] expected: &str) {
+        assert_eq!(trim_trailing_whitespace(buffer), expected);
+    }
+
+    #[rstest]
+    #[case("ls   -la\t /tmp ", "ls -la /tmp")]
+    #[case("  cd    ..", "  cd ..")]
+    #[case("echo \"a   b\"   'c  d'  e", "echo \"a   b\" 'c  d' e")]
+    #[case("echo 'it''s'   ok", "echo 'it''s' ok")]
+    #[case("for x in a  b\n    echo  $x\nend", "for x in a b\n    echo $x\nend")]
+    #[case("echo 'a  \n  b'  c", "echo 'a\n  b' c")]
+    fn whitespace_runs_are_collapsed_outside_quotes(#[case] buffer: &str, #[case] expected: &str) {
+        assert_eq!(collapse_whitespace(buffer), expected);
+    }
+}
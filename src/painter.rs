@@ -2,19 +2,42 @@ use crate::core_editor::Editor;
 
 use {
     crate::{
+        markup::MarkupWriter,
         prompt::{PromptEditMode, PromptHistorySearch},
-        Prompt,
+        text_manipulation::{grapheme_width, wrapped_position, wrapped_width},
+        ByteOffset, DisplayWidth, Prompt,
     },
     crossterm::{
         cursor::{self, position, MoveTo, MoveToColumn, RestorePosition, SavePosition},
-        style::{Print, ResetColor, SetForegroundColor},
-        terminal::{self, Clear, ClearType},
+        event::{
+            DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+            PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+        },
+        style::{Attribute, Print, ResetColor, SetAttribute, SetForegroundColor},
+        terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
         QueueableCommand, Result,
     },
-    std::io::{Stdout, Write},
-    unicode_width::UnicodeWidthStr,
+    nu_ansi_term::Style,
+    std::{
+        borrow::Cow,
+        cell::OnceCell,
+        io::{Stdout, Write},
+        ops::Range,
+        path::Path,
+    },
+    unicode_segmentation::UnicodeSegmentation,
+    unicode_width::{UnicodeWidthChar, UnicodeWidthStr},
 };
 
+/// Marker appended to the collapsed previous command if parts of it are hidden
+const COLLAPSED_MARKER: &str = "…";
+
+/// Semantic prompt markers (OSC 133) around the prompt, the input and the command output
+const PROMPT_START_MARKER: &str = "\x1b]133;A\x1b\\";
+const INPUT_START_MARKER: &str = "\x1b]133;B\x1b\\";
+const OUTPUT_START_MARKER: &str = "\x1b]133;C\x1b\\";
+const COMMAND_END_MARKER: &str = "\x1b]133;D\x1b\\";
+
 #[derive(Default)]
 struct PromptCoordinates {
     prompt_start: (u16, u16),
@@ -35,20 +58,279 @@ impl PromptCoordinates {
     }
 }
 
-pub struct Painter {
+/// Paints the prompt and the buffer to the terminal
+///
+/// Writes to stdout, other writers make it possible to inspect the output,
+/// e.g. to track the number of bytes written per repaint.
+pub struct Painter<W: Write = Stdout> {
     // Stdout
-    stdout: Stdout,
+    stdout: W,
     prompt_coords: PromptCoordinates,
     terminal_size: (u16, u16),
+    // Input area as painted by the last `queue_buffer` call.
+    // Cleared whenever something else is written over the input area.
+    last_frame: Option<Frame>,
+    // Previously submitted command shown dimmed above the prompt
+    previous_command: Option<String>,
+    // Status message painted on the row below the buffer
+    status_row: Option<String>,
+    // First line of the frame that is shown, if it is taller than the terminal
+    viewport_start: usize,
+    // Edit mode the prompt indicator was last painted for
+    prompt_mode: Option<PromptEditMode>,
+    // Column of the cursor inside the term of the last painted history search indicator
+    history_search_cursor: Option<DisplayWidth>,
+    // History entries previewed around the input area and their rendered rows
+    history_preview: Option<HistoryPreview>,
+    history_preview_rows: (String, String),
+    // Hint shown on its own rows right below the buffer instead of inline
+    hint_rows: Option<String>,
+    // Rows of the host widget painted beneath everything else
+    widget_rows: Option<String>,
+    // Mark the prompt, the input and the command output for the terminal
+    semantic_prompt_markers: bool,
+    // The output of a submitted command was marked, its end is marked with the next prompt
+    command_running: bool,
+    // Host of the reported working directory, looked up once
+    hostname: OnceCell<String>,
+}
+
+/// A gutter painted in front of each line of a buffer spanning multiple lines
+///
+/// Only shown once the buffer contains a line break, rows of a line wrapped by the terminal
+/// are not prefixed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultilineGutter {
+    /// Number the lines starting at 1, e.g. ` 9│ ` and `10│ `
+    LineNumbers,
+    /// Mark every line after the first with the given marker, the first line is padded to match
+    ContinuationMarker(String),
+}
+
+impl MultilineGutter {
+    /// Prefix every line of the rendered buffer, split at the cursor, with the gutter
+    pub(crate) fn apply(
+        &self,
+        highlighted_line: (String, String),
+        use_ansi_coloring: bool,
+    ) -> (String, String) {
+        let (before_cursor, after_cursor) = highlighted_line;
+        let line_break = if cfg!(windows) { "\r\n" } else { "\n" };
+        let line_count = before_cursor.matches(line_break).count()
+            + after_cursor.matches(line_break).count()
+            + 1;
+        if line_count == 1 {
+            return (before_cursor, after_cursor);
+        }
+
+        let mut lines: Vec<String> = before_cursor.split(line_break).map(String::from).collect();
+        let cursor_line = lines.len() - 1;
+        let cursor_offset = lines[cursor_line].len();
+        let mut after_cursor_lines = after_cursor.split(line_break);
+        lines[cursor_line].push_str(after_cursor_lines.next().unwrap_or_default());
+        lines.extend(after_cursor_lines.map(String::from));
+
+        let mut prefixed = Vec::with_capacity(lines.len());
+        for (idx, line) in lines.iter().enumerate() {
+            let gutter = self.render(idx, line_count);
+            if use_ansi_coloring {
+                // Styles started on the previous line, e.g. by the multiline indicator,
+                // continue after the gutter
+                let previous_escapes = match idx {
+                    0 => String::new(),
+                    _ => ansi_escapes(&lines[idx - 1]),
+                };
+                prefixed.push(format!(
+                    "{}{}{}",
+                    Style::new().dimmed().paint(gutter),
+                    previous_escapes,
+                    line
+                ));
+            } else {
+                prefixed.push(format!("{}{}", gutter, line));
+            }
+        }
+
+        let mut after_cursor = prefixed.split_off(cursor_line + 1);
+        let mut before_cursor = prefixed.join(line_break);
+        let split_at = before_cursor.len() - (lines[cursor_line].len() - cursor_offset);
+        after_cursor.insert(0, before_cursor.split_off(split_at));
+
+        (before_cursor, after_cursor.join(line_break))
+    }
+
+    /// Columns taken by the gutter of each line in a buffer of `line_count` lines
+    pub(crate) fn width(&self, line_count: usize) -> DisplayWidth {
+        if line_count > 1 {
+            DisplayWidth::of(&self.render(0, line_count))
+        } else {
+            DisplayWidth(0)
+        }
+    }
+
+    /// The gutter of the line at `idx`, padded to the same width for all `line_count` lines
+    fn render(&self, idx: usize, line_count: usize) -> String {
+        match self {
+            MultilineGutter::LineNumbers => {
+                let width = line_count.to_string().len();
+                format!("{:>width$}│ ", idx + 1, width = width)
+            }
+            MultilineGutter::ContinuationMarker(marker) if idx > 0 => marker.clone(),
+            MultilineGutter::ContinuationMarker(marker) => " ".repeat(marker.width()),
+        }
+    }
+}
+
+/// The history entries adjacent to the one shown in the buffer while traversing the history
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct HistoryPreview {
+    pub older: Option<String>,
+    pub newer: Option<String>,
 }
 
-impl Painter {
-    pub fn new(stdout: Stdout) -> Self {
+impl<W: Write> Painter<W> {
+    pub fn new(stdout: W) -> Self {
         Painter {
             stdout,
             prompt_coords: PromptCoordinates::default(),
             terminal_size: (0, 0),
+            last_frame: None,
+            previous_command: None,
+            status_row: None,
+            viewport_start: 0,
+            prompt_mode: None,
+            history_search_cursor: None,
+            history_preview: None,
+            history_preview_rows: (String::new(), String::new()),
+            hint_rows: None,
+            widget_rows: None,
+            semantic_prompt_markers: false,
+            command_running: false,
+            hostname: OnceCell::new(),
+        }
+    }
+
+    /// Emit semantic prompt markers (OSC 133) with the prompt and the submitted command
+    pub(crate) fn set_semantic_prompt_markers(&mut self, enabled: bool) {
+        self.semantic_prompt_markers = enabled;
+    }
+
+    /// Set the status message to display below the buffer, lines separated by `\n` take a row each
+    pub(crate) fn set_status_row(&mut self, status_row: Option<String>) {
+        self.status_row = status_row;
+    }
+
+    /// Set the hint to display on its own rows right below the buffer
+    pub(crate) fn set_hint_rows(&mut self, hint_rows: Option<String>) {
+        self.hint_rows = hint_rows;
+    }
+
+    /// Set the rows of the host widget to display beneath the input area
+    pub(crate) fn set_widget_rows(&mut self, widget_rows: Option<String>) {
+        self.widget_rows = widget_rows;
+    }
+
+    /// Set the previously submitted command to display above the prompt
+    pub(crate) fn set_previous_command(&mut self, previous_command: Option<String>) {
+        self.previous_command = previous_command;
+    }
+
+    /// Checks if the history preview on screen differs from `preview`
+    pub(crate) fn is_history_preview_outdated(&self, preview: &Option<HistoryPreview>) -> bool {
+        self.history_preview != *preview
+    }
+
+    /// Set the history entries to preview dimmed on the rows above and below the input area
+    ///
+    /// The older entry needs a row of its own above the prompt, so the prompt moves down a row
+    /// when the preview appears and back up when it is removed. Everything from the prompt on
+    /// is cleared in that case and has to be painted again.
+    pub(crate) fn set_history_preview(
+        &mut self,
+        preview: Option<HistoryPreview>,
+        use_ansi_coloring: bool,
+    ) -> Result<()> {
+        let (prompt_start_col, prompt_start_row) = self.prompt_coords.prompt_start;
+        let (input_start_col, input_start_row) = self.prompt_coords.input_start;
+        match (&self.history_preview, &preview) {
+            (None, Some(_)) => {
+                self.clear_from_prompt_start()?;
+                self.prompt_coords
+                    .set_prompt_start(prompt_start_col, prompt_start_row + 1);
+                self.prompt_coords
+                    .set_input_start(input_start_col, input_start_row + 1);
+            }
+            (Some(_), None) if prompt_start_row > 0 => {
+                self.prompt_coords
+                    .set_prompt_start(prompt_start_col, prompt_start_row - 1);
+                self.prompt_coords
+                    .set_input_start(input_start_col, input_start_row.saturating_sub(1));
+                self.clear_from_prompt_start()?;
+            }
+            _ => self.invalidate_frame(),
         }
+
+        let columns = self.terminal_columns() as usize;
+        self.history_preview_rows = match &preview {
+            Some(preview) => (
+                history_preview_row(preview.older.as_deref(), columns, use_ansi_coloring),
+                history_preview_row(preview.newer.as_deref(), columns, use_ansi_coloring),
+            ),
+            None => (String::new(), String::new()),
+        };
+        self.history_preview = preview;
+
+        Ok(())
+    }
+
+    /// The rows painted below the buffer, the status row takes precedence over the preview
+    /// and the widget rows come last
+    fn rows_below_buffer(&self) -> Option<Cow<'_, str>> {
+        let (_, newer_row) = &self.history_preview_rows;
+        let rows = match &self.status_row {
+            Some(status_row) => Some(status_row.as_str()),
+            None if !newer_row.is_empty() => Some(newer_row.as_str()),
+            None => None,
+        };
+
+        let rows: Vec<&str> = [self.hint_rows.as_deref(), rows, self.widget_rows.as_deref()]
+            .iter()
+            .copied()
+            .flatten()
+            .collect();
+        match rows.as_slice() {
+            [] => None,
+            [rows] => Some(Cow::Borrowed(rows)),
+            rows => Some(Cow::Owned(rows.join("\n"))),
+        }
+    }
+
+    /// Checks if painting the given buffer content would reproduce what is already on screen
+    pub(crate) fn is_buffer_unchanged(
+        &self,
+        highlighted_line: &(String, String),
+        hint: &str,
+    ) -> bool {
+        match &self.last_frame {
+            Some(last_frame) => {
+                let hint = self.fit_hint(highlighted_line, hint);
+                let frame =
+                    Frame::new(highlighted_line, &hint, self.rows_below_buffer().as_deref());
+                let viewport = self.viewport(&frame);
+                viewport.start == self.viewport_start && *last_frame == frame.slice(viewport)
+            }
+            None => false,
+        }
+    }
+
+    /// Checks if the prompt indicator on screen was painted for a different edit mode
+    pub(crate) fn is_prompt_mode_outdated(&self, prompt_mode: &PromptEditMode) -> bool {
+        self.prompt_mode.as_ref() != Some(prompt_mode)
+    }
+
+    /// Forget the last painted frame, forcing the next buffer paint to redraw the whole input area
+    fn invalidate_frame(&mut self) {
+        self.last_frame = None;
     }
 
     /// Update the terminal size information by polling the system
@@ -57,7 +339,7 @@ impl Painter {
         Ok(())
     }
 
-    fn terminal_columns(&self) -> u16 {
+    pub(crate) fn terminal_columns(&self) -> u16 {
         self.terminal_size.0
     }
 
@@ -82,8 +364,25 @@ impl Painter {
         use_ansi_coloring: bool,
     ) -> Result<()> {
         let (screen_width, _) = self.terminal_size;
+        self.prompt_mode = Some(prompt_mode.clone());
+
+        if let Some(title) = prompt.render_window_title() {
+            // A control character like BEL or ESC would end the title sequence early
+            let title: String = title.chars().filter(|c| !c.is_control()).collect();
+            self.stdout.queue(SetTitle(title))?;
+        }
+        if let Some(working_directory) = prompt.get_working_directory() {
+            let url = file_url(self.hostname.get_or_init(hostname), &working_directory);
+            self.stdout.queue(Print(format!("\x1b]7;{}\x1b\\", url)))?;
+        }
 
         self.stdout.queue(MoveToColumn(0))?;
+        if self.semantic_prompt_markers {
+            if std::mem::take(&mut self.command_running) {
+                self.stdout.queue(Print(COMMAND_END_MARKER))?;
+            }
+            self.stdout.queue(Print(PROMPT_START_MARKER))?;
+        }
         if use_ansi_coloring {
             // print our prompt with color
             self.stdout
@@ -95,6 +394,21 @@ impl Painter {
         if use_ansi_coloring {
             self.stdout.queue(ResetColor)?;
         }
+        if self.semantic_prompt_markers {
+            self.stdout.queue(Print(INPUT_START_MARKER))?;
+        }
+
+        Ok(())
+    }
+
+    /// Mark the start of the output of the submitted command, if semantic markers are enabled
+    ///
+    /// Its end is marked in front of the next prompt.
+    pub(crate) fn mark_command_start(&mut self) -> Result<()> {
+        if self.semantic_prompt_markers {
+            self.command_running = true;
+            self.stdout.queue(Print(OUTPUT_START_MARKER))?.flush()?;
+        }
 
         Ok(())
     }
@@ -102,55 +416,252 @@ impl Painter {
     /// Repaint logic for the normal input prompt buffer
     ///
     /// Requires coordinates where the input buffer begins after the prompt.
+    /// Only the part of the input area that differs from the previously painted frame is written.
     pub fn queue_buffer(&mut self, highlighted_line: (String, String), hint: String) -> Result<()> {
-        let (before_cursor, after_cursor) = highlighted_line;
-
-        let before_cursor_lines = if cfg!(windows) {
-            before_cursor.split("\r\n")
-        } else {
-            #[allow(clippy::single_char_pattern)]
-            before_cursor.split("\n")
-        };
-
-        let after_cursor_lines = if cfg!(windows) {
-            after_cursor.split("\r\n")
-        } else {
-            #[allow(clippy::single_char_pattern)]
-            after_cursor.split("\n")
+        let hint = self.fit_hint(&highlighted_line, &hint);
+        let frame = Frame::new(
+            &highlighted_line,
+            &hint,
+            self.rows_below_buffer().as_deref(),
+        );
+        let viewport = self.viewport(&frame);
+        if viewport.start != self.viewport_start {
+            // Every row moved, nothing on screen can be reused
+            self.viewport_start = viewport.start;
+            self.invalidate_frame();
+        }
+        let frame = frame.slice(viewport);
+        let (cursor_line, before_cursor) = &frame.cursor;
+
+        // Skip the leading rows and the start of the first changed row that are already on screen.
+        // Output has to restart at the latest on the cursor row, to be able to save its position.
+        let (first_line, mut unchanged_len) = match &self.last_frame {
+            Some(last_frame) => {
+                let first_line = frame
+                    .lines
+                    .iter()
+                    .zip(last_frame.lines.iter())
+                    .take_while(|(new, old)| new == old)
+                    .count()
+                    .min(*cursor_line);
+                let unchanged_len = match (
+                    frame.lines.get(first_line),
+                    last_frame.lines.get(first_line),
+                ) {
+                    (Some(new), Some(old)) => {
+                        let limit = if first_line == *cursor_line {
+                            before_cursor.len()
+                        } else {
+                            new.len()
+                        };
+                        unchanged_prefix_len(new, old, limit)
+                    }
+                    _ => 0,
+                };
+                (first_line, unchanged_len)
+            }
+            None => (0, 0),
         };
 
-        let mut commands = self.stdout.queue(MoveTo(
-            self.prompt_coords.input_start.0,
-            self.prompt_coords.input_start.1,
-        ))?;
-
-        for (idx, before_cursor_line) in before_cursor_lines.enumerate() {
-            if idx != 0 {
-                commands = commands.queue(Clear(ClearType::UntilNewLine))?;
-                commands = commands.queue(Print("\r\n"))?;
-            }
-            commands = commands.queue(Print(before_cursor_line))?;
+        let (mut column, mut row) = self.screen_position(
+            &frame.lines[..first_line],
+            &frame.lines[first_line][..unchanged_len],
+        );
+        if column == 0 && unchanged_len > 0 {
+            // The kept prefix ends exactly at the terminal edge, where the terminal has not
+            // wrapped yet. Repaint the whole row instead of guessing the cursor state.
+            unchanged_len = 0;
+            let (line_column, line_row) = self.screen_position(&frame.lines[..first_line], "");
+            column = line_column;
+            row = line_row;
         }
+        let mut commands = self.stdout.queue(MoveTo(column, row))?;
 
-        commands = commands.queue(SavePosition)?.queue(Print(hint))?;
-
-        for (idx, after_cursor_line) in after_cursor_lines.enumerate() {
-            if idx != 0 {
+        for (idx, line) in frame.lines.iter().enumerate().skip(first_line) {
+            let line = if idx == first_line {
+                // Restore the styling active at the point we continue from
+                commands = commands.queue(Print(ansi_escapes(&line[..unchanged_len])))?;
+                &line[unchanged_len..]
+            } else {
                 commands = commands.queue(Clear(ClearType::UntilNewLine))?;
                 commands = commands.queue(Print("\r\n"))?;
+                line.as_str()
+            };
+
+            if idx == *cursor_line {
+                let cursor_offset = before_cursor.len() - (frame.lines[idx].len() - line.len());
+                commands = commands
+                    .queue(Print(&line[..cursor_offset]))?
+                    .queue(SavePosition)?
+                    .queue(Print(&line[cursor_offset..]))?;
+            } else {
+                commands = commands.queue(Print(line))?;
             }
-            commands = commands.queue(Print(after_cursor_line))?;
         }
 
         commands
             .queue(Clear(ClearType::FromCursorDown))?
             .queue(RestorePosition)?;
 
+        self.last_frame = Some(frame);
+
         Ok(())
     }
 
+    /// Where painting of the visible part of the buffer starts
+    ///
+    /// If the start of the buffer is scrolled out of view, the prompt gets overwritten.
+    fn input_area_start(&self) -> (u16, u16) {
+        let (input_start_col, input_start_row) = self.prompt_coords.input_start;
+        if self.viewport_start > 0 {
+            (0, input_start_row)
+        } else {
+            (input_start_col, input_start_row)
+        }
+    }
+
+    /// The lines of `frame` to show, keeping the cursor visible if the frame does not fit
+    /// between the start of the input area and the bottom of the terminal
+    fn viewport(&self, frame: &Frame) -> Range<usize> {
+        let (input_start_col, input_start_row) = self.prompt_coords.input_start;
+        let terminal_columns = self.terminal_columns() as usize;
+        let height = self.terminal_rows().saturating_sub(input_start_row) as usize;
+        if height == 0 {
+            return 0..frame.lines.len();
+        }
+
+        let heights: Vec<usize> = frame
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| {
+                // Once scrolled, the first visible line starts at the left edge
+                let line_start = if idx == 0 {
+                    input_start_col as usize
+                } else {
+                    0
+                };
+                wrapped_position(&strip_ansi_escapes(line), line_start, terminal_columns).0 + 1
+            })
+            .collect();
+
+        frame.viewport(&heights, self.viewport_start, height)
+    }
+
+    /// Truncate the rows of `hint` that do not fit into the terminal behind the cursor
+    ///
+    /// A hint wrapped by the terminal would move the text behind it and the rows below, so it
+    /// is cut at a grapheme that fits and ends in a `…` instead.
+    fn fit_hint<'hint>(
+        &self,
+        highlighted_line: &(String, String),
+        hint: &'hint str,
+    ) -> Cow<'hint, str> {
+        if hint.is_empty() {
+            return Cow::Borrowed(hint);
+        }
+        let line_break = if cfg!(windows) { "\r\n" } else { "\n" };
+        let terminal_columns = (self.terminal_columns() as usize).max(1);
+        let (before_cursor, _) = highlighted_line;
+        let cursor_line_start = before_cursor
+            .rfind(line_break)
+            .map(|idx| idx + line_break.len());
+        let line_start = match cursor_line_start {
+            Some(_) => 0,
+            None => self.input_area_start().0 as usize,
+        };
+        let (_, column) = wrapped_position(
+            &strip_ansi_escapes(&before_cursor[cursor_line_start.unwrap_or(0)..]),
+            line_start,
+            terminal_columns,
+        );
+        let mut column = column % terminal_columns;
+
+        let mut fitted = String::with_capacity(hint.len());
+        for (idx, row) in hint.split(line_break).enumerate() {
+            if idx > 0 {
+                fitted.push_str(line_break);
+                column = 0;
+            }
+            fitted.push_str(&truncate_styled(row, terminal_columns - column));
+        }
+
+        if fitted == hint {
+            Cow::Borrowed(hint)
+        } else {
+            Cow::Owned(fitted)
+        }
+    }
+
+    /// Screen position reached after printing `lines` (each ending in a line break)
+    /// followed by `partial_line`, starting from the beginning of the input area
+    fn screen_position(&self, lines: &[String], partial_line: &str) -> (u16, u16) {
+        let (input_start_col, input_start_row) = self.input_area_start();
+        let terminal_columns = (self.terminal_columns() as usize).max(1);
+
+        let mut row = input_start_row as usize;
+        let mut line_start = input_start_col as usize;
+        for line in lines {
+            let (wrapped_rows, _) =
+                wrapped_position(&strip_ansi_escapes(line), line_start, terminal_columns);
+            row += wrapped_rows + 1;
+            line_start = 0;
+        }
+
+        let (wrapped_rows, column) = wrapped_position(
+            &strip_ansi_escapes(partial_line),
+            line_start,
+            terminal_columns,
+        );
+        row += wrapped_rows;
+
+        if column >= terminal_columns {
+            // Printing anything else will continue on the next row
+            (0, (row + 1) as u16)
+        } else {
+            (column as u16, row as u16)
+        }
+    }
+
+    /// The line of the last painted frame shown at the screen position and the columns in front
+    /// of the position within that line
+    ///
+    /// Lines are counted from the start of the frame, including the lines scrolled out of view.
+    /// `None` if the position is in front of the input area or below the frame.
+    pub(crate) fn frame_position(&self, column: u16, row: u16) -> Option<(usize, DisplayWidth)> {
+        let frame = self.last_frame.as_ref()?;
+        let (input_start_col, input_start_row) = self.input_area_start();
+        let terminal_columns = (self.terminal_columns() as usize).max(1);
+        let (column, row) = (column as usize, row as usize);
+        if row < input_start_row as usize {
+            return None;
+        }
+
+        let mut line_row = input_start_row as usize;
+        let mut line_start = input_start_col as usize;
+        for (idx, line) in frame.lines.iter().enumerate() {
+            let (wrapped_rows, _) =
+                wrapped_position(&strip_ansi_escapes(line), line_start, terminal_columns);
+            if row <= line_row + wrapped_rows {
+                let width = wrapped_width(
+                    &strip_ansi_escapes(line),
+                    line_start,
+                    terminal_columns,
+                    row - line_row,
+                    column,
+                );
+                return Some((self.viewport_start + idx, width));
+            }
+            line_row += wrapped_rows + 1;
+            line_start = 0;
+        }
+
+        None
+    }
+
     /// Scroll by n rows
     pub fn scroll_rows(&mut self, num_rows: u16) -> Result<()> {
+        self.invalidate_frame();
         self.stdout
             .queue(crossterm::terminal::ScrollUp(num_rows))?
             .flush()?;
@@ -160,6 +671,10 @@ impl Painter {
 
     /// Sets the prompt origin position.
     pub(crate) fn initialize_prompt_position(&mut self) -> Result<()> {
+        self.invalidate_frame();
+        self.viewport_start = 0;
+        self.history_preview = None;
+        self.history_preview_rows = (String::new(), String::new());
         // Cursor positions are 0 based here.
         let (column, row) = cursor::position()?;
         // Assumption: if the cursor is not on the zeroth column,
@@ -186,6 +701,48 @@ impl Painter {
         Ok(())
     }
 
+    /// Checks if the screen row shows the prompt or the input area, not output above it
+    pub(crate) fn is_in_prompt_area(&self, row: u16) -> bool {
+        row >= self.prompt_coords.prompt_start.1
+    }
+
+    /// Moves the cursor back to the start of the prompt, e.g. to continue the line later
+    ///
+    /// A following [`Painter::initialize_prompt_position()`] then reuses the same origin.
+    pub(crate) fn move_to_prompt_start(&mut self) -> Result<()> {
+        self.queue_move_to(0, self.prompt_coords.prompt_start.1)?;
+        self.flush()
+    }
+
+    /// Paints the collapsed view of the previous command on its own row, if one is set
+    ///
+    /// Has to happen before [`Painter::initialize_prompt_position()`] as the prompt starts below.
+    pub(crate) fn paint_previous_command(&mut self, use_ansi_coloring: bool) -> Result<()> {
+        let previous_command = match &self.previous_command {
+            Some(previous_command) => {
+                collapse_previous_command(previous_command, self.terminal_columns() as usize)
+            }
+            None => return Ok(()),
+        };
+
+        let (column, _) = cursor::position()?;
+        if column > 0 {
+            self.print_crlf()?;
+        }
+
+        self.stdout.queue(MoveToColumn(0))?;
+        if use_ansi_coloring {
+            self.stdout
+                .queue(SetAttribute(Attribute::Dim))?
+                .queue(Print(previous_command))?
+                .queue(SetAttribute(Attribute::Reset))?;
+        } else {
+            self.stdout.queue(Print(previous_command))?;
+        }
+        self.stdout.queue(Clear(ClearType::UntilNewLine))?;
+        self.print_crlf()
+    }
+
     pub fn repaint_everything(
         &mut self,
         prompt: &dyn Prompt,
@@ -195,6 +752,13 @@ impl Painter {
         use_ansi_coloring: bool,
     ) -> Result<()> {
         self.stdout.queue(cursor::Hide)?;
+        if self.history_preview.is_some() && self.prompt_coords.prompt_start.1 > 0 {
+            let (older_row, _) = &self.history_preview_rows;
+            self.stdout
+                .queue(MoveTo(0, self.prompt_coords.prompt_start.1 - 1))?
+                .queue(Print(older_row))?
+                .queue(Clear(ClearType::UntilNewLine))?;
+        }
         self.queue_move_to(
             self.prompt_coords.prompt_start.0,
             self.prompt_coords.prompt_start.1,
@@ -202,7 +766,12 @@ impl Painter {
         self.queue_prompt(prompt, prompt_mode, use_ansi_coloring)?;
         self.flush()?;
         // set where the input begins
-        self.prompt_coords.input_start = position()?;
+        let input_start = position()?;
+        if input_start != self.prompt_coords.input_start {
+            // The previous frame is no longer where the input is painted
+            self.invalidate_frame();
+            self.prompt_coords.input_start = input_start;
+        }
         self.queue_buffer(highlighted_line, hint)?;
         self.stdout.queue(cursor::Show)?;
         self.flush()?;
@@ -212,6 +781,7 @@ impl Painter {
 
     /// Updates prompt origin and offset to handle a screen resize event
     pub(crate) fn handle_resize(&mut self, width: u16, height: u16) {
+        self.invalidate_frame();
         let prev_terminal_size = self.terminal_size;
 
         self.terminal_size = (width, height);
@@ -239,6 +809,42 @@ impl Painter {
         }
     }
 
+    /// The size of the terminal if it changed since the last resize was handled
+    ///
+    /// Used where resize events get lost, like in the Windows console with ConPTY.
+    #[cfg(windows)]
+    pub(crate) fn changed_terminal_size(&self) -> Result<Option<(u16, u16)>> {
+        let size = terminal::size()?;
+        Ok(if size != self.terminal_size {
+            Some(size)
+        } else {
+            None
+        })
+    }
+
+    /// Handle a resize after which the terminal may have reflowed the painted rows
+    ///
+    /// Instead of guessing like [`Painter::handle_resize()`], the prompt origin is derived
+    /// from where the terminal left the cursor, assuming it kept its distance to the prompt start.
+    #[cfg(windows)]
+    pub(crate) fn handle_reflowing_resize(&mut self, width: u16, height: u16) -> Result<()> {
+        let prompt_start_row = self.prompt_coords.prompt_start.1;
+        let cursor_rows_below_prompt = self.last_frame.as_ref().map_or(0, |frame| {
+            let (cursor_line, before_cursor) = &frame.cursor;
+            let (_, cursor_row) = self.screen_position(&frame.lines[..*cursor_line], before_cursor);
+            cursor_row.saturating_sub(prompt_start_row)
+        });
+
+        self.handle_resize(width, height);
+        let (_, cursor_row) = cursor::position()?;
+        let prompt_start_row = cursor_row
+            .saturating_sub(cursor_rows_below_prompt)
+            .min(height.saturating_sub(1));
+        self.prompt_coords.set_prompt_start(0, prompt_start_row);
+
+        Ok(())
+    }
+
     /// TODO! FIX the naming and provide an accurate doccomment
     /// This function repaints and updates offsets but does not purely concern it self with wrapping
     pub(crate) fn wrap(&mut self, highlighted_line: (String, String), hint: String) -> Result<()> {
@@ -274,18 +880,21 @@ impl Painter {
     }
 
     /// Heuristic to determine if we need to wrap text around.
+    ///
+    /// True as soon as any line of the buffer reaches the edge of the terminal.
     pub(crate) fn require_wrapping(&self, editor: &Editor) -> bool {
-        let line_start = if editor.line() == 0 {
-            self.prompt_coords.input_start_col()
-        } else {
-            0
-        };
-
-        let terminal_width = self.terminal_columns();
+        let terminal_columns = self.terminal_columns() as usize;
 
-        let display_width = UnicodeWidthStr::width(editor.get_buffer()) + line_start as usize;
+        editor.get_buffer().lines().enumerate().any(|(idx, line)| {
+            let line_start = if idx == 0 {
+                self.prompt_coords.input_start_col() as usize
+            } else {
+                0
+            };
+            let (wrapped_rows, column) = wrapped_position(line, line_start, terminal_columns);
 
-        display_width >= terminal_width as usize
+            wrapped_rows > 0 || column >= terminal_columns
+        })
     }
 
     /// Repositions the prompt offset position, if the buffer content would overflow the bottom of the screen.
@@ -297,25 +906,30 @@ impl Painter {
         let (input_start_col, input_start_row) = self.prompt_coords.input_start;
 
         let mut buffer_line_count = editor.num_lines() as u16;
+        if let Some(rows_below_buffer) = self.rows_below_buffer() {
+            buffer_line_count += rows_below_buffer.split('\n').count() as u16;
+        }
+        let terminal_rows = self.terminal_rows();
 
         let terminal_columns = self.terminal_columns();
 
         // Estimate where we're going to wrap around the edge of the terminal
-        for line in editor.get_buffer().lines() {
-            let estimated_width = UnicodeWidthStr::width(line);
-
-            let estimated_line_count = estimated_width as f64 / terminal_columns as f64;
-            let estimated_line_count = estimated_line_count.ceil() as u64;
+        for (idx, line) in editor.get_buffer().lines().enumerate() {
+            let line_start = if idx == 0 {
+                input_start_col as usize
+            } else {
+                0
+            };
+            let (wrapped_rows, _) = wrapped_position(line, line_start, terminal_columns as usize);
 
             // Any wrapping we estimate we might have, go ahead and add it to our line count
-            if estimated_line_count >= 1 {
-                buffer_line_count += (estimated_line_count - 1) as u16;
-            }
+            buffer_line_count += wrapped_rows as u16;
         }
 
-        let ends_in_newline = editor.ends_with('\n');
+        // Buffers taller than the terminal are shown partially, there is no need to scroll further
+        let buffer_line_count = buffer_line_count.min(terminal_rows);
 
-        let terminal_rows = self.terminal_rows();
+        let ends_in_newline = editor.ends_with('\n');
 
         if input_start_row + buffer_line_count > terminal_rows {
             let spill = input_start_row + buffer_line_count - terminal_rows;
@@ -348,12 +962,16 @@ impl Painter {
         Ok(())
     }
 
+    /// Queue the indicator of the history search, the cursor belongs at `term_offset` of its term
     pub fn queue_history_search_indicator(
         &mut self,
         prompt: &dyn Prompt,
         prompt_search: PromptHistorySearch,
+        term_offset: ByteOffset,
         use_ansi_coloring: bool,
     ) -> Result<()> {
+        self.invalidate_frame();
+        self.history_search_cursor = search_term_cursor_column(prompt, &prompt_search, term_offset);
         // print search prompt
         self.stdout.queue(MoveToColumn(0))?;
         if use_ansi_coloring {
@@ -369,27 +987,56 @@ impl Painter {
         Ok(())
     }
 
+    /// Queue the entry found by the history search with the `matches` of the search string
+    /// painted in `match_style`, the cursor is saved at `offset`
     pub fn queue_history_search_result(
         &mut self,
         history_result: &str,
-        offset: usize,
+        offset: ByteOffset,
+        matches: &[Range<usize>],
+        match_style: Style,
     ) -> Result<()> {
+        let len = history_result.len();
         self.stdout
-            .queue(Print(&history_result[..offset]))?
+            .queue(Print(paint_matches(
+                history_result,
+                0..offset.0,
+                matches,
+                match_style,
+            )))?
             .queue(SavePosition)?
-            .queue(Print(&history_result[offset..]))?
+            .queue(Print(paint_matches(
+                history_result,
+                offset.0..len,
+                matches,
+                match_style,
+            )))?
             .queue(Clear(ClearType::UntilNewLine))?
             .queue(RestorePosition)?;
 
         Ok(())
     }
 
+    /// Move the cursor into the term of the history search indicator painted last
+    ///
+    /// The cursor stays where it is if the prompt does not show the term as it was typed.
+    pub(crate) fn move_to_history_search_cursor(&mut self) -> Result<()> {
+        if let Some(column) = self.history_search_cursor {
+            self.stdout.queue(MoveToColumn(column.0 as u16))?;
+            self.stdout.flush()?;
+        }
+
+        Ok(())
+    }
+
     /// Writes `line` to the terminal with a following carriage return and newline
     pub fn paint_line(&mut self, line: &str) -> Result<()> {
+        self.invalidate_frame();
         self.stdout
             .queue(Print(line))?
+            .queue(Clear(ClearType::UntilNewLine))?
             .queue(Print("\n"))?
-            .queue(MoveToColumn(1))?;
+            .queue(MoveToColumn(0))?;
         self.stdout.flush()?;
 
         Ok(())
@@ -399,6 +1046,7 @@ impl Painter {
     ///
     /// Also works in raw mode
     pub(crate) fn print_crlf(&mut self) -> Result<()> {
+        self.invalidate_frame();
         self.stdout.queue(Print("\r\n"))?;
         self.stdout.flush()?;
 
@@ -408,6 +1056,7 @@ impl Painter {
     /// Clear the screen by printing enough whitespace to start the prompt or
     /// other output back at the first line of the terminal.
     pub fn clear_screen(&mut self) -> Result<()> {
+        self.invalidate_frame();
         let (_, num_lines) = terminal::size()?;
         for _ in 0..2 * num_lines {
             self.stdout.queue(Print("\n"))?;
@@ -418,7 +1067,72 @@ impl Painter {
         Ok(())
     }
 
+    /// Erase everything from the start of the prompt to the end of the screen
+    pub(crate) fn clear_from_prompt_start(&mut self) -> Result<()> {
+        self.invalidate_frame();
+        self.queue_move_to(0, self.prompt_coords.prompt_start.1)?;
+        self.stdout.queue(Clear(ClearType::FromCursorDown))?;
+        self.stdout.flush()?;
+
+        Ok(())
+    }
+
+    /// Enable the kitty keyboard protocol to tell apart keys legacy terminals report alike
+    pub(crate) fn enable_keyboard_enhancement(&mut self) -> Result<()> {
+        self.stdout
+            .queue(PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES,
+            ))?
+            .flush()?;
+
+        Ok(())
+    }
+
+    /// Restore the keyboard mode active before [`Painter::enable_keyboard_enhancement()`]
+    pub(crate) fn disable_keyboard_enhancement(&mut self) -> Result<()> {
+        self.stdout.queue(PopKeyboardEnhancementFlags)?.flush()?;
+
+        Ok(())
+    }
+
+    /// Switch to the alternate screen of the terminal and paint from its top left corner
+    ///
+    /// Everything painted there scrolls inside the alternate screen and leaves the normal
+    /// screen and its scrollback untouched.
+    pub(crate) fn enter_alternate_screen(&mut self) -> Result<()> {
+        self.invalidate_frame();
+        self.stdout
+            .queue(EnterAlternateScreen)?
+            .queue(MoveTo(0, 0))?
+            .flush()?;
+
+        Ok(())
+    }
+
+    /// Return to the normal screen as it was before [`Painter::enter_alternate_screen()`]
+    pub(crate) fn leave_alternate_screen(&mut self) -> Result<()> {
+        self.invalidate_frame();
+        self.stdout.queue(LeaveAlternateScreen)?.flush()?;
+
+        Ok(())
+    }
+
+    /// Let the terminal report mouse events instead of handling them itself
+    pub(crate) fn enable_mouse_capture(&mut self) -> Result<()> {
+        self.stdout.queue(EnableMouseCapture)?.flush()?;
+
+        Ok(())
+    }
+
+    /// Return the mouse to the terminal after [`Painter::enable_mouse_capture()`]
+    pub(crate) fn disable_mouse_capture(&mut self) -> Result<()> {
+        self.stdout.queue(DisableMouseCapture)?.flush()?;
+
+        Ok(())
+    }
+
     pub(crate) fn clear_until_newline(&mut self) -> Result<()> {
+        self.invalidate_frame();
         self.stdout.queue(Clear(ClearType::UntilNewLine))?;
         self.stdout.flush()?;
 
@@ -429,3 +1143,836 @@ impl Painter {
         self.stdout.flush()
     }
 }
+
+impl<W: Write> Painter<MarkupWriter<W>> {
+    /// Write colors and styles as readable markup instead of ANSI sequences
+    pub(crate) fn set_color_markup(&mut self, enabled: bool) {
+        self.stdout.set_enabled(enabled);
+    }
+}
+
+/// The rendered content of the input area, split into the rows delimited by line breaks
+#[derive(Debug, PartialEq, Eq)]
+struct Frame {
+    // Rendered rows of the buffer, the hint and status row included
+    lines: Vec<String>,
+    // Index of the row holding the cursor and the rendered content before the cursor on that row
+    cursor: (usize, String),
+}
+
+impl Frame {
+    fn new(highlighted_line: &(String, String), hint: &str, status_row: Option<&str>) -> Self {
+        let (before_cursor, after_cursor) = highlighted_line;
+        let line_break = if cfg!(windows) { "\r\n" } else { "\n" };
+
+        let mut lines: Vec<String> = before_cursor.split(line_break).map(String::from).collect();
+        let cursor_line = lines.len() - 1;
+        let before_cursor = lines[cursor_line].clone();
+
+        let mut after_cursor_lines = after_cursor.split(line_break);
+        lines[cursor_line].push_str(hint);
+        lines[cursor_line].push_str(after_cursor_lines.next().unwrap_or_default());
+        lines.extend(after_cursor_lines.map(String::from));
+        lines.extend(
+            status_row
+                .into_iter()
+                .flat_map(|status| status.split('\n'))
+                .map(String::from),
+        );
+
+        Frame {
+            lines,
+            cursor: (cursor_line, before_cursor),
+        }
+    }
+}
+
+impl Frame {
+    /// The range of lines to show in `height` rows, given the rows each line occupies
+    ///
+    /// Keeps the cursor line visible while moving as little as possible from the
+    /// previous viewport starting at `previous_start`.
+    fn viewport(&self, heights: &[usize], previous_start: usize, height: usize) -> Range<usize> {
+        let (cursor_line, _) = self.cursor;
+        let rows = |range: Range<usize>| heights[range].iter().sum::<usize>();
+        if rows(0..heights.len()) <= height {
+            return 0..heights.len();
+        }
+
+        // Lines that fit from `start` on, always at least one
+        let end_from = |start: usize| {
+            let mut end = start + 1;
+            while end < heights.len() && rows(start..end + 1) <= height {
+                end += 1;
+            }
+            end
+        };
+
+        let mut start = previous_start.min(cursor_line);
+        while end_from(start) <= cursor_line {
+            start += 1;
+        }
+        let end = end_from(start);
+        // Fill up free rows at the bottom, e.g. after lines got deleted
+        while start > 0 && rows(start - 1..end) <= height {
+            start -= 1;
+        }
+
+        start..end
+    }
+
+    /// The frame consisting only of the lines in `range`, which has to contain the cursor line
+    fn slice(&self, range: Range<usize>) -> Frame {
+        let (cursor_line, before_cursor) = &self.cursor;
+        Frame {
+            lines: self.lines[range.clone()].to_vec(),
+            cursor: (cursor_line - range.start, before_cursor.clone()),
+        }
+    }
+}
+
+/// Byte ranges of the ANSI escape sequences contained in `text`
+fn ansi_escape_ranges(text: &str) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut ranges = vec![];
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == 0x1b {
+            let start = idx;
+            idx += 1;
+            if bytes.get(idx) == Some(&b'[') {
+                idx += 1;
+                // Parameter and intermediate bytes until the final byte
+                while idx < bytes.len() && !(0x40..=0x7e).contains(&bytes[idx]) {
+                    idx += 1;
+                }
+            }
+            idx = (idx + 1).min(bytes.len());
+            ranges.push(start..idx);
+        } else {
+            idx += 1;
+        }
+    }
+    ranges
+}
+
+/// The ANSI escape sequences of `text`, without the visible content
+fn ansi_escapes(text: &str) -> String {
+    ansi_escape_ranges(text)
+        .into_iter()
+        .map(|range| &text[range])
+        .collect()
+}
+
+/// The visible content of `text`, without ANSI escape sequences
+fn strip_ansi_escapes(text: &str) -> String {
+    let mut visible = String::with_capacity(text.len());
+    let mut visible_start = 0;
+    for range in ansi_escape_ranges(text) {
+        visible.push_str(&text[visible_start..range.start]);
+        visible_start = range.end;
+    }
+    visible.push_str(&text[visible_start..]);
+    visible
+}
+
+/// Cut `text` ending in a `…` to fit in front of the last of `columns` columns
+///
+/// The cut falls between two graphemes, so a wide character is dropped as a whole. The escape
+/// sequences behind the cut are kept to end the styles of the text.
+fn truncate_styled(text: &str, columns: usize) -> String {
+    if DisplayWidth::of(&strip_ansi_escapes(text)).0 < columns {
+        return text.to_string();
+    }
+
+    let budget = columns.saturating_sub(COLLAPSED_MARKER.width() + 1);
+    let mut truncated = String::new();
+    let mut used = 0;
+    let mut visible_start = 0;
+    let mut escapes = ansi_escape_ranges(text).into_iter().peekable();
+    let cut = 'cut: loop {
+        let visible_end = escapes.peek().map_or(text.len(), |range| range.start);
+        for (offset, grapheme) in text[visible_start..visible_end].grapheme_indices(true) {
+            let columns = grapheme_width(grapheme);
+            if used + columns > budget {
+                break 'cut visible_start + offset;
+            }
+            used += columns;
+            truncated.push_str(grapheme);
+        }
+        match escapes.next() {
+            Some(range) => {
+                truncated.push_str(&text[range.clone()]);
+                visible_start = range.end;
+            }
+            None => break text.len(),
+        }
+    };
+    if columns > COLLAPSED_MARKER.width() {
+        truncated.push_str(COLLAPSED_MARKER);
+    }
+    truncated.push_str(&ansi_escapes(&text[cut..]));
+
+    truncated
+}
+
+/// Length of the common prefix of `new` and `old` that can stay on screen, at most `limit` bytes
+///
+/// The prefix never ends inside an escape sequence or before a zero width character
+/// (e.g. a combining mark), so the remainder of `new` can be printed on its own.
+fn unchanged_prefix_len(new: &str, old: &str, limit: usize) -> usize {
+    let mut len = new
+        .bytes()
+        .zip(old.bytes())
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(limit);
+
+    let escapes = ansi_escape_ranges(new);
+    let is_safe = |len: usize| {
+        new.is_char_boundary(len)
+            && !escapes
+                .iter()
+                .any(|range| range.start < len && len < range.end)
+            && !matches!(new[len..].chars().next(), Some(c) if c.width() == Some(0))
+    };
+    while len > 0 && !is_safe(len) {
+        len -= 1;
+    }
+
+    len
+}
+
+/// Collapse a command to its first line so it fits into a single terminal row of `width` columns
+///
+/// Hidden lines or truncated content are indicated by a trailing `…`
+fn collapse_previous_command(command: &str, width: usize) -> String {
+    let mut lines = command.lines();
+    let first_line = lines.next().unwrap_or_default();
+    let has_more_lines = lines.next().is_some();
+
+    if !has_more_lines && DisplayWidth::of(first_line).0 < width {
+        return first_line.to_string();
+    }
+
+    let budget = width.saturating_sub(COLLAPSED_MARKER.width() + 1);
+    let mut collapsed = String::new();
+    let mut used = 0;
+    for grapheme in first_line.graphemes(true) {
+        let columns = grapheme_width(grapheme);
+        if used + columns > budget {
+            break;
+        }
+        used += columns;
+        collapsed.push_str(grapheme);
+    }
+    collapsed.push_str(COLLAPSED_MARKER);
+
+    collapsed
+}
+
+/// Render a previewed history entry dimmed into a single row of `width` columns
+fn history_preview_row(entry: Option<&str>, width: usize, use_ansi_coloring: bool) -> String {
+    let row = entry
+        .map(|entry| collapse_previous_command(entry, width))
+        .unwrap_or_default();
+    if use_ansi_coloring && !row.is_empty() {
+        Style::new().dimmed().paint(row).to_string()
+    } else {
+        row
+    }
+}
+
+/// The part `window` of `text` with the parts covered by `matches` painted in `match_style`
+///
+/// `matches` are byte ranges of the whole `text` in ascending order.
+fn paint_matches(
+    text: &str,
+    window: Range<usize>,
+    matches: &[Range<usize>],
+    match_style: Style,
+) -> String {
+    let mut painted = String::new();
+    let mut start = window.start;
+    for range in matches {
+        let match_start = range.start.clamp(start, window.end);
+        let match_end = range.end.clamp(match_start, window.end);
+        if match_start == match_end {
+            continue;
+        }
+        painted.push_str(&text[start..match_start]);
+        painted.push_str(&match_style.paint(&text[match_start..match_end]).to_string());
+        start = match_end;
+    }
+    painted.push_str(&text[start..window.end]);
+
+    painted
+}
+
+/// Column of `term_offset` inside the search term of the rendered history search indicator
+///
+/// The indicator is rendered a second time with a marker at the offset to find it,
+/// `None` if the prompt does not show the marker.
+fn search_term_cursor_column(
+    prompt: &dyn Prompt,
+    prompt_search: &PromptHistorySearch,
+    term_offset: ByteOffset,
+) -> Option<DisplayWidth> {
+    // A private use character, not expected in any prompt
+    const MARKER: char = '\u{E000}';
+
+    let term = &prompt_search.term;
+    let marked_term = format!(
+        "{}{}{}",
+        term.get(..term_offset.0)?,
+        MARKER,
+        term.get(term_offset.0..)?
+    );
+    let rendered = prompt.render_prompt_history_search_indicator(PromptHistorySearch::new(
+        prompt_search.status,
+        marked_term,
+    ));
+
+    let before_marker = &rendered[..rendered.find(MARKER)?];
+    let line_start = before_marker.rfind('\n').map_or(0, |newline| newline + 1);
+    let visible = strip_ansi_escapes::strip(&before_marker[line_start..]).ok()?;
+
+    Some(DisplayWidth::of(&String::from_utf8_lossy(&visible)))
+}
+
+/// The `file://` URL of `path` on `host` as reported with OSC 7, the path percent-encoded
+fn file_url(host: &str, path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut url = format!("file://{}", host);
+    if !path.starts_with('/') {
+        // Windows paths start with the drive letter
+        url.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+/// The name of this machine, terminals ignore directories reported for other hosts
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut name = [0u8; 256];
+    // SAFETY: `gethostname` writes at most the passed length into the buffer
+    let result = unsafe { libc::gethostname(name.as_mut_ptr() as *mut libc::c_char, name.len()) };
+    if result != 0 {
+        return String::new();
+    }
+    let len = name
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(name.len());
+    String::from_utf8_lossy(&name[..len]).into_owned()
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::DefaultPrompt;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("/home/me/src", "file://host/home/me/src")]
+    #[case("/tmp/with space/ü", "file://host/tmp/with%20space/%C3%BC")]
+    #[case("C:\\Users\\me", "file://host/C:/Users/me")]
+    fn file_url_encodes_path(#[case] path: &str, #[case] expected: &str) {
+        assert_eq!(file_url("host", Path::new(path)), expected);
+    }
+
+    struct TitledPrompt(&'static str);
+
+    impl Prompt for TitledPrompt {
+        fn render_prompt(&self, _: usize) -> Cow<'_, str> {
+            Cow::Borrowed("")
+        }
+
+        fn render_prompt_indicator(&self, _: PromptEditMode) -> Cow<'_, str> {
+            Cow::Borrowed("> ")
+        }
+
+        fn render_prompt_multiline_indicator(&self, _: Option<&str>) -> Cow<'_, str> {
+            Cow::Borrowed("::: ")
+        }
+
+        fn render_prompt_history_search_indicator(&self, _: PromptHistorySearch) -> Cow<'_, str> {
+            Cow::Borrowed("? ")
+        }
+
+        fn render_window_title(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+    }
+
+    #[test]
+    fn window_title_drops_control_characters() {
+        let mut painter = Painter::new(Vec::new());
+        painter
+            .queue_prompt(
+                &TitledPrompt("vim\x07\x1b]0;owned\x1b\\"),
+                PromptEditMode::Default,
+                false,
+            )
+            .unwrap();
+
+        let output = String::from_utf8(painter.stdout).unwrap();
+        assert!(output.starts_with("\x1b]0;vim]0;owned\\\x07"));
+    }
+
+    #[test]
+    fn semantic_markers_wrap_prompt_and_command() {
+        let mut painter = Painter::new(Vec::new());
+        painter.set_semantic_prompt_markers(true);
+        let prompt = DefaultPrompt::default();
+
+        painter
+            .queue_prompt(&prompt, PromptEditMode::Default, false)
+            .unwrap();
+        painter.mark_command_start().unwrap();
+        let first = String::from_utf8(std::mem::take(&mut painter.stdout)).unwrap();
+        assert!(first.contains(PROMPT_START_MARKER));
+        assert!(first.ends_with(&format!("{}{}", INPUT_START_MARKER, OUTPUT_START_MARKER)));
+        assert!(!first.contains(COMMAND_END_MARKER));
+
+        painter
+            .queue_prompt(&prompt, PromptEditMode::Default, false)
+            .unwrap();
+        let second = String::from_utf8(std::mem::take(&mut painter.stdout)).unwrap();
+        assert!(second.contains(&format!("{}{}", COMMAND_END_MARKER, PROMPT_START_MARKER)));
+    }
+
+    #[test]
+    fn frame_joins_hint_into_cursor_line() {
+        let frame = Frame::new(&("ab\ncd".to_string(), "ef\ngh".to_string()), "HINT", None);
+
+        assert_eq!(
+            frame,
+            Frame {
+                lines: vec!["ab".to_string(), "cdHINTef".to_string(), "gh".to_string()],
+                cursor: (1, "cd".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn frame_of_empty_buffer_has_one_line() {
+        let frame = Frame::new(&(String::new(), String::new()), "", None);
+
+        assert_eq!(frame.lines, vec![String::new()]);
+        assert_eq!(frame.cursor, (0, String::new()));
+    }
+
+    #[test]
+    fn frame_puts_status_row_below_buffer() {
+        let frame = Frame::new(&("a".to_string(), "\nb".to_string()), "", Some("status"));
+
+        assert_eq!(frame.lines, vec!["a", "b", "status"]);
+        assert_eq!(frame.cursor, (0, "a".to_string()));
+    }
+
+    #[test]
+    fn frame_splits_status_into_rows() {
+        let frame = Frame::new(&("a".to_string(), String::new()), "", Some("one\ntwo"));
+
+        assert_eq!(frame.lines, vec!["a", "one", "two"]);
+    }
+
+    #[rstest]
+    #[case(6, 2, Some((0, DisplayWidth(2))))]
+    #[case(1, 2, Some((0, DisplayWidth(0))))]
+    #[case(1, 3, Some((0, DisplayWidth(7))))]
+    #[case(1, 4, Some((1, DisplayWidth(1))))]
+    #[case(9, 4, Some((1, DisplayWidth(9))))]
+    #[case(5, 1, None)]
+    #[case(0, 5, None)]
+    fn frame_position_maps_wrapped_rows_to_lines(
+        #[case] column: u16,
+        #[case] row: u16,
+        #[case] expected: Option<(usize, DisplayWidth)>,
+    ) {
+        let mut painter = Painter::new(std::io::stdout());
+        painter.terminal_size = (10, 24);
+        painter.prompt_coords.set_input_start(4, 2);
+        painter.last_frame = Some(Frame::new(
+            &("abcdefghij\nxy".to_string(), String::new()),
+            "",
+            None,
+        ));
+
+        assert_eq!(painter.frame_position(column, row), expected);
+    }
+
+    #[rstest]
+    #[case(0, 3, Some((0, DisplayWidth(6))))]
+    // The empty cell left by the wide character moved to the next row
+    #[case(9, 3, Some((0, DisplayWidth(15))))]
+    #[case(1, 4, Some((0, DisplayWidth(15))))]
+    #[case(3, 4, Some((0, DisplayWidth(18))))]
+    fn frame_position_maps_cells_inside_long_token(
+        #[case] column: u16,
+        #[case] row: u16,
+        #[case] expected: Option<(usize, DisplayWidth)>,
+    ) {
+        let mut painter = Painter::new(std::io::stdout());
+        painter.terminal_size = (10, 24);
+        painter.prompt_coords.set_input_start(4, 2);
+        painter.last_frame = Some(Frame::new(
+            &("aGVsbG8gd29漢漢漢bGQ".to_string(), String::new()),
+            "",
+            None,
+        ));
+
+        assert_eq!(painter.frame_position(column, row), expected);
+    }
+
+    fn frame_with_cursor_line(line_count: usize, cursor_line: usize) -> Frame {
+        Frame {
+            lines: (0..line_count).map(|idx| idx.to_string()).collect(),
+            cursor: (cursor_line, String::new()),
+        }
+    }
+
+    #[rstest]
+    #[case(3, 0, 0, 5, 0..3)]
+    #[case(10, 9, 0, 4, 6..10)]
+    #[case(10, 2, 0, 4, 0..4)]
+    // Moving the cursor within the viewport does not scroll
+    #[case(10, 5, 4, 4, 4..8)]
+    // Moving the cursor above the viewport scrolls up
+    #[case(10, 2, 4, 4, 2..6)]
+    // The previous viewport got out of range, fill the rows again
+    #[case(6, 5, 4, 4, 2..6)]
+    fn viewport_keeps_cursor_visible(
+        #[case] line_count: usize,
+        #[case] cursor_line: usize,
+        #[case] previous_start: usize,
+        #[case] height: usize,
+        #[case] expected: Range<usize>,
+    ) {
+        let frame = frame_with_cursor_line(line_count, cursor_line);
+        let heights = vec![1; line_count];
+
+        assert_eq!(frame.viewport(&heights, previous_start, height), expected);
+    }
+
+    #[test]
+    fn viewport_accounts_for_wrapped_lines() {
+        let frame = frame_with_cursor_line(4, 3);
+
+        assert_eq!(frame.viewport(&[1, 1, 3, 2], 0, 5), 2..4);
+        assert_eq!(frame.viewport(&[1, 1, 1, 9], 0, 5), 3..4);
+    }
+
+    #[test]
+    fn slice_moves_cursor_line() {
+        let frame = frame_with_cursor_line(5, 3).slice(2..5);
+
+        assert_eq!(frame.lines, vec!["2", "3", "4"]);
+        assert_eq!(frame.cursor, (1, String::new()));
+    }
+
+    #[rstest]
+    #[case("plain", "plain")]
+    #[case("\x1b[1;32mgreen\x1b[0m", "green")]
+    #[case("日本\x1b[0m", "日本")]
+    #[case("", "")]
+    fn strip_ansi_escapes_keeps_visible_content(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(strip_ansi_escapes(text), expected);
+    }
+
+    #[test]
+    fn ansi_escapes_keeps_only_sequences() {
+        assert_eq!(ansi_escapes("\x1b[32mab\x1b[0mc"), "\x1b[32m\x1b[0m");
+    }
+
+    #[rstest]
+    #[case("hello", "help", 10, 3)]
+    #[case("hello", "hello", 2, 2)]
+    #[case("abc", "xyz", 10, 0)]
+    // Never stop inside an escape sequence
+    #[case("\x1b[32mab", "\x1b[31mab", 10, 0)]
+    // Never split a multi byte character
+    #[case("aä", "aö", 10, 1)]
+    // Keep combining marks together with their base character
+    #[case("ae\u{301}", "ae", 10, 1)]
+    fn unchanged_prefix_stops_at_safe_position(
+        #[case] new: &str,
+        #[case] old: &str,
+        #[case] limit: usize,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(unchanged_prefix_len(new, old, limit), expected);
+    }
+
+    #[rstest]
+    #[case("ls -la", 80, "ls -la")]
+    #[case("", 80, "")]
+    #[case("for x in y {\n  x\n}", 80, "for x in y {…")]
+    #[case("echo hello world", 10, "echo hel…")]
+    #[case("echo 日本語", 10, "echo 日…")]
+    fn collapses_previous_command(
+        #[case] command: &str,
+        #[case] width: usize,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(collapse_previous_command(command, width), expected);
+    }
+
+    #[rstest]
+    #[case(("ab".into(), "c".into()), ("ab".into(), "c".into()))]
+    #[case(("a\nb".into(), "c\nd".into()), ("1│ a\n2│ b".into(), "c\n3│ d".into()))]
+    #[case(("a".into(), "\n".into()), ("1│ a".into(), "\n2│ ".into()))]
+    fn gutter_numbers_lines(
+        #[case] highlighted_line: (String, String),
+        #[case] expected: (String, String),
+    ) {
+        assert_eq!(
+            MultilineGutter::LineNumbers.apply(highlighted_line, false),
+            expected
+        );
+    }
+
+    #[test]
+    fn gutter_pads_line_numbers_to_same_width() {
+        let buffer = (1..=10)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let (before_cursor, _) = MultilineGutter::LineNumbers.apply((buffer, String::new()), false);
+
+        assert!(before_cursor.starts_with(" 1│ 1\n 2│ 2\n"));
+        assert!(before_cursor.ends_with("\n10│ 10"));
+    }
+
+    #[test]
+    fn gutter_marks_continuation_lines() {
+        let gutter = MultilineGutter::ContinuationMarker("┆ ".into());
+
+        assert_eq!(
+            gutter.apply(("a\nb".into(), "\nc".into()), false),
+            ("  a\n┆ b".into(), "\n┆ c".into())
+        );
+    }
+
+    #[test]
+    fn gutter_is_dimmed_with_ansi_coloring() {
+        let (before_cursor, _) =
+            MultilineGutter::LineNumbers.apply(("a\nb".into(), String::new()), true);
+
+        assert_eq!(strip_ansi_escapes(&before_cursor), "1│ a\n2│ b");
+        assert!(before_cursor.starts_with(&Style::new().dimmed().prefix().to_string()));
+    }
+
+    #[test]
+    fn gutter_keeps_style_continuing_from_previous_line() {
+        let blue = Style::new().fg(nu_ansi_term::Color::Blue);
+        let highlighted_line = (
+            format!("a{}\n::: {}b", blue.prefix(), blue.suffix()),
+            String::new(),
+        );
+
+        let (before_cursor, _) = MultilineGutter::LineNumbers.apply(highlighted_line, true);
+        let second_line = before_cursor.split('\n').nth(1).unwrap();
+
+        assert!(second_line.ends_with(&format!("{}::: {}b", blue.prefix(), blue.suffix())));
+    }
+
+    #[test]
+    fn history_preview_row_is_collapsed_and_dimmed() {
+        assert_eq!(
+            history_preview_row(Some("echo hello\nworld"), 80, false),
+            "echo hello…"
+        );
+        assert_eq!(
+            history_preview_row(Some("ls"), 80, true),
+            Style::new().dimmed().paint("ls").to_string()
+        );
+        assert_eq!(history_preview_row(None, 80, true), "");
+    }
+
+    #[test]
+    fn status_row_takes_precedence_over_history_preview() {
+        let mut painter = Painter::new(std::io::stdout());
+        painter.history_preview_rows = ("older".to_string(), "newer".to_string());
+        assert_eq!(painter.rows_below_buffer().as_deref(), Some("newer"));
+
+        painter.set_status_row(Some("status".to_string()));
+        assert_eq!(painter.rows_below_buffer().as_deref(), Some("status"));
+    }
+
+    #[test]
+    fn widget_rows_come_last() {
+        let mut painter = Painter::new(std::io::stdout());
+        painter.set_widget_rows(Some("widget".to_string()));
+        assert_eq!(painter.rows_below_buffer().as_deref(), Some("widget"));
+
+        painter.set_status_row(Some("menu\nrows".to_string()));
+        assert_eq!(
+            painter.rows_below_buffer().as_deref(),
+            Some("menu\nrows\nwidget")
+        );
+    }
+
+    #[test]
+    fn hint_rows_come_right_below_buffer() {
+        let mut painter = Painter::new(std::io::stdout());
+        painter.set_hint_rows(Some("hint".to_string()));
+        painter.set_widget_rows(Some("widget".to_string()));
+        painter.set_status_row(Some("status".to_string()));
+
+        assert_eq!(
+            painter.rows_below_buffer().as_deref(),
+            Some("hint\nstatus\nwidget")
+        );
+    }
+
+    #[rstest]
+    #[case("hint", 10, "hint")]
+    #[case("hint", 4, "hi…")]
+    #[case("日本語です", 8, "日本語…")]
+    #[case("日本語です", 7, "日本…")]
+    #[case("👩\u{200d}👩\u{200d}👧 family", 4, "👩\u{200d}👩\u{200d}👧…")]
+    #[case("hint", 1, "")]
+    fn hints_are_truncated_at_graphemes(
+        #[case] hint: &str,
+        #[case] columns: usize,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(truncate_styled(hint, columns), expected);
+    }
+
+    #[test]
+    fn truncated_hint_keeps_its_style() {
+        let green = nu_ansi_term::Color::Green;
+        let hint = green.paint("abcdef").to_string();
+
+        assert_eq!(
+            truncate_styled(&hint, 5),
+            format!("{}abc…{}", green.prefix(), green.suffix())
+        );
+    }
+
+    #[test]
+    fn hint_is_fitted_behind_cursor() {
+        let mut painter = Painter::new(std::io::stdout());
+        painter.terminal_size = (20, 24);
+        painter.prompt_coords.set_input_start(2, 0);
+        let line = ("ls\necho ".to_string(), String::new());
+
+        assert_eq!(painter.fit_hint(&line, "hello"), "hello");
+        assert_eq!(
+            painter.fit_hint(&line, "hello wonderful world"),
+            "hello wonderf…"
+        );
+        let first_line = ("echo ".to_string(), String::new());
+        assert_eq!(
+            painter.fit_hint(&first_line, "hello wonderful world"),
+            "hello wonde…"
+        );
+    }
+
+    #[rstest]
+    #[case("abc", 3, 20)]
+    #[case("abc", 0, 17)]
+    #[case("日本語", 3, 19)]
+    #[case("", 0, 17)]
+    fn search_term_cursor_column_counts_wide_characters(
+        #[case] term: &str,
+        #[case] offset: usize,
+        #[case] expected: usize,
+    ) {
+        let prompt = crate::DefaultPrompt::default();
+        let prompt_search =
+            PromptHistorySearch::new(crate::PromptHistorySearchStatus::Passing, term.to_string());
+
+        assert_eq!(
+            search_term_cursor_column(&prompt, &prompt_search, ByteOffset(offset)),
+            Some(DisplayWidth(expected))
+        );
+    }
+
+    /// Bytes written to repaint the buffer split at the cursor into `after` when `before`
+    /// is painted already, on a terminal of 80 columns
+    fn repaint_bytes(before: (&str, &str), after: (&str, &str)) -> usize {
+        let mut painter = Painter::new(Vec::new());
+        painter.terminal_size = (80, 24);
+        painter.prompt_coords.set_input_start(2, 0);
+        painter
+            .queue_buffer((before.0.into(), before.1.into()), String::new())
+            .unwrap();
+        painter.stdout.clear();
+        painter
+            .queue_buffer((after.0.into(), after.1.into()), String::new())
+            .unwrap();
+
+        painter.stdout.len()
+    }
+
+    #[test]
+    fn matches_are_painted_within_window() {
+        let bold = Style::new().bold();
+        let text = "git commit -m commit";
+        let matches = [4..10, 14..20];
+
+        assert_eq!(
+            paint_matches(text, 0..text.len(), &matches, bold),
+            format!("git {} -m {}", bold.paint("commit"), bold.paint("commit"))
+        );
+        assert_eq!(
+            paint_matches(text, 7..16, &matches, bold),
+            format!("{} -m {}", bold.paint("mit"), bold.paint("co"))
+        );
+        assert_eq!(paint_matches(text, 0..3, &matches, bold), "git");
+    }
+
+    #[test]
+    fn color_markup_replaces_styles_of_painted_frame() {
+        let mut painter = Painter::new(MarkupWriter::new(Vec::new()));
+        painter.set_color_markup(true);
+        painter.terminal_size = (80, 24);
+        let styled = nu_ansi_term::Color::Green.paint("ls").to_string();
+        painter
+            .queue_buffer((styled, String::new()), String::new())
+            .unwrap();
+        painter.flush().unwrap();
+
+        let output = String::from_utf8(painter.stdout.into_inner()).unwrap();
+        assert!(output.contains("[fg=green]ls[/]"), "{:?}", output);
+        assert!(!output.contains("\x1b[32m"));
+    }
+
+    #[test]
+    fn small_edits_repaint_few_bytes() {
+        let long_line = "x".repeat(1000);
+        let lines = vec!["echo hello world | grep hello | wc -l &&"; 100].join("\n");
+        let (head, tail) = lines.split_at(lines.len() / 2);
+
+        // Only the changed part and the cursor handling is written, independent of the size
+        assert!(repaint_bytes((&long_line, ""), (&format!("{}y", long_line), "")) <= 32);
+        assert!(repaint_bytes((&long_line, ""), (&long_line[1..], "x")) <= 32);
+        assert!(repaint_bytes((head, tail), (&format!("{}y", head), tail)) <= 32);
+        assert!(
+            repaint_bytes(
+                (head, tail),
+                (&head[..head.len() - 1], &lines[head.len() - 1..])
+            ) <= 32
+        );
+        assert!(repaint_bytes((head, tail), (head, tail)) <= 32);
+    }
+}
@@ -0,0 +1,141 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What happens to terminal escape sequences and control characters in pasted text
+///
+/// The terminal sends a paste like typed input, so an escape sequence or control character
+/// copied along with some text arrives as a key press, e.g. `\x1b[A` as `Up` or `\x03` as
+/// `Ctrl-C`, and would run the command bound to it. Pasted keys that do not insert text are
+/// handled as set here, tabs are always inserted as they are.
+///
+/// See [`Reedline::with_paste_sanitization()`](crate::Reedline::with_paste_sanitization).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PasteSanitization {
+    /// Insert the sequence of the key into the buffer, where it is shown in caret notation
+    /// like `^[[A` and can be edited
+    #[default]
+    Visualize,
+    /// Leave the key out of the paste
+    Strip,
+    /// Handle the key like a typed one
+    Off,
+}
+
+/// Whether `key` only inserts text, i.e. a character or a line break
+pub(crate) fn is_text_key(key: &KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char(_) => (key.modifiers - KeyModifiers::SHIFT).is_empty(),
+        KeyCode::Enter => key.modifiers.is_empty(),
+        _ => false,
+    }
+}
+
+/// The text a terminal sends for `key`, `None` for keys without a common sequence
+pub(crate) fn key_sequence(key: &KeyEvent) -> Option<String> {
+    let modifiers = key.modifiers;
+    let alt = if modifiers.contains(KeyModifiers::ALT) {
+        "\x1b"
+    } else {
+        ""
+    };
+    // Modifier parameter of the xterm sequences, 1 without modifiers
+    let parameter = 1
+        + u8::from(modifiers.contains(KeyModifiers::SHIFT))
+        + 2 * u8::from(modifiers.contains(KeyModifiers::ALT))
+        + 4 * u8::from(modifiers.contains(KeyModifiers::CONTROL));
+    let csi = |last: char| match parameter {
+        1 => format!("\x1b[{}", last),
+        _ => format!("\x1b[1;{}{}", parameter, last),
+    };
+    let tilde = |number: u8| match parameter {
+        1 => format!("\x1b[{}~", number),
+        _ => format!("\x1b[{};{}~", number, parameter),
+    };
+
+    let sequence = match key.code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+            let control = match c {
+                ' ' | '@' => '\0',
+                '?' => '\x7f',
+                c if c.is_ascii_alphabetic() || ('['..='_').contains(&c) => {
+                    ((c.to_ascii_uppercase() as u8) & 0x1f) as char
+                }
+                _ => return None,
+            };
+            format!("{}{}", alt, control)
+        }
+        KeyCode::Char(c) => format!("{}{}", alt, c),
+        KeyCode::Esc => format!("{}\x1b", alt),
+        KeyCode::Backspace => format!("{}\x7f", alt),
+        KeyCode::Enter => format!("{}\r", alt),
+        KeyCode::Tab => format!("{}\t", alt),
+        KeyCode::BackTab => "\x1b[Z".to_string(),
+        KeyCode::Up => csi('A'),
+        KeyCode::Down => csi('B'),
+        KeyCode::Right => csi('C'),
+        KeyCode::Left => csi('D'),
+        KeyCode::Home => csi('H'),
+        KeyCode::End => csi('F'),
+        KeyCode::Insert => tilde(2),
+        KeyCode::Delete => tilde(3),
+        KeyCode::PageUp => tilde(5),
+        KeyCode::PageDown => tilde(6),
+        KeyCode::F(number @ 1..=4) => format!("\x1bO{}", (b'P' + number - 1) as char),
+        KeyCode::F(5) => tilde(15),
+        KeyCode::F(number @ 6..=10) => tilde(number + 11),
+        KeyCode::F(number @ 11..=12) => tilde(number + 12),
+        _ => return None,
+    };
+
+    Some(sequence)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(KeyCode::Char('a'), KeyModifiers::NONE, true)]
+    #[case(KeyCode::Char('A'), KeyModifiers::SHIFT, true)]
+    #[case(KeyCode::Enter, KeyModifiers::NONE, true)]
+    #[case(KeyCode::Char('c'), KeyModifiers::CONTROL, false)]
+    #[case(KeyCode::Char('b'), KeyModifiers::ALT, false)]
+    #[case(KeyCode::Esc, KeyModifiers::NONE, false)]
+    #[case(KeyCode::Up, KeyModifiers::NONE, false)]
+    #[case(KeyCode::Tab, KeyModifiers::NONE, false)]
+    fn only_characters_and_line_breaks_are_text(
+        #[case] code: KeyCode,
+        #[case] modifiers: KeyModifiers,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_text_key(&KeyEvent::new(code, modifiers)), expected);
+    }
+
+    #[rstest]
+    #[case(KeyCode::Char('c'), KeyModifiers::CONTROL, Some("\x03"))]
+    #[case(KeyCode::Char('['), KeyModifiers::CONTROL, Some("\x1b"))]
+    #[case(KeyCode::Char(' '), KeyModifiers::CONTROL, Some("\0"))]
+    #[case(KeyCode::Char('b'), KeyModifiers::ALT, Some("\x1bb"))]
+    #[case(KeyCode::Esc, KeyModifiers::NONE, Some("\x1b"))]
+    #[case(KeyCode::Backspace, KeyModifiers::NONE, Some("\x7f"))]
+    #[case(KeyCode::Up, KeyModifiers::NONE, Some("\x1b[A"))]
+    #[case(KeyCode::Left, KeyModifiers::CONTROL, Some("\x1b[1;5D"))]
+    #[case(KeyCode::Delete, KeyModifiers::NONE, Some("\x1b[3~"))]
+    #[case(KeyCode::PageUp, KeyModifiers::SHIFT, Some("\x1b[5;2~"))]
+    #[case(KeyCode::F(1), KeyModifiers::NONE, Some("\x1bOP"))]
+    #[case(KeyCode::F(5), KeyModifiers::NONE, Some("\x1b[15~"))]
+    #[case(KeyCode::F(12), KeyModifiers::NONE, Some("\x1b[24~"))]
+    #[case(KeyCode::F(13), KeyModifiers::NONE, None)]
+    #[case(KeyCode::Null, KeyModifiers::NONE, None)]
+    fn keys_are_turned_back_into_their_sequences(
+        #[case] code: KeyCode,
+        #[case] modifiers: KeyModifiers,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(
+            key_sequence(&KeyEvent::new(code, modifiers)).as_deref(),
+            expected
+        );
+    }
+}
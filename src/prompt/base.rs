@@ -0,0 +1,114 @@
+use {
+    crossterm::style::Color,
+    std::{borrow::Cow, path::PathBuf},
+};
+
+/// The default color for the prompt
+pub static DEFAULT_PROMPT_COLOR: Color = Color::Blue;
+
+/// The default prompt indicator
+pub static DEFAULT_PROMPT_INDICATOR: &str = "〉";
+pub static DEFAULT_VI_INSERT_PROMPT_INDICATOR: &str = ": ";
+pub static DEFAULT_VI_VISUAL_PROMPT_INDICATOR: &str = "v ";
+pub static DEFAULT_MULTILINE_INDICATOR: &str = "::: ";
+
+/// The current success/failure of the history search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptHistorySearchStatus {
+    /// Success for the search
+    Passing,
+
+    /// Failure to find the search
+    Failing,
+}
+
+/// A representation of the history search
+#[derive(Debug, Clone)]
+pub struct PromptHistorySearch {
+    /// The status of the search
+    pub status: PromptHistorySearchStatus,
+
+    /// The search term used during the search
+    pub term: String,
+}
+
+impl PromptHistorySearch {
+    /// A constructor to create a history search
+    pub fn new(status: PromptHistorySearchStatus, search_term: String) -> Self {
+        PromptHistorySearch {
+            status,
+            term: search_term,
+        }
+    }
+}
+
+/// Modes that the prompt can be in
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PromptEditMode {
+    /// The default mode
+    Default,
+
+    /// Emacs normal mode
+    Emacs,
+
+    /// A vi-specific mode
+    Vi(PromptViMode),
+
+    /// A custom mode
+    Custom(String),
+}
+
+/// The vi-specific modes that the prompt can be in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PromptViMode {
+    /// The default mode
+    Normal,
+
+    /// Insertion mode
+    Insert,
+
+    /// Visual mode
+    Visual,
+}
+
+/// API to provide a custom prompt.
+///
+/// Implementors have to provide [`str`]-based content which will be
+/// displayed before the `LineBuffer` is drawn.
+pub trait Prompt {
+    /// Provide content off the full prompt. May use a line above the entry buffer that fits into `screen_width`.
+    fn render_prompt(&self, screen_width: usize) -> Cow<str>;
+    /// Render the prompt indicator for the current edit mode
+    ///
+    /// Called with the live mode whenever it changes, e.g. when switching between
+    /// the vi normal and insert mode.
+    fn render_prompt_indicator(&self, prompt_mode: PromptEditMode) -> Cow<str>;
+    /// Render the indicator in front of the continuation lines of a multiline buffer
+    ///
+    /// `incomplete_reason` is the reason the [`Validator`](crate::Validator) gave for the
+    /// input being incomplete at the last attempt to submit it, e.g. `quote` or `brace`.
+    fn render_prompt_multiline_indicator(&self, incomplete_reason: Option<&str>) -> Cow<str>;
+    /// Render the default prompt indicator
+    fn render_prompt_history_search_indicator(
+        &self,
+        history_search: PromptHistorySearch,
+    ) -> Cow<str>;
+    /// Render the vi insert mode prompt indicator
+    /// Get back the prompt color
+    fn get_prompt_color(&self) -> Color {
+        DEFAULT_PROMPT_COLOR
+    }
+    /// Render the title of the terminal window, set whenever the prompt is painted
+    ///
+    /// `None`, the default, leaves the title to the terminal and the programs run.
+    fn render_window_title(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+    /// Get the working directory reported to the terminal whenever the prompt is painted
+    ///
+    /// Terminals use the report (OSC 7) to open new tabs and windows in the same directory.
+    /// `None`, the default, reports nothing.
+    fn get_working_directory(&self) -> Option<PathBuf> {
+        None
+    }
+}
@@ -0,0 +1,227 @@
+use {
+    super::{
+        PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus, PromptSegments,
+        PromptViMode, DEFAULT_MULTILINE_INDICATOR, DEFAULT_PROMPT_INDICATOR,
+        DEFAULT_VI_INSERT_PROMPT_INDICATOR, DEFAULT_VI_VISUAL_PROMPT_INDICATOR,
+    },
+    crate::Prompt,
+    chrono::Local,
+    std::{borrow::Cow, collections::HashMap, env},
+};
+
+impl Prompt for DefaultPrompt {
+    fn render_prompt(&self, screen_width: usize) -> Cow<str> {
+        DefaultPrompt::render_prompt(self, screen_width)
+    }
+
+    fn render_prompt_indicator(&self, edit_mode: PromptEditMode) -> Cow<str> {
+        if let Some(indicator) = self.indicators.get(&edit_mode) {
+            return indicator.as_str().into();
+        }
+
+        match edit_mode {
+            PromptEditMode::Default | PromptEditMode::Emacs => DEFAULT_PROMPT_INDICATOR.into(),
+            PromptEditMode::Vi(vi_mode) => match vi_mode {
+                PromptViMode::Normal => DEFAULT_PROMPT_INDICATOR.into(),
+                PromptViMode::Insert => DEFAULT_VI_INSERT_PROMPT_INDICATOR.into(),
+                PromptViMode::Visual => DEFAULT_VI_VISUAL_PROMPT_INDICATOR.into(),
+            },
+            PromptEditMode::Custom(str) => {
+                DefaultPrompt::default_wrapped_custom_string(&str).into()
+            }
+        }
+    }
+
+    fn render_prompt_multiline_indicator(&self, incomplete_reason: Option<&str>) -> Cow<str> {
+        match incomplete_reason {
+            Some(reason) => Cow::Owned(format!("{}> ", reason)),
+            None => Cow::Borrowed(DEFAULT_MULTILINE_INDICATOR),
+        }
+    }
+
+    fn render_prompt_history_search_indicator(
+        &self,
+        history_search: PromptHistorySearch,
+    ) -> Cow<str> {
+        let prefix = match history_search.status {
+            PromptHistorySearchStatus::Passing => "",
+            PromptHistorySearchStatus::Failing => "failing ",
+        };
+        // NOTE: magic strings, givent there is logic on how these compose I am not sure if it
+        // is worth extracting in to static constant
+        Cow::Owned(format!(
+            "({}reverse-search: {}) ",
+            prefix, history_search.term
+        ))
+    }
+}
+
+impl Default for DefaultPrompt {
+    fn default() -> Self {
+        DefaultPrompt::new(1)
+    }
+}
+
+/// Simple two-line [`Prompt`] displaying the current working directory and the time above the entry line.
+#[derive(Clone)]
+pub struct DefaultPrompt {
+    // The minimum number of line buffer character space between the
+    // the left prompt and the right prompt. When this encroaches
+    // into the right side prompt, we should not show the right
+    // prompt.
+    min_center_spacing: u16,
+    // Indicators replacing the default ones for specific edit modes
+    indicators: HashMap<PromptEditMode, String>,
+    // Host content shown after the working directory
+    segments: PromptSegments,
+}
+
+impl DefaultPrompt {
+    /// Constructor for the default prompt, which takes the amount of spaces required between the left and right-hand sides of the prompt
+    pub fn new(min_center_spacing: u16) -> DefaultPrompt {
+        DefaultPrompt {
+            min_center_spacing,
+            indicators: HashMap::new(),
+            segments: PromptSegments::default(),
+        }
+    }
+
+    /// A builder to show a custom prompt indicator when in the given edit mode
+    ///
+    /// ```rust
+    /// use reedline::{DefaultPrompt, PromptEditMode, PromptViMode};
+    ///
+    /// let prompt = DefaultPrompt::default()
+    ///     .with_indicator(PromptEditMode::Vi(PromptViMode::Normal), "[N] ")
+    ///     .with_indicator(PromptEditMode::Vi(PromptViMode::Insert), "> ");
+    /// ```
+    pub fn with_indicator(
+        mut self,
+        edit_mode: PromptEditMode,
+        indicator: impl Into<String>,
+    ) -> DefaultPrompt {
+        self.indicators.insert(edit_mode, indicator.into());
+        self
+    }
+
+    /// A builder to show the values of host defined segments after the working directory
+    pub fn with_segments(mut self, segments: PromptSegments) -> DefaultPrompt {
+        self.segments = segments;
+        self
+    }
+
+    // NOTE: This method currently assumes all characters are 1 column wide. This should be
+    // ok for now since we're just displaying the current directory and date/time, which are
+    // unlikely to contain characters that use 2 columns.
+    fn render_prompt(&self, cols: usize) -> Cow<str> {
+        let mut prompt_str = String::new();
+
+        let mut left_prompt = get_working_dir().unwrap_or_else(|_| String::from("no path"));
+        for segment in self.segments.render() {
+            if !segment.is_empty() {
+                left_prompt.push(' ');
+                left_prompt.push_str(&segment);
+            }
+        }
+        left_prompt.truncate(cols);
+        let left_prompt_width = left_prompt.chars().count();
+        prompt_str.push_str(&left_prompt);
+
+        let right_prompt = get_now();
+        let right_prompt_width = right_prompt.chars().count();
+
+        // Only print right prompt if there's enough room for it.
+        if left_prompt_width + usize::from(self.min_center_spacing) + right_prompt_width <= cols {
+            let right_prompt = format!("{:>width$}", get_now(), width = cols - left_prompt_width);
+            prompt_str.push_str(&right_prompt);
+        } else if left_prompt_width < cols {
+            let right_padding = format!("{:>width$}", "", width = cols - left_prompt_width);
+            prompt_str.push_str(&right_padding);
+        }
+
+        Cow::Owned(prompt_str)
+    }
+
+    fn default_wrapped_custom_string(str: &str) -> String {
+        format!("({})", str)
+    }
+}
+
+fn get_working_dir() -> Result<String, std::io::Error> {
+    let path = env::current_dir()?;
+    Ok(path.display().to_string())
+}
+
+fn get_now() -> String {
+    let now = Local::now();
+    format!("{}", now.format("%m/%d/%Y %I:%M:%S %p"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn default_prompt_distinguishes_vi_modes() {
+        let prompt = DefaultPrompt::default();
+
+        assert_eq!(
+            prompt.render_prompt_indicator(PromptEditMode::Vi(PromptViMode::Normal)),
+            DEFAULT_PROMPT_INDICATOR
+        );
+        assert_eq!(
+            prompt.render_prompt_indicator(PromptEditMode::Vi(PromptViMode::Insert)),
+            DEFAULT_VI_INSERT_PROMPT_INDICATOR
+        );
+    }
+
+    #[test]
+    fn custom_indicator_replaces_default_for_its_mode_only() {
+        let prompt = DefaultPrompt::default()
+            .with_indicator(PromptEditMode::Vi(PromptViMode::Normal), "[N] ")
+            .with_indicator(PromptEditMode::Custom("notebook".into()), "nb> ");
+
+        assert_eq!(
+            prompt.render_prompt_indicator(PromptEditMode::Vi(PromptViMode::Normal)),
+            "[N] "
+        );
+        assert_eq!(
+            prompt.render_prompt_indicator(PromptEditMode::Vi(PromptViMode::Insert)),
+            DEFAULT_VI_INSERT_PROMPT_INDICATOR
+        );
+        assert_eq!(
+            prompt.render_prompt_indicator(PromptEditMode::Custom("notebook".into())),
+            "nb> "
+        );
+        assert_eq!(
+            prompt.render_prompt_indicator(PromptEditMode::Custom("other".into())),
+            "(other)"
+        );
+    }
+
+    #[test]
+    fn multiline_indicator_shows_incomplete_reason() {
+        let prompt = DefaultPrompt::default();
+
+        assert_eq!(prompt.render_prompt_multiline_indicator(None), "::: ");
+        assert_eq!(
+            prompt.render_prompt_multiline_indicator(Some("quote")),
+            "quote> "
+        );
+    }
+
+    #[test]
+    fn segments_follow_working_directory() {
+        let segments = PromptSegments::default()
+            .with_segment("empty", Duration::from_millis(100), String::new)
+            .with_segment("branch", Duration::from_millis(100), || "main".to_string());
+        let prompt = DefaultPrompt::default().with_segments(segments);
+        let working_dir = get_working_dir().unwrap();
+
+        let rendered = prompt.render_prompt(working_dir.len() + 5);
+
+        assert_eq!(rendered, format!("{} main", working_dir));
+    }
+}
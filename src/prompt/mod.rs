@@ -0,0 +1,11 @@
+mod base;
+mod default;
+mod segment;
+
+pub use base::{
+    Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus, PromptViMode,
+    DEFAULT_MULTILINE_INDICATOR, DEFAULT_PROMPT_COLOR, DEFAULT_PROMPT_INDICATOR,
+    DEFAULT_VI_INSERT_PROMPT_INDICATOR, DEFAULT_VI_VISUAL_PROMPT_INDICATOR,
+};
+pub use default::DefaultPrompt;
+pub use segment::PromptSegments;
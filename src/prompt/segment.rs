@@ -0,0 +1,230 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Last value of a segment and whether a refresh is running in the background
+#[derive(Default)]
+struct SegmentState {
+    value: String,
+    refreshing: bool,
+}
+
+/// A named piece of the prompt produced by a host closure
+#[derive(Clone)]
+struct PromptSegment {
+    name: String,
+    budget: Duration,
+    render: Arc<dyn Fn() -> String + Send + Sync>,
+    state: Arc<Mutex<SegmentState>>,
+}
+
+impl PromptSegment {
+    /// Run the closure on a background thread, the receiver is notified once the value is stored
+    ///
+    /// Returns `None` if the previous refresh did not finish yet.
+    fn start_refresh(&self) -> Option<mpsc::Receiver<()>> {
+        {
+            let mut state = self.state.lock().ok()?;
+            if state.refreshing {
+                return None;
+            }
+            state.refreshing = true;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let render = Arc::clone(&self.render);
+        let state = Arc::clone(&self.state);
+        thread::spawn(move || {
+            // A panicking closure keeps the last value instead of freezing the segment
+            let value = panic::catch_unwind(AssertUnwindSafe(|| render())).ok();
+            if let Ok(mut state) = state.lock() {
+                if let Some(value) = value {
+                    state.value = value;
+                }
+                state.refreshing = false;
+            }
+            let _ = sender.send(());
+        });
+
+        Some(receiver)
+    }
+
+    fn cached_value(&self) -> String {
+        self.state
+            .lock()
+            .map(|state| state.value.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Prompt content provided by host closures that are evaluated on every prompt repaint
+///
+/// Each segment has a time budget. A closure taking longer, e.g. querying the status of a large
+/// git repository, does not block the editor: the segment shows its previous value and the
+/// result is picked up by a later repaint. All segments of a repaint are evaluated in parallel.
+///
+/// Segments are shown by [`DefaultPrompt`](crate::DefaultPrompt) after the working directory,
+/// custom [`Prompt`](crate::Prompt) implementations can render them wherever they want.
+///
+/// ## Example
+/// ```rust
+/// use std::time::Duration;
+/// use reedline::{DefaultPrompt, PromptSegments};
+///
+/// let segments = PromptSegments::default()
+///     .with_segment("user", Duration::from_millis(50), || "ferris".to_string())
+///     .with_segment("branch", Duration::from_millis(50), || "main".to_string());
+/// assert_eq!(segments.render_segment("user"), Some("ferris".to_string()));
+///
+/// let prompt = DefaultPrompt::default().with_segments(segments);
+/// ```
+#[derive(Clone, Default)]
+pub struct PromptSegments {
+    segments: Vec<PromptSegment>,
+}
+
+impl PromptSegments {
+    /// A builder that adds a segment, replacing a segment with the same name
+    ///
+    /// `render` is called on a background thread and waited for at most `budget`.
+    pub fn with_segment(
+        mut self,
+        name: impl Into<String>,
+        budget: Duration,
+        render: impl Fn() -> String + Send + Sync + 'static,
+    ) -> PromptSegments {
+        let segment = PromptSegment {
+            name: name.into(),
+            budget,
+            render: Arc::new(render),
+            state: Arc::new(Mutex::new(SegmentState::default())),
+        };
+
+        match self.segments.iter_mut().find(|s| s.name == segment.name) {
+            Some(registered) => *registered = segment,
+            None => self.segments.push(segment),
+        }
+        self
+    }
+
+    /// Names of the segments in the order they were added
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().map(|segment| segment.name.as_str())
+    }
+
+    /// Refresh all segments and return their values in the order they were added
+    ///
+    /// Segments exceeding their budget return the value of their last finished refresh,
+    /// an empty string before the first one finished.
+    pub fn render(&self) -> Vec<String> {
+        let start = Instant::now();
+        let refreshes: Vec<_> = self
+            .segments
+            .iter()
+            .map(|segment| segment.start_refresh())
+            .collect();
+
+        self.segments
+            .iter()
+            .zip(refreshes)
+            .map(|(segment, refresh)| {
+                if let Some(refresh) = refresh {
+                    let _ = refresh.recv_timeout(segment.budget.saturating_sub(start.elapsed()));
+                }
+                segment.cached_value()
+            })
+            .collect()
+    }
+
+    /// Refresh the segment with the given name and return its value, `None` if it is unknown
+    pub fn render_segment(&self, name: &str) -> Option<String> {
+        let segment = self.segments.iter().find(|segment| segment.name == name)?;
+        if let Some(refresh) = segment.start_refresh() {
+            let _ = refresh.recv_timeout(segment.budget);
+        }
+
+        Some(segment.cached_value())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const SLOW: Duration = Duration::from_millis(200);
+
+    #[test]
+    fn fast_segments_are_rendered_in_order() {
+        let segments = PromptSegments::default()
+            .with_segment("a", SLOW, || "first".to_string())
+            .with_segment("b", SLOW, || "second".to_string());
+
+        assert_eq!(segments.render(), vec!["first", "second"]);
+        assert_eq!(segments.names().collect::<Vec<_>>(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn segment_with_same_name_is_replaced() {
+        let segments = PromptSegments::default()
+            .with_segment("a", SLOW, || "old".to_string())
+            .with_segment("a", SLOW, || "new".to_string());
+
+        assert_eq!(segments.render(), vec!["new"]);
+    }
+
+    #[test]
+    fn slow_segment_shows_cached_value_until_refreshed() {
+        let segments = PromptSegments::default().with_segment("slow", Duration::ZERO, || {
+            thread::sleep(Duration::from_millis(50));
+            "done".to_string()
+        });
+
+        assert_eq!(segments.render_segment("slow"), Some(String::new()));
+
+        thread::sleep(SLOW);
+        assert_eq!(segments.render_segment("slow"), Some("done".to_string()));
+    }
+
+    #[test]
+    fn running_refresh_is_not_started_again() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let segments = PromptSegments::default().with_segment("slow", Duration::ZERO, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+            String::new()
+        });
+
+        segments.render();
+        segments.render();
+        thread::sleep(SLOW);
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn panicking_segment_keeps_last_value() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let segments = PromptSegments::default().with_segment("flaky", SLOW, move || {
+            if counter.fetch_add(1, Ordering::SeqCst) > 0 {
+                panic!("segment failed");
+            }
+            "ok".to_string()
+        });
+
+        assert_eq!(segments.render(), vec!["ok"]);
+        assert_eq!(segments.render(), vec!["ok"]);
+        assert_eq!(segments.render(), vec!["ok"]);
+    }
+
+    #[test]
+    fn unknown_segment() {
+        assert_eq!(PromptSegments::default().render_segment("missing"), None);
+    }
+}
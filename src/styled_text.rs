@@ -1,4 +1,8 @@
-use nu_ansi_term::{Color, Style};
+use {
+    crate::text_manipulation::{caret_notation, tab_width, DisplayWidth, TabRendering},
+    nu_ansi_term::{Color, Style},
+    std::ops::Range,
+};
 
 /// A representation of a buffer with styling, used for doing syntax highlighting
 pub struct StyledText {
@@ -22,6 +26,124 @@ impl StyledText {
         self.buffer.push(styled_string);
     }
 
+    /// Layer `style` over the text in the byte range `range` of the buffer
+    ///
+    /// Colors set in `style` replace those of the text, its attributes are added to them.
+    pub fn overlay(&mut self, range: Range<usize>, style: Style) {
+        let mut layered = Vec::with_capacity(self.buffer.len() + 2);
+        let mut start = 0;
+        for (base, text) in self.buffer.drain(..) {
+            let end = start + text.len();
+            // Split the part of the range inside this piece off its start and end
+            let inner_start = range.start.clamp(start, end) - start;
+            let inner_end = range.end.clamp(start, end) - start;
+            if inner_start >= inner_end {
+                layered.push((base, text));
+            } else {
+                layered.push((base, text[..inner_start].to_string()));
+                layered.push((layer(base, style), text[inner_start..inner_end].to_string()));
+                layered.push((base, text[inner_end..].to_string()));
+            }
+            start = end;
+        }
+        layered.retain(|(_, text)| !text.is_empty());
+        self.buffer = layered;
+    }
+
+    /// Style `line` with `base_style` and layer the styles of `ranges` over it in their order
+    ///
+    /// Ranges are byte ranges of `line`, they may overlap and leave parts of the line out.
+    pub fn from_ranges(line: &str, base_style: Style, ranges: &[(Range<usize>, Style)]) -> Self {
+        let mut styled_text = StyledText::new();
+        styled_text.push((base_style, line.to_string()));
+        for (range, style) in ranges {
+            styled_text.overlay(range.clone(), *style);
+        }
+        styled_text
+    }
+
+    /// Show the control characters of the text in caret notation, e.g. `ESC` as `^[`, with
+    /// `style` layered over their style
+    ///
+    /// Printed as they are, control characters from a paste would be executed by the terminal.
+    /// Tabs and line breaks are kept. Returns `insertion_point` moved along with the text in
+    /// front of it.
+    pub(crate) fn show_control_chars(&mut self, style: Style, insertion_point: usize) -> usize {
+        let text: String = self.buffer.iter().map(|(_, text)| text.as_str()).collect();
+        let mut shown = Vec::with_capacity(self.buffer.len());
+        let mut offset = 0;
+        let mut shown_insertion_point = insertion_point;
+        for (base, piece) in self.buffer.drain(..) {
+            let mut plain = String::new();
+            for c in piece.chars() {
+                let is_line_break = c == '\r' && text[offset + 1..].starts_with('\n');
+                match caret_notation(c).filter(|_| !is_line_break) {
+                    Some(caret) => {
+                        if !plain.is_empty() {
+                            shown.push((base, std::mem::take(&mut plain)));
+                        }
+                        if offset < insertion_point {
+                            shown_insertion_point =
+                                shown_insertion_point + caret.len() - c.len_utf8();
+                        }
+                        shown.push((layer(base, style), caret));
+                    }
+                    None => plain.push(c),
+                }
+                offset += c.len_utf8();
+            }
+            if !plain.is_empty() {
+                shown.push((base, plain));
+            }
+        }
+        self.buffer = shown;
+
+        shown_insertion_point
+    }
+
+    /// Replace every tab by the columns up to the next multiple of `tab_stop` of its line
+    ///
+    /// The painter can then find the columns of the text without knowing about tabs, which the
+    /// terminal would expand on its own. Returns `insertion_point` moved along with the text
+    /// in front of it.
+    pub(crate) fn expand_tabs(
+        &mut self,
+        tab_stop: usize,
+        rendering: TabRendering,
+        insertion_point: usize,
+    ) -> usize {
+        let mut column = 0;
+        let mut offset = 0;
+        let mut expanded_insertion_point = insertion_point;
+        for (_, text) in &mut self.buffer {
+            let mut expanded = String::with_capacity(text.len());
+            for part in text.split_inclusive(['\t', '\n']) {
+                let content = part.trim_end_matches(['\t', '\n']);
+                expanded.push_str(content);
+                column += DisplayWidth::of(content).0;
+                offset += part.len();
+                if part.ends_with('\n') {
+                    expanded.push('\n');
+                    column = 0;
+                } else if part.ends_with('\t') {
+                    let width = tab_width(column, tab_stop);
+                    let tab = match rendering {
+                        TabRendering::Spaces => " ".repeat(width),
+                        TabRendering::Glyph(glyph) => format!("{}{}", glyph, " ".repeat(width - 1)),
+                    };
+                    if offset <= insertion_point {
+                        expanded_insertion_point = expanded_insertion_point + tab.len() - 1;
+                    }
+                    expanded.push_str(&tab);
+                    column += width;
+                }
+            }
+            *text = expanded;
+        }
+
+        expanded_insertion_point
+    }
+
     /// Render the styled string. We use the insertion point to render around so that
     /// we can properly write out the styled string to the screen and find the correct
     /// place to put the cursor. This assumes a logic that prints the first part of the
@@ -78,6 +200,22 @@ fn strip_ansi(astring: &str) -> String {
     }
 }
 
+/// `top` layered over `base`, see [`StyledText::overlay()`]
+fn layer(base: Style, top: Style) -> Style {
+    let mut style = base;
+    style.foreground = top.foreground.or(base.foreground);
+    style.background = top.background.or(base.background);
+    style.is_bold |= top.is_bold;
+    style.is_dimmed |= top.is_dimmed;
+    style.is_italic |= top.is_italic;
+    style.is_underline |= top.is_underline;
+    style.is_blink |= top.is_blink;
+    style.is_reverse |= top.is_reverse;
+    style.is_hidden |= top.is_hidden;
+    style.is_strikethrough |= top.is_strikethrough;
+    style
+}
+
 fn render_as_string(
     renderable: &(Style, String),
     prompt_style: &Style,
@@ -93,3 +231,73 @@ fn render_as_string(
     }
     rendered
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn expanded(text: &[&str], rendering: TabRendering, insertion_point: usize) -> (String, usize) {
+        let mut styled_text = StyledText::new();
+        for text in text {
+            styled_text.push((Style::new(), text.to_string()));
+        }
+        let insertion_point = styled_text.expand_tabs(4, rendering, insertion_point);
+        let text = styled_text
+            .buffer
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect();
+
+        (text, insertion_point)
+    }
+
+    #[rstest]
+    #[case(&["a\tb"], 3, ("a   b", 5))]
+    #[case(&["a\tb"], 1, ("a   b", 1))]
+    #[case(&["ab", "cd\t", "\te"], 6, ("abcd        e", 12))]
+    #[case(&["\t\n\t"], 3, ("    \n    ", 9))]
+    #[case(&["漢\tb"], 4, ("漢  b", 5))]
+    fn tabs_are_expanded_to_tab_stops(
+        #[case] text: &[&str],
+        #[case] insertion_point: usize,
+        #[case] expected: (&str, usize),
+    ) {
+        assert_eq!(
+            expanded(text, TabRendering::Spaces, insertion_point),
+            (expected.0.to_string(), expected.1)
+        );
+    }
+
+    #[test]
+    fn control_characters_are_shown_in_caret_notation() {
+        let red = Style::new().fg(Color::Red);
+        let mut styled_text = StyledText::new();
+        styled_text.push((red, "a\x1b[1m".to_string()));
+        styled_text.push((Style::new(), "\x07\r\nb\r".to_string()));
+
+        let insertion_point = styled_text.show_control_chars(Style::new().reverse(), 7);
+
+        assert_eq!(
+            styled_text.buffer,
+            vec![
+                (red, "a".to_string()),
+                (red.reverse(), "^[".to_string()),
+                (red, "[1m".to_string()),
+                (Style::new().reverse(), "^G".to_string()),
+                (Style::new(), "\r\nb".to_string()),
+                (Style::new().reverse(), "^M".to_string()),
+            ]
+        );
+        assert_eq!(insertion_point, 9);
+    }
+
+    #[test]
+    fn tabs_can_show_a_glyph() {
+        assert_eq!(
+            expanded(&["a\tb\t"], TabRendering::Glyph('→'), 4),
+            ("a→  b→  ".to_string(), 12)
+        );
+    }
+}
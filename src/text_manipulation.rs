@@ -1,12 +1,250 @@
-use unicode_segmentation::UnicodeSegmentation;
+use {
+    std::ops::{Add, Sub},
+    unicode_segmentation::UnicodeSegmentation,
+    unicode_width::UnicodeWidthStr,
+};
 
-pub fn remove_last_grapheme(string: &str) -> &str {
-    let mut it = UnicodeSegmentation::graphemes(string, true);
+/// A position inside a string counted in bytes
+///
+/// Only byte offsets are valid for slicing the buffer, they must not be used as terminal columns.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub struct ByteOffset(pub usize);
 
-    if it.next_back().is_some() {
-        it.as_str()
+/// A number of terminal columns occupied by displayed text
+///
+/// Wide characters (CJK, most emoji) take two columns while combining marks take none.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub struct DisplayWidth(pub usize);
+
+impl ByteOffset {
+    /// Columns occupied by the part of `text` in front of this offset
+    ///
+    /// # Panics
+    /// If the offset is not on a char boundary of `text`
+    pub fn display_width_in(self, text: &str) -> DisplayWidth {
+        DisplayWidth::of(&text[..self.0])
+    }
+}
+
+impl DisplayWidth {
+    /// Columns occupied by `text` when printed to the terminal
+    ///
+    /// Measured per grapheme cluster, so emoji sequences count as a single wide character.
+    pub fn of(text: &str) -> DisplayWidth {
+        DisplayWidth(text.graphemes(true).map(grapheme_width).sum())
+    }
+
+    /// The offset of the last grapheme boundary in `text` that does not exceed this width
+    pub fn to_byte_offset(self, text: &str) -> ByteOffset {
+        let mut width = 0;
+        for (offset, grapheme) in text.grapheme_indices(true) {
+            width += grapheme_width(grapheme);
+            if width > self.0 {
+                return ByteOffset(offset);
+            }
+        }
+        ByteOffset(text.len())
+    }
+}
+
+impl From<usize> for ByteOffset {
+    fn from(offset: usize) -> Self {
+        ByteOffset(offset)
+    }
+}
+
+impl From<usize> for DisplayWidth {
+    fn from(width: usize) -> Self {
+        DisplayWidth(width)
+    }
+}
+
+impl Add for ByteOffset {
+    type Output = ByteOffset;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        ByteOffset(self.0 + rhs.0)
+    }
+}
+
+impl Sub for ByteOffset {
+    type Output = ByteOffset;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        ByteOffset(self.0 - rhs.0)
+    }
+}
+
+impl Add for DisplayWidth {
+    type Output = DisplayWidth;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        DisplayWidth(self.0 + rhs.0)
+    }
+}
+
+impl Sub for DisplayWidth {
+    type Output = DisplayWidth;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        DisplayWidth(self.0 - rhs.0)
+    }
+}
+
+/// How the tab characters of the buffer are shown
+///
+/// A tab reaches from its column up to the next tab stop of its line, see
+/// [`Reedline::with_tab_stop()`](crate::Reedline::with_tab_stop).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TabRendering {
+    /// Blank columns, like the terminal shows a tab
+    #[default]
+    Spaces,
+    /// A glyph like `→` in the first column of the tab, followed by blank columns
+    Glyph(char),
+}
+
+/// Columns occupied by a tab at `column` of its line, with a tab stop every `tab_stop` columns
+pub(crate) fn tab_width(column: usize, tab_stop: usize) -> usize {
+    let tab_stop = tab_stop.max(1);
+    tab_stop - column % tab_stop
+}
+
+/// Columns occupied by the line `text`, its tabs reaching up to the next tab stop
+pub(crate) fn expanded_width(text: &str, tab_stop: usize) -> DisplayWidth {
+    DisplayWidth(text.graphemes(true).fold(0, |column, grapheme| {
+        column + expanded_grapheme_width(grapheme, column, tab_stop)
+    }))
+}
+
+/// The offset of the last grapheme boundary in the line `text` that does not exceed `width`
+/// columns, its tabs reaching up to the next tab stop
+///
+/// A position covered by a tab is in front of it, as for wide characters.
+pub(crate) fn expanded_width_to_byte_offset(
+    text: &str,
+    width: DisplayWidth,
+    tab_stop: usize,
+) -> ByteOffset {
+    let mut column = 0;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        column += expanded_grapheme_width(grapheme, column, tab_stop);
+        if column > width.0 {
+            return ByteOffset(offset);
+        }
+    }
+    ByteOffset(text.len())
+}
+
+fn expanded_grapheme_width(grapheme: &str, column: usize, tab_stop: usize) -> usize {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (Some('\t'), None) => tab_width(column, tab_stop),
+        (Some(c), None) => {
+            caret_notation(c).map_or_else(|| grapheme_width(grapheme), |caret| caret.len())
+        }
+        _ => grapheme_width(grapheme),
+    }
+}
+
+/// The caret notation a control character is shown in, `None` for other characters
+///
+/// `ESC` is shown as `^[`, `DEL` as `^?` and the C1 controls prefixed with `M-` like `cat -v`
+/// does. Tabs and line feeds are left to the line layout.
+pub(crate) fn caret_notation(c: char) -> Option<String> {
+    match c as u32 {
+        0x09 | 0x0a => None,
+        code @ 0x00..=0x1f => Some(format!("^{}", (code as u8 + 0x40) as char)),
+        0x7f => Some("^?".to_string()),
+        code @ 0x80..=0x9f => Some(format!("M-^{}", (code as u8 - 0x40) as char)),
+        _ => None,
+    }
+}
+
+/// Columns occupied by a single grapheme cluster
+///
+/// Emoji presentation (`U+FE0F`), zero width joiner sequences and flags are rendered
+/// as one wide glyph by terminals, instead of the sum of their parts.
+pub(crate) fn grapheme_width(grapheme: &str) -> usize {
+    let width = UnicodeWidthStr::width(grapheme);
+    let is_emoji_sequence = grapheme.chars().count() > 1
+        && grapheme.chars().any(|c| {
+            c == '\u{200d}' || c == '\u{fe0f}' || ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+        });
+
+    if is_emoji_sequence {
+        2
+    } else {
+        width
+    }
+}
+
+/// Position of the terminal cursor after printing `text` starting at `start_column`
+/// on a terminal that is `columns` wide
+///
+/// Returns the number of rows the output wrapped and the column on the final row.
+/// A wide grapheme that does not fit at the end of a row is moved to the next row as a whole.
+/// The column equals `columns` if the last grapheme filled the row exactly and the terminal
+/// has not wrapped yet.
+///
+/// Text without whitespace is wrapped like any other, a token longer than the row continues
+/// on as many rows as it needs.
+pub(crate) fn wrapped_position(text: &str, start_column: usize, columns: usize) -> (usize, usize) {
+    let columns = columns.max(1);
+    let mut rows = 0;
+    let mut column = start_column;
+    for grapheme in text.graphemes(true) {
+        column = wrapped_column(&mut rows, column, grapheme_width(grapheme), columns);
+        column += grapheme_width(grapheme);
+    }
+
+    (rows, column)
+}
+
+/// Columns occupied by the graphemes of `text` in front of the screen cell at `column` on the
+/// `row`th row of its output, the inverse of [`wrapped_position()`]
+///
+/// A cell covered by a wide grapheme belongs to that grapheme. Cells left empty at the end of a
+/// row, because the next grapheme did not fit, map to the start of that grapheme. Cells behind
+/// the end of the text count as if it continued with single width characters.
+pub(crate) fn wrapped_width(
+    text: &str,
+    start_column: usize,
+    columns: usize,
+    row: usize,
+    column: usize,
+) -> DisplayWidth {
+    let columns = columns.max(1);
+    let mut rows = 0;
+    let mut current = start_column;
+    let mut width_in_front = 0;
+    for grapheme in text.graphemes(true) {
+        let width = grapheme_width(grapheme);
+        current = wrapped_column(&mut rows, current, width, columns);
+        if rows > row || (rows == row && current + width > column) {
+            return DisplayWidth(width_in_front);
+        }
+        current += width;
+        width_in_front += width;
+    }
+
+    if rows == row {
+        DisplayWidth(width_in_front + column.saturating_sub(current))
     } else {
-        ""
+        DisplayWidth(width_in_front)
+    }
+}
+
+/// The column a grapheme of `width` is printed at when the cursor is at `column`, counting
+/// the row it moves on to in `rows`
+///
+/// A grapheme wider than the whole row stays at the start of the row instead of moving on.
+fn wrapped_column(rows: &mut usize, column: usize, width: usize, columns: usize) -> usize {
+    if width > 0 && column > 0 && column + width > columns {
+        *rows += 1;
+        0
+    } else {
+        column
     }
 }
 
@@ -14,25 +252,168 @@ pub fn remove_last_grapheme(string: &str) -> &str {
 mod test {
     use super::*;
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
     #[test]
-    fn remove_last_char_works_with_empty_string() {
-        let string = "";
+    fn display_width_counts_wide_characters_twice() {
+        assert_eq!(DisplayWidth::of("a漢字😄"), DisplayWidth(7));
+        assert_eq!(ByteOffset(4).display_width_in("a漢字😄"), DisplayWidth(3));
+    }
+
+    #[test]
+    fn display_width_to_byte_offset_stops_in_front_of_split_graphemes() {
+        let text = "a漢字b";
+
+        assert_eq!(DisplayWidth(1).to_byte_offset(text), ByteOffset(1));
+        assert_eq!(DisplayWidth(2).to_byte_offset(text), ByteOffset(1));
+        assert_eq!(DisplayWidth(3).to_byte_offset(text), ByteOffset(4));
+        assert_eq!(
+            DisplayWidth(10).to_byte_offset(text),
+            ByteOffset(text.len())
+        );
+    }
 
-        assert_eq!(remove_last_grapheme(string), "");
+    #[rstest]
+    #[case('\x1b', Some("^["))]
+    #[case('\x07', Some("^G"))]
+    #[case('\0', Some("^@"))]
+    #[case('\r', Some("^M"))]
+    #[case('\x7f', Some("^?"))]
+    #[case('\u{9b}', Some("M-^["))]
+    #[case('\t', None)]
+    #[case('\n', None)]
+    #[case('a', None)]
+    #[case('\u{a0}', None)]
+    fn control_characters_have_caret_notation(#[case] c: char, #[case] expected: Option<&str>) {
+        assert_eq!(caret_notation(c).as_deref(), expected);
     }
 
     #[test]
-    fn remove_last_char_works_with_normal_string() {
-        let string = "this is a string";
+    fn control_characters_take_the_width_of_their_caret_notation() {
+        assert_eq!(expanded_width("a\x1b[0m", 8), DisplayWidth(6));
+        assert_eq!(
+            expanded_width_to_byte_offset("a\x1bb", DisplayWidth(2), 8),
+            ByteOffset(1)
+        );
+    }
+
+    #[rstest]
+    #[case("\t", 4, 4)]
+    #[case("ab\tc", 4, 5)]
+    #[case("abcd\t", 4, 8)]
+    #[case("漢\t\t", 4, 8)]
+    #[case("a\tb", 8, 9)]
+    fn tabs_reach_up_to_the_next_tab_stop(
+        #[case] text: &str,
+        #[case] tab_stop: usize,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(expanded_width(text, tab_stop), DisplayWidth(expected));
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(1, 1)]
+    #[case(3, 1)]
+    #[case(4, 2)]
+    #[case(9, 3)]
+    fn columns_covered_by_tab_map_in_front_of_it(#[case] width: usize, #[case] expected: usize) {
+        assert_eq!(
+            expanded_width_to_byte_offset("a\tb", DisplayWidth(width), 4),
+            ByteOffset(expected)
+        );
+    }
+
+    #[rstest]
+    #[case("a", 1)]
+    #[case("漢", 2)]
+    #[case("e\u{301}", 1)]
+    #[case("👨\u{200d}👩\u{200d}👧", 2)]
+    #[case("❤\u{fe0f}", 2)]
+    #[case("🇩🇪", 2)]
+    fn grapheme_width_treats_sequences_as_one_glyph(
+        #[case] grapheme: &str,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(grapheme_width(grapheme), expected);
+    }
+
+    #[rstest]
+    #[case("abcd", 0, 10, (0, 4))]
+    #[case("abcd", 8, 10, (1, 2))]
+    // Exactly filling the row leaves the terminal in the pending wrap state
+    #[case("abcd", 6, 10, (0, 10))]
+    // The wide character does not fit into the last column and moves on as a whole
+    #[case("a漢", 8, 10, (1, 2))]
+    #[case("漢字漢字漢字", 0, 4, (2, 4))]
+    #[case("", 3, 10, (0, 3))]
+    // A single token spanning several rows
+    #[case("aGVsbG8gd29ybGQgaGVsbG8gd29ybGQ=", 4, 10, (3, 6))]
+    // Too wide for any row, printed where it is
+    #[case("漢", 0, 1, (0, 2))]
+    fn wrapped_position_moves_wide_graphemes_to_next_row(
+        #[case] text: &str,
+        #[case] start_column: usize,
+        #[case] columns: usize,
+        #[case] expected: (usize, usize),
+    ) {
+        assert_eq!(wrapped_position(text, start_column, columns), expected);
+    }
+
+    #[rstest]
+    #[case(0, 2, DisplayWidth(0))]
+    #[case(0, 4, DisplayWidth(0))]
+    #[case(0, 7, DisplayWidth(3))]
+    #[case(2, 3, DisplayWidth(19))]
+    // Past the end of the text on its last row
+    #[case(3, 8, DisplayWidth(34))]
+    // Below the text
+    #[case(4, 0, DisplayWidth(30))]
+    fn wrapped_width_maps_cells_inside_long_token(
+        #[case] row: usize,
+        #[case] column: usize,
+        #[case] expected: DisplayWidth,
+    ) {
+        let token = "aGVsbG8gd29ybGQgaGVsbG8gd29ybA";
+
+        assert_eq!(wrapped_width(token, 4, 10, row, column), expected);
+    }
+
+    #[rstest]
+    // The second cell of a wide character
+    #[case(0, 2, DisplayWidth(1))]
+    // The empty last cell in front of a character moved to the next row
+    #[case(0, 9, DisplayWidth(9))]
+    #[case(1, 0, DisplayWidth(9))]
+    #[case(1, 2, DisplayWidth(11))]
+    fn wrapped_width_maps_cells_of_wide_characters(
+        #[case] row: usize,
+        #[case] column: usize,
+        #[case] expected: DisplayWidth,
+    ) {
+        let text = "a漢字漢字漢字";
 
-        assert_eq!(remove_last_grapheme(string), "this is a strin");
+        assert_eq!(wrapped_width(text, 0, 10, row, column), expected);
     }
 
     #[test]
-    fn remove_last_char_works_with_string_containing_emojis() {
-        let string = "this is a 😞😄";
+    fn wrapped_width_inverts_wrapped_position() {
+        let text = "漢a字bcdefghi漢jklmnopqrstu😄vwxyz";
+        for start_column in 0..6 {
+            for (offset, _) in text.grapheme_indices(true) {
+                let (row, column) = wrapped_position(&text[..offset], start_column, 6);
+                // A row filled exactly is continued on the next one
+                let (row, column) = if column == 6 {
+                    (row + 1, 0)
+                } else {
+                    (row, column)
+                };
 
-        assert_eq!(remove_last_grapheme(string), "this is a 😞");
+                assert_eq!(
+                    wrapped_width(text, start_column, 6, row, column),
+                    DisplayWidth::of(&text[..offset])
+                );
+            }
+        }
     }
 }
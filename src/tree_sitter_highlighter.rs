@@ -0,0 +1,189 @@
+use {
+    crate::{
+        highlighter::RangeHighlighter, styled_text::StyledText, text_manipulation::ByteOffset,
+        Highlighter,
+    },
+    nu_ansi_term::Style,
+    std::{cell::RefCell, ops::Range},
+    tree_sitter::{Language, QueryError},
+    tree_sitter_highlight::{HighlightConfiguration, HighlightEvent},
+};
+
+/// A highlighter styling the line by the syntax tree of a tree-sitter grammar
+///
+/// The captures of the grammar's highlights query are styled by a theme of capture names. A
+/// theme entry also styles the more specific captures starting with its name, `function`
+/// covers `function.builtin` unless the theme has an entry of its own for it. Text without a
+/// styled capture gets the neutral style.
+///
+/// Only available with the `tree-sitter` feature.
+///
+/// ## Example
+#[cfg_attr(feature = "tree-sitter-rust", doc = "```rust")]
+#[cfg_attr(not(feature = "tree-sitter-rust"), doc = "```ignore")]
+/// use nu_ansi_term::{Color, Style};
+/// use reedline::{Reedline, TreeSitterHighlighter};
+///
+/// let highlighter = TreeSitterHighlighter::new(
+///     tree_sitter_rust::language(),
+///     tree_sitter_rust::HIGHLIGHT_QUERY,
+///     &[
+///         ("keyword", Style::new().fg(Color::Purple)),
+///         ("function", Style::new().fg(Color::Blue)),
+///         ("string", Style::new().fg(Color::Green)),
+///     ],
+/// )
+/// .expect("the query is valid");
+/// let line_editor = Reedline::create()?.with_highlighter(Box::new(highlighter));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct TreeSitterHighlighter {
+    config: HighlightConfiguration,
+    // Styles of the theme in the order its names were passed to the configuration
+    styles: Vec<Style>,
+    neutral_style: Style,
+    // Highlighting needs mutable parser state, the trait only hands out `&self`
+    highlighter: RefCell<tree_sitter_highlight::Highlighter>,
+}
+
+impl TreeSitterHighlighter {
+    /// Highlight the captures of `highlights_query` for `language` as set in `theme`
+    ///
+    /// Fails if the query does not fit the grammar.
+    pub fn new(
+        language: Language,
+        highlights_query: &str,
+        theme: &[(&str, Style)],
+    ) -> Result<Self, QueryError> {
+        let mut config = HighlightConfiguration::new(language, highlights_query, "", "")?;
+        let names: Vec<&str> = theme.iter().map(|(name, _)| *name).collect();
+        config.configure(&names);
+
+        Ok(TreeSitterHighlighter {
+            config,
+            styles: theme.iter().map(|(_, style)| *style).collect(),
+            neutral_style: Style::new(),
+            highlighter: RefCell::new(tree_sitter_highlight::Highlighter::new()),
+        })
+    }
+
+    /// A builder that sets the style of text without a styled capture
+    pub fn with_neutral_style(mut self, neutral_style: Style) -> Self {
+        self.neutral_style = neutral_style;
+        self
+    }
+}
+
+impl RangeHighlighter for TreeSitterHighlighter {
+    /// The innermost styled capture of every part of the line
+    ///
+    /// A line the grammar cannot be run on is left unstyled.
+    fn highlight_ranges(&self, line: &str, _cursor: ByteOffset) -> Vec<(Range<usize>, Style)> {
+        let mut highlighter = self.highlighter.borrow_mut();
+        let events = match highlighter.highlight(&self.config, line.as_bytes(), None, |_| None) {
+            Ok(events) => events,
+            Err(_) => return vec![],
+        };
+
+        let mut ranges = vec![];
+        let mut captures = vec![];
+        for event in events {
+            match event {
+                Ok(HighlightEvent::HighlightStart(highlight)) => captures.push(highlight.0),
+                Ok(HighlightEvent::HighlightEnd) => {
+                    captures.pop();
+                }
+                Ok(HighlightEvent::Source { start, end }) => {
+                    if let Some(style) = captures.last().and_then(|index| self.styles.get(*index)) {
+                        ranges.push((start..end, *style));
+                    }
+                }
+                Err(_) => return vec![],
+            }
+        }
+
+        ranges
+    }
+}
+
+impl Highlighter for TreeSitterHighlighter {
+    fn highlight(&self, line: &str, cursor: ByteOffset) -> StyledText {
+        // The ranges follow each other without overlapping, the neutral style fills the gaps
+        let mut styled_text = StyledText::new();
+        let mut end = 0;
+        for (range, style) in self.highlight_ranges(line, cursor) {
+            if end < range.start {
+                styled_text.push((self.neutral_style, line[end..range.start].to_string()));
+            }
+            end = range.end;
+            styled_text.push((style, line[range].to_string()));
+        }
+        if end < line.len() {
+            styled_text.push((self.neutral_style, line[end..].to_string()));
+        }
+
+        styled_text
+    }
+}
+
+#[cfg(all(test, feature = "tree-sitter-rust"))]
+mod test {
+    use super::*;
+    use nu_ansi_term::Color;
+    use pretty_assertions::assert_eq;
+
+    const QUERY: &str = r#"
+        "fn" @keyword
+        (function_item name: (identifier) @function.definition)
+        (call_expression function: (identifier) @function.builtin)
+        (string_literal) @string
+    "#;
+
+    fn highlighter(theme: &[(&str, Style)]) -> TreeSitterHighlighter {
+        TreeSitterHighlighter::new(tree_sitter_rust::language(), QUERY, theme).unwrap()
+    }
+
+    #[test]
+    fn captures_are_styled_by_the_theme() {
+        let keyword = Style::new().fg(Color::Purple);
+        let function = Style::new().fg(Color::Blue);
+        let builtin = Style::new().fg(Color::Cyan);
+        let highlighter = highlighter(&[
+            ("keyword", keyword),
+            ("function", function),
+            ("function.builtin", builtin),
+        ]);
+
+        assert_eq!(
+            highlighter.highlight_ranges(r#"fn main() { run("x") }"#, ByteOffset(0)),
+            vec![(0..2, keyword), (3..7, function), (12..15, builtin)]
+        );
+    }
+
+    #[test]
+    fn text_without_styled_capture_gets_neutral_style() {
+        let string = Style::new().fg(Color::Green);
+        let neutral = Style::new().dimmed();
+        let highlighter = highlighter(&[("string", string)]).with_neutral_style(neutral);
+
+        let mut expected = StyledText::new();
+        expected.push((neutral, "f(".to_string()));
+        expected.push((string, r#""x""#.to_string()));
+        expected.push((neutral, ");".to_string()));
+
+        let render =
+            |styled_text: StyledText| styled_text.render_around_insertion_point(7, "", true);
+        assert_eq!(
+            render(highlighter.highlight(r#"f("x");"#, ByteOffset(0))),
+            render(expected)
+        );
+    }
+
+    #[test]
+    fn query_not_fitting_the_grammar_fails() {
+        let result =
+            TreeSitterHighlighter::new(tree_sitter_rust::language(), "(no_such_node) @x", &[]);
+
+        assert!(result.is_err());
+    }
+}
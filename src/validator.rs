@@ -6,62 +6,168 @@ pub trait Validator {
 }
 
 /// Whether or not the validation shows the input was complete
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ValidationResult {
     /// An incomplete input which may need to span multiple lines to be complete
-    Incomplete,
+    ///
+    /// The `reason`, e.g. the unclosed delimiter, is passed on to
+    /// [`Prompt::render_prompt_multiline_indicator()`](crate::Prompt::render_prompt_multiline_indicator).
+    Incomplete {
+        /// Short description of what is missing, like `quote` or `brace`
+        reason: Option<String>,
+    },
 
     /// An input that is complete as-is
     Complete,
 }
 
-/// A default validator which checks for mismatched quotes
+/// A default validator for shell-like syntax
+///
+/// The input is incomplete while a single or double quote, a bracket out of `()[]{}` or a
+/// trailing `\` escape are left open. Brackets inside quotes don't count, a backslash
+/// escapes the next character outside of single quotes. Closing brackets without a matching
+/// opening one are ignored.
+///
+/// Reports the unclosed delimiter as `quote`, `dquote`, `brace`, `bracket` or `paren`,
+/// for brackets the innermost one.
 pub struct DefaultValidator;
 
 impl Validator for DefaultValidator {
     fn validate(&self, line: &str) -> ValidationResult {
-        if line.split('"').count() % 2 == 0 || incomplete_brackets(line) {
-            ValidationResult::Incomplete
-        } else {
-            ValidationResult::Complete
+        match find_unclosed(line) {
+            Some(unclosed) => ValidationResult::Incomplete {
+                reason: unclosed.reason().map(String::from),
+            },
+            None => ValidationResult::Complete,
         }
     }
 }
 
-fn incomplete_brackets(line: &str) -> bool {
-    let mut balance: Vec<char> = Vec::new();
+/// Part of the input the [`DefaultValidator`] is scanning
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Unquoted,
+    SingleQuoted,
+    DoubleQuoted,
+}
+
+/// The delimiter keeping the input from being complete
+#[derive(Debug, PartialEq, Eq)]
+enum Unclosed {
+    SingleQuote,
+    DoubleQuote,
+    Escape,
+    // Holds the missing closing bracket
+    Bracket(char),
+}
+
+impl Unclosed {
+    fn reason(&self) -> Option<&'static str> {
+        match self {
+            Unclosed::SingleQuote => Some("quote"),
+            Unclosed::DoubleQuote => Some("dquote"),
+            Unclosed::Escape => None,
+            Unclosed::Bracket('}') => Some("brace"),
+            Unclosed::Bracket(']') => Some("bracket"),
+            Unclosed::Bracket(_) => Some("paren"),
+        }
+    }
+}
+
+/// Scan `line` for the innermost delimiter left open
+fn find_unclosed(line: &str) -> Option<Unclosed> {
+    let mut state = State::Unquoted;
+    let mut escaped = false;
+    let mut brackets: Vec<char> = Vec::new();
 
     for c in line.chars() {
-        if c == '{' {
-            balance.push('}');
-        } else if c == '[' {
-            balance.push(']');
-        } else if c == '(' {
-            balance.push(')');
-        } else if ['}', ']', ')'].contains(&c) {
-            if let Some(last) = balance.last() {
-                if last == &c {
-                    balance.pop();
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match (state, c) {
+            (State::SingleQuoted, '\'') | (State::DoubleQuoted, '"') => state = State::Unquoted,
+            (State::SingleQuoted, _) => {}
+            (State::DoubleQuoted, '\\') => escaped = true,
+            (State::DoubleQuoted, _) => {}
+            (State::Unquoted, '\\') => escaped = true,
+            (State::Unquoted, '\'') => state = State::SingleQuoted,
+            (State::Unquoted, '"') => state = State::DoubleQuoted,
+            (State::Unquoted, '(') => brackets.push(')'),
+            (State::Unquoted, '[') => brackets.push(']'),
+            (State::Unquoted, '{') => brackets.push('}'),
+            (State::Unquoted, ')' | ']' | '}') => {
+                if brackets.last() == Some(&c) {
+                    brackets.pop();
                 }
             }
+            (State::Unquoted, _) => {}
         }
     }
 
-    !balance.is_empty()
+    match state {
+        State::SingleQuoted => Some(Unclosed::SingleQuote),
+        State::DoubleQuoted => Some(Unclosed::DoubleQuote),
+        State::Unquoted if escaped => Some(Unclosed::Escape),
+        State::Unquoted => brackets.pop().map(Unclosed::Bracket),
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use pretty_assertions::assert_eq;
     use rstest::rstest;
 
     #[rstest]
-    #[case("(([[]]))", false)]
-    #[case("(([[]]", true)]
-    #[case("{[}]", true)]
-    #[case("{[]}{()}", false)]
-    fn test_incomplete_brackets(#[case] input: &str, #[case] expected: bool) {
-        let result = incomplete_brackets(input);
+    #[case("(([[]]))", None)]
+    #[case("(([[]]", Some(Unclosed::Bracket(')')))]
+    #[case("{[}]", Some(Unclosed::Bracket('}')))]
+    #[case("{[]}{()}", None)]
+    #[case("{ foo(", Some(Unclosed::Bracket(')')))]
+    #[case("echo '(' \"[\"", None)]
+    #[case("echo \\(", None)]
+    #[case("echo 'it\\'s", None)]
+    #[case("echo \"say \\\"hi\\\"\"", None)]
+    #[case("echo \"say \\\"hi\\\"", Some(Unclosed::DoubleQuote))]
+    #[case("echo 'hello", Some(Unclosed::SingleQuote))]
+    #[case("echo \"it's", Some(Unclosed::DoubleQuote))]
+    #[case("ls \\", Some(Unclosed::Escape))]
+    #[case("ls \\\\", None)]
+    fn test_find_unclosed(#[case] input: &str, #[case] expected: Option<Unclosed>) {
+        let result = find_unclosed(input);
 
         assert_eq!(result, expected);
     }
+
+    #[rstest]
+    #[case("echo \"hello", Some("dquote"))]
+    #[case("echo 'hello {", Some("quote"))]
+    #[case("if x {", Some("brace"))]
+    #[case("let a = [1, (2", Some("paren"))]
+    #[case("let a = [1,", Some("bracket"))]
+    #[case("echo \"hello\" ()", None)]
+    fn default_validator_reports_unclosed_delimiter(
+        #[case] input: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let result = DefaultValidator.validate(input);
+
+        let expected = match expected {
+            Some(reason) => ValidationResult::Incomplete {
+                reason: Some(reason.to_string()),
+            },
+            None => ValidationResult::Complete,
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn trailing_escape_is_incomplete_without_reason() {
+        assert_eq!(
+            DefaultValidator.validate("echo \\"),
+            ValidationResult::Incomplete { reason: None }
+        );
+    }
 }
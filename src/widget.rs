@@ -0,0 +1,129 @@
+use crate::LineBuffer;
+
+/// The rows reserved for a [`Widget`] beneath the input area, in terminal cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WidgetArea {
+    /// Number of columns, the width of the terminal
+    pub width: u16,
+    /// Number of rows, as requested by [`Widget::height()`]
+    pub height: u16,
+}
+
+/// Content drawn by the host in rows reserved beneath the input area, e.g. a bar listing the
+/// available keys or a small file browser
+///
+/// The widget is rendered as part of every frame painted by the engine, so it is updated
+/// together with the buffer and never overwritten by a later paint. Set it with
+/// [`Reedline::with_widget()`](crate::Reedline::with_widget). An open menu or the history
+/// preview is shown between the buffer and the widget.
+///
+/// ## Example
+/// ```rust
+/// use reedline::{LineBuffer, Reedline, Widget, WidgetArea};
+///
+/// /// Shows the size of the buffer
+/// struct BufferSize;
+///
+/// impl Widget for BufferSize {
+///     fn height(&self) -> u16 {
+///         1
+///     }
+///
+///     fn render(
+///         &mut self,
+///         _area: WidgetArea,
+///         line_buffer: &LineBuffer,
+///         _use_ansi_coloring: bool,
+///     ) -> Vec<String> {
+///         vec![format!("{} bytes", line_buffer.get_buffer().len())]
+///     }
+/// }
+///
+/// let line_editor = Reedline::create()?.with_widget(Some(Box::new(BufferSize)));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub trait Widget {
+    /// Number of rows to reserve beneath the input area, asked before each frame
+    fn height(&self) -> u16;
+
+    /// The rows filling `area`, rendered for the current state of the buffer
+    ///
+    /// Rows should fit into the width of `area`. Missing rows stay empty, rows beyond the
+    /// height are not shown.
+    fn render(
+        &mut self,
+        area: WidgetArea,
+        line_buffer: &LineBuffer,
+        use_ansi_coloring: bool,
+    ) -> Vec<String>;
+}
+
+/// The rows of `widget` joined by `\n`, exactly as many as it reserved
+pub(crate) fn render_rows(
+    widget: &mut dyn Widget,
+    width: u16,
+    line_buffer: &LineBuffer,
+    use_ansi_coloring: bool,
+) -> Option<String> {
+    let height = widget.height();
+    if height == 0 {
+        return None;
+    }
+
+    let mut rows = widget.render(WidgetArea { width, height }, line_buffer, use_ansi_coloring);
+    rows.resize(height as usize, String::new());
+
+    Some(rows.join("\n"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    struct Rows(u16, Vec<&'static str>);
+
+    impl Widget for Rows {
+        fn height(&self) -> u16 {
+            self.0
+        }
+
+        fn render(
+            &mut self,
+            area: WidgetArea,
+            _line_buffer: &LineBuffer,
+            _use_ansi_coloring: bool,
+        ) -> Vec<String> {
+            assert_eq!(
+                area,
+                WidgetArea {
+                    width: 80,
+                    height: self.0
+                }
+            );
+            self.1.iter().map(|row| row.to_string()).collect()
+        }
+    }
+
+    #[test]
+    fn rows_are_padded_and_cut_to_height() {
+        let line_buffer = LineBuffer::new();
+
+        assert_eq!(
+            render_rows(&mut Rows(3, vec!["a"]), 80, &line_buffer, false),
+            Some("a\n\n".to_string())
+        );
+        assert_eq!(
+            render_rows(&mut Rows(1, vec!["a", "b"]), 80, &line_buffer, false),
+            Some("a".to_string())
+        );
+    }
+
+    #[test]
+    fn no_rows_without_height() {
+        assert_eq!(
+            render_rows(&mut Rows(0, vec!["a"]), 80, &LineBuffer::new(), false),
+            None
+        );
+    }
+}